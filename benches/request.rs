@@ -0,0 +1,75 @@
+//! Allocations on the hot path of checking parameters: every chunk becomes a request
+//! (and a random request of the same size when the page changes).
+//!
+//! cargo bench --bench request
+
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use x8::{Request, RequestDefaults};
+
+fn request_defaults() -> RequestDefaults {
+    let mut request_defaults = RequestDefaults::default();
+    request_defaults.method = "GET".to_string();
+    request_defaults.path = "/path".to_string();
+    request_defaults.template = "%k=%v".to_string();
+    request_defaults.joiner = "&".to_string();
+    request_defaults
+}
+
+fn chunk(size: usize) -> Arc<[String]> {
+    (0..size).map(|i| format!("param{}", i)).collect()
+}
+
+fn construction(c: &mut Criterion) {
+    let request_defaults = request_defaults();
+    let mut group = c.benchmark_group("request");
+
+    for size in [128, 1024, 8192] {
+        let params = chunk(size);
+
+        group.bench_with_input(BenchmarkId::new("new", size), &params, |b, params| {
+            b.iter(|| black_box(Request::new(&request_defaults, Arc::clone(params))))
+        });
+
+        group.bench_with_input(BenchmarkId::new("new_random", size), &size, |b, size| {
+            b.iter(|| black_box(Request::new_random(&request_defaults, *size)))
+        });
+
+        group.bench_with_input(BenchmarkId::new("prepare", size), &params, |b, params| {
+            b.iter(|| {
+                let mut request = Request::new(&request_defaults, Arc::clone(params));
+                request.prepare();
+                black_box(request.make_query())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// the whole chunk is split in halves until every parameter is checked on its own,
+/// so the amount of requests is close to 2 * size
+fn bisection(c: &mut Criterion) {
+    let request_defaults = request_defaults();
+    let params = chunk(1024);
+
+    c.bench_function("request/bisection/1024", |b| {
+        b.iter(|| {
+            let mut queue = vec![Arc::clone(&params)];
+
+            while let Some(params) = queue.pop() {
+                black_box(Request::new(&request_defaults, Arc::clone(&params)));
+
+                if params.len() > 1 {
+                    let (first, second) = params.split_at(params.len() / 2);
+                    queue.push(first.into());
+                    queue.push(second.into());
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, construction, bisection);
+criterion_main!(benches);
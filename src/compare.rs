@@ -0,0 +1,134 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    replay::{parse_results, SavedOutput},
+    runner::utils::FoundParameter,
+};
+
+/// a found parameter together with its target
+#[derive(Debug, Serialize)]
+struct Finding<'a> {
+    method: &'a str,
+    url: &'a str,
+
+    #[serde(flatten)]
+    found_param: &'a FoundParameter,
+}
+
+/// compares two result files and prints appeared, disappeared and changed parameters.
+/// parameters are matched by method, url and name, and are considered changed
+/// in case their reason kind, value or response code differ
+pub fn compare(old: &str, new: &str, output_format: &str) -> Result<(), Box<dyn Error>> {
+    let (old, new) = (
+        parse_results(&std::fs::read_to_string(old)?)?,
+        parse_results(&std::fs::read_to_string(new)?)?,
+    );
+
+    let (old, new) = (findings(&old), findings(&new));
+
+    let appeared: Vec<&Finding> = new.iter().filter(|x| find(&old, x).is_none()).collect();
+    let disappeared: Vec<&Finding> = old.iter().filter(|x| find(&new, x).is_none()).collect();
+
+    let changed: Vec<(&Finding, &Finding)> = old
+        .iter()
+        .filter_map(|x| find(&new, x).map(|y| (x, y)))
+        .filter(|(x, y)| {
+            x.found_param.reason_kind != y.found_param.reason_kind
+                || x.found_param.value != y.found_param.value
+                || x.found_param.status != y.found_param.status
+        })
+        .collect();
+
+    let output = if output_format == "json" {
+        json!({
+            "appeared": appeared,
+            "disappeared": disappeared,
+            "changed": changed
+                .iter()
+                .map(|(old, new)| json!({
+                    "method": new.method,
+                    "url": new.url,
+                    "name": new.found_param.name,
+                    "old": old.found_param,
+                    "new": new.found_param,
+                }))
+                .collect::<Vec<serde_json::Value>>(),
+        })
+        .to_string()
+    } else {
+        let mut lines = Vec::new();
+
+        for finding in appeared.iter() {
+            lines.push(format!(
+                "{} {} {} % {}",
+                "+".green(),
+                finding.method.blue(),
+                finding.url,
+                finding.found_param.get_colored()
+            ));
+        }
+
+        for finding in disappeared.iter() {
+            lines.push(format!(
+                "{} {} {} % {}",
+                "-".red(),
+                finding.method.blue(),
+                finding.url,
+                finding.found_param.name
+            ));
+        }
+
+        for (old, new) in changed.iter() {
+            lines.push(format!(
+                "{} {} {} % {} ({:?} {} -> {:?} {})",
+                "~".yellow(),
+                new.method.blue(),
+                new.url,
+                new.found_param.get_colored(),
+                old.found_param.reason_kind,
+                old.found_param.status,
+                new.found_param.reason_kind,
+                new.found_param.status
+            ));
+        }
+
+        lines.push(format!(
+            "\n[#] {} appeared, {} disappeared, {} changed",
+            appeared.len(),
+            disappeared.len(),
+            changed.len()
+        ));
+
+        lines.join("\n")
+    };
+
+    writeln!(io::stdout(), "{}", output).ok();
+
+    Ok(())
+}
+
+fn findings(outputs: &[SavedOutput]) -> Vec<Finding<'_>> {
+    outputs
+        .iter()
+        .flat_map(|output| {
+            output.found_params.iter().map(move |found_param| Finding {
+                method: &output.method,
+                url: &output.url,
+                found_param,
+            })
+        })
+        .collect()
+}
+
+fn find<'a>(findings: &'a [Finding<'a>], finding: &Finding) -> Option<&'a Finding<'a>> {
+    findings.iter().find(|x| {
+        x.method == finding.method && x.url == finding.url && x.found_param.name == finding.found_param.name
+    })
+}
@@ -1,19 +1,109 @@
 use crate::{
     config::{
         structs::Config,
-        utils::{convert_to_string_if_some, parse_request},
+        utils::{convert_to_string_if_some, parse_har, parse_request},
     },
-    network::utils::{DataType, Headers},
+    network::{budget::Budget, burp::BurpExport, cache::ResponseCache, cassette::{Player, Recorder}, csrf::CsrfRule, fingerprints::Fingerprints, graphql::GraphQLMode, oob::Oob, proxy_pool::ProxyRotation, rate_limiter::{Gate, RateLimiter}, redirect::{RedirectPolicy, Redirects}, retry::RetryPolicy, matchers::{Matchers, NumberSet}, mutator::Mutator, normalize::Normalization, script::Script, session::SessionRefresh, sigv4::SigV4, utils::{DataType, Headers}, values::ValueStrategies, webhook::Webhook},
+    runner::{
+        checkpoint::{Checkpoint, CheckpointStore},
+        utils::{UnstableCodeAction, UnstableCodeThresholds},
+        warmup::AutoTune,
+    },
+    database::is_database_file,
+    error::{categorize, X8Error},
+    metrics::Metrics,
+    utils::{interleave_hosts, read_stdin_lines, url_host},
 };
-use clap::{crate_version, App, AppSettings, Arg};
-use std::{collections::HashMap, error::Error, fs, io::{self, Write}};
-use tokio::time::Duration;
+use clap::{crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use regex::Regex;
+use std::{collections::HashMap, error::Error, fs, io::{self, Write}, net::SocketAddr, sync::Arc};
+use tokio::{sync::Semaphore, time::Duration};
 use url::Url;
 
-use super::utils::{read_urls_if_possible, mimic_browser_headers, add_default_headers};
+use super::file::with_config_file;
+use super::utils::{
+    read_urls_if_possible, mimic_browser_headers, add_default_headers, read_client_identity, parse_resolve,
+    interpolate_env, interpolate_env_headers,
+    DEFAULT_CUSTOM_PARAMETERS, DEFAULT_CUSTOM_VALUES,
+};
 
 pub fn get_config() -> Result<Config, Box<dyn Error>> {
-    let app = App::new("x8")
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+
+    config_from_matches(&with_config_file(get_app().get_matches(), &argv)?)
+}
+
+/// parses the config from command line arguments (without the program name).
+/// unlike get_config() it doesn't exit the process on incorrect arguments
+pub fn get_config_from(args: &[String]) -> Result<Config, Box<dyn Error>> {
+    let matches = get_app().get_matches_from_safe(std::iter::once("x8").chain(args.iter().map(|x| x.as_str())))?;
+
+    config_from_matches(&with_config_file(matches, args)?)
+}
+
+/// options that jobs of `x8 serve` and of distributed scans can use.
+/// the others read or write local files, run commands or read stdin, so they aren't accepted from remote
+const REMOTE_OPTIONS: &[&str] = &[
+    "url", "method", "headers", "invert", "headers-discovery", "cookies", "force", "disable-custom-parameters",
+    "disable-calibration", "parameter-template", "joiner", "body", "json-pointer", "xml-node", "xml-attributes",
+    "graphql", "data-type", "proxy", "burp-proxy", "pin-cookies", "csrf-from", "csrf-to", "csrf-url", "csrf-marker",
+    "seed", "resolve", "delay", "retries", "retry-backoff", "retry-jitter", "retry-codes", "rate-limit", "host-rate-limit",
+    "host-workers",
+    "cache-responses", "max-requests", "max-findings", "max-time", "remove-empty", "disable-trustdns",
+    "custom-parameters", "custom-values", "time-based", "time-sigma", "time-confirmations", "filter-regex",
+    "match-code", "match-size", "match-words", "match-lines", "match-regex", "filter-code", "filter-size",
+    "filter-words", "filter-lines", "isolate-server-errors", "normalize", "html-diff", "json-diff", "json-diff-ignore",
+    "json-diff-unordered", "similarity", "fuzz-values", "min-confidence", "block-pause", "block-rotate", "oob",
+    "oob-wait", "pollution", "array-syntax", "switch-content-type", "cache-poisoning", "value-strategy", "fuzz-header",
+    "follow-redirects", "max-redirects", "encode", "strict", "unstable-code-threshold", "unstable-code-action",
+    "verbose", "learn-requests-count", "recursion-depth", "max", "timeout", "concurrency", "adaptive-concurrency",
+    "auto-tune", "workers", "discover-methods", "scan-discovered-methods", "verify", "verify-attempts",
+    "reflected-only", "one-worker-per-host", "mimic-browser", "http", "http2", "http2-prior-knowledge", "http3",
+    "pool-max-idle", "pool-idle-timeout", "tcp-keepalive", "no-keepalive", "accept-encoding", "max-response-size",
+    "check-binary", "disable-colors", "disable-progress-bar", "remove-banner",
+];
+
+/// parses the arguments of a job received from remote. Only REMOTE_OPTIONS and the given local options are accepted,
+/// and urls have to be http(s) ones, because other values are read as filenames or stdin.
+/// ${NAME} isn't accepted either, because it would be replaced with the environment variable of this host
+pub fn get_remote_config_from(args: &[String], local_options: &[&str]) -> Result<Config, Box<dyn Error>> {
+    if args.iter().any(|x| x.contains("${")) {
+        Err("Remote jobs can't use ${NAME}, because environment variables of this host aren't shared")?;
+    }
+
+    let matches = get_app().get_matches_from_safe(std::iter::once("x8").chain(args.iter().map(|x| x.as_str())))?;
+
+    if matches.subcommand.is_some() {
+        Err("Subcommands can't be used within remote jobs")?;
+    }
+
+    // options with default values are within matches as well, so only the provided ones are checked
+    let mut forbidden: Vec<&str> = matches
+        .args
+        .keys()
+        .filter(|x| matches.occurrences_of(x) > 0 && !REMOTE_OPTIONS.contains(x) && !local_options.contains(x))
+        .copied()
+        .collect();
+
+    if !forbidden.is_empty() {
+        forbidden.sort_unstable();
+        Err(format!("These options can't be used within remote jobs: {}", forbidden.join(", ")))?;
+    }
+
+    if let Some(url) = matches
+        .values_of("url")
+        .into_iter()
+        .flatten()
+        .find(|x| !x.starts_with("http://") && !x.starts_with("https://"))
+    {
+        Err(format!("{} isn't an http(s) url. Remote jobs can't read urls from files or stdin", url))?;
+    }
+
+    config_from_matches(&matches)
+}
+
+pub fn get_app() -> App<'static, 'static> {
+    App::new("x8")
         .setting(AppSettings::ArgRequiredElseHelp)
         .version(crate_version!())
         .author("sh1yo <sh1yo@tuta.io>")
@@ -21,11 +111,24 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
         .arg(Arg::with_name("url")
             .short("u")
             .long("url")
-            .help("You can add a custom injection point with %s.\nMultiple urls and filenames are supported:\n-u filename.txt\n-u https://url1 http://url2")
+            .help("You can add a custom injection point with %s.\nMultiple urls and filenames are supported:\n-u filename.txt\n-u https://url1 http://url2\n-u - (read urls from stdin, requires -w)")
             .takes_value(true)
             .min_values(1)
             .conflicts_with("request")
         )
+        .arg(Arg::with_name("config")
+            .long("config")
+            .value_name("file")
+            .help("Take options from the toml file. Options from the command line override the ones from the file")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("profile")
+            .long("profile")
+            .value_name("name")
+            .help("Use the options of the [profile.<name>] table from the config file on top of the top level ones")
+            .takes_value(true)
+            .requires("config")
+        )
         .arg(Arg::with_name("request")
             .short("r")
             .long("request")
@@ -33,6 +136,12 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
             .takes_value(true)
             .conflicts_with("url")
         )
+        .arg(Arg::with_name("har")
+            .long("har")
+            .help("The HAR file with requests to check. Every request keeps its method, headers and body")
+            .takes_value(true)
+            .conflicts_with_all(&["url", "request"])
+        )
         .arg(Arg::with_name("proto")
             .long("proto")
             .help("Protocol to use with request file (default is \"https\")")
@@ -63,6 +172,24 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
                 .default_value("")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("mutations")
+                .long("mutations")
+                .help("Add case and affix variants of parameters to the wordlist: userId, user_id, user-id, debug_user_id, ..\nFor headers: forwarded_host -> X-Forwarded-Host, Forwarded-Host")
+        )
+        .arg(
+            Arg::with_name("scrape-js")
+                .long("scrape-js")
+                .help("Fetch scripts linked from the page and check identifiers from the page and the scripts before the wordlist")
+        )
+        .arg(
+            Arg::with_name("wordlist-batch")
+                .long("wordlist-batch")
+                .value_name("size")
+                .help("Read the wordlist lazily and check it in batches of <size> unique parameters.\nUseful for huge wordlists that don't fit into memory")
+                .takes_value(true)
+                .conflicts_with_all(&["checkpoint", "resume"])
+        )
         .arg(
             Arg::with_name("parameter-template")
                 .short("P")
@@ -85,11 +212,41 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
                 .value_name("body")
                 .conflicts_with("request")
         )
+        .arg(
+            Arg::with_name("json-pointer")
+                .long("json-pointer")
+                .help("Insert parameters as keys of the nested json object. Example: --json-pointer /data/filters")
+                .value_name("pointer")
+                .takes_value(true)
+                .conflicts_with_all(&["headers-discovery", "cookies"])
+        )
+        .arg(
+            Arg::with_name("xml-node")
+                .long("xml-node")
+                .help("Insert parameters as child elements of the xml node. Example: --xml-node soap:Body")
+                .value_name("node")
+                .takes_value(true)
+                .conflicts_with_all(&["headers-discovery", "cookies", "json-pointer"])
+        )
+        .arg(
+            Arg::with_name("xml-attributes")
+                .long("xml-attributes")
+                .help("Insert parameters as attributes of the xml node (the root element or --xml-node)")
+                .conflicts_with_all(&["headers-discovery", "cookies", "json-pointer"])
+        )
+        .arg(
+            Arg::with_name("graphql")
+                .long("graphql")
+                .help("Discover GraphQL names: arguments, fields, variables\nExample: --graphql arguments -b '{\"query\":\"{user(%s){id}}\"}'")
+                .value_name("mode")
+                .takes_value(true)
+                .conflicts_with_all(&["headers-discovery", "cookies", "xml-node", "xml-attributes"])
+        )
         .arg(
             Arg::with_name("data-type")
                 .short("t")
                 .long("data-type")
-                .help("Available: urlencode, json\nCan be detected automatically if --body is specified (default is \"urlencode\")")
+                .help("Available: urlencode, json, multipart, xml\nCan be detected automatically if --body is specified (default is \"urlencode\")")
                 .value_name("data-type")
         )
         .arg(
@@ -97,8 +254,184 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
                 .short("x")
                 .long("proxy")
                 .value_name("proxy")
+                .help("Supported schemes: http, https, socks5, socks5h (dns resolution on the proxy side).\nhttp:// is used by default")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("proxy-file")
+                .long("proxy-file")
+                .value_name("file")
+                .help("The file with proxies to rotate between. Dead proxies are evicted automatically")
+                .takes_value(true)
+                .conflicts_with_all(&["proxy", "burp-proxy"])
+        )
+        .arg(
+            Arg::with_name("proxy-rotation")
+                .long("proxy-rotation")
+                .help("How to choose the next proxy from --proxy-file: round-robin (default), random")
+                .takes_value(true)
+                .requires("proxy-file")
+        )
+        .arg(
+            Arg::with_name("cookie-jar")
+                .long("cookie-jar")
+                .help("Apply cookies from Set-Cookie headers to every next request, including the ones from the Cookie header (session rotation, anti-bot cookies)")
+                .conflicts_with("pin-cookies")
+        )
+        .arg(
+            Arg::with_name("pin-cookies")
+                .long("pin-cookies")
+                .help("Send only the original cookies and ignore Set-Cookie headers")
+        )
+        .arg(
+            Arg::with_name("refresh-command")
+                .long("refresh-command")
+                .value_name("command")
+                .help("The shell command that outputs headers (Name: value) of the new session. It's executed when responses become 401/403 or redirect to the login page")
+                .takes_value(true)
+                .conflicts_with("refresh-request")
+        )
+        .arg(
+            Arg::with_name("refresh-request")
+                .long("refresh-request")
+                .value_name("file")
+                .help("The file with the raw http request (e.g. login) whose Set-Cookie headers refresh the session. It's sent when responses become 401/403 or redirect to the login page")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("csrf-from")
+                .long("csrf-from")
+                .value_name("regex|json pointer")
+                .help("Where to take the csrf token from. Either a regex (the first group is used) matched against the body and headers or a json pointer like /data/csrf")
+                .takes_value(true)
+                .requires("csrf-to")
+        )
+        .arg(
+            Arg::with_name("csrf-to")
+                .long("csrf-to")
+                .value_name("place:name")
+                .help("Where to insert the csrf token: header:X-CSRF-Token, query:csrf, body:csrf or cookie:csrf")
+                .takes_value(true)
+                .requires("csrf-from")
+        )
+        .arg(
+            Arg::with_name("csrf-url")
+                .long("csrf-url")
+                .value_name("url")
+                .help("The url to fetch the csrf token from. The target url without the query is used by default")
+                .takes_value(true)
+                .requires("csrf-from")
+        )
+        .arg(
+            Arg::with_name("csrf-marker")
+                .long("csrf-marker")
+                .value_name("regex")
+                .help("Responses that match the regex mean the csrf token was rejected")
+                .takes_value(true)
+                .requires("csrf-from")
+        )
+        .arg(
+            Arg::with_name("aws-sigv4")
+                .long("aws-sigv4")
+                .value_name("region:service")
+                .help("Sign every request with AWS Signature Version 4, e.g. us-east-1:execute-api. Credentials are taken from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN by default")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("aws-credentials")
+                .long("aws-credentials")
+                .value_name("access_key:secret_key[:session_token]")
+                .help("AWS credentials for --aws-sigv4")
+                .takes_value(true)
+                .requires("aws-sigv4")
+        )
+        .arg(
+            Arg::with_name("script")
+                .long("script")
+                .value_name("file")
+                .help("Rhai script with on_request, on_response and is_interesting hooks for custom signing, response normalization or detection")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("mutator-cmd")
+                .long("mutator-cmd")
+                .value_name("command")
+                .help("Pipe every request as json through the command and send the request it prints instead (custom signing, checksums, encryption)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("raw")
+                .long("raw")
+                .help("Write HTTP/1.1 requests directly to the socket, so headers are sent exactly as they are (unusual whitespaces, duplicated headers, obs-fold)")
+                .conflicts_with_all(&["proxy", "burp-proxy", "proxy-file", "cert", "http", "http2", "http2-prior-knowledge"])
+        )
+        .arg(
+            Arg::with_name("unix-socket")
+                .long("unix-socket")
+                .value_name("path")
+                .help("Send requests via the unix socket. The host and the path are taken from the url: -u http://localhost/api --unix-socket /var/run/app.sock")
+                .takes_value(true)
+                .conflicts_with_all(&["proxy", "burp-proxy", "proxy-file", "cert", "http", "http2", "http2-prior-knowledge", "resolve"])
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .value_name("cassette")
+                .help("Write every request and response to the cassette file, so the scan can be repeated offline with --replay")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("cassette")
+                .help("Answer requests with the responses from the cassette file instead of sending them to the target")
+                .takes_value(true)
+                .conflicts_with_all(&["record", "raw", "unix-socket"])
+        )
+        .arg(
+            Arg::with_name("notify-webhook")
+                .long("notify-webhook")
+                .value_name("url")
+                .help("POST found parameters and the summary of the scan to the webhook (Slack, Discord or any other url)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("num")
+                .help("Seed for random parameter names and values, so the scan can be repeated exactly")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Only learn how the targets behave and print the plan of the scan (requests, duration, stability) without checking parameters")
+                .conflicts_with_all(&["fuzz-header", "pollution"])
+        )
+        .arg(
+            Arg::with_name("resolve")
+                .long("resolve")
+                .value_name("host:port:ip")
+                .help("Connect to the ip instead of resolving the host. The original Host header and SNI are kept. Can be used multiple times")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("cert")
+                .long("cert")
+                .value_name("file")
+                .help("PEM encoded client certificate for mutual TLS. Can contain the private key as well")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("key")
+                .long("key")
+                .value_name("file")
+                .help("PEM encoded private key for the client certificate")
+                .takes_value(true)
+                .requires("cert")
+        )
         .arg(
             Arg::with_name("burp-proxy")
                 .short("B")
@@ -113,6 +446,95 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
                 .default_value("0")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("retries")
+                .long("retries")
+                .value_name("uint")
+                .help("How many times to resend requests that failed because of network errors or --retry-codes")
+                .default_value("2")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("retry-backoff")
+                .long("retry-backoff")
+                .value_name("ms")
+                .help("The delay before the first retry in milliseconds. It's doubled with every next retry")
+                .default_value("1000")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("retry-jitter")
+                .long("retry-jitter")
+                .value_name("ms")
+                .help("The upper bound for the random delay in milliseconds added to every retry")
+                .default_value("500")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("retry-codes")
+                .long("retry-codes")
+                .value_name("codes")
+                .help("Response codes that cause the request to be resent. Codes returned by the initial request are ignored")
+                .default_value("502,503,504")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("rate-limit")
+                .long("rate-limit")
+                .value_name("rps")
+                .help("The maximum number of requests per second across all the urls and concurrent requests")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("host-rate-limit")
+                .long("host-rate-limit")
+                .value_name("rps")
+                .help("The maximum number of requests per second to every host")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("host-workers")
+                .long("host-workers")
+                .value_name("num")
+                .help("The maximum number of concurrently checked urls of every host.\nUrls of different hosts are interleaved so workers aren't stuck on a single host")
+                .takes_value(true)
+                .conflicts_with("one-worker-per-host")
+        )
+        .arg(
+            Arg::with_name("cache-responses")
+                .long("cache-responses")
+                .help("Do not resend requests that were already sent during the run (with the same parameters and values)")
+                .conflicts_with("time-based")
+        )
+        .arg(
+            Arg::with_name("max-requests")
+                .long("max-requests")
+                .alias("budget")
+                .value_name("num")
+                .help("Stop the scan after <num> requests and output the results collected so far.\nEvery request to the target is counted: learning and random requests, retries, redirects and verification")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("max-findings")
+                .long("max-findings")
+                .value_name("num")
+                .help("Stop the scan after <num> found parameters and output the results collected so far")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .value_name("address")
+                .help("Serve Prometheus metrics (requests, rps, errors, findings, queue depth) on the address, e.g. 127.0.0.1:9090")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("max-time")
+                .long("max-time")
+                .value_name("secs")
+                .help("Stop the scan after <secs> seconds and output the results collected so far")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -124,10 +546,48 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
             Arg::with_name("output-format")
                 .short("O")
                 .long("output-format")
-                .help("standart, json, url, request")
+                .help("standart, json, json-by-target, jsonl, sarif, junit, url, request")
                 .default_value("standart")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("format")
+                .help("Write a standalone report with the requests, responses and diffs of every finding.\nOnly html is supported")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("report-file")
+                .long("report-file")
+                .value_name("file")
+                .help("Where to write the report")
+                .default_value("report.html")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .value_name("file")
+                .help("Periodically save the progress to the file so that the scan can be continued with --resume")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .value_name("file")
+                .help("Continue the interrupted scan from the checkpoint file.\nThe progress is saved to the same file unless --checkpoint is specified")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("porcelain")
+                .long("porcelain")
+                .value_name("curl")
+                .help("Print only a ready-to-run curl command for every found parameter")
+                .takes_value(true)
+                .possible_values(&["curl"])
+                .conflicts_with("output-format")
+        )
         .arg(
             Arg::with_name("append")
                 .long("append")
@@ -157,6 +617,11 @@ pub fn get_config() -> Result<Config, Box<dyn Error>> {
                 .min_values(1)
                 .conflicts_with("request")
         )
+        .arg(
+            Arg::with_name("require-env")
+                .long("require-env")
+                .help("Fail in case an environment variable used via ${NAME} within headers or the body isn't set. Otherwise, ${NAME} is sent as it is")
+        )
         .arg(
             Arg::with_name("invert")
                 .long("invert")
@@ -181,6 +646,11 @@ It's possible to overwrite this behavior by specifying the option")
                 .long("disable-custom-parameters")
                 .help("Do not automatically check parameters like admin=true")
         )
+        .arg(
+            Arg::with_name("disable-calibration")
+                .long("disable-calibration")
+                .help("Do not learn how the page reacts to nonexistent parameters")
+        )
         .arg(
             Arg::with_name("disable-colors")
                 .long("disable-colors")
@@ -198,6 +668,15 @@ It's possible to overwrite this behavior by specifying the option")
             Arg::with_name("disable-progress-bar")
                 .long("disable-progress-bar")
         )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .value_name("format")
+                .help("How to show the progress. json writes a progress event per second to stderr instead of progress bars")
+                .takes_value(true)
+                .possible_values(&["bar", "json"])
+                .default_value("bar")
+        )
         .arg(
             Arg::with_name("progress-bar-len")
                 .long("progress-bar-len")
@@ -231,11 +710,279 @@ It's possible to overwrite this behavior by specifying the option")
                 .min_values(1)
                 .conflicts_with("disable-custom-parameters")
         )
+        .arg(
+            Arg::with_name("time-based")
+                .long("time-based")
+                .help("Flag parameters that consistently increase the response time")
+        )
+        .arg(
+            Arg::with_name("time-sigma")
+                .long("time-sigma")
+                .value_name("float")
+                .help("How many standard deviations from the baseline response time are considered as a time shift (the shift is at least 250ms)")
+                .default_value("3")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("time-confirmations")
+                .long("time-confirmations")
+                .value_name("uint")
+                .help("How many times the time shift needs to be reproduced")
+                .default_value("3")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("filter-regex")
+                .long("filter-regex")
+                .value_name("regex")
+                .help("Remove matched substrings (csrf tokens, nonces, timestamps) from responses before comparing. Can be repeated\nExample: --filter-regex 'csrf_token\" value=\"[^\"]+'")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("match-code")
+                .long("match-code")
+                .value_name("codes")
+                .help("Consider only responses with the codes as changed. Example: 200,300-399")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("match-size")
+                .long("match-size")
+                .value_name("sizes")
+                .help("Consider only responses with the body sizes in bytes as changed. Example: 0,1000-2000")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("match-words")
+                .long("match-words")
+                .value_name("words")
+                .help("Consider only responses with the amounts of words as changed. Example: 1-50")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("match-lines")
+                .long("match-lines")
+                .value_name("lines")
+                .help("Consider only responses with the amounts of lines as changed. Example: 1,10-20")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("match-regex")
+                .long("match-regex")
+                .value_name("regex")
+                .help("Consider only responses whose headers or body match the regex as changed")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("filter-code")
+                .long("filter-code")
+                .value_name("codes")
+                .help("Ignore responses with the codes as changed. Example: 403,429")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("filter-size")
+                .long("filter-size")
+                .value_name("sizes")
+                .help("Ignore responses with the body sizes in bytes as changed. Example: 0,1000-2000")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("filter-words")
+                .long("filter-words")
+                .value_name("words")
+                .help("Ignore responses with the amounts of words as changed. Example: 1-50")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("filter-lines")
+                .long("filter-lines")
+                .value_name("lines")
+                .help("Ignore responses with the amounts of lines as changed. Example: 1,10-20")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("error-signatures")
+                .long("error-signatures")
+                .value_name("file")
+                .help("File with additional technology:regex error signatures to look for within responses of found parameters")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("isolate-server-errors")
+                .long("isolate-server-errors")
+                .help("Always search for parameters that cause 5xx codes, even with --reflected-only or --match-*/--filter-* options")
+        )
+        .arg(
+            Arg::with_name("normalize")
+                .long("normalize")
+                .value_name("steps")
+                .help("Normalize responses before comparing. Steps: comments (remove html comments), entities (decode html entities), whitespace (collapse whitespaces), lowercase\nExample: --normalize comments,whitespace")
+                .takes_value(true)
+                .use_delimiter(true)
+                .possible_values(&["comments", "entities", "whitespace", "lowercase"])
+        )
+        .arg(
+            Arg::with_name("filter-file")
+                .long("filter-file")
+                .value_name("file")
+                .help("The file with regexes to remove from responses before comparing. One regex per line")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("html-diff")
+                .long("html-diff")
+                .help("Compare html responses by their elements and attributes (in any order) instead of their formatting")
+        )
+        .arg(
+            Arg::with_name("json-diff")
+                .long("json-diff")
+                .help("Compare json responses structurally (added/removed/changed keys) instead of line by line")
+        )
+        .arg(
+            Arg::with_name("json-diff-ignore")
+                .long("json-diff-ignore")
+                .value_name("pointers")
+                .help("Json pointers to ignore while comparing json responses. Implies --json-diff\nExample: --json-diff-ignore /meta/timestamp /items/*/id")
+                .takes_value(true)
+                .min_values(1)
+        )
+        .arg(
+            Arg::with_name("json-diff-unordered")
+                .long("json-diff-unordered")
+                .help("Ignore the order of elements within json arrays. Implies --json-diff")
+        )
+        .arg(
+            Arg::with_name("similarity")
+                .long("similarity")
+                .value_name("ratio")
+                .help("Consider bodies with the token-level similarity ratio above the threshold the same (from 0 to 1, e.g. 0.98)")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("fuzz-values")
+                .long("fuzz-values")
+                .help("Fuzz values of found parameters and report values that change the response\n(default values are \"true false 1 0 -1 yes no on off null admin debug test root dev en de fr ru zh\")")
+        )
+        .arg(
+            Arg::with_name("value-wordlist")
+                .long("value-wordlist")
+                .value_name("file")
+                .help("The file with values for fuzzing values of found parameters. Implies --fuzz-values")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("min-confidence")
+                .long("min-confidence")
+                .value_name("0-100")
+                .help("Report only parameters with the same or higher confidence")
+                .default_value("0")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("block-pause")
+                .long("block-pause")
+                .value_name("secs")
+                .help("Detect WAF, anti-bot and ban pages and pause requests for <secs> when the target responds with them")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("block-rotate")
+                .long("block-rotate")
+                .help("Switch the User-Agent after blocks. Blocked proxies from --proxy-file are rotated regardless of the option")
+                .requires("block-pause")
+        )
+        .arg(
+            Arg::with_name("oob")
+                .long("oob")
+                .value_name("domain")
+                .help("Send parameters with unique http://<id>.<domain>/ values and report the ones that cause dns or http interactions.\nThe domain should be controlled by an interaction provider like interactsh")
+                .takes_value(true)
+                .requires("oob-poll")
+        )
+        .arg(
+            Arg::with_name("oob-poll")
+                .long("oob-poll")
+                .value_name("file/url")
+                .help("The log of received interactions (e.g. the output of interactsh-client -o <file>) to search the ids in")
+                .takes_value(true)
+                .requires("oob")
+        )
+        .arg(
+            Arg::with_name("oob-wait")
+                .long("oob-wait")
+                .value_name("secs")
+                .help("How long to wait for interactions after the requests are sent")
+                .default_value("5")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("pollution")
+                .long("pollution")
+                .help("Resend parameters of the original request with different values, duplicated and moved between the query and the body\ninstead of discovering parameters. Reports the ways of duplication that change the response")
+                .conflicts_with_all(&["fuzz-header", "headers-discovery", "cookies"])
+        )
+        .arg(
+            Arg::with_name("array-syntax")
+                .long("array-syntax")
+                .help("Check parameters that weren't found once more as name[]=x, name[0]=x and name[key]=x")
+                .conflicts_with_all(&["headers-discovery", "cookies"])
+        )
+        .arg(
+            Arg::with_name("switch-content-type")
+                .long("switch-content-type")
+                .help("Resend found parameters within the query, urlencoded, json and multipart bodies and report the encodings the parser accepts")
+                .conflicts_with_all(&["headers-discovery", "cookies"])
+        )
+        .arg(
+            Arg::with_name("cache-poisoning")
+                .long("cache-poisoning")
+                .help("Check whether found headers change cached responses of requests without them (unkeyed inputs). Implies --headers")
+        )
+        .arg(
+            Arg::with_name("pairs")
+                .long("pairs")
+                .value_name("file")
+                .help("The file with name=value pairs that are sent with exactly these values after the wordlist.\nFound pairs are reported separately")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("value-strategy")
+                .long("value-strategy")
+                .value_name("strategies")
+                .help("How to generate values of parameters: random (default), numeric, boolean, uuid, fixed:<value>, file:<path>.\nname=<strategy> overrides the strategy for the parameter.\nExample: --value-strategy numeric debug=boolean token=fixed:abc")
+                .takes_value(true)
+                .min_values(1)
+        )
+        .arg(
+            Arg::with_name("fuzz-header")
+                .long("fuzz-header")
+                .value_name("names")
+                .help("Fuzz values of the headers with values from --value-wordlist instead of discovering parameters.\nExample: --fuzz-header X-Forwarded-For Accept-Language X-Original-URL")
+                .takes_value(true)
+                .min_values(1)
+                .conflicts_with("cookies")
+        )
         .arg(
             Arg::with_name("follow-redirects")
                 .long("follow-redirects")
                 .short("L")
-                .help("Follow redirections")
+                .value_name("policy")
+                .help("Follow redirections and compare the final responses. -L without the policy follows all of them")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .possible_values(&["none", "same-host", "all"])
+        )
+        .arg(
+            Arg::with_name("max-redirects")
+                .long("max-redirects")
+                .value_name("hops")
+                .help("The maximum amount of followed redirects per request")
+                .default_value("10")
+                .takes_value(true)
         )
         .arg(
             Arg::with_name("encode")
@@ -247,6 +994,22 @@ It's possible to overwrite this behavior by specifying the option")
                 .long("strict")
                 .help("Only report parameters that have changed the different parts of a page")
         )
+        .arg(
+            Arg::with_name("unstable-code-threshold")
+                .long("unstable-code-threshold")
+                .value_name("thresholds")
+                .help("How many times the page can return an unexpected code before it's considered unstable [default: 50].\ncode=<num> overrides the threshold for the code.\nExample: --unstable-code-threshold 100 429=5 403=20")
+                .takes_value(true)
+                .min_values(1)
+        )
+        .arg(
+            Arg::with_name("unstable-code-action")
+                .long("unstable-code-action")
+                .value_name("action")
+                .help("What to do when the page becomes unstable: abort (skip the rest of the parameters), pause[:<secs>] (pause requests and recheck, 60 secs by default) or ignore")
+                .default_value("abort")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("test")
                 .long("test")
@@ -263,7 +1026,22 @@ It's possible to overwrite this behavior by specifying the option")
         .arg(
             Arg::with_name("save-responses")
                 .long("save-responses")
-                .help("Save request and response to a directory when a parameter is found")
+                .value_name("dir")
+                .help("Save the request, the response and the details of every finding to <dir>/<host>/<method>/<parameter>-<nonce>/")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("burp-export")
+                .long("burp-export")
+                .value_name("file")
+                .help("Write the requests and responses of the findings to <file> as Burp Suite items xml")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("nuclei-templates")
+                .long("nuclei-templates")
+                .value_name("dir")
+                .help("Write a nuclei template for every found parameter to <dir>, so findings can be re-verified with nuclei")
                 .takes_value(true)
         )
         .arg(
@@ -302,6 +1080,22 @@ Conflicts with --verify for now.")
                 .default_value("1")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("adaptive-concurrency")
+                .long("adaptive-concurrency")
+                .value_name("max")
+                .help("Adjust the number of concurrent requests per url automatically between 1 and <max>.\nStarts with the -c value, backs off on timeouts, 429 and 5xx bursts")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("auto-tune")
+                .long("auto-tune")
+                .value_name("requests")
+                .help("Spend up to <requests> warm-up requests on measuring the target and choose --max and -c automatically.\nValues set via --max, -c or --adaptive-concurrency are kept (default requests: 64)")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+        )
         .arg(
             Arg::with_name("workers")
                 .short("W")
@@ -310,11 +1104,29 @@ Conflicts with --verify for now.")
                 .default_value("1")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("discover-methods")
+                .long("discover-methods")
+                .help("Probe urls with alternative methods and method override headers before the discovery")
+        )
+        .arg(
+            Arg::with_name("scan-discovered-methods")
+                .long("scan-discovered-methods")
+                .help("Search for parameters with every method that caused a different response as well")
+                .requires("discover-methods")
+        )
         .arg(
             Arg::with_name("verify")
                 .long("verify")
                 .help("Verify found parameters.")
         )
+        .arg(
+            Arg::with_name("verify-attempts")
+                .long("verify-attempts")
+                .help("Resend every found parameter separately N times during the verification.\nParameters whose change doesn't reproduce every time are dropped.\nImplies --verify")
+                .takes_value(true)
+                .conflicts_with("recursion-depth")
+        )
         .arg(
             Arg::with_name("reflected-only")
                 .long("reflected-only")
@@ -336,6 +1148,58 @@ Conflicts with --verify for now.")
                 .long("http")
                 .help("HTTP version. Supported versions: --http 1.1, --http 2")
                 .takes_value(true)
+                .conflicts_with_all(&["http2", "http2-prior-knowledge"])
+        ).arg(
+            Arg::with_name("http2")
+                .long("http2")
+                .help("Negotiate HTTP/2 via ALPN (HTTP/1.1 is used in case the server doesn't support HTTP/2)")
+                .conflicts_with("http2-prior-knowledge")
+        ).arg(
+            Arg::with_name("http2-prior-knowledge")
+                .long("http2-prior-knowledge")
+                .help("Send HTTP/2 requests without negotiating the protocol (the same as --http 2)")
+        ).arg(
+            Arg::with_name("http3")
+                .long("http3")
+                .help("Experimental. Send requests over QUIC and fall back to HTTP/2 or HTTP/1.1 in case the target doesn't support HTTP/3. Needs x8 to be built with the http3 feature")
+                .conflicts_with_all(&[
+                    "http", "http2", "http2-prior-knowledge", "proxy", "burp-proxy", "proxy-file", "raw", "unix-socket", "replay",
+                ])
+        ).arg(
+            Arg::with_name("pool-max-idle")
+                .long("pool-max-idle")
+                .value_name("num")
+                .help("The maximum amount of idle connections kept per host")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("pool-idle-timeout")
+                .long("pool-idle-timeout")
+                .value_name("seconds")
+                .help("Close idle connections after the timeout (90 by default)")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("tcp-keepalive")
+                .long("tcp-keepalive")
+                .value_name("seconds")
+                .help("Send TCP keepalive probes with the interval")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("no-keepalive")
+                .long("no-keepalive")
+                .help("Open a new connection for every request instead of reusing them")
+                .conflicts_with_all(&["pool-max-idle", "pool-idle-timeout"])
+        ).arg(
+            Arg::with_name("accept-encoding")
+                .long("accept-encoding")
+                .value_name("encodings")
+                .help("The Accept-Encoding header to advertise. Example: 'gzip, br, zstd'. An empty value removes the header")
+                .takes_value(true)
+        ).arg(
+            Arg::with_name("max-response-size")
+                .long("max-response-size")
+                .value_name("bytes")
+                .help("Stop reading response bodies after <bytes>. Truncated bodies are compared by their hash")
+                .takes_value(true)
         ).arg(
             Arg::with_name("check-binary")
                 .long("check-binary")
@@ -343,29 +1207,318 @@ Conflicts with --verify for now.")
         ).arg(
             Arg::with_name("cookies")
                 .long("cookies")
-                .help("Shortcut for adding injection point to cookies")
+                .help("Search for cookie names. Candidates are sent as name=value pairs within the Cookie header")
         ).arg(
             Arg::with_name("remove-banner")
                 .long("remove-banner")
                 .help("Do not print initial banner")
-        );
-
-    let args = app.clone().get_matches();
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Run x8 as a daemon with a REST API for submitting scan jobs")
+                .arg(
+                    Arg::with_name("listen")
+                        .long("listen")
+                        .value_name("address")
+                        .help("The address to listen on")
+                        .takes_value(true)
+                        .default_value("127.0.0.1:8008")
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .long("jobs")
+                        .value_name("num")
+                        .help("The amount of jobs to run at the same time")
+                        .takes_value(true)
+                        .default_value("1")
+                )
+                .arg(
+                    Arg::with_name("metrics")
+                        .long("metrics")
+                        .help("Serve Prometheus metrics of all the jobs on GET /metrics")
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .value_name("token")
+                        .help("The token clients send within the Authorization: Bearer header. A random one is generated and printed by default")
+                        .takes_value(true)
+                        .env("X8_TOKEN")
+                        .hide_env_values(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("coordinator")
+                .about("Split the wordlist into leases for remote workers and print their merged findings")
+                .arg(
+                    Arg::with_name("listen")
+                        .long("listen")
+                        .value_name("address")
+                        .help("The address to listen on")
+                        .takes_value(true)
+                        .default_value("127.0.0.1:8009")
+                )
+                .arg(
+                    Arg::with_name("lease-size")
+                        .long("lease-size")
+                        .value_name("num")
+                        .help("The amount of parameters per lease")
+                        .takes_value(true)
+                        .default_value("1024")
+                )
+                .arg(
+                    Arg::with_name("lease-timeout")
+                        .long("lease-timeout")
+                        .value_name("seconds")
+                        .help("Leases of workers that didn't report back within the timeout are handed out again")
+                        .takes_value(true)
+                        .default_value("300")
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .value_name("token")
+                        .help("The token workers send within the Authorization: Bearer header. A random one is generated and printed by default")
+                        .takes_value(true)
+                        .env("X8_TOKEN")
+                        .hide_env_values(true)
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .value_name("scan options")
+                        .help("Options of the scan, e.g. -- -u https://example.com/ -w params.txt")
+                        .required(true)
+                        .multiple(true)
+                        .last(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("worker")
+                .about("Check leases of the wordlist handed out by the coordinator")
+                .arg(
+                    Arg::with_name("coordinator")
+                        .value_name("address")
+                        .help("The address of the coordinator, e.g. 10.0.0.1:8009")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("name")
+                        .help("The name of the worker within the coordinator's messages")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("token")
+                        .long("token")
+                        .value_name("token")
+                        .help("The token printed by the coordinator")
+                        .takes_value(true)
+                        .required(true)
+                        .env("X8_TOKEN")
+                        .hide_env_values(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Resend found parameters from the json output and report which of them still reproduce")
+                .arg(
+                    Arg::with_name("results")
+                        .value_name("file")
+                        .help("The output of --output-format json, json-by-target or jsonl")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::with_name("replay-proxy")
+                        .long("replay-proxy")
+                        .value_name("proxy")
+                        .help("Send the requests via the proxy")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("args")
+                        .value_name("scan options")
+                        .help("Options of the original scan except --url and --method, e.g. -- -H \"Cookie: a=b\" -b '{\"a\":%s}'")
+                        .multiple(true)
+                        .last(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Show parameters that appeared, disappeared or changed between two result files")
+                .arg(
+                    Arg::with_name("old")
+                        .value_name("old")
+                        .help("The results of the previous run (--output-format json, json-by-target or jsonl)")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .value_name("new")
+                        .help("The results of the new run")
+                        .required(true)
+                        .index(2)
+                )
+                .arg(
+                    Arg::with_name("output-format")
+                        .short("O")
+                        .long("output-format")
+                        .help("standart, json")
+                        .default_value("standart")
+                        .takes_value(true)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("Print results stored with --output <file>.db")
+                .arg(
+                    Arg::with_name("database")
+                        .value_name("database")
+                        .help("The sqlite database written by --output")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::with_name("table")
+                        .value_name("table")
+                        .help("runs, targets, findings or params")
+                        .default_value("findings")
+                        .index(2)
+                )
+                .arg(
+                    Arg::with_name("run")
+                        .long("run")
+                        .value_name("id")
+                        .help("Only the run with the id. Use last for the latest run")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("url")
+                        .long("url")
+                        .value_name("part")
+                        .help("Only the targets whose url contains <part>")
+                        .takes_value(true)
+                )
+                .arg(
+                    Arg::with_name("reason")
+                        .long("reason")
+                        .value_name("kind")
+                        .help("Only the findings with the reason kind, e.g. code, text, reflected")
+                        .takes_value(true)
+                )
+        )
+}
 
-    if args.value_of("url").is_none() && args.value_of("request").is_none() {
+pub fn config_from_matches(args: &ArgMatches) -> Result<Config, Box<dyn Error>> {
+    if args.value_of("url").is_none() && args.value_of("request").is_none() && args.value_of("har").is_none() {
         Err("A target was not provided")?;
     }
 
     // parse numbers
     let delay = Duration::from_millis(args.value_of("delay").unwrap().parse()?);
 
+    let rate_limiter = match args.value_of("rate-limit") {
+        Some(val) => {
+            let rps: f64 = val.parse()?;
+            if rps <= 0. {
+                Err("--rate-limit should be greater than 0")?;
+            }
+            Some(Arc::new(RateLimiter::new(rps)))
+        }
+        None => None,
+    };
+
+    let budget = Arc::new(Budget::new(
+        args.value_of("max-requests").map(|x| x.parse()).transpose()?,
+        args.value_of("max-findings").map(|x| x.parse()).transpose()?,
+        args.value_of("max-time").map(|x| x.parse().map(Duration::from_secs)).transpose()?,
+    ));
+
+    let retry_policy = RetryPolicy {
+        retries: args.value_of("retries").unwrap().parse()?,
+        backoff: Duration::from_millis(args.value_of("retry-backoff").unwrap().parse()?),
+        jitter: Duration::from_millis(args.value_of("retry-jitter").unwrap().parse()?),
+        codes: args
+            .value_of("retry-codes")
+            .unwrap()
+            .split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse())
+            .collect::<Result<Vec<u16>, _>>()?,
+    };
+
     let learn_requests_count = args.value_of("learn-requests-count").unwrap().parse()?;
     let concurrency = args.value_of("concurrency").unwrap().parse()?;
     let workers = args.value_of("workers").unwrap().parse()?;
-    let verbose = args.value_of("verbose").unwrap().parse()?;
+    // porcelain output contains nothing besides the results
+    let porcelain = args.value_of("porcelain").unwrap_or("").to_string();
+    let verbose = if porcelain.is_empty() { args.value_of("verbose").unwrap().parse()? } else { 0 };
     let timeout = args.value_of("timeout").unwrap().parse()?;
+    let pool_max_idle: Option<usize> = match args.value_of("pool-max-idle") {
+        Some(val) => Some(val.parse()?),
+        None => None,
+    };
+    let pool_idle_timeout: Option<Duration> = match args.value_of("pool-idle-timeout") {
+        Some(val) => Some(Duration::from_secs(val.parse()?)),
+        None => None,
+    };
+    let tcp_keepalive: Option<Duration> = match args.value_of("tcp-keepalive") {
+        Some(val) => Some(Duration::from_secs(val.parse()?)),
+        None => None,
+    };
     let recursion_depth = args.value_of("recursion-depth").unwrap_or("0").parse()?;
     let progress_bar_len = args.value_of("progress-bar-len").unwrap().parse()?;
+    let verify_attempts: usize = args.value_of("verify-attempts").unwrap_or("1").parse()?;
+
+    if verify_attempts == 0 {
+        Err("--verify-attempts should be greater than 0")?;
+    }
+
+    let similarity: Option<f64> = match args.value_of("similarity") {
+        Some(val) => Some(val.parse()?),
+        None => None,
+    };
+
+    if similarity.is_some_and(|x| !(0. ..=1.).contains(&x)) {
+        Err("--similarity should be between 0 and 1")?;
+    }
+
+    let json_diff_ignore: Vec<String> = args
+        .values_of("json-diff-ignore")
+        .map(|x| x.map(|x| x.trim_end_matches('/').to_string()).collect())
+        .unwrap_or_default();
+
+    // the root pointer would ignore the whole body
+    if let Some(pointer) = json_diff_ignore.iter().find(|x| !x.starts_with('/')) {
+        Err(format!(
+            "--json-diff-ignore: {:?} isn't a json pointer to a part of the body. Example: /meta/timestamp",
+            pointer
+        ))?;
+    }
+
+    let adaptive_concurrency: Option<usize> = if args.is_present("adaptive-concurrency") {
+        Some(args.value_of("adaptive-concurrency").unwrap().parse()?)
+    } else {
+        None
+    };
+
+    let wordlist_batch: usize = match args.value_of("wordlist-batch") {
+        Some(val) => {
+            if args.value_of("wordlist").unwrap_or("").is_empty() {
+                Err("--wordlist-batch requires a wordlist file (-w)")?;
+            }
+
+            match val.parse()? {
+                0 => Err("--wordlist-batch should be greater than 0")?,
+                val => val,
+            }
+        }
+        None => 0,
+    };
 
     let max: Option<usize> = if args.is_present("max") {
         Some(args.value_of("max").unwrap().parse()?)
@@ -373,24 +1526,119 @@ Conflicts with --verify for now.")
         None
     };
 
+    let auto_tune = if args.is_present("auto-tune") {
+        Some(AutoTune {
+            requests: args.value_of("auto-tune").unwrap_or("64").parse()?,
+            max: max.is_none(),
+            concurrency: args.occurrences_of("concurrency") == 0 && adaptive_concurrency.is_none(),
+        })
+    } else {
+        None
+    };
+
     if workers == 1 && args.is_present("one-worker-per-host") && !args.is_present("force") {
         Err("The --one-worker-per-host option doesn't increase the amount of workers. \
 So there's no point in --one-worker-per-host with 1 worker. \
 Increase the amount of workers to remove the error or use --force.")?;
     }
 
+    let checkpoint = match (args.value_of("resume"), args.value_of("checkpoint")) {
+        (Some(resume), checkpoint) => Some(Arc::new(CheckpointStore::new(
+            checkpoint.unwrap_or(resume),
+            Checkpoint::load(resume).map_err(|err| categorize(err, X8Error::Parse))?,
+        ))),
+        (None, Some(checkpoint)) => Some(Arc::new(CheckpointStore::new(
+            checkpoint,
+            Checkpoint::default(),
+        ))),
+        (None, None) => None,
+    };
+
     // try to read request file
     let request = match args.value_of("request") {
         Some(val) => fs::read_to_string(val)?,
         None => String::new(),
     };
 
+    let session_refresh = if let Some(command) = args.value_of("refresh-command") {
+        Some(SessionRefresh::Command(command.to_string()))
+    } else if let Some(file) = args.value_of("refresh-request") {
+        let scheme = args.value_of("proto").unwrap_or("https").replace("://", "");
+
+        let (methods, urls, headers, body, _, _) = parse_request(
+            &fs::read_to_string(file)?,
+            &scheme,
+            None,
+            None,
+            args.value_of("split-by"),
+        )
+        .map_err(|err| X8Error::Parse(format!("Unable to parse the refresh request: {}", err)))?;
+
+        Some(SessionRefresh::Request {
+            method: methods[0].to_owned(),
+            url: urls[0].to_owned(),
+            headers: interpolate_env_headers(headers, args.is_present("require-env"))?,
+            body: interpolate_env(&body, args.is_present("require-env"))?,
+        })
+    } else {
+        None
+    };
+
+    let csrf = match (args.value_of("csrf-from"), args.value_of("csrf-to")) {
+        (Some(from), Some(to)) => Some(CsrfRule::new(
+            from,
+            to,
+            convert_to_string_if_some(args.value_of("csrf-url")),
+            args.value_of("csrf-marker"),
+        )?),
+        _ => None,
+    };
+
+    let sigv4 = match args.value_of("aws-sigv4") {
+        Some(scope) => Some(SigV4::new(scope, args.value_of("aws-credentials"))?),
+        None => None,
+    };
+
+    let min_confidence: u8 = args.value_of("min-confidence").unwrap().parse()?;
+    if min_confidence > 100 {
+        Err("--min-confidence should be between 0 and 100")?;
+    }
+
+    let block_pause = match args.value_of("block-pause").map(|x| x.parse()).transpose()? {
+        Some(0) => Err("--block-pause should be greater than 0")?,
+        secs => secs.map(Duration::from_secs),
+    };
+
+    let oob = match args.value_of("oob") {
+        Some(val) => Some(Oob::new(
+            val,
+            args.value_of("oob-poll").unwrap(),
+            Duration::from_secs(args.value_of("oob-wait").unwrap().parse()?),
+        )),
+        None => None,
+    };
+
+    let script = match args.value_of("script") {
+        Some(val) => Some(Arc::new(Script::new(&fs::read_to_string(val)?)?)),
+        None => None,
+    };
+
+    // try to read HAR file
+    let har_entries = match args.value_of("har") {
+        Some(val) => parse_har(&fs::read_to_string(val)?).map_err(|err| categorize(err, X8Error::Parse))?,
+        None => Vec::new(),
+    };
+
     let data_type  = match args.value_of("data-type") { 
         Some(val) => {
             if val == "json" {
                 Some(DataType::Json)
             } else if val == "urlencoded" {
                 Some(DataType::Urlencoded)
+            } else if val == "multipart" {
+                Some(DataType::Multipart)
+            } else if val == "xml" {
+                Some(DataType::Xml)
             } else {
                 Err("Incorrect --data-type specified")?
             }
@@ -400,7 +1648,7 @@ Increase the amount of workers to remove the error or use --force.")?;
 
     // parse the default request information
     // either via the request file or via provided parameters
-    let (methods, urls, mut headers, body, data_type, http_version) = if !request.is_empty() {
+    let (methods, urls, headers, body, data_type, http_version) = if !request.is_empty() {
         // if the request file is specified - get protocol (https/http) from args, specify scheme and port, and parse request file
         let proto = args
             .value_of("proto")
@@ -415,7 +1663,23 @@ Increase the amount of workers to remove the error or use --force.")?;
             None
         };
 
-        parse_request(&request, &scheme, port, data_type, args.value_of("split-by"))?
+        parse_request(&request, &scheme, port, data_type, args.value_of("split-by"))
+            .map_err(|err| categorize(err, X8Error::Parse))?
+    } else if !har_entries.is_empty() {
+        // headers and body are stored per every HAR entry and applied within RequestDefaults::from_config
+        let mut methods: Vec<String> = Vec::new();
+        let mut urls: Vec<String> = Vec::new();
+
+        for entry in har_entries.iter() {
+            if !methods.contains(&entry.method) {
+                methods.push(entry.method.clone());
+            }
+            if !urls.contains(&entry.url) {
+                urls.push(entry.url.clone());
+            }
+        }
+
+        (methods, urls, Vec::new(), String::new(), data_type, None)
     } else {
         // parse everything from user-supplied command line arguments
         let methods = if args.is_present("method") {
@@ -488,7 +1752,13 @@ Increase the amount of workers to remove the error or use --force.")?;
             .values_of("url")
             .unwrap();
 
-        let urls = if urls.len() == 1 && !urls.clone().any(|x| x.contains("://")) {
+        let urls = if urls.len() == 1 && urls.clone().next() == Some("-") {
+            // parameters can't be read from stdin at the same time
+            if !args.is_present("wordlist") {
+                Err("-u - requires a wordlist (-w) because stdin is used for urls")?;
+            }
+            read_stdin_lines().into_iter().filter(|x| !x.trim().is_empty()).collect()
+        } else if urls.len() == 1 && !urls.clone().any(|x| x.contains("://")) {
             // it can be a file
             match read_urls_if_possible(urls.clone().next().unwrap())? {
                 Some(urls) => urls,
@@ -521,33 +1791,184 @@ Increase the amount of workers to remove the error or use --force.")?;
         }
     };
 
+    // ${NAME} within header values (cookies included) and the body is replaced with the environment variable
+    let mut headers = interpolate_env_headers(headers, args.is_present("require-env"))?;
+    let body = interpolate_env(&body, args.is_present("require-env"))?;
+
+    let report = args.value_of("report").unwrap_or("").to_lowercase();
+    if !report.is_empty() && report != "html" {
+        Err("--report supports only html")?;
+    }
+
+    // one limiter and one semaphore per host are shared between all the urls of the host
+    let mut host_rate_limiters = HashMap::new();
+    if let Some(val) = args.value_of("host-rate-limit") {
+        let rps: f64 = val.parse()?;
+        if rps <= 0. {
+            Err("--host-rate-limit should be greater than 0")?;
+        }
+        for url in urls.iter() {
+            host_rate_limiters
+                .entry(url_host(url))
+                .or_insert_with(|| Arc::new(RateLimiter::new(rps)));
+        }
+    }
+
+    let mut host_semaphores = HashMap::new();
+    let urls = match args.value_of("host-workers") {
+        Some(val) => {
+            let host_workers: usize = val.parse()?;
+            if host_workers == 0 {
+                Err("--host-workers should be greater than 0")?;
+            }
+            for url in urls.iter() {
+                host_semaphores
+                    .entry(url_host(url))
+                    .or_insert_with(|| Arc::new(Semaphore::new(host_workers)));
+            }
+            interleave_hosts(&urls)
+        }
+        None => urls,
+    };
+
+    // --http2 and --http2-prior-knowledge have a priority against the request file's http version
+    let (http_version, http2_prior_knowledge) = if args.is_present("http2-prior-knowledge") {
+        (Some(http::Version::HTTP_2), true)
+    } else if args.is_present("http2") {
+        (Some(http::Version::HTTP_2), false)
+    } else {
+        (http_version, true)
+    };
+
+    if args.is_present("http3") && !cfg!(feature = "http3") {
+        Err("x8 was built without HTTP/3 support. Build it with RUSTFLAGS='--cfg reqwest_unstable' cargo build --release --features http3")?;
+    }
+
+    // the host header breaks the h2 lib, so the host is taken from the url instead
+    if http_version == Some(http::Version::HTTP_2) {
+        headers.retain(|(k, _)| !k.eq_ignore_ascii_case("host"));
+    }
+
+    // raw responses aren't decompressed
+    if args.is_present("raw") || args.is_present("unix-socket") {
+        headers.retain(|(k, _)| !k.eq_ignore_ascii_case("accept-encoding"));
+    }
+
+    // replaces the advertised encodings, even the ones from the request file. An empty value removes the header
+    if let Some(val) = args.value_of("accept-encoding") {
+        headers.retain(|(k, _)| !k.eq_ignore_ascii_case("accept-encoding"));
+        if !val.is_empty() {
+            headers.push(("Accept-Encoding".to_string(), val.to_string()));
+        }
+    }
+
     // generate custom param values like admin=true
     let custom_keys: Vec<String> = match args.values_of("custom-parameters") {
         Some(val) => val.map(|x| x.to_string()).collect(),
-        None => [
-            "admin",
-            "bot",
-            "captcha",
-            "debug",
-            "disable",
-            "encryption",
-            "env",
-            "show",
-            "sso",
-            "test",
-            "waf",
-        ]
-        .iter()
-        .map(|x| x.to_string())
-        .collect(),
+        None => DEFAULT_CUSTOM_PARAMETERS.iter().map(|x| x.to_string()).collect(),
     };
 
     let custom_values: Vec<String> = match args.values_of("custom-values") {
         Some(val) => val.map(|x| x.to_string()).collect(),
-        None => ["1", "0", "false", "off", "null", "true", "yes", "no"]
-            .iter()
+        None => DEFAULT_CUSTOM_VALUES.iter().map(|x| x.to_string()).collect(),
+    };
+
+    let mut filters: Vec<String> = args
+        .values_of("filter-regex")
+        .map(|x| x.map(|x| x.to_string()).collect())
+        .unwrap_or_default();
+
+    if let Some(val) = args.value_of("filter-file") {
+        filters.extend(
+            fs::read_to_string(val)?
+                .lines()
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string()),
+        );
+    }
+
+    let filters = filters
+        .iter()
+        .map(|x| Regex::new(x).map_err(|err| format!("Unable to parse the filter regex {}: {}", x, err)))
+        .collect::<Result<Vec<Regex>, String>>()?;
+
+    let normalization = Normalization::new(
+        &args.values_of("normalize").map(|x| x.collect::<Vec<&str>>()).unwrap_or_default(),
+        filters,
+    )?;
+
+    let number_set = |name: &str| -> Result<NumberSet, Box<dyn Error>> {
+        match args.value_of(name) {
+            Some(val) => Ok(NumberSet::parse(val).map_err(|err| format!("Unable to parse --{}: {}", name, err))?),
+            None => Ok(NumberSet::default()),
+        }
+    };
+
+    let matchers = Matchers {
+        match_codes: number_set("match-code")?,
+        match_sizes: number_set("match-size")?,
+        match_words: number_set("match-words")?,
+        match_lines: number_set("match-lines")?,
+        match_regex: match args.value_of("match-regex") {
+            Some(val) => Some(Regex::new(val).map_err(|err| format!("Unable to parse --match-regex: {}", err))?),
+            None => None,
+        },
+        filter_codes: number_set("filter-code")?,
+        filter_sizes: number_set("filter-size")?,
+        filter_words: number_set("filter-words")?,
+        filter_lines: number_set("filter-lines")?,
+        server_errors: args.is_present("isolate-server-errors"),
+    };
+
+    let redirects = Redirects {
+        policy: match args.value_of("follow-redirects") {
+            Some(val) => RedirectPolicy::parse(val)?,
+            None if args.is_present("follow-redirects") => RedirectPolicy::All,
+            None => RedirectPolicy::None,
+        },
+        max_hops: args.value_of("max-redirects").unwrap().parse()?,
+    };
+
+    let fingerprints = match args.value_of("error-signatures") {
+        Some(val) => Fingerprints::from_file(val)?,
+        None => Fingerprints::default(),
+    };
+
+    let pairs: Vec<(String, String)> = match args.value_of("pairs") {
+        Some(val) => fs::read_to_string(val)?
+            .lines()
+            .enumerate()
+            .filter(|(_, x)| !x.trim().is_empty())
+            .map(|(i, x)| match x.split_once('=') {
+                Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+                _ => Err(format!("Unable to parse the pair at line {}: {}. Use the name=value format", i + 1, x)),
+            })
+            .collect::<Result<Vec<(String, String)>, String>>()?,
+        None => Vec::new(),
+    };
+
+    let value_wordlist: Vec<String> = match args.value_of("value-wordlist") {
+        Some(val) => fs::read_to_string(val)?
+            .lines()
+            .filter(|x| !x.is_empty())
             .map(|x| x.to_string())
             .collect(),
+        // values that usually change the behavior of access control, routing and localization headers
+        None if args.is_present("fuzz-header") => [
+            "127.0.0.1", "localhost", "0.0.0.0", "::1", "10.0.0.1", "192.168.0.1", "/", "/admin", "/%2e/", "*", "null",
+            "true", "1", "0", "en", "de", "fr", "ru", "zh", "http", "https",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect(),
+        None if args.is_present("fuzz-values") => [
+            "true", "false", "1", "0", "-1", "yes", "no", "on", "off", "null", "admin", "debug", "test", "root",
+            "dev", "en", "de", "fr", "ru", "zh",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect(),
+        None => Vec::new(),
     };
 
     let mut custom_parameters: HashMap<String, Vec<String>> =
@@ -584,9 +2005,47 @@ Increase the amount of workers to remove the error or use --force.")?;
         args.value_of("proxy").unwrap_or("").to_string()
     };
 
+    let proxies: Vec<String> = match args.value_of("proxy-file") {
+        Some(val) => fs::read_to_string(val)?
+            .lines()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .map(|x| x.to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let resolve = match args.values_of("resolve") {
+        Some(val) => val.map(parse_resolve).collect::<Result<Vec<(String, SocketAddr)>, _>>()?,
+        None => Vec::new(),
+    };
+
+    let client_identity = match args.value_of("cert") {
+        Some(cert) => Some(read_client_identity(cert, args.value_of("key"))?),
+        None => None,
+    };
+
+    if args.is_present("proxy-file") && proxies.is_empty() {
+        Err("The --proxy-file doesn't contain any proxies")?;
+    }
+
+    let graphql = match args.value_of("graphql") {
+        Some("arguments") => Some(GraphQLMode::Arguments),
+        Some("fields") => Some(GraphQLMode::Fields),
+        Some("variables") => Some(GraphQLMode::Variables),
+        Some(_) => Err("Incorrect --graphql mode specified")?,
+        None => None,
+    };
+
+    let proxy_rotation = match args.value_of("proxy-rotation").unwrap_or("round-robin") {
+        "round-robin" => ProxyRotation::RoundRobin,
+        "random" => ProxyRotation::Random,
+        _ => Err("Incorrect --proxy-rotation specified")?,
+    };
+
     if args.is_present("cookies") {
         if let Some(index) = headers.get_index_case_insensitive("cookie") {
-            headers[index] = (headers[index].0.clone(), headers[index].1.clone()+";%s")
+            headers[index] = (headers[index].0.clone(), headers[index].1.clone()+"; %s")
         } else {
             headers.push(("Cookie".to_string(), "%s".to_string()));
         }
@@ -597,39 +2056,139 @@ Increase the amount of workers to remove the error or use --force.")?;
         urls,
         methods,
         wordlist: args.value_of("wordlist").unwrap_or("").to_string(),
+        wordlist_batch,
+        scrape_js: args.is_present("scrape-js"),
+        values: match args.values_of("value-strategy") {
+            Some(val) => ValueStrategies::parse(val)?,
+            None => ValueStrategies::default(),
+        },
+        mutations: args.is_present("mutations"),
         custom_parameters,
         proxy,
+        proxies,
+        proxy_rotation,
         replay_proxy: args.value_of("replay-proxy").unwrap_or("").to_string(),
         replay_once: args.is_present("replay-once"),
-        output_file: args.value_of("output").unwrap_or("").to_string(),
+        output_file: match args.value_of("output") {
+            Some(val) if !is_database_file(val) => val.to_string(),
+            _ => String::new(),
+        },
+        output_db: args.value_of("output").filter(|x| is_database_file(x)).map(|x| x.to_string()),
+        checkpoint,
         save_responses: args.value_of("save-responses").unwrap_or("").to_string(),
+        nuclei_templates: args.value_of("nuclei-templates").unwrap_or("").to_string(),
+        burp_export: match args.value_of("burp-export") {
+            Some(val) => Some(Arc::new(BurpExport::new(val).map_err(|err| categorize(err, X8Error::Output))?)),
+            None => None,
+        },
         output_format: args.value_of("output-format").unwrap_or("").to_string(),
+        report,
+        report_file: args.value_of("report-file").unwrap().to_string(),
         append: args.is_present("append"),
         remove_empty: args.is_present("remove-empty"),
         force: args.is_present("force"),
         strict: args.is_present("strict"),
-        disable_progress_bar: args.is_present("disable-progress-bar"),
+        unstable_code_thresholds: match args.values_of("unstable-code-threshold") {
+            Some(val) => UnstableCodeThresholds::parse(val)?,
+            None => UnstableCodeThresholds::default(),
+        },
+        unstable_code_action: UnstableCodeAction::parse(args.value_of("unstable-code-action").unwrap())?,
+        // progress bars are replaced by progress events
+        disable_progress_bar: args.is_present("disable-progress-bar") || args.value_of("progress") == Some("json"),
+        progress_json: args.value_of("progress") == Some("json"),
         progress_bar_len,
-        follow_redirects: args.is_present("follow-redirects"),
+        redirects,
         test: args.is_present("test"),
         verbose,
         learn_requests_count,
         concurrency,
+        adaptive_concurrency,
+        auto_tune,
         workers,
         timeout,
         recursion_depth,
-        verify: args.is_present("verify"),
+        verify: args.is_present("verify") || args.is_present("verify-attempts"),
+        verify_attempts,
+        min_confidence,
+        block_pause,
+        block_rotate: args.is_present("block-rotate"),
+        oob,
         reflected_only: args.is_present("reflected-only"),
         http_version,
+        http2_prior_knowledge,
+        http3: args.is_present("http3"),
         template: convert_to_string_if_some(args.value_of("parameter-template")),
         joiner: convert_to_string_if_some(args.value_of("joiner")),
         encode: args.is_present("encode"),
         disable_custom_parameters: args.is_present("disable-custom-parameters"),
+        disable_calibration: args.is_present("disable-calibration"),
         one_worker_per_host: args.is_present("one-worker-per-host"),
         invert: args.is_present("invert"),
-        headers_discovery: args.is_present("headers-discovery") || args.is_present("cookies"),
+        headers_discovery: args.is_present("headers-discovery")
+            || args.is_present("cookies")
+            || args.is_present("fuzz-header")
+            || args.is_present("cache-poisoning"),
+        cache_poisoning: args.is_present("cache-poisoning"),
+        pollution: args.is_present("pollution"),
+        switch_content_type: args.is_present("switch-content-type"),
+        array_syntax: args.is_present("array-syntax"),
+        pairs,
+        fuzz_headers: args
+            .values_of("fuzz-header")
+            .map(|x| x.map(|x| x.to_string()).collect())
+            .unwrap_or_default(),
         body,
+        json_pointer: convert_to_string_if_some(args.value_of("json-pointer")),
+        xml_node: convert_to_string_if_some(args.value_of("xml-node")),
+        xml_attributes: args.is_present("xml-attributes"),
+        value_wordlist,
+        time_based: args.is_present("time-based"),
+        time_sigma: args.value_of("time-sigma").unwrap().parse()?,
+        time_confirmations: args.value_of("time-confirmations").unwrap().parse()?,
+        normalization,
+        matchers,
+        fingerprints,
+        html_diff: args.is_present("html-diff"),
+        json_diff: args.is_present("json-diff")
+            || args.is_present("json-diff-ignore")
+            || args.is_present("json-diff-unordered"),
+        json_diff_ignore,
+        json_diff_unordered: args.is_present("json-diff-unordered"),
+        similarity,
+        discover_methods: args.is_present("discover-methods"),
+        scan_discovered_methods: args.is_present("scan-discovered-methods"),
+        graphql,
         delay,
+        rate_limiter,
+        host_rate_limiters,
+        host_semaphores,
+        budget,
+        response_cache: if args.is_present("cache-responses") {
+            Some(Arc::new(ResponseCache::default()))
+        } else {
+            None
+        },
+        metrics: Arc::new(Metrics::default()),
+        metrics_listen: args.value_of("metrics").map(|x| x.to_string()),
+        gate: Arc::new(Gate::default()),
+        record: match args.value_of("record") {
+            Some(val) => Some(Arc::new(Recorder::new(val)?)),
+            None => None,
+        },
+        replay: match args.value_of("replay") {
+            Some(val) => Some(Arc::new(Player::new(val).map_err(|err| categorize(err, X8Error::Parse))?)),
+            None => None,
+        },
+        webhook: match args.value_of("notify-webhook") {
+            Some(val) => Some(Arc::new(Webhook::new(val)?)),
+            None => None,
+        },
+        seed: match args.value_of("seed") {
+            Some(val) => Some(val.parse().map_err(|_| "--seed should be a non-negative integer")?),
+            None => None,
+        },
+        dry_run: args.is_present("dry-run"),
+        retry_policy,
         custom_headers: headers
             .iter()
             .map(|(k, v)| (k.to_string(), v.to_string()))
@@ -637,8 +2196,26 @@ Increase the amount of workers to remove the error or use --force.")?;
         data_type,
         max,
         disable_colors: args.is_present("disable-colors"),
-        remove_banner: args.is_present("remove-banner"),
+        remove_banner: args.is_present("remove-banner") || !porcelain.is_empty(),
+        porcelain,
         disable_trustdns: args.is_present("disable-trustdns"),
+        pool_max_idle,
+        pool_idle_timeout,
+        tcp_keepalive,
+        keepalive: !args.is_present("no-keepalive"),
+        client_identity,
+        resolve,
+        raw: args.is_present("raw"),
+        cookie_jar: args.is_present("cookie-jar"),
+        pin_cookies: args.is_present("pin-cookies"),
+        session_refresh,
+        csrf,
+        sigv4,
+        script,
+        mutator: args.value_of("mutator-cmd").map(|x| Arc::new(Mutator::new(x))),
+        unix_socket: convert_to_string_if_some(args.value_of("unix-socket")),
         check_binary: args.is_present("check-binary"),
+        max_response_size: args.value_of("max-response-size").map(|x| x.parse()).transpose()?,
+        har_entries,
     })
 }
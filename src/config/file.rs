@@ -0,0 +1,113 @@
+use std::{error::Error, fs};
+
+use clap::ArgMatches;
+use toml::Value;
+
+use super::args::get_app;
+
+/// arguments whose command line name differs from their name within the config file
+const ARG_FLAGS: &[(&str, &str)] = &[
+    ("headers", "-H"),
+    ("concurrency", "-c"),
+    ("burp-proxy", "-B"),
+    ("headers-discovery", "--headers"),
+    ("parameter-template", "--param-template"),
+    ("learn-requests-count", "--learn-requests"),
+];
+
+/// re-parses the arguments together with the options from the --config file (and its --profile).
+/// options from the command line override the ones from the file.
+/// `argv` are the command line arguments without the program name
+pub fn with_config_file(args: ArgMatches<'static>, argv: &[String]) -> Result<ArgMatches<'static>, Box<dyn Error>> {
+    let filename = match args.value_of("config") {
+        Some(val) => val,
+        None => return Ok(args),
+    };
+
+    let file_args = config_file_args(filename, args.value_of("profile"), &args)?;
+
+    // the options from the file go first, so a subcommand from the command line stays the last one
+    let args = get_app().get_matches_from_safe(
+        std::iter::once("x8".to_string())
+            .chain(file_args)
+            .chain(argv.iter().cloned()),
+    )?;
+
+    Ok(args)
+}
+
+/// turns the options of the file into command line arguments.
+/// top level options are used as defaults for profiles
+fn config_file_args(filename: &str, profile: Option<&str>, args: &ArgMatches) -> Result<Vec<String>, Box<dyn Error>> {
+    let text = fs::read_to_string(filename)
+        .map_err(|err| format!("Unable to read the config file {}: {}", filename, err))?;
+
+    let mut options = match text.parse::<Value>() {
+        Ok(Value::Table(val)) => val,
+        Ok(_) => Err(format!("Unable to parse the config file {}", filename))?,
+        Err(err) => Err(format!("Unable to parse the config file {}: {}", filename, err))?,
+    };
+
+    let profiles = options.remove("profile");
+
+    if let Some(profile) = profile {
+        let profile_options = profiles
+            .as_ref()
+            .and_then(|x| x.get(profile))
+            .and_then(|x| x.as_table())
+            .ok_or_else(|| format!("The profile {} isn't within the config file {}", profile, filename))?;
+
+        for (key, value) in profile_options {
+            options.insert(key.to_owned(), value.to_owned());
+        }
+    }
+
+    // multiple values are written first, so they don't take the next arguments as their values
+    let (mut multiple, mut single) = (Vec::new(), Vec::new());
+
+    for (key, value) in options {
+        if key == "config" || key == "profile" {
+            Err(format!("{} can't be set within the config file", key))?;
+        }
+
+        // the command line overrides the file
+        if args.occurrences_of(&key) > 0 {
+            continue;
+        }
+
+        let flag = ARG_FLAGS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, flag)| flag.to_string())
+            .unwrap_or_else(|| format!("--{}", key));
+
+        match value {
+            Value::Boolean(true) => single.push(flag),
+            Value::Boolean(false) => (),
+            Value::Array(values) => {
+                multiple.push(flag);
+                for value in values {
+                    multiple.push(option_value(&key, value)?);
+                }
+            }
+            value => {
+                single.push(flag);
+                single.push(option_value(&key, value)?);
+            }
+        }
+    }
+
+    multiple.append(&mut single);
+
+    Ok(multiple)
+}
+
+fn option_value(key: &str, value: Value) -> Result<String, Box<dyn Error>> {
+    Ok(match value {
+        Value::String(val) => val,
+        Value::Integer(val) => val.to_string(),
+        Value::Float(val) => val.to_string(),
+        Value::Boolean(val) => val.to_string(),
+        _ => Err(format!("Unsupported value of {} within the config file", key))?,
+    })
+}
@@ -1,3 +1,7 @@
 pub mod args;
+pub mod file;
 pub mod structs;
 pub mod utils;
+
+#[cfg(test)]
+mod tests;
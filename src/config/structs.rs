@@ -1,6 +1,32 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 
-use crate::network::utils::DataType;
+use tokio::sync::Semaphore;
+
+use super::utils::{add_default_headers, DEFAULT_CUSTOM_PARAMETERS, DEFAULT_CUSTOM_VALUES};
+
+use crate::{
+    metrics::Metrics,
+    network::{budget::Budget, burp::BurpExport, cache::ResponseCache, cassette::{Player, Recorder}, csrf::CsrfRule, fingerprints::Fingerprints, graphql::GraphQLMode, oob::Oob, proxy_pool::ProxyRotation, rate_limiter::{Gate, RateLimiter}, redirect::Redirects, retry::RetryPolicy, matchers::Matchers, mutator::Mutator, normalize::Normalization, script::Script, session::SessionRefresh, sigv4::SigV4, utils::DataType, values::ValueStrategies, webhook::Webhook},
+    runner::{
+        checkpoint::CheckpointStore,
+        utils::{UnstableCodeAction, UnstableCodeThresholds},
+        warmup::AutoTune,
+    },
+};
+
+/// a single request recorded within a HAR file
+#[derive(Debug, Clone)]
+pub struct HarEntry {
+    pub method: String,
+    pub url: String,
+
+    /// original headers (including cookies) without pseudo and content-length headers
+    pub headers: Vec<(String, String)>,
+
+    pub body: String,
+
+    pub data_type: Option<DataType>,
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -16,9 +42,60 @@ pub struct Config {
     /// how much to sleep between requests in millisecs
     pub delay: Duration,
 
+    /// limits the amount of requests per second across all the urls and concurrent requests
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// limit the amount of requests per second to every host
+    pub host_rate_limiters: HashMap<String, Arc<RateLimiter>>,
+
+    /// limit the amount of concurrently checked urls of every host
+    pub host_semaphores: HashMap<String, Arc<Semaphore>>,
+
+    /// stops the scan after the amount of requests, found parameters or time
+    pub budget: Arc<Budget>,
+
+    /// answers the requests that were already sent with their responses
+    pub response_cache: Option<Arc<ResponseCache>>,
+
+    /// counters exposed on the metrics listener
+    pub metrics: Arc<Metrics>,
+
+    /// where to serve the metrics
+    pub metrics_listen: Option<String>,
+
+    /// holds all the requests while the scan is paused with SIGUSR1 or the `p` key
+    pub gate: Arc<Gate>,
+
+    /// write every request and response to the cassette
+    pub record: Option<Arc<Recorder>>,
+
+    /// answer requests with the responses from the cassette instead of sending them
+    pub replay: Option<Arc<Player>>,
+
+    /// seed for random names and values
+    pub seed: Option<u64>,
+
+    /// stop after the learning phase and print the plan of the scan
+    pub dry_run: bool,
+
+    /// notify about found parameters and the end of the scan
+    pub webhook: Option<Arc<Webhook>>,
+
+    /// how to resend requests that failed because of network errors or gateway errors
+    pub retry_policy: RetryPolicy,
+
     /// user supplied wordlist file
     pub wordlist: String,
 
+    /// the amount of unique parameters per batch in case the wordlist is read lazily. 0 to load the whole wordlist
+    pub wordlist_batch: usize,
+
+    /// collect parameter names from the page and its scripts and check them first
+    pub scrape_js: bool,
+
+    /// add case and affix variants of parameters to the wordlist
+    pub mutations: bool,
+
     /// max amount of parameters to send per request.
     /// Can be specified by user otherwise detects automatically based on the request method
     pub max: Option<usize>,
@@ -35,6 +112,79 @@ pub struct Config {
     /// default body
     pub body: String,
 
+    /// json pointer (like /data/filters) to the nested object where parameters should be inserted
+    pub json_pointer: Option<String>,
+
+    /// xml node where parameters should be inserted (the root element by default)
+    pub xml_node: Option<String>,
+
+    /// insert parameters as attributes of the xml node instead of child elements
+    pub xml_attributes: bool,
+
+    /// flag parameters that consistently increase the response time
+    pub time_based: bool,
+
+    /// how many standard deviations from the mean response time are considered as a time shift
+    pub time_sigma: f64,
+
+    /// how many times the time shift needs to be reproduced
+    pub time_confirmations: usize,
+
+    /// applied to responses before comparing: removes comments and matched substrings, decodes entities, etc
+    pub normalization: Normalization,
+
+    /// ffuf-like conditions on the code, size, words and lines of responses
+    pub matchers: Matchers,
+
+    /// error messages that tag found parameters with the technology that produced them
+    pub fingerprints: Fingerprints,
+
+    /// compare html bodies by their elements and attributes instead of their formatting
+    pub html_diff: bool,
+
+    /// compare json bodies structurally instead of line by line
+    pub json_diff: bool,
+
+    /// json pointers to ignore while comparing json bodies (* matches any segment)
+    pub json_diff_ignore: Vec<String>,
+
+    /// compare json arrays regardless of the order of their elements
+    pub json_diff_unordered: bool,
+
+    /// bodies with the token-level similarity ratio above this threshold are considered the same
+    pub similarity: Option<f64>,
+
+    /// values to fuzz found parameters with. Empty in case value fuzzing is disabled
+    pub value_wordlist: Vec<String>,
+
+    /// headers whose values are fuzzed with value_wordlist instead of discovering parameters
+    pub fuzz_headers: Vec<String>,
+
+    /// how to generate values of parameters
+    pub values: ValueStrategies,
+
+    /// check whether found headers are unkeyed by the cache
+    pub cache_poisoning: bool,
+
+    /// resend parameters of the original request in duplicated ways instead of discovering parameters
+    pub pollution: bool,
+
+    /// resend found parameters with other encodings
+    pub switch_content_type: bool,
+
+    /// check parameters that weren't found with the array syntaxes
+    pub array_syntax: bool,
+
+    /// probe urls with alternative methods before the discovery
+    pub discover_methods: bool,
+
+    /// search for parameters with discovered methods as well
+    pub scan_discovered_methods: bool,
+
+    /// where to insert names within graphql requests.
+    /// graphql errors are used to determine which names are accepted
+    pub graphql: Option<GraphQLMode>,
+
     /// Json type handles differently because values like null, true, ints needs to be sent without quotes
     /// Probably better to replace with just isJson for now..
     pub data_type: Option<DataType>,
@@ -42,12 +192,61 @@ pub struct Config {
     /// whether to include parameters like debug=true to the list
     pub disable_custom_parameters: bool,
 
+    /// do not learn how the page reacts to nonexistent parameters
+    pub disable_calibration: bool,
+
     /// proxy server with schema or http:// by default.
+    /// http, https, socks5 and socks5h schemes are supported
     pub proxy: String,
 
+    /// proxies to rotate between. Dead proxies are evicted automatically
+    pub proxies: Vec<String>,
+
+    /// how to choose the next proxy from self.proxies
+    pub proxy_rotation: ProxyRotation,
+
+    /// merge cookies from Set-Cookie headers into the Cookie header of every request
+    pub cookie_jar: bool,
+
+    /// send only the original cookies and ignore Set-Cookie headers
+    pub pin_cookies: bool,
+
+    /// how to obtain the new session in case the current one expires
+    pub session_refresh: Option<SessionRefresh>,
+
+    /// where to take the csrf token from and where to insert it
+    pub csrf: Option<CsrfRule>,
+
+    /// sign every request with AWS Signature Version 4
+    pub sigv4: Option<SigV4>,
+
+    /// rhai script with hooks for requests, responses and detection
+    pub script: Option<Arc<Script>>,
+
+    /// the external program that changes every request before it's sent
+    pub mutator: Option<Arc<Mutator>>,
+
+    /// write requests directly to the socket instead of using the http client
+    pub raw: bool,
+
+    /// send requests via the unix socket. Implies raw requests
+    pub unix_socket: Option<String>,
+
+    /// PEM encoded client certificate and private key for mutual TLS
+    pub client_identity: Option<Vec<u8>>,
+
+    /// (host, address) pairs that override dns resolution. The original Host header and SNI are kept
+    pub resolve: Vec<(String, SocketAddr)>,
+
     /// file to output
     pub output_file: String,
 
+    /// sqlite database to store the results in. Used instead of output_file for .db, .sqlite and .sqlite3 files
+    pub output_db: Option<String>,
+
+    /// periodically saves the progress of checking to the file in order to be able to resume interrupted scans
+    pub checkpoint: Option<Arc<CheckpointStore>>,
+
     /// whether to append to the output file instead of overwriting
     pub append: bool,
 
@@ -57,9 +256,24 @@ pub struct Config {
     /// output format for file & stdout outputs
     pub output_format: String,
 
+    /// machine readable output instead of the output format. Only curl is supported for now
+    pub porcelain: String,
+
     /// a directory for saving request & responses with found parameters
     pub save_responses: String,
 
+    /// directory to write a nuclei template for every found parameter to
+    pub nuclei_templates: String,
+
+    /// write requests and responses of the findings as Burp Suite items
+    pub burp_export: Option<Arc<BurpExport>>,
+
+    /// the report format. Only html is supported for now
+    pub report: String,
+
+    /// where to write the report
+    pub report_file: String,
+
     /// ignore some custom errors like when page's size > MAX_PAGE_SIZE
     pub force: bool,
 
@@ -68,11 +282,23 @@ pub struct Config {
     /// greatly reduces false positives and a bit increases false negatives
     pub strict: bool,
 
+    /// how many times the page can return an unexpected code before it's considered unstable
+    pub unstable_code_thresholds: UnstableCodeThresholds,
+
+    /// what to do when the page is considered unstable
+    pub unstable_code_action: UnstableCodeAction,
+
     /// custom parameters to check like <admin, [true, 1, false, ..]>
     pub custom_parameters: HashMap<String, Vec<String>>,
 
+    /// user supplied name=value pairs to send with exactly these values
+    pub pairs: Vec<(String, String)>,
+
     pub disable_progress_bar: bool,
 
+    /// write progress events as json lines to stderr instead of drawing progress bars
+    pub progress_json: bool,
+
     /// the size of progress bar in chars
     pub progress_bar_len: usize,
 
@@ -101,6 +327,13 @@ pub struct Config {
     /// amount of concurrent requests per url
     pub concurrency: usize,
 
+    /// the upper bound for the amount of concurrent requests per url.
+    /// in case it's set, the amount of concurrent requests is adjusted automatically starting with self.concurrency
+    pub adaptive_concurrency: Option<usize>,
+
+    /// choose --max and the concurrency after the warm-up requests
+    pub auto_tune: Option<AutoTune>,
+
     /// amount of concurrent url checks
     pub workers: usize,
 
@@ -111,6 +344,22 @@ pub struct Config {
     /// in future wil check for _false_potives like when every parameter that starts with _ is found
     pub verify: bool,
 
+    /// how many times every found parameter is resent during the verification.
+    /// the parameter is dropped in case at least one of the attempts doesn't reproduce the change
+    pub verify_attempts: usize,
+
+    /// pause requests after WAF or anti-bot block pages. None disables the detection
+    pub block_pause: Option<Duration>,
+
+    /// switch the User-Agent after blocks
+    pub block_rotate: bool,
+
+    /// report only parameters with the same or higher confidence
+    pub min_confidence: u8,
+
+    /// send parameters with unique callback urls as values and report the ones that caused interactions
+    pub oob: Option<Oob>,
+
     /// check only for reflected parameters in order to decrease the amount of requests
     /// usually makes 2+learn_request_count+words/max requests
     /// but in rare cases its number may be higher
@@ -120,6 +369,12 @@ pub struct Config {
 
     pub http_version: Option<http::Version>,
 
+    /// send HTTP/2 requests without negotiating the protocol via ALPN
+    pub http2_prior_knowledge: bool,
+
+    /// send requests over QUIC and fall back to h2/h1 in case the target doesn't support it
+    pub http3: bool,
+
     /// by default parameters are sent within the body only in case PUT or POST methods are used.
     /// it's possible to overwrite this behavior by specifying this option
     pub invert: bool,
@@ -127,7 +382,8 @@ pub struct Config {
     /// true in case the injection points is within the header or the headers are injection point itself
     pub headers_discovery: bool,
 
-    pub follow_redirects: bool,
+    /// --follow-redirects policy together with the maximum amount of hops
+    pub redirects: Redirects,
 
     pub disable_colors: bool,
 
@@ -135,6 +391,200 @@ pub struct Config {
 
     pub disable_trustdns: bool,
 
+    /// the maximum amount of idle connections per host. None keeps the client's default
+    pub pool_max_idle: Option<usize>,
+
+    /// close idle connections after the timeout. None keeps the client's default
+    pub pool_idle_timeout: Option<Duration>,
+
+    /// the interval of TCP keepalive probes
+    pub tcp_keepalive: Option<Duration>,
+
+    /// reuse connections between requests. --no-keepalive opens a new connection for every request
+    pub keepalive: bool,
+
     /// check body of responses with binary content type
     pub check_binary: bool,
+
+    /// stop reading response bodies after this amount of bytes
+    pub max_response_size: Option<usize>,
+
+    /// requests imported from a HAR file.
+    /// every entry keeps its own method, headers and body
+    pub har_entries: Vec<HarEntry>,
+}
+
+impl Default for Config {
+    /// the defaults of the command line arguments, except that the progress bar, the config banner
+    /// and intermediate messages are disabled.
+    /// it's meant to be used by library users: Config { urls: vec![..], ..Default::default() }
+    fn default() -> Self {
+        let custom_values: Vec<String> = DEFAULT_CUSTOM_VALUES.iter().map(|x| x.to_string()).collect();
+
+        Self {
+            urls: Vec::new(),
+            methods: vec!["GET".to_string()],
+            custom_headers: add_default_headers(HashMap::new()),
+            delay: Duration::from_millis(0),
+            rate_limiter: None,
+            host_rate_limiters: HashMap::new(),
+            host_semaphores: HashMap::new(),
+            budget: Arc::new(Budget::default()),
+            response_cache: None,
+            metrics: Arc::new(Metrics::default()),
+            metrics_listen: None,
+            gate: Arc::new(Gate::default()),
+            record: None,
+            replay: None,
+            seed: None,
+            dry_run: false,
+            webhook: None,
+            retry_policy: RetryPolicy {
+                retries: 2,
+                backoff: Duration::from_millis(1000),
+                jitter: Duration::from_millis(500),
+                codes: vec![502, 503, 504],
+            },
+            wordlist: String::new(),
+            wordlist_batch: 0,
+            scrape_js: false,
+            mutations: false,
+            max: None,
+            template: None,
+            joiner: None,
+            encode: false,
+            body: String::new(),
+            json_pointer: None,
+            xml_node: None,
+            xml_attributes: false,
+            time_based: false,
+            time_sigma: 3.,
+            time_confirmations: 3,
+            normalization: Normalization::default(),
+            matchers: Matchers::default(),
+            fingerprints: Fingerprints::default(),
+            html_diff: false,
+            json_diff: false,
+            json_diff_ignore: Vec::new(),
+            json_diff_unordered: false,
+            similarity: None,
+            value_wordlist: Vec::new(),
+            fuzz_headers: Vec::new(),
+            values: ValueStrategies::default(),
+            cache_poisoning: false,
+            pollution: false,
+            switch_content_type: false,
+            array_syntax: false,
+            discover_methods: false,
+            scan_discovered_methods: false,
+            graphql: None,
+            data_type: None,
+            disable_custom_parameters: false,
+            disable_calibration: false,
+            proxy: String::new(),
+            proxies: Vec::new(),
+            proxy_rotation: ProxyRotation::default(),
+            cookie_jar: false,
+            pin_cookies: false,
+            session_refresh: None,
+            csrf: None,
+            sigv4: None,
+            script: None,
+            mutator: None,
+            raw: false,
+            unix_socket: None,
+            client_identity: None,
+            resolve: Vec::new(),
+            output_file: String::new(),
+            output_db: None,
+            checkpoint: None,
+            append: false,
+            remove_empty: false,
+            output_format: "standart".to_string(),
+            save_responses: String::new(),
+            nuclei_templates: String::new(),
+            burp_export: None,
+            porcelain: String::new(),
+            report: String::new(),
+            report_file: "report.html".to_string(),
+            force: false,
+            strict: false,
+            unstable_code_thresholds: UnstableCodeThresholds::default(),
+            unstable_code_action: UnstableCodeAction::default(),
+            custom_parameters: DEFAULT_CUSTOM_PARAMETERS
+                .iter()
+                .map(|x| (x.to_string(), custom_values.clone()))
+                .collect(),
+            pairs: Vec::new(),
+            disable_progress_bar: true,
+            progress_json: false,
+            progress_bar_len: 26,
+            replay_proxy: String::new(),
+            replay_once: false,
+            test: false,
+            verbose: 0,
+            learn_requests_count: 9,
+            recursion_depth: 0,
+            concurrency: 1,
+            adaptive_concurrency: None,
+            auto_tune: None,
+            workers: 1,
+            timeout: 15,
+            verify: false,
+            verify_attempts: 1,
+            block_pause: None,
+            block_rotate: false,
+            min_confidence: 0,
+            oob: None,
+            reflected_only: false,
+            one_worker_per_host: false,
+            http_version: None,
+            http2_prior_knowledge: false,
+            http3: false,
+            invert: false,
+            headers_discovery: false,
+            redirects: Redirects::default(),
+            disable_colors: false,
+            remove_banner: true,
+            disable_trustdns: false,
+            pool_max_idle: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            keepalive: true,
+            check_binary: false,
+            max_response_size: None,
+            har_entries: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// whether the output can be printed only after all the urls are checked
+    pub fn is_output_deferred(&self) -> bool {
+        matches!(self.output_format.as_str(), "json" | "json-by-target" | "sarif" | "junit")
+    }
+
+    /// returns methods to check the url with.
+    /// in case the targets were imported from a HAR file -- only methods recorded for this url are returned
+    pub fn methods_for(&self, url: &str) -> Vec<String> {
+        if self.har_entries.is_empty() {
+            return self.methods.clone();
+        }
+
+        let mut methods = Vec::new();
+        for entry in self.har_entries.iter().filter(|x| x.url == url) {
+            if !methods.contains(&entry.method) {
+                methods.push(entry.method.clone());
+            }
+        }
+
+        methods
+    }
+
+    /// returns the HAR entry recorded for the method:url pair
+    pub fn har_entry(&self, method: &str, url: &str) -> Option<&HarEntry> {
+        self.har_entries
+            .iter()
+            .find(|x| x.method == method && x.url == url)
+    }
 }
@@ -0,0 +1,78 @@
+use super::{args::get_remote_config_from, utils::interpolate_env};
+
+fn remote_error(args: &[&str]) -> String {
+    let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+
+    get_remote_config_from(&args, &[]).unwrap_err().to_string()
+}
+
+#[test]
+fn remote_options() {
+    let args: Vec<String> = ["-u", "https://example.com/", "-X", "POST", "-H", "X-Header: 1", "--follow-redirects"]
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+
+    let config = get_remote_config_from(&args, &[]).unwrap();
+    assert_eq!(config.urls, ["https://example.com/"]);
+}
+
+#[test]
+fn remote_local_files() {
+    assert!(remote_error(&["-u", "https://example.com/", "--script", "script.rhai"]).contains("script"));
+    assert!(remote_error(&["-u", "https://example.com/", "--refresh-command", "id"]).contains("refresh-command"));
+    assert!(remote_error(&["-u", "https://example.com/", "--mutator-cmd", "id"]).contains("mutator-cmd"));
+    assert!(remote_error(&["-u", "https://example.com/", "-o", "/tmp/x", "--save-responses", "/tmp"]).contains("output, save-responses"));
+    assert!(remote_error(&["-u", "https://example.com/", "-w", "/etc/passwd"]).contains("wordlist"));
+    assert!(remote_error(&["-u", "https://example.com/", "--config", "/etc/passwd"]).contains("config"));
+}
+
+#[test]
+fn remote_env_interpolation() {
+    assert!(remote_error(&["-u", "https://example.com/", "-H", "X-Secret: ${AWS_SECRET_ACCESS_KEY}"]).contains("${NAME}"));
+    assert!(remote_error(&["-u", "https://example.com/", "--body", "${HOME}"]).contains("${NAME}"));
+}
+
+#[test]
+fn remote_urls() {
+    assert!(remote_error(&["-u", "-"]).contains("http(s)"));
+    assert!(remote_error(&["-u", "/etc/hosts"]).contains("http(s)"));
+}
+
+#[test]
+fn http3() {
+    let args: Vec<String> = ["-u", "https://example.com/", "--http3"].iter().map(|x| x.to_string()).collect();
+
+    match get_remote_config_from(&args, &[]) {
+        Ok(config) => assert!(cfg!(feature = "http3") && config.http3),
+        Err(err) => assert!(!cfg!(feature = "http3") && err.to_string().contains("--features http3")),
+    }
+
+    assert!(remote_error(&["-u", "https://example.com/", "--http3", "--http2"]).contains("http2"));
+}
+
+#[test]
+fn json_diff_ignore_pointers() {
+    let args: Vec<String> = ["-u", "https://example.com/", "--json-diff-ignore", "/meta/timestamp/", "/items/*/id"]
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+
+    let config = get_remote_config_from(&args, &[]).unwrap();
+    assert_eq!(config.json_diff_ignore, ["/meta/timestamp", "/items/*/id"]);
+
+    assert!(remote_error(&["-u", "https://example.com/", "--json-diff-ignore", "/"]).contains("json pointer"));
+    assert!(remote_error(&["-u", "https://example.com/", "--json-diff-ignore", "meta"]).contains("json pointer"));
+}
+
+#[test]
+fn env_interpolation() {
+    std::env::set_var("X8_TEST_TOKEN", "secret");
+
+    assert_eq!(interpolate_env("Bearer ${X8_TEST_TOKEN}", false).unwrap(), "Bearer secret");
+    assert_eq!(interpolate_env("$${X8_TEST_TOKEN}", false).unwrap(), "${X8_TEST_TOKEN}");
+
+    // unset variables are kept unless they are required
+    assert_eq!(interpolate_env("${X8_TEST_UNSET}", false).unwrap(), "${X8_TEST_UNSET}");
+    assert!(interpolate_env("${X8_TEST_UNSET}", true).is_err());
+}
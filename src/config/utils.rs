@@ -1,15 +1,33 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     collections::HashMap,
     error::Error,
     io::{self, BufRead, Write},
+    net::{IpAddr, SocketAddr},
 };
 
 use colored::Colorize;
 
-use crate::network::utils::DataType;
+use crate::network::utils::{DataType, Headers};
 
-use super::structs::Config;
+use super::structs::{Config, HarEntry};
+
+/// parameters that are checked with DEFAULT_CUSTOM_VALUES in case --custom-parameters isn't provided
+pub(super) const DEFAULT_CUSTOM_PARAMETERS: [&str; 11] = [
+    "admin",
+    "bot",
+    "captcha",
+    "debug",
+    "disable",
+    "encryption",
+    "env",
+    "show",
+    "sso",
+    "test",
+    "waf",
+];
+
+pub(super) const DEFAULT_CUSTOM_VALUES: [&str; 8] = ["1", "0", "false", "off", "null", "true", "yes", "no"];
 
 /// shorcut to convert Option<&str> to Option<String> to be able to return it from the function
 pub(super) fn convert_to_string_if_some(el: Option<&str>) -> Option<String> {
@@ -53,12 +71,12 @@ pub(super) fn parse_request<'a>(
 
     // parse the first line
     let mut firstline = lines.next().ok_or("Unable to parse firstline")?.split(' ');
-    let method = firstline
+    let mut method = firstline
         .next()
         .ok_or("Unable to parse method")?
         .to_string();
-    let path = firstline.next().ok_or("Unable to parse path")?.to_string(); //include ' ' in path too?
-    let http2 = firstline
+    let mut path = firstline.next().ok_or("Unable to parse path")?.to_string(); //include ' ' in path too?
+    let mut http2 = firstline
         .next()
         .ok_or("Unable to parse http version")?
         .contains("HTTP/2");
@@ -69,6 +87,25 @@ pub(super) fn parse_request<'a>(
             break;
         }
 
+        // HTTP/2 pseudo-headers (:authority, :method, :path, :scheme) can't be sent as usual headers
+        if let Some(pseudo_header) = line.strip_prefix(':') {
+            let mut k_v = pseudo_header.splitn(2, ':');
+            let key = k_v.next().unwrap().trim().to_lowercase();
+            let value = k_v.next().ok_or("Unable to parse pseudo-header value")?.trim().to_string();
+
+            match key.as_str() {
+                "authority" => host = value,
+                "method" => method = value,
+                "path" => path = value,
+                // the scheme is taken from --proto
+                "scheme" => (),
+                _ => Err(format!("Unknown pseudo-header :{}", key))?,
+            }
+
+            http2 = true;
+            continue;
+        }
+
         let mut k_v = line.split(':');
         let key = k_v.next().ok_or("Unable to parse header key")?;
         let value: String = [
@@ -84,10 +121,17 @@ pub(super) fn parse_request<'a>(
             "content-type" => {
                 if value.contains("json") && data_type.is_none() {
                     data_type = Some(DataType::ProbablyJson)
+                } else if value.contains("multipart/form-data") && data_type.is_none() {
+                    data_type = Some(DataType::Multipart)
+                } else if value.contains("xml") && data_type.is_none() {
+                    data_type = Some(DataType::Xml)
                 }
             }
             "host" => {
-                host = value.clone();
+                // :authority has a priority against the host header
+                if host.is_empty() {
+                    host = value.clone();
+                }
                 // host header in http2 breaks the h2 lib for now
                 if http2 {
                     continue;
@@ -102,9 +146,12 @@ pub(super) fn parse_request<'a>(
         headers.push((key.to_string(), value));
     }
 
+    // empty lines separate headers and content of every form part
+    let keep_empty_lines = data_type == Some(DataType::Multipart);
+
     let mut body = lines.next().unwrap_or(&"").to_string();
     for part in lines {
-        if !part.is_empty() {
+        if !part.is_empty() || keep_empty_lines {
             body.push_str("\r\n");
             body.push_str(part);
         }
@@ -135,7 +182,109 @@ pub(super) fn parse_request<'a>(
     ))
 }
 
+/// parse requests from the HAR file
+pub(super) fn parse_har(har: &str) -> Result<Vec<HarEntry>, Box<dyn Error>> {
+    let har: serde_json::Value = serde_json::from_str(har)?;
+
+    let entries = har["log"]["entries"]
+        .as_array()
+        .ok_or("Unable to find log.entries within the HAR file")?;
+
+    let mut har_entries = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let request = &entry["request"];
+
+        let method = request["method"]
+            .as_str()
+            .ok_or("Unable to parse method from the HAR file")?
+            .to_string();
+        let url = request["url"]
+            .as_str()
+            .ok_or("Unable to parse url from the HAR file")?
+            .to_string();
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut data_type = None;
+
+        for header in request["headers"].as_array().into_iter().flatten() {
+            let key = header["name"].as_str().unwrap_or("");
+            let value = header["value"].as_str().unwrap_or("");
+
+            // skip http/2 pseudo headers like :authority
+            if key.is_empty() || key.starts_with(':') {
+                continue;
+            }
+
+            match key.to_lowercase().as_str() {
+                "content-type" => {
+                    if value.contains("json") {
+                        data_type = Some(DataType::ProbablyJson)
+                    } else if value.contains("multipart/form-data") {
+                        data_type = Some(DataType::Multipart)
+                    } else if value.contains("xml") {
+                        data_type = Some(DataType::Xml)
+                    }
+                }
+                // the same as with request files. The body will be changed anyway
+                "content-length" => continue,
+                _ => (),
+            };
+
+            headers.push((key.to_string(), value.to_string()));
+        }
+
+        // some HAR exporters store cookies only within the separate list
+        if headers.get_index_case_insensitive("cookie").is_none() {
+            let cookies = request["cookies"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|x| Some(format!("{}={}", x["name"].as_str()?, x["value"].as_str()?)))
+                .collect::<Vec<String>>();
+
+            if !cookies.is_empty() {
+                headers.push(("Cookie".to_string(), cookies.join("; ")));
+            }
+        }
+
+        let body = request["postData"]["text"]
+            .as_str()
+            .unwrap_or("")
+            .to_string();
+
+        if data_type.is_none()
+            && request["postData"]["mimeType"]
+                .as_str()
+                .unwrap_or("")
+                .contains("json")
+        {
+            data_type = Some(DataType::ProbablyJson)
+        }
+
+        har_entries.push(HarEntry {
+            method,
+            url,
+            headers,
+            body,
+            data_type,
+        });
+    }
+
+    if har_entries.is_empty() {
+        Err("The HAR file doesn't contain any requests")?;
+    }
+
+    Ok(har_entries)
+}
+
 pub fn write_banner_config(config: &Config, params: &Vec<String>) {
+    let wordlist = if config.wordlist_batch != 0 {
+        format!("batches of {}", config.wordlist_batch)
+    } else {
+        params.len().to_string()
+    };
+
     let mut output = format!(
         "{}:         {}\n{}:      {}\n{}: {}",
         "urls".green(),
@@ -143,7 +292,7 @@ pub fn write_banner_config(config: &Config, params: &Vec<String>) {
         "methods".blue(),
         config.methods.join(" "),
         "wordlist len".cyan(),
-        params.len(),
+        wordlist,
     );
 
     if !config.proxy.is_empty() {
@@ -224,4 +373,101 @@ pub(super) fn mimic_browser_headers(curr_headers: HashMap<&str, String>) -> Vec<
     curr_headers.iter().map(|(k, v)| headers.push((k.to_string(), v.to_string()))).for_each(drop);
 
     headers
-}
\ No newline at end of file
+}
+/// reads the client certificate and the private key (in case it's in a separate file) for mutual TLS.
+/// returns them concatenated, as expected by reqwest::Identity::from_pem
+pub fn read_client_identity(cert: &str, key: Option<&str>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut identity = fs::read(cert)
+        .map_err(|err| format!("Unable to read the certificate {}: {}", cert, err))?;
+
+    if !String::from_utf8_lossy(&identity).contains("-----BEGIN") {
+        Err(format!(
+            "{} isn't PEM encoded. PKCS#12 bundles can be converted with `openssl pkcs12 -in {} -out cert.pem -nodes`",
+            cert, cert
+        ))?;
+    }
+
+    if let Some(key) = key {
+        identity.push(b'\n');
+        identity.append(
+            &mut fs::read(key).map_err(|err| format!("Unable to read the private key {}: {}", key, err))?,
+        );
+    }
+
+    if !String::from_utf8_lossy(&identity).contains("PRIVATE KEY-----") {
+        Err("The private key is missing. Provide it via --key or append it to the certificate file")?;
+    }
+
+    Ok(identity)
+}
+
+/// replaces ${NAME} with the value of the environment variable, so secrets don't have to be within the shell history.
+/// $${NAME} is kept as ${NAME}. Unset variables are kept as they are unless required is set
+pub fn interpolate_env(s: &str, required: bool) -> Result<String, Box<dyn Error>> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result += &rest[..start - 1];
+            result += "${";
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let end = match rest[start + 2..].find('}') {
+            Some(val) => start + 2 + val,
+            None => break,
+        };
+
+        let name = &rest[start + 2..end];
+        if name.is_empty() || !name.chars().all(|x| x.is_ascii_alphanumeric() || x == '_') {
+            result += &rest[..start + 2];
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let value = match std::env::var(name) {
+            Ok(val) => val,
+            Err(_) if required => Err(format!("The environment variable {} isn't set", name))?,
+            Err(_) => rest[start..=end].to_string(),
+        };
+
+        result += &rest[..start];
+        result += &value;
+        rest = &rest[end + 1..];
+    }
+
+    result += rest;
+
+    Ok(result)
+}
+
+/// interpolates environment variables within header values
+pub fn interpolate_env_headers(headers: Vec<(String, String)>, required: bool) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    headers
+        .into_iter()
+        .map(|(k, v)| Ok((k, interpolate_env(&v, required)?)))
+        .collect()
+}
+
+/// parses host:port:ip (curl's --resolve format). IPv6 addresses can be within brackets
+pub fn parse_resolve(entry: &str) -> Result<(String, SocketAddr), Box<dyn Error>> {
+    let mut parts = entry.splitn(3, ':');
+
+    let (host, port, ip) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(host), Some(port), Some(ip)) if !host.is_empty() => (host, port, ip),
+        _ => Err(format!("Unable to parse --resolve {}. The format is host:port:ip", entry))?,
+    };
+
+    let port: u16 = port
+        .parse()
+        .map_err(|_| format!("Unable to parse the port within --resolve {}", entry))?;
+    let ip: IpAddr = ip
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .map_err(|_| format!("Unable to parse the ip within --resolve {}", entry))?;
+
+    Ok((host.to_lowercase(), SocketAddr::new(ip, port)))
+}
@@ -0,0 +1,347 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use url::Url;
+
+use crate::runner::output::RunnerOutput;
+
+/// created on every open, so files from older versions get the new tables as well
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL DEFAULT (datetime('now')),
+    finished_at TEXT,
+    version TEXT NOT NULL,
+    args TEXT NOT NULL,
+    requests INTEGER,
+    findings INTEGER,
+    stopped TEXT
+);
+
+CREATE TABLE IF NOT EXISTS targets (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    checked_at TEXT NOT NULL DEFAULT (datetime('now')),
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    injection_place TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    found INTEGER NOT NULL,
+    skipped INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS findings (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    target_id INTEGER NOT NULL REFERENCES targets(id),
+    name TEXT NOT NULL,
+    value TEXT,
+    reason_kind TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    confidence INTEGER NOT NULL,
+    diffs TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS targets_url ON targets(url);
+CREATE INDEX IF NOT EXISTS findings_name ON findings(name);
+";
+
+/// replaces credentials within the stored arguments
+const REDACTED: &str = "***";
+
+/// whether the output file should be a database rather than a text file
+pub fn is_database_file(filename: &str) -> bool {
+    let filename = filename.to_lowercase();
+    filename.ends_with(".db") || filename.ends_with(".sqlite") || filename.ends_with(".sqlite3")
+}
+
+/// name, value, reason kind, status, size, confidence and diffs of the found parameter
+type FindingRow = (String, Option<String>, String, u16, i64, u8, String);
+
+/// stores every checked target and its findings in the sqlite database.
+/// every scan is a separate run, so the same file can be used across runs and by several processes.
+/// rusqlite calls block (up to the busy timeout in case other processes write), so they run on the blocking thread pool
+#[derive(Debug)]
+pub struct ResultsDb {
+    connection: Arc<Mutex<Connection>>,
+    run_id: i64,
+}
+
+impl ResultsDb {
+    /// opens or creates the database and starts a new run.
+    /// credentials within the arguments are redacted before they're stored
+    pub async fn open(filename: &str, args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let filename = filename.to_string();
+        let args = redact_args(args).join(" ");
+
+        let (connection, run_id) = tokio::task::spawn_blocking(move || -> Result<_, Box<dyn Error + Send + Sync>> {
+            let connection = open(&filename)?;
+
+            connection.execute_batch(SCHEMA)?;
+            connection.execute(
+                "INSERT INTO runs (version, args) VALUES (?1, ?2)",
+                params![env!("CARGO_PKG_VERSION"), args],
+            )?;
+            let run_id = connection.last_insert_rowid();
+
+            Ok((connection, run_id))
+        })
+        .await?
+        .map_err(|err| err as Box<dyn Error>)?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            run_id,
+        })
+    }
+
+    /// runs f with the connection on the blocking thread pool
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Connection, i64) -> rusqlite::Result<T> + Send + 'static,
+    ) -> Result<T, Box<dyn Error>> {
+        let connection = Arc::clone(&self.connection);
+        let run_id = self.run_id;
+
+        Ok(tokio::task::spawn_blocking(move || f(&mut connection.lock(), run_id)).await??)
+    }
+
+    pub async fn add_target(&self, output: &RunnerOutput, duration: Duration) -> Result<(), Box<dyn Error>> {
+        let target = (
+            output.method.clone(),
+            output.url.clone(),
+            format!("{:?}", output.injection_place),
+            output.status,
+            output.size as i64,
+            duration.as_millis() as i64,
+            output.found_params.len() as i64,
+            output.skipped_params.len() as i64,
+        );
+
+        let findings: Vec<FindingRow> = output
+            .found_params
+            .iter()
+            .map(|x| {
+                (
+                    x.name.clone(),
+                    x.value.clone(),
+                    format!("{:?}", x.reason_kind),
+                    x.status,
+                    x.size as i64,
+                    x.confidence,
+                    x.diffs.clone(),
+                )
+            })
+            .collect();
+
+        self.blocking(move |connection, run_id| {
+            let transaction = connection.transaction()?;
+
+            let (method, url, injection_place, status, size, duration_ms, found, skipped) = target;
+            transaction.execute(
+                "INSERT INTO targets (run_id, method, url, injection_place, status, size, duration_ms, found, skipped)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![run_id, method, url, injection_place, status, size, duration_ms, found, skipped],
+            )?;
+            let target_id = transaction.last_insert_rowid();
+
+            for (name, value, reason_kind, status, size, confidence, diffs) in findings {
+                transaction.execute(
+                    "INSERT INTO findings (run_id, target_id, name, value, reason_kind, status, size, confidence, diffs)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![run_id, target_id, name, value, reason_kind, status, size, confidence, diffs],
+                )?;
+            }
+
+            transaction.commit()
+        })
+        .await
+    }
+
+    /// remembers the end of the run together with its totals
+    pub async fn finish(&self, requests: usize, findings: usize, stopped: Option<String>) -> Result<(), Box<dyn Error>> {
+        self.blocking(move |connection, run_id| {
+            connection
+                .execute(
+                    "UPDATE runs SET finished_at = datetime('now'), requests = ?1, findings = ?2, stopped = ?3 WHERE id = ?4",
+                    params![requests as i64, findings as i64, stopped, run_id],
+                )
+                .map(|_| ())
+        })
+        .await
+    }
+}
+
+/// replaces header values, --aws-credentials and the credentials of proxies within the arguments
+pub(crate) fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+
+    // the last option, its values follow it
+    let mut option = "";
+
+    for arg in args {
+        if !arg.starts_with('-') || arg == "-" {
+            redacted.push(redact_value(option, arg));
+            continue;
+        }
+
+        option = arg;
+
+        // -H"Name: value" and --option=value
+        if let Some(header) = arg.strip_prefix("-H").filter(|x| !x.is_empty()) {
+            option = "-H";
+            redacted.push(format!("-H{}", redact_value("-H", header)));
+        } else if let Some((name, value)) = arg.split_once('=').filter(|(name, _)| name.starts_with("--")) {
+            redacted.push(format!("{}={}", name, redact_value(name, value)));
+        } else {
+            redacted.push(arg.to_owned());
+        }
+    }
+
+    redacted
+}
+
+fn redact_value(option: &str, value: &str) -> String {
+    match option {
+        "-H" => match value.split_once(':') {
+            Some((name, _)) => format!("{}: {}", name, REDACTED),
+            None => value.to_string(),
+        },
+        "--aws-credentials" => REDACTED.to_string(),
+        "-x" | "--proxy" => match Url::parse(value) {
+            Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+                url.set_username(REDACTED).ok();
+                url.set_password(None).ok();
+                url.to_string()
+            }
+            _ => value.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+fn open(filename: &str) -> Result<Connection, Box<dyn Error + Send + Sync>> {
+    let connection = Connection::open(filename)
+        .map_err(|err| format!("Unable to open the database {}: {}", filename, err))?;
+
+    // other processes can write to the same file at the same time
+    connection.busy_timeout(Duration::from_secs(30))?;
+    connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+
+    Ok(connection)
+}
+
+/// filters of `x8 query`
+#[derive(Debug, Default)]
+pub struct QueryFilters<'a> {
+    /// the id of the run or `last`
+    pub run: Option<&'a str>,
+
+    /// part of the url
+    pub url: Option<&'a str>,
+
+    pub reason_kind: Option<&'a str>,
+}
+
+/// prints runs, targets, findings or found parameter names from the database as tab separated rows
+pub fn query(filename: &str, table: &str, filters: &QueryFilters) -> Result<(), Box<dyn Error>> {
+    let (select, run_column, order_by) = match table {
+        "runs" => (
+            "SELECT id, started_at, finished_at, version, requests, findings, stopped, args FROM runs",
+            "id",
+            " ORDER BY id",
+        ),
+        "targets" => (
+            "SELECT t.run_id, t.checked_at, t.method, t.url, t.status, t.size, t.duration_ms, t.found, t.skipped
+             FROM targets t",
+            "t.run_id",
+            " ORDER BY t.id",
+        ),
+        "findings" => (
+            "SELECT f.run_id, t.method, t.url, f.name, f.value, f.reason_kind, f.status, f.size, f.confidence
+             FROM findings f JOIN targets t ON t.id = f.target_id",
+            "f.run_id",
+            " ORDER BY f.id",
+        ),
+        "params" => (
+            "SELECT f.name, COUNT(DISTINCT t.url) AS urls, COUNT(*) AS findings
+             FROM findings f JOIN targets t ON t.id = f.target_id",
+            "f.run_id",
+            " GROUP BY f.name ORDER BY urls DESC, f.name",
+        ),
+        _ => Err(format!("Unknown table: {}. Use runs, targets, findings or params", table))?,
+    };
+
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+
+    match filters.run {
+        Some("last") => conditions.push(format!("{} = (SELECT MAX(id) FROM runs)", run_column)),
+        Some(run) => {
+            conditions.push(format!("{} = ?", run_column));
+            values.push(Value::Integer(run.parse().map_err(|_| "--run should be a run id or last")?));
+        }
+        None => (),
+    }
+
+    if let Some(url) = filters.url {
+        if table == "runs" {
+            Err("--url can't be used with runs")?;
+        }
+        conditions.push("t.url LIKE ?".to_string());
+        values.push(Value::Text(format!("%{}%", url)));
+    }
+
+    if let Some(reason_kind) = filters.reason_kind {
+        if table == "runs" || table == "targets" {
+            Err(format!("--reason can't be used with {}", table))?;
+        }
+        conditions.push("f.reason_kind = ? COLLATE NOCASE".to_string());
+        values.push(Value::Text(reason_kind.to_string()));
+    }
+
+    let mut sql = select.to_string();
+    if !conditions.is_empty() {
+        sql += &format!(" WHERE {}", conditions.join(" AND "));
+    }
+    sql += order_by;
+
+    let connection = open(filename).map_err(|err| err as Box<dyn Error>)?;
+    let mut statement = connection.prepare(&sql)?;
+    let columns = statement.column_count();
+
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{}", statement.column_names().join("\t")).ok();
+
+    let mut rows = statement.query(params_from_iter(values))?;
+    while let Some(row) = rows.next()? {
+        let line = (0..columns)
+            .map(|i| {
+                Ok(match row.get::<_, Value>(i)? {
+                    Value::Null => String::new(),
+                    Value::Integer(val) => val.to_string(),
+                    Value::Real(val) => val.to_string(),
+                    Value::Text(val) => val,
+                    Value::Blob(val) => String::from_utf8_lossy(&val).to_string(),
+                })
+            })
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?
+            .join("\t");
+
+        writeln!(stdout, "{}", line).ok();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -1,6 +1,17 @@
 //! Mostly taken from https://github.com/changeutils/diff-rs/blob/master/src/lib.rs
 
-use std::{collections::VecDeque, io};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    io,
+};
+
+use regex::Regex;
+
+/// every diff within headers starts with this prefix
+pub const HEADER_DIFF_PREFIX: &str = "header ";
+
+/// every diff within issued cookies starts with this prefix
+pub const COOKIE_DIFF_PREFIX: &str = "cookie ";
 
 pub fn diff(text1: &str, text2: &str) -> io::Result<Vec<String>> {
     let mut processor = Processor::new();
@@ -177,3 +188,495 @@ impl diffs::Diff for Processor {
         Ok(())
     }
 }
+
+/// compares json documents structurally and returns json pointers of changed parts:
+/// +/path for added, -/path for removed and ~/path for changed values.
+/// paths from ignore (json pointers, * matches any segment) and their children are skipped.
+/// in case unordered is true, arrays are compared regardless of the order of their elements
+pub fn json_diff(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    ignore: &[String],
+    unordered: bool,
+) -> Vec<String> {
+    let mut result = Vec::new();
+    json_diff_recursive(old, new, String::new(), ignore, unordered, &mut result);
+    result
+}
+
+fn json_diff_recursive(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    path: String,
+    ignore: &[String],
+    unordered: bool,
+    result: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if ignore.iter().any(|x| json_path_matches(x, &path)) {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old.iter() {
+                let child_path = format!("{}/{}", path, escape_json_pointer(key));
+                match new.get(key) {
+                    Some(new_value) => {
+                        json_diff_recursive(old_value, new_value, child_path, ignore, unordered, result)
+                    }
+                    None => {
+                        if !ignore.iter().any(|x| json_path_matches(x, &child_path)) {
+                            result.push(format!("-{}", child_path))
+                        }
+                    }
+                }
+            }
+
+            for key in new.keys().filter(|x| !old.contains_key(*x)) {
+                let child_path = format!("{}/{}", path, escape_json_pointer(key));
+                if !ignore.iter().any(|x| json_path_matches(x, &child_path)) {
+                    result.push(format!("+{}", child_path));
+                }
+            }
+        }
+        (Value::Array(old_array), Value::Array(new_array)) if unordered => {
+            let mut old_array: Vec<String> = old_array.iter().map(|x| x.to_string()).collect();
+            let mut new_array: Vec<String> = new_array.iter().map(|x| x.to_string()).collect();
+            old_array.sort();
+            new_array.sort();
+
+            if old_array != new_array {
+                result.push(format!("~{}", path));
+            }
+        }
+        (Value::Array(old_array), Value::Array(new_array)) => {
+            for (i, old_value) in old_array.iter().enumerate() {
+                let child_path = format!("{}/{}", path, i);
+                match new_array.get(i) {
+                    Some(new_value) => {
+                        json_diff_recursive(old_value, new_value, child_path, ignore, unordered, result)
+                    }
+                    None => {
+                        if !ignore.iter().any(|x| json_path_matches(x, &child_path)) {
+                            result.push(format!("-{}", child_path))
+                        }
+                    }
+                }
+            }
+
+            for i in old_array.len()..new_array.len() {
+                let child_path = format!("{}/{}", path, i);
+                if !ignore.iter().any(|x| json_path_matches(x, &child_path)) {
+                    result.push(format!("+{}", child_path));
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                result.push(format!("~{}", path));
+            }
+        }
+    }
+}
+
+/// elements whose content isn't parsed as html
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "textarea", "title"];
+
+/// turns the html into one node per line, so pages are compared by their structure rather than by their formatting:
+/// tags are written with lowercased names and sorted attributes, whitespaces within text nodes are collapsed
+/// and comments are removed. Text nodes that match any of the ignore regexes are skipped
+pub fn html_lines(html: &str, ignore: &[Regex]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut rest = html;
+
+    // ascii lowercasing keeps byte offsets the same, so the ends of raw text elements are searched within the copy
+    let lowercase_html = html.to_ascii_lowercase();
+
+    let flush_text = |lines: &mut Vec<String>, text: &mut String| {
+        let node = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !node.is_empty() && !ignore.iter().any(|x| x.is_match(&node)) {
+            lines.push(node);
+        }
+        text.clear();
+    };
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // < that doesn't start a tag is a part of the text
+        if !matches!(rest[1..].chars().next(), Some(c) if c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?') {
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        }
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = match comment.find("-->") {
+                Some(end) => &comment[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+
+        let end = match tag_end(rest) {
+            Some(val) => val,
+            None => break,
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        flush_text(&mut lines, &mut text);
+
+        // doctypes and processing instructions are kept as they are
+        if tag.starts_with('!') || tag.starts_with('?') {
+            lines.push(format!("<{}>", tag.trim()));
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            lines.push(format!("</{}>", name.trim().to_lowercase()));
+            continue;
+        }
+
+        let (name, attributes) = parse_tag(tag);
+
+        let mut line = format!("<{}", name);
+        for (key, value) in attributes.iter() {
+            match value {
+                Some(value) => line += &format!(" {}=\"{}\"", key, value),
+                None => line += &format!(" {}", key),
+            }
+        }
+        line.push('>');
+        lines.push(line);
+
+        // the content of scripts and styles is a single text node
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) && !tag.trim_end().ends_with('/') {
+            let end = lowercase_html[html.len() - rest.len()..]
+                .find(&format!("</{}", name))
+                .unwrap_or(rest.len());
+            text.push_str(&rest[..end]);
+            flush_text(&mut lines, &mut text);
+            rest = &rest[end..];
+        }
+    }
+
+    text.push_str(rest);
+    flush_text(&mut lines, &mut text);
+
+    lines.join("\n")
+}
+
+/// the position of the > that closes the tag at the start of the text. > within quoted attribute values is skipped.
+/// in case a quote isn't closed, the tag ends at the first >, so the rest of the page isn't swallowed
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => (),
+        }
+    }
+
+    text.find('>')
+}
+
+/// returns the lowercased name of the tag and its attributes sorted by their names
+fn parse_tag(tag: &str) -> (String, BTreeMap<String, Option<String>>) {
+    let tag = tag.trim().trim_end_matches('/');
+
+    let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+    let name = tag[..name_end].to_lowercase();
+
+    let mut attributes = BTreeMap::new();
+    let mut rest = tag[name_end..].trim_start();
+
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let value = if let Some(value) = rest.strip_prefix('=') {
+            let value = value.trim_start();
+            let (val, remaining) = match value.chars().next() {
+                Some(q) if q == '"' || q == '\'' => match value[1..].find(q) {
+                    Some(end) => (&value[1..end + 1], &value[end + 2..]),
+                    None => (&value[1..], ""),
+                },
+                _ => {
+                    let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                    (&value[..end], &value[end..])
+                }
+            };
+            rest = remaining.trim_start();
+            Some(val.to_string())
+        } else {
+            None
+        };
+
+        // stray slashes (<br/ >) and equal signs aren't attributes
+        if key.is_empty() {
+            if value.is_none() {
+                let mut chars = rest.chars();
+                chars.next();
+                rest = chars.as_str().trim_start();
+            }
+            continue;
+        }
+
+        attributes.insert(key, value);
+    }
+
+    (name, attributes)
+}
+
+/// escapes the key according to RFC 6901
+fn escape_json_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// checks whether the path equals the pattern or is within it. * within the pattern matches any segment
+fn json_path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern_segments = pattern.split('/');
+    let mut path_segments = path.split('/');
+
+    loop {
+        match (pattern_segments.next(), path_segments.next()) {
+            (None, _) => return true,
+            (Some(_), None) => return false,
+            (Some(pattern_segment), Some(path_segment)) => {
+                if pattern_segment != "*" && pattern_segment != path_segment {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// compares headers by their names: "header +name" for added, "header -name" for removed
+/// and "header ~name" for headers with changed values. Names are case insensitive
+pub fn headers_diff(old: &[(String, String)], new: &[(String, String)]) -> Vec<String> {
+    fn group(headers: &[(String, String)]) -> BTreeMap<String, Vec<&str>> {
+        let mut grouped: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (k, v) in headers.iter() {
+            grouped.entry(k.to_lowercase()).or_default().push(v);
+        }
+        // headers like Set-Cookie may appear multiple times in any order
+        for values in grouped.values_mut() {
+            values.sort_unstable();
+        }
+        grouped
+    }
+
+    let (old, new) = (group(old), group(new));
+    let mut result = Vec::new();
+
+    for (name, old_values) in old.iter() {
+        match new.get(name) {
+            Some(new_values) if new_values != old_values => {
+                result.push(format!("{}~{}", HEADER_DIFF_PREFIX, name))
+            }
+            Some(_) => (),
+            None => result.push(format!("{}-{}", HEADER_DIFF_PREFIX, name)),
+        }
+    }
+
+    for name in new.keys().filter(|x| !old.contains_key(*x)) {
+        result.push(format!("{}+{}", HEADER_DIFF_PREFIX, name));
+    }
+
+    result
+}
+
+/// compares cookies from Set-Cookie headers by their names and attributes: "cookie +name" for issued,
+/// "cookie -name" for no longer issued and "cookie ~name" for cookies with changed attributes.
+/// Values and expiration dates are ignored since they are usually different every time
+pub fn cookies_diff(old: &[(String, String)], new: &[(String, String)]) -> Vec<String> {
+    fn group(headers: &[(String, String)]) -> BTreeMap<&str, Vec<String>> {
+        let mut grouped: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+        for (_, v) in headers.iter().filter(|(k, _)| k.eq_ignore_ascii_case("set-cookie")) {
+            let mut parts = v.split(';');
+            let name = parts.next().unwrap_or_default().split('=').next().unwrap_or_default().trim();
+
+            let mut attributes: Vec<String> = parts
+                .map(|x| x.trim().to_lowercase())
+                .filter(|x| !x.is_empty() && !x.starts_with("expires"))
+                .collect();
+            attributes.sort_unstable();
+
+            grouped.entry(name).or_default().push(attributes.join("; "));
+        }
+
+        for values in grouped.values_mut() {
+            values.sort_unstable();
+        }
+
+        grouped
+    }
+
+    let (old, new) = (group(old), group(new));
+    let mut result = Vec::new();
+
+    for (name, old_attributes) in old.iter() {
+        match new.get(name) {
+            Some(new_attributes) if new_attributes != old_attributes => {
+                result.push(format!("{}~{}", COOKIE_DIFF_PREFIX, name))
+            }
+            Some(_) => (),
+            None => result.push(format!("{}-{}", COOKIE_DIFF_PREFIX, name)),
+        }
+    }
+
+    for name in new.keys().filter(|x| !old.contains_key(*x)) {
+        result.push(format!("{}+{}", COOKIE_DIFF_PREFIX, name));
+    }
+
+    result
+}
+
+/// returns the token-level similarity ratio of two texts (from 0 to 1).
+/// texts are split into alphanumeric tokens, and the ratio is 2 * common tokens / all tokens
+pub fn similarity(text1: &str, text2: &str) -> f64 {
+    fn tokens(text: &str) -> HashMap<&str, usize> {
+        let mut tokens: HashMap<&str, usize> = HashMap::new();
+        for token in text.split(|c: char| !c.is_alphanumeric()).filter(|x| !x.is_empty()) {
+            *tokens.entry(token).or_default() += 1;
+        }
+        tokens
+    }
+
+    let (tokens1, tokens2) = (tokens(text1), tokens(text2));
+
+    let total: usize = tokens1.values().sum::<usize>() + tokens2.values().sum::<usize>();
+    if total == 0 {
+        return 1.;
+    }
+
+    let common: usize = tokens1
+        .iter()
+        .map(|(token, count)| *count.min(tokens2.get(token).unwrap_or(&0)))
+        .sum();
+
+    2. * common as f64 / total as f64
+}
+
+/// parts of the initial page that vary between requests with random parameters.
+/// learned from multiple baseline responses and used to ignore changes within these parts
+#[derive(Debug, Default, Clone)]
+pub struct StabilityMask {
+    /// indexes of the initial page's lines that were changed or removed
+    lines: BTreeSet<usize>,
+
+    /// indexes of the initial page's lines before which new lines were inserted
+    insertions: BTreeSet<usize>,
+}
+
+/// collects changes as (old index, old length, new index, new length)
+#[derive(Default)]
+struct ChangesCollector {
+    changes: Vec<(usize, usize, usize, usize)>,
+}
+
+impl diffs::Diff for ChangesCollector {
+    type Error = io::Error;
+
+    fn delete(&mut self, old: usize, len: usize) -> Result<(), Self::Error> {
+        self.changes.push((old, len, 0, 0));
+        Ok(())
+    }
+
+    fn insert(&mut self, old: usize, new: usize, new_len: usize) -> Result<(), Self::Error> {
+        self.changes.push((old, 0, new, new_len));
+        Ok(())
+    }
+
+    fn replace(
+        &mut self,
+        old: usize,
+        old_len: usize,
+        new: usize,
+        new_len: usize,
+    ) -> Result<(), Self::Error> {
+        self.changes.push((old, old_len, new, new_len));
+        Ok(())
+    }
+}
+
+/// returns hunks of changed lines: a @@ -old,len +new,len @@ line followed by removed (-) and inserted (+) lines
+pub fn changed_lines(initial: &str, text: &str) -> io::Result<Vec<String>> {
+    let (initial_lines, lines) = (
+        initial.lines().collect::<Vec<&str>>(),
+        text.lines().collect::<Vec<&str>>(),
+    );
+
+    let mut result = Vec::new();
+
+    for (old, old_len, new, new_len) in changes(initial, text)? {
+        result.push(format!("@@ -{},{} +{},{} @@", old + 1, old_len, new + 1, new_len));
+        result.extend(initial_lines[old..old + old_len].iter().map(|x| format!("-{}", x)));
+        result.extend(lines[new..new + new_len].iter().map(|x| format!("+{}", x)));
+    }
+
+    Ok(result)
+}
+
+fn changes(initial: &str, text: &str) -> io::Result<Vec<(usize, usize, usize, usize)>> {
+    let mut collector = ChangesCollector::default();
+    {
+        let mut replace = diffs::Replace::new(&mut collector);
+        diffs::myers::diff(
+            &mut replace,
+            &initial.lines().collect::<Vec<&str>>(),
+            &text.lines().collect::<Vec<&str>>(),
+        )?;
+    }
+    Ok(collector.changes)
+}
+
+impl StabilityMask {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.insertions.is_empty()
+    }
+
+    /// remembers parts of the initial page that differ from the baseline text
+    pub fn learn(&mut self, initial: &str, text: &str) -> io::Result<()> {
+        for (old, old_len, _, _) in changes(initial, text)? {
+            if old_len == 0 {
+                self.insertions.insert(old);
+            } else {
+                self.lines.extend(old..old + old_len);
+            }
+        }
+        Ok(())
+    }
+
+    /// returns changes between the initial page and the text that are outside the masked parts
+    pub fn diff(&self, initial: &str, text: &str) -> io::Result<Vec<String>> {
+        Ok(changes(initial, text)?
+            .into_iter()
+            .filter(|(old, old_len, _, _)| {
+                if *old_len == 0 {
+                    !self.insertions.contains(old)
+                } else {
+                    (*old..old + old_len).any(|x| !self.lines.contains(&x))
+                }
+            })
+            .map(|(old, old_len, new, new_len)| {
+                format!("-{},{} +{},{}", old + 1, old_len, new + 1, new_len)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests;
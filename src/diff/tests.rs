@@ -0,0 +1,49 @@
+use regex::Regex;
+
+use super::html_lines;
+
+#[test]
+fn html_structure() {
+    let html = "<!DOCTYPE html>\n<HTML><body  class='b'   id=main>\n  Hello,\n   world <!-- comment -->\n<BR/><p>a < b</p></body></html>";
+
+    assert_eq!(
+        html_lines(html, &[]),
+        "<!DOCTYPE html>\n<html>\n<body class=\"b\" id=\"main\">\nHello, world\n<br>\n<p>\na < b\n</p>\n</body>\n</html>"
+    );
+
+    // formatting and the order of attributes don't matter
+    assert_eq!(
+        html_lines("<p id=\"x\"   class=\"y\">text</p>", &[]),
+        html_lines("<P class='y' id=x>\n  text\n</P>", &[])
+    );
+}
+
+#[test]
+fn html_raw_text() {
+    let html = "<SCRIPT>if (a <b && c > d) { x = '</p>'; }</Script><p>text</p>";
+
+    assert_eq!(
+        html_lines(html, &[]),
+        "<script>\nif (a <b && c > d) { x = '</p>'; }\n</script>\n<p>\ntext\n</p>"
+    );
+
+    let ignore = [Regex::new("^token: ").unwrap()];
+    assert_eq!(
+        html_lines("<title>Page</title><p>token: 123</p>", &ignore),
+        "<title>\nPage\n</title>\n<p>\n</p>"
+    );
+}
+
+#[test]
+fn html_unbalanced_quotes() {
+    // the tag with the unclosed quote ends at the first >, so the rest of the page is still parsed
+    let html = "<a href=\"/x>link</a><p class=\"y\">text</p>";
+
+    assert_eq!(
+        html_lines(html, &[]),
+        "<a href=\"/x\">\nlink\n</a>\n<p class=\"y\">\ntext\n</p>"
+    );
+
+    // > within quoted values doesn't end the tag
+    assert_eq!(html_lines("<a title=\"a > b\">x</a>", &[]), "<a title=\"a > b\">\nx\n</a>");
+}
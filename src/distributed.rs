@@ -0,0 +1,770 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    io::{self, Write},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use indicatif::ProgressBar;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    config::{args::get_remote_config_from, structs::Config},
+    network::{
+        request::{Request, RequestDefaults},
+        response::Response,
+    },
+    runner::{
+        output::{ParseOutputs, RunnerOutput},
+        runner::Runner,
+        utils::{FoundParameter, Parameters},
+    },
+    server::{generate_token, read_request, write_json, ApiRequest, CLIENT_TIMEOUT},
+    utils::{self, read_lines},
+    wordlist::mutate,
+};
+
+/// the lease is handed out this amount of times before its parameters are reported as skipped
+const MAX_ATTEMPTS: usize = 3;
+
+/// workers ask for a lease again after this delay in case all the leases are taken
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// the coordinator keeps answering for a while after the last lease is done, so waiting workers exit as well
+const DONE_GRACE: Duration = Duration::from_secs(5);
+
+/// options of the scan that only the coordinator uses. Workers accept them besides the remote ones, but ignore them
+const COORDINATOR_OPTIONS: &[&str] = &["wordlist", "mutations", "output", "output-format"];
+
+/// the response to GET /job
+#[derive(Debug, Serialize, Deserialize)]
+struct JobInfo {
+    /// the scan arguments, e.g. ["-u", "https://example.com", "-X", "POST"]
+    args: Vec<String>,
+
+    /// url:method pairs. Leases refer to them by index
+    pairs: Vec<(String, String)>,
+}
+
+/// a part of the wordlist for one url:method pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Lease {
+    id: usize,
+
+    pair: usize,
+
+    params: Vec<String>,
+
+    /// custom parameters are checked only within the first lease of the pair
+    first: bool,
+
+    /// the diffs already collected for the pair by the other workers
+    #[serde(default)]
+    diffs: Vec<String>,
+}
+
+/// the body of POST /join
+#[derive(Debug, Serialize, Deserialize)]
+struct JoinRequest {
+    name: String,
+}
+
+/// the response to POST /join
+#[derive(Debug, Serialize, Deserialize)]
+struct JoinResponse {
+    /// the id the worker uses within the other requests
+    worker: String,
+}
+
+/// the body of POST /lease
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseRequest {
+    worker: String,
+
+    /// the pair the worker has already learned
+    pair: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseResponse {
+    lease: Option<Lease>,
+
+    /// every lease is done, so the worker can exit
+    done: bool,
+}
+
+/// the body of POST /result
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseResult {
+    lease: usize,
+
+    worker: String,
+
+    /// initial response code and size
+    status: u16,
+    size: usize,
+
+    diffs: Vec<String>,
+
+    found_params: Vec<FoundParameter>,
+
+    skipped_params: Vec<String>,
+}
+
+/// the body of POST /fail
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseFailure {
+    lease: usize,
+
+    worker: String,
+
+    error: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum LeaseState {
+    Pending,
+    Leased { worker: String, until: Instant },
+    Done,
+}
+
+struct LeaseEntry {
+    lease: Lease,
+
+    state: LeaseState,
+
+    /// the workers the lease was handed out to. Late results of the workers that ran out of time are accepted as well
+    workers: Vec<String>,
+}
+
+/// the results of the leases of one url:method pair
+#[derive(Debug, Default)]
+struct Target {
+    status: u16,
+    size: usize,
+    diffs: Vec<String>,
+    found_params: Vec<FoundParameter>,
+    skipped_params: Vec<String>,
+}
+
+struct Coordinator {
+    pairs: Vec<(String, String)>,
+    leases: Vec<LeaseEntry>,
+    targets: Vec<Target>,
+    lease_timeout: Duration,
+
+    /// ids of the workers that joined
+    workers: HashSet<String>,
+}
+
+impl Coordinator {
+    fn new(pairs: Vec<(String, String)>, params: &[String], lease_size: usize, lease_timeout: Duration) -> Self {
+        let mut leases = Vec::new();
+
+        for pair in 0..pairs.len() {
+            for (i, chunk) in params.chunks(lease_size.max(1)).enumerate() {
+                leases.push(LeaseEntry {
+                    lease: Lease {
+                        id: leases.len(),
+                        pair,
+                        params: chunk.to_vec(),
+                        first: i == 0,
+                        diffs: Vec::new(),
+                    },
+                    state: LeaseState::Pending,
+                    workers: Vec::new(),
+                });
+            }
+        }
+
+        Self {
+            targets: pairs.iter().map(|_| Target::default()).collect(),
+            pairs,
+            leases,
+            lease_timeout,
+            workers: HashSet::new(),
+        }
+    }
+
+    /// registers the worker under a random id. Every worker shares the token,
+    /// so names alone would let one worker take over the leases of another one
+    fn join(&mut self, name: &str) -> String {
+        let worker = format!("{}-{}", name, &generate_token()[..8]);
+        self.workers.insert(worker.clone());
+        worker
+    }
+
+    fn is_done(&self) -> bool {
+        self.leases.iter().all(|x| x.state == LeaseState::Done)
+    }
+
+    fn left(&self) -> usize {
+        self.leases.iter().filter(|x| x.state != LeaseState::Done).count()
+    }
+
+    /// hands out a pending lease of the pair the worker has already learned.
+    /// without the pair, leases of the pairs nobody is working on are preferred, so fewer workers have to learn the same pair
+    fn next_lease(&mut self, worker: &str, pair: Option<usize>) -> Option<Lease> {
+        self.expire();
+
+        let index = match pair {
+            Some(pair) => self
+                .leases
+                .iter()
+                .position(|x| x.lease.pair == pair && x.state == LeaseState::Pending),
+            None => {
+                let is_busy = |pair: usize| {
+                    self.leases
+                        .iter()
+                        .any(|x| x.lease.pair == pair && matches!(x.state, LeaseState::Leased { .. }))
+                };
+
+                self.leases
+                    .iter()
+                    .position(|x| x.state == LeaseState::Pending && !is_busy(x.lease.pair))
+                    .or_else(|| self.leases.iter().position(|x| x.state == LeaseState::Pending))
+            }
+        }?;
+
+        let entry = &mut self.leases[index];
+        entry.workers.push(worker.to_string());
+        entry.state = LeaseState::Leased {
+            worker: worker.to_string(),
+            until: Instant::now() + self.lease_timeout,
+        };
+
+        let mut lease = entry.lease.clone();
+        lease.diffs = self.targets[lease.pair].diffs.clone();
+
+        Some(lease)
+    }
+
+    /// returns the leases of the workers that didn't answer in time
+    fn expire(&mut self) {
+        let now = Instant::now();
+
+        let expired: Vec<usize> = self
+            .leases
+            .iter()
+            .filter(|x| matches!(&x.state, LeaseState::Leased { until, .. } if *until < now))
+            .map(|x| x.lease.id)
+            .collect();
+
+        for id in expired {
+            self.release(id, "the lease timed out");
+        }
+    }
+
+    /// puts the lease back to the queue, or gives up on it after MAX_ATTEMPTS
+    fn release(&mut self, id: usize, reason: &str) {
+        let entry = &mut self.leases[id];
+
+        let worker = match &entry.state {
+            LeaseState::Leased { worker, .. } => worker.clone(),
+            _ => return,
+        };
+
+        let (url, method) = &self.pairs[entry.lease.pair];
+
+        if entry.workers.len() >= MAX_ATTEMPTS {
+            entry.state = LeaseState::Done;
+            self.targets[entry.lease.pair]
+                .skipped_params
+                .extend(entry.lease.params.iter().cloned());
+
+            utils::error(
+                format!("lease {} ({}) is skipped after {} attempts: {}", id, method, MAX_ATTEMPTS, reason),
+                Some(url),
+                None,
+                None,
+            );
+        } else {
+            entry.state = LeaseState::Pending;
+
+            writeln!(io::stdout(), "[#] Lease {} of {} {} is re-leased ({}: {})", id, method, url, worker, reason).ok();
+        }
+    }
+
+    /// merges the results of the lease. Results of the leases that are already done or were given up on are ignored
+    fn complete(&mut self, result: LeaseResult) -> Result<(), Box<dyn Error>> {
+        let entry = self.leases.get_mut(result.lease).ok_or("The lease is not found")?;
+
+        if !entry.workers.contains(&result.worker) {
+            Err("The lease wasn't handed out to the worker")?;
+        }
+
+        if entry.state == LeaseState::Done {
+            return Ok(());
+        }
+        entry.state = LeaseState::Done;
+
+        let target = &mut self.targets[entry.lease.pair];
+
+        if result.status != 0 {
+            target.status = result.status;
+            target.size = result.size;
+        }
+
+        for diff in result.diffs {
+            if !target.diffs.contains(&diff) {
+                target.diffs.push(diff);
+            }
+        }
+
+        for found_param in result.found_params {
+            if !target.found_params.contains_name(&found_param.name) {
+                target.found_params.push(found_param);
+            }
+        }
+
+        target.skipped_params.extend(result.skipped_params);
+
+        Ok(())
+    }
+
+    /// puts the lease back to the queue. Failures of the workers that don't hold the lease anymore are ignored
+    fn fail(&mut self, failure: LeaseFailure) -> Result<(), Box<dyn Error>> {
+        let entry = self.leases.get(failure.lease).ok_or("The lease is not found")?;
+
+        if !entry.workers.contains(&failure.worker) {
+            Err("The lease wasn't handed out to the worker")?;
+        }
+
+        if matches!(&entry.state, LeaseState::Leased { worker, .. } if *worker == failure.worker) {
+            self.release(failure.lease, &failure.error);
+        }
+
+        Ok(())
+    }
+}
+
+/// splits the wordlist of the scan into leases of lease_size parameters and hands them out to the workers.
+/// prints the merged results of every url:method pair once all the leases are done.
+/// every request of the workers needs the token within the Authorization header
+pub async fn coordinate(
+    listen: &str,
+    lease_size: usize,
+    lease_timeout: Duration,
+    args: Vec<String>,
+    token: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    // workers parse the same options, so only the ones they accept are forwarded
+    let config = get_remote_config_from(&args, COORDINATOR_OPTIONS)?;
+
+    if config.wordlist.is_empty() {
+        Err("The coordinator needs the wordlist (-w) to split")?;
+    }
+
+    let mut params: Vec<String> = read_lines(&config.wordlist)?.map_while(Result::ok).collect();
+
+    // such headers usually cause server to timeout
+    if config.headers_discovery {
+        params.retain(|x| "content-length" != x.to_lowercase() && "host" != x.to_lowercase());
+    }
+
+    if config.mutations {
+        params = mutate(params, config.headers_discovery);
+    }
+
+    if params.is_empty() {
+        Err("The wordlist is empty")?;
+    }
+
+    let pairs: Vec<(String, String)> = config
+        .urls
+        .iter()
+        .flat_map(|url| {
+            config
+                .methods_for(url)
+                .into_iter()
+                .map(move |method| (url.to_owned(), method))
+        })
+        .collect();
+
+    let job = Arc::new(serde_json::to_value(JobInfo { args, pairs: pairs.clone() })?);
+    let coordinator = Arc::new(Mutex::new(Coordinator::new(pairs, &params, lease_size, lease_timeout)));
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|err| format!("Unable to listen on {}: {}", listen, err))?;
+
+    writeln!(
+        io::stdout(),
+        "Coordinating {} leases on http://{}",
+        coordinator.lock().leases.len(),
+        listen
+    )
+    .ok();
+
+    let token: Arc<str> = match token {
+        Some(token) => token.into(),
+        None => {
+            let token = generate_token();
+            writeln!(io::stdout(), "[#] Workers should use the token {}", token).ok();
+            token.into()
+        }
+    };
+
+    let mut finished: Option<Instant> = None;
+
+    loop {
+        if finished.is_none() && coordinator.lock().is_done() {
+            write_outputs(&config, &coordinator.lock())?;
+            finished = Some(Instant::now());
+        }
+
+        if finished.is_some_and(|x| x.elapsed() > DONE_GRACE) {
+            break;
+        }
+
+        // the accept is interrupted from time to time to check whether the job is done
+        let (mut stream, addr) = match tokio::time::timeout(RETRY_DELAY, listener.accept()).await {
+            Ok(Ok(val)) => val,
+            Ok(Err(err)) => {
+                log::debug!("Unable to accept the connection: {}", err);
+                continue;
+            }
+            Err(_) => continue,
+        };
+
+        let (coordinator, job, token) = (Arc::clone(&coordinator), Arc::clone(&job), Arc::clone(&token));
+
+        // every connection is handled separately, so slow workers don't block the others
+        tokio::spawn(async move {
+            if let Err(err) = tokio::time::timeout(CLIENT_TIMEOUT, handle_connection(&mut stream, &coordinator, &job, &token))
+                .await
+                .unwrap_or_else(|_| Err("timed out".into()))
+            {
+                log::debug!("Unable to handle the request from {}: {}", addr, err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn handle_connection(
+    stream: &mut TcpStream,
+    coordinator: &Mutex<Coordinator>,
+    job: &serde_json::Value,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let request = read_request(stream).await?;
+
+    let (code, response) = match request.check(token) {
+        Some(val) => val,
+        None => respond(coordinator, job, &request),
+    };
+
+    write_json(stream, code, &response).await
+}
+
+/// returns the code and the body of the response to the api request
+fn respond(coordinator: &Mutex<Coordinator>, job: &serde_json::Value, request: &ApiRequest) -> (u16, serde_json::Value) {
+    let body = &request.body;
+
+    let result: Result<serde_json::Value, Box<dyn Error>> = match (request.method.as_str(), request.path.trim_end_matches('/')) {
+        ("GET", "/job") => Ok(job.clone()),
+        ("POST", "/join") => serde_json::from_str::<JoinRequest>(body)
+            .map_err(|err| err.into())
+            .and_then(|request| {
+                let worker = coordinator.lock().join(&request.name);
+                writeln!(io::stdout(), "[#] {} joined", worker).ok();
+
+                Ok(serde_json::to_value(JoinResponse { worker })?)
+            }),
+        ("POST", "/lease") => serde_json::from_str::<LeaseRequest>(body)
+            .map_err(|err| err.into())
+            .and_then(|request| {
+                let mut coordinator = coordinator.lock();
+                if !coordinator.workers.contains(&request.worker) {
+                    Err("Unknown worker. Workers have to join via POST /join first")?;
+                }
+
+                let lease = coordinator.next_lease(&request.worker, request.pair);
+
+                Ok(serde_json::to_value(LeaseResponse { lease, done: coordinator.is_done() })?)
+            }),
+        ("POST", "/result") => serde_json::from_str::<LeaseResult>(body)
+            .map_err(|err| err.into())
+            .and_then(|result| {
+                let mut coordinator = coordinator.lock();
+                coordinator.complete(result)?;
+
+                Ok(json!({ "left": coordinator.left() }))
+            }),
+        ("POST", "/fail") => serde_json::from_str::<LeaseFailure>(body)
+            .map_err(|err| err.into())
+            .and_then(|failure| {
+                coordinator.lock().fail(failure)?;
+
+                Ok(json!({}))
+            }),
+        _ => return (404, json!({ "error": "Not found" })),
+    };
+
+    match result {
+        Ok(val) => (200, val),
+        Err(err) => (400, json!({ "error": err.to_string() })),
+    }
+}
+
+/// prints the merged results and writes them to the output file
+fn write_outputs(config: &Config, coordinator: &Coordinator) -> Result<(), Box<dyn Error>> {
+    let mut outputs = Vec::new();
+
+    for ((url, method), target) in coordinator.pairs.iter().zip(coordinator.targets.iter()) {
+        let request_defaults = RequestDefaults::from_config(config, method, url)?;
+
+        let mut output = RunnerOutput::new(&request_defaults, &Response::default(), target.found_params.clone())
+            .with_skipped_params(target.skipped_params.clone());
+        output.status = target.status;
+        output.size = target.size;
+        output.prepare(config, &request_defaults);
+
+        if !(config.remove_empty && output.found_params.is_empty()) {
+            outputs.push(output);
+        }
+    }
+
+    let output = if config.is_output_deferred() {
+        outputs.parse_output(config)
+    } else {
+        outputs.iter().map(|x| x.parse(config)).collect::<Vec<String>>().join("\n")
+    };
+
+    if !config.output_file.is_empty() {
+        fs::write(&config.output_file, strip_ansi_escapes::strip(output.as_bytes())?)?;
+    }
+
+    writeln!(io::stdout(), "\n{}", output).ok();
+
+    Ok(())
+}
+
+/// asks the coordinator for leases and checks them until every lease is done
+pub async fn work(coordinator: &str, name: Option<&str>, token: &str) -> Result<(), Box<dyn Error>> {
+    let base = if coordinator.starts_with("http://") {
+        coordinator.trim_end_matches('/').to_string()
+    } else {
+        format!("http://{}", coordinator)
+    };
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(reqwest::header::AUTHORIZATION, format!("Bearer {}", token).parse()?);
+
+    let client = reqwest::Client::builder()
+        .timeout(CLIENT_TIMEOUT)
+        .default_headers(headers)
+        .build()?;
+
+    let response = client
+        .get(format!("{}/job", base))
+        .send()
+        .await
+        .map_err(|err| format!("Unable to connect to the coordinator {}: {}", base, err))?;
+
+    let status = response.status();
+    let text = response.text().await?;
+
+    if !status.is_success() {
+        Err(format!("The coordinator rejected the worker: {}", text))?;
+    }
+
+    let job: JobInfo = serde_json::from_str(&text)?;
+    let config = job_config(&job.args)?;
+
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => format!("worker-{}", std::process::id()),
+    };
+    let worker = post::<_, JoinResponse>(&client, &base, "join", &JoinRequest { name }).await?.worker;
+
+    writeln!(io::stdout(), "[#] {} is checking leases of {}", worker, base).ok();
+
+    loop {
+        // the leases in progress are re-leased by the coordinator after the timeout
+        if let Some(reason) = config.budget.exhausted() {
+            writeln!(io::stdout(), "[#] Budget exhausted ({})", reason).ok();
+            break;
+        }
+
+        let response: LeaseResponse = post(&client, &base, "lease", &LeaseRequest { worker: worker.clone(), pair: None }).await?;
+
+        let lease = match response.lease {
+            Some(lease) => lease,
+            None if response.done => break,
+            None => {
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        let (url, method) = job
+            .pairs
+            .get(lease.pair)
+            .ok_or("The coordinator sent an unknown url:method pair")?;
+
+        check_pair(&config, &client, &base, &worker, url, method, lease).await?;
+    }
+
+    Ok(())
+}
+
+/// parses the scan arguments of the job.
+/// the options are checked in the same way as for x8 serve (${NAME} included),
+/// so the coordinator can't make the worker run commands, touch files or read its environment
+fn job_config(args: &[String]) -> Result<Config, Box<dyn Error>> {
+    let mut config = get_remote_config_from(args, COORDINATOR_OPTIONS)?;
+
+    // the wordlist is split and the results are printed by the coordinator
+    config.disable_progress_bar = true;
+    config.remove_banner = true;
+    config.wordlist = String::new();
+    config.mutations = false;
+    config.output_file = String::new();
+    config.output_db = None;
+
+    Ok(config)
+}
+
+/// learns the url:method pair and checks its leases until the coordinator runs out of them.
+/// in case the pair can't be checked, the current lease is returned to the coordinator
+async fn check_pair(
+    config: &Config,
+    client: &reqwest::Client,
+    base: &str,
+    worker: &str,
+    url: &str,
+    method: &str,
+    mut lease: Lease,
+) -> Result<(), Box<dyn Error>> {
+    let progress_bar = ProgressBar::hidden();
+
+    let request_defaults = async {
+        let request_defaults = RequestDefaults::from_config(config, method, url)?;
+
+        // get cookies
+        Request::new(&request_defaults, Vec::new()).send().await?;
+
+        Ok::<RequestDefaults, Box<dyn Error>>(request_defaults)
+    }
+    .await;
+
+    let mut request_defaults = match request_defaults {
+        Ok(val) => val,
+        Err(err) => return fail(config, client, base, worker, url, &lease, err).await,
+    };
+
+    let mut runner = match Runner::new(config, &mut request_defaults, &progress_bar, lease.pair + 1).await {
+        Ok(val) => val,
+        Err(err) => return fail(config, client, base, worker, url, &lease, err).await,
+    };
+
+    if let Err(err) = runner.learn().await {
+        return fail(config, client, base, worker, url, &lease, err).await;
+    }
+
+    loop {
+        for diff in std::mem::take(&mut lease.diffs) {
+            if !runner.diffs.contains(&diff) {
+                runner.diffs.push(diff);
+            }
+        }
+
+        // custom parameters and --pairs are checked once per pair
+        let (diffs, output) = match runner.discover(&lease.params, lease.first).await {
+            Ok(val) => val,
+            Err(err) => return fail(config, client, base, worker, url, &lease, err).await,
+        };
+
+        // the next leases of the pair are compared with the new diffs as well
+        for diff in diffs.iter() {
+            if !runner.diffs.contains(diff) {
+                runner.diffs.push(diff.to_owned());
+            }
+        }
+
+        let _: serde_json::Value = post(
+            client,
+            base,
+            "result",
+            &LeaseResult {
+                lease: lease.id,
+                worker: worker.to_string(),
+                status: output.status,
+                size: output.size,
+                diffs,
+                found_params: output.found_params,
+                skipped_params: output.skipped_params,
+            },
+        )
+        .await?;
+
+        if config.budget.is_exhausted() {
+            return Ok(());
+        }
+
+        let response: LeaseResponse = post(
+            client,
+            base,
+            "lease",
+            &LeaseRequest { worker: worker.to_string(), pair: Some(lease.pair) },
+        )
+        .await?;
+
+        match response.lease {
+            Some(next) => lease = next,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// prints the error and returns the lease to the coordinator
+async fn fail(
+    config: &Config,
+    client: &reqwest::Client,
+    base: &str,
+    worker: &str,
+    url: &str,
+    lease: &Lease,
+    err: Box<dyn Error>,
+) -> Result<(), Box<dyn Error>> {
+    let error = err.to_string();
+    utils::error(err, Some(url), None, Some(config));
+
+    let _: serde_json::Value = post(client, base, "fail", &LeaseFailure { lease: lease.id, worker: worker.to_string(), error }).await?;
+
+    Ok(())
+}
+
+async fn post<T: Serialize, R: DeserializeOwned>(
+    client: &reqwest::Client,
+    base: &str,
+    endpoint: &str,
+    body: &T,
+) -> Result<R, Box<dyn Error>> {
+    let response = client
+        .post(format!("{}/{}", base, endpoint))
+        .json(body)
+        .send()
+        .await
+        .map_err(|err| format!("Unable to reach the coordinator: {}", err))?;
+
+    let status = response.status();
+    let text = response.text().await?;
+
+    if !status.is_success() {
+        Err(format!("The coordinator rejected /{}: {}", endpoint, text))?;
+    }
+
+    Ok(serde_json::from_str(&text)?)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde_json::json;
+
+use crate::{
+    runner::utils::{FoundParameter, ReasonKind},
+    server::ApiRequest,
+};
+
+use super::{job_config, respond, Coordinator, LeaseFailure, LeaseResult, LeaseState, MAX_ATTEMPTS};
+
+fn coordinator(lease_timeout: Duration) -> Coordinator {
+    let params: Vec<String> = (0..4).map(|x| format!("param{}", x)).collect();
+
+    Coordinator::new(
+        vec![("https://example.com/".to_string(), "GET".to_string())],
+        &params,
+        2,
+        lease_timeout,
+    )
+}
+
+fn result(lease: usize, worker: &str, found: &[&str]) -> LeaseResult {
+    LeaseResult {
+        lease,
+        worker: worker.to_string(),
+        status: 200,
+        size: 10,
+        diffs: Vec::new(),
+        found_params: found
+            .iter()
+            .map(|x| FoundParameter::new(*x, &[], 200, 10, ReasonKind::Text))
+            .collect(),
+        skipped_params: Vec::new(),
+    }
+}
+
+fn failure(lease: usize, worker: &str) -> LeaseFailure {
+    LeaseFailure {
+        lease,
+        worker: worker.to_string(),
+        error: "error".to_string(),
+    }
+}
+
+#[test]
+fn leases() {
+    let mut coordinator = coordinator(Duration::from_secs(60));
+    assert_eq!(coordinator.left(), 2);
+
+    let first = coordinator.next_lease("a", None).unwrap();
+    assert!(first.first);
+    assert_eq!(first.params, ["param0", "param1"]);
+
+    let second = coordinator.next_lease("b", Some(0)).unwrap();
+    assert!(!second.first);
+    assert!(coordinator.next_lease("c", None).is_none());
+
+    coordinator.complete(result(first.id, "a", &["param1"])).unwrap();
+    coordinator.complete(result(second.id, "b", &[])).unwrap();
+    assert!(coordinator.is_done());
+    assert_eq!(coordinator.targets[0].found_params.len(), 1);
+}
+
+#[test]
+fn re_lease_after_timeout() {
+    let mut coordinator = coordinator(Duration::from_millis(1));
+
+    let lease = coordinator.next_lease("a", None).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+
+    // the expired lease is handed out again before the other one
+    let again = coordinator.next_lease("b", None).unwrap();
+    assert_eq!(again.id, lease.id);
+    assert_eq!(coordinator.leases[lease.id].workers, ["a", "b"]);
+}
+
+#[test]
+fn max_attempts() {
+    let mut coordinator = coordinator(Duration::from_secs(60));
+
+    for i in 0..MAX_ATTEMPTS {
+        let worker = format!("worker{}", i);
+        let lease = coordinator.next_lease(&worker, Some(0)).unwrap();
+        assert_eq!(lease.id, 0);
+
+        coordinator.fail(failure(lease.id, &worker)).unwrap();
+    }
+
+    // the parameters of the lease are reported as skipped and the other lease is handed out
+    assert_eq!(coordinator.leases[0].state, LeaseState::Done);
+    assert_eq!(coordinator.targets[0].skipped_params, ["param0", "param1"]);
+    assert_eq!(coordinator.next_lease("a", Some(0)).unwrap().id, 1);
+}
+
+#[test]
+fn late_results() {
+    let mut coordinator = coordinator(Duration::from_millis(1));
+
+    let lease = coordinator.next_lease("a", None).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    coordinator.next_lease("b", None).unwrap();
+
+    // the late failure of the first worker doesn't release the lease of the second one
+    coordinator.fail(failure(lease.id, "a")).unwrap();
+    assert!(matches!(&coordinator.leases[lease.id].state, LeaseState::Leased { worker, .. } if worker == "b"));
+
+    // the late result is accepted, and the result of the second worker is ignored
+    coordinator.complete(result(lease.id, "a", &["param0"])).unwrap();
+    coordinator.complete(result(lease.id, "b", &["param1"])).unwrap();
+    assert_eq!(coordinator.leases[lease.id].state, LeaseState::Done);
+    assert_eq!(coordinator.targets[0].found_params.len(), 1);
+    assert_eq!(coordinator.targets[0].found_params[0].name, "param0");
+}
+
+#[test]
+fn unknown_workers() {
+    let mut coordinator = coordinator(Duration::from_secs(60));
+
+    let lease = coordinator.next_lease("a", None).unwrap();
+
+    assert!(coordinator.complete(result(lease.id, "b", &["param0"])).is_err());
+    assert!(coordinator.fail(failure(lease.id, "b")).is_err());
+    assert!(coordinator.complete(result(100, "a", &[])).is_err());
+    assert!(matches!(coordinator.leases[lease.id].state, LeaseState::Leased { .. }));
+}
+
+fn api_request(path: &str, body: serde_json::Value) -> ApiRequest {
+    ApiRequest {
+        method: "POST".to_string(),
+        path: path.to_string(),
+        headers: Vec::new(),
+        body: body.to_string(),
+    }
+}
+
+#[test]
+fn joined_workers() {
+    let coordinator = Mutex::new(coordinator(Duration::from_secs(60)));
+    let job = json!({});
+
+    // names aren't accepted without joining
+    let (code, _) = respond(&coordinator, &job, &api_request("/lease", json!({ "worker": "a", "pair": null })));
+    assert_eq!(code, 400);
+
+    let (code, first) = respond(&coordinator, &job, &api_request("/join", json!({ "name": "a" })));
+    assert_eq!(code, 200);
+    let (_, second) = respond(&coordinator, &job, &api_request("/join", json!({ "name": "a" })));
+
+    let worker = first["worker"].as_str().unwrap();
+    assert!(worker.starts_with("a-"));
+    assert_ne!(first["worker"], second["worker"]);
+
+    let (code, response) = respond(&coordinator, &job, &api_request("/lease", json!({ "worker": worker, "pair": null })));
+    assert_eq!(code, 200);
+    assert_eq!(response["lease"]["id"], 0);
+}
+
+#[test]
+fn job_env_interpolation() {
+    let args = |header: &str| -> Vec<String> {
+        ["-u", "https://example.com/", "-w", "params.txt", "-H", header]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    };
+
+    assert!(job_config(&args("X-Header: 1")).is_ok());
+
+    let err = job_config(&args("X-Secret: ${AWS_SECRET_ACCESS_KEY}")).unwrap_err();
+    assert!(err.to_string().contains("${NAME}"));
+}
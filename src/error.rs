@@ -0,0 +1,89 @@
+use std::{error::Error, io};
+
+use thiserror::Error;
+
+/// categories of errors, so scripts can tell a down target from an unstable page or a wrong option
+#[derive(Debug, Error)]
+pub enum X8Error {
+    /// the target can't be reached or keeps blocking requests
+    #[error("{0}")]
+    Network(String),
+
+    /// the page changes on its own, so parameters can't be checked
+    #[error("{0}")]
+    Stability(String),
+
+    /// the request, HAR, checkpoint or cassette file can't be parsed
+    #[error("{0}")]
+    Parse(String),
+
+    /// wrong options
+    #[error("{0}")]
+    Config(String),
+
+    /// the results can't be written
+    #[error("{0}")]
+    Output(String),
+}
+
+impl X8Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            X8Error::Config(_) => 2,
+            X8Error::Network(_) => 3,
+            X8Error::Stability(_) => 4,
+            X8Error::Parse(_) => 5,
+            X8Error::Output(_) => 6,
+        }
+    }
+}
+
+/// puts the error into the category unless it's already categorized
+pub fn categorize(err: Box<dyn Error>, category: fn(String) -> X8Error) -> Box<dyn Error> {
+    if err.is::<X8Error>() {
+        err
+    } else {
+        Box::new(category(err.to_string()))
+    }
+}
+
+/// returns the exit code of the error's category. Errors of http clients and sockets are network ones,
+/// while other io errors come from files: missing or unreadable ones are config errors and malformed ones are parse errors
+pub fn exit_code(err: &(dyn Error + 'static)) -> i32 {
+    if let Some(err) = err.downcast_ref::<X8Error>() {
+        err.exit_code()
+    } else if err.is::<reqwest::Error>() {
+        X8Error::Network(String::new()).exit_code()
+    } else if let Some(err) = err.downcast_ref::<io::Error>() {
+        io_error_category(err)(String::new()).exit_code()
+    } else {
+        1
+    }
+}
+
+/// whether the error won't go away on its own (wrong options or results that can't be written),
+/// so checking the rest of the parameters is pointless
+pub fn is_permanent(err: &(dyn Error + 'static)) -> bool {
+    match err.downcast_ref::<X8Error>() {
+        Some(err) => matches!(err, X8Error::Config(_) | X8Error::Output(_)),
+        None => false,
+    }
+}
+
+fn io_error_category(err: &io::Error) -> fn(String) -> X8Error {
+    match err.kind() {
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::AddrInUse
+        | io::ErrorKind::AddrNotAvailable
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::TimedOut => X8Error::Network,
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => X8Error::Parse,
+        _ => X8Error::Config,
+    }
+}
+
+#[cfg(test)]
+mod tests;
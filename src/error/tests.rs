@@ -0,0 +1,26 @@
+use std::{error::Error, io};
+
+use super::{exit_code, X8Error};
+
+fn io_exit_code(kind: io::ErrorKind) -> i32 {
+    let err: Box<dyn Error> = Box::new(io::Error::new(kind, "test"));
+    exit_code(err.as_ref())
+}
+
+#[test]
+fn io_error_codes() {
+    // files
+    assert_eq!(io_exit_code(io::ErrorKind::NotFound), 2);
+    assert_eq!(io_exit_code(io::ErrorKind::PermissionDenied), 2);
+    assert_eq!(io_exit_code(io::ErrorKind::InvalidData), 5);
+
+    // sockets
+    assert_eq!(io_exit_code(io::ErrorKind::ConnectionRefused), 3);
+    assert_eq!(io_exit_code(io::ErrorKind::TimedOut), 3);
+
+    let err: Box<dyn Error> = Box::new(X8Error::Output("test".to_string()));
+    assert_eq!(exit_code(err.as_ref()), 6);
+
+    let err: Box<dyn Error> = "test".into();
+    assert_eq!(exit_code(err.as_ref()), 1);
+}
@@ -1,8 +1,61 @@
+//! Hidden parameters discovery suite.
+//!
+//! Besides the command line tool, the crate can be used to embed parameter discovery into other tools:
+//!
+//! ```no_run
+//! use indicatif::ProgressBar;
+//! use x8::{scan, Config, RequestDefaults};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let url = "https://example.com/";
+//!
+//! let config = Config {
+//!     urls: vec![url.to_string()],
+//!     ..Default::default()
+//! };
+//!
+//! let mut request_defaults = RequestDefaults::from_config(&config, "GET", url)?;
+//! let mut params = vec!["debug".to_string(), "admin".to_string()];
+//!
+//! let output = scan(&config, &mut request_defaults, &mut params, &ProgressBar::hidden(), 0).await?;
+//!
+//! for param in output.found_params {
+//!     println!("{} {:?}", param.name, param.reason_kind);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! For more control, [`Runner`] can be created directly: [`Runner::new`] makes the initial requests
+//! and [`Runner::check_parameters`] checks a list of parameters once.
+//! Responses are compared via [`Response::compare`].
+
+pub mod compare;
 pub mod config;
+pub mod database;
 pub mod diff;
+pub mod distributed;
+pub mod error;
+pub mod metrics;
 pub mod network;
+pub mod replay;
 pub mod runner;
+pub mod server;
 pub mod utils;
+pub mod wordlist;
+
+pub use config::structs::Config;
+pub use error::X8Error;
+pub use network::{
+    request::{Request, RequestDefaults},
+    response::Response,
+};
+pub use runner::{
+    output::RunnerOutput,
+    runner::Runner,
+    scan::scan,
+    utils::{FoundParameter, ReasonKind},
+};
 
 const RANDOM_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
 
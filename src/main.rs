@@ -1,9 +1,12 @@
 extern crate x8;
 use std::{
     error::Error,
-    sync::Arc,
-    io::{self, Write},
-    iter::FromIterator,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
 };
 
 use parking_lot::Mutex;
@@ -12,31 +15,49 @@ use atty::Stream;
 use futures::StreamExt;
 use indicatif::ProgressBar;
 use colored::Colorize;
+use serde_json::json;
 
 use x8::{
-    config::args::get_config,
+    compare::compare,
+    database::{query, QueryFilters, ResultsDb},
+    distributed::{coordinate, work},
+    error::{categorize, exit_code, is_permanent, X8Error},
+    metrics::serve_metrics,
+    config::args::{config_from_matches, get_app},
+    config::file::with_config_file,
     config::{structs::Config, utils::write_banner_config},
     network::{
+        rate_limiter::Gate,
         request::{Request, RequestDefaults},
-        utils::Headers,
     },
     runner::{
+        methods::discover_methods,
         output::{ParseOutputs, RunnerOutput},
-        runner::Runner,
-        utils::{Parameters, ReasonKind},
+        report::html_report,
+        scan::{scan, scan_batches},
     },
+    replay::replay,
+    server::serve,
     utils::{self, init_progress, read_lines, read_stdin_lines},
+    wordlist::{mutate, WordlistBatches},
 };
 
+/// the exit code of the first url that failed
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+
+/// how often progress events are written with --progress json
+const PROGRESS_JSON_INTERVAL: Duration = Duration::from_secs(1);
+
 #[cfg(windows)]
 #[tokio::main]
 async fn main() {
     colored::control::set_virtual_terminal(true).unwrap();
     std::process::exit(match init().await {
-        Ok(_) => 0,
+        Ok(_) => EXIT_CODE.load(Ordering::SeqCst),
         Err(err) => {
+            let code = exit_code(&*err);
             utils::error(err, None, None, None);
-            1
+            code
         }
     });
 }
@@ -45,10 +66,11 @@ async fn main() {
 #[tokio::main]
 async fn main() {
     std::process::exit(match init().await {
-        Ok(_) => 0,
+        Ok(_) => EXIT_CODE.load(Ordering::SeqCst),
         Err(err) => {
+            let code = exit_code(&*err);
             utils::error(err, None, None, None);
-            1
+            code
         }
     });
 }
@@ -58,14 +80,86 @@ async fn main() {
 async fn init() -> Result<(), Box<dyn Error>> {
     env_logger::init();
 
-    let config: Config = get_config()?;
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    let args = with_config_file(get_app().get_matches(), &argv).map_err(|err| categorize(err, X8Error::Config))?;
+
+    if let Some(serve_args) = args.subcommand_matches("serve") {
+        return serve(
+            serve_args.value_of("listen").unwrap(),
+            serve_args.value_of("jobs").unwrap().parse()?,
+            serve_args.is_present("metrics"),
+            serve_args.value_of("token"),
+        )
+        .await;
+    }
+
+    if let Some(coordinator_args) = args.subcommand_matches("coordinator") {
+        return coordinate(
+            coordinator_args.value_of("listen").unwrap(),
+            coordinator_args.value_of("lease-size").unwrap().parse()?,
+            Duration::from_secs(coordinator_args.value_of("lease-timeout").unwrap().parse()?),
+            coordinator_args
+                .values_of("args")
+                .map(|x| x.map(|x| x.to_string()).collect::<Vec<String>>())
+                .unwrap_or_default(),
+            coordinator_args.value_of("token"),
+        )
+        .await;
+    }
+
+    if let Some(worker_args) = args.subcommand_matches("worker") {
+        return work(
+            worker_args.value_of("coordinator").unwrap(),
+            worker_args.value_of("name"),
+            worker_args.value_of("token").unwrap(),
+        )
+        .await;
+    }
+
+    if let Some(compare_args) = args.subcommand_matches("compare") {
+        return compare(
+            compare_args.value_of("old").unwrap(),
+            compare_args.value_of("new").unwrap(),
+            compare_args.value_of("output-format").unwrap(),
+        );
+    }
+
+    if let Some(query_args) = args.subcommand_matches("query") {
+        return query(
+            query_args.value_of("database").unwrap(),
+            query_args.value_of("table").unwrap(),
+            &QueryFilters {
+                run: query_args.value_of("run"),
+                url: query_args.value_of("url"),
+                reason_kind: query_args.value_of("reason"),
+            },
+        );
+    }
+
+    if let Some(replay_args) = args.subcommand_matches("replay") {
+        return replay(
+            replay_args.value_of("results").unwrap(),
+            replay_args.value_of("replay-proxy"),
+            &replay_args
+                .values_of("args")
+                .map(|x| x.map(|x| x.to_string()).collect::<Vec<String>>())
+                .unwrap_or_default(),
+        )
+        .await;
+    }
+
+    let config: Config = config_from_matches(&args).map_err(|err| categorize(err, X8Error::Config))?;
+
+    if let Some(seed) = config.seed {
+        utils::set_seed(seed);
+    }
 
     //if --test option is used - print request/response and quit
     if config.test {
         if config.urls.len() != 1 {
-            Err("--test option works only with 1 url")?;
+            Err(X8Error::Config("--test option works only with 1 url".to_string()))?;
         } else if config.methods.len() != 1 {
-            Err("--test option works only with 1 method")?;
+            Err(X8Error::Config("--test option works only with 1 method".to_string()))?;
         }
 
         //TODO move to func?
@@ -88,19 +182,51 @@ async fn init() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
+    // the first Ctrl-C stops the scan gracefully and the results collected so far are written as usual.
+    // the second one exits immediately
+    let budget = Arc::clone(&config.budget);
+    let gate = Arc::clone(&config.gate);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+
+        budget.interrupt();
+        // otherwise paused requests never finish
+        gate.resume();
+        writeln!(
+            io::stderr(),
+            "\n[#] Interrupted. Finishing the requests in flight and writing the results. Press Ctrl-C again to exit immediately"
+        )
+        .ok();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+
+    listen_for_pause(Arc::clone(&config.gate));
+
+    if let Some(listen) = &config.metrics_listen {
+        serve_metrics(listen, Arc::clone(&config.metrics))
+            .await
+            .map_err(|err| categorize(err, X8Error::Config))?;
+    }
+
     if !config.save_responses.is_empty() {
-        fs::create_dir_all(&config.save_responses).await?;
+        fs::create_dir_all(&config.save_responses).await.map_err(|err| X8Error::Output(err.to_string()))?;
     }
 
     let mut params: Vec<String> = Vec::new();
 
-    if !config.wordlist.is_empty() {
+    // with --wordlist-batch the wordlist is read lazily for every url:method pair
+    if !config.wordlist.is_empty() && config.wordlist_batch == 0 {
         // read parameters from a file
         for line in read_lines(&config.wordlist)?.flatten() {
             params.push(line);
         }
     // just accept piped stdin
-    } else if !atty::is(Stream::Stdin) {
+    } else if config.wordlist.is_empty() && !atty::is(Stream::Stdin) {
         // read parameters from stdin
         params = read_stdin_lines();
     }
@@ -109,11 +235,10 @@ async fn init() -> Result<(), Box<dyn Error>> {
         write_banner_config(&config, &params);
     }
 
-    // such headers usually cause server to timeout
-    // especially when http/2 is used
-    // probably better to add a flag for keeping such parameters?
-    if config.headers_discovery {
-        params.retain(|x| "content-length" != x.to_lowercase() && "host" != x.to_lowercase());
+    filter_params(&config, &mut params);
+
+    if config.mutations {
+        params = mutate(params, config.headers_discovery);
     }
 
     // -W 0 is a special option to run everything in parallel
@@ -135,7 +260,7 @@ async fn init() -> Result<(), Box<dyn Error>> {
 
         let file = match file.open(&config.output_file).await {
             Ok(file) => file,
-            Err(_) => fs::File::create(&config.output_file).await?,
+            Err(_) => fs::File::create(&config.output_file).await.map_err(|err| X8Error::Output(err.to_string()))?,
         };
 
         Some(file)
@@ -145,8 +270,37 @@ async fn init() -> Result<(), Box<dyn Error>> {
 
     let shared_output_file = Arc::new(Mutex::new(&mut output_file));
 
+    // every scan is stored as a new run within the database
+    let results_db = match &config.output_db {
+        Some(filename) => Some(
+            ResultsDb::open(filename, &std::env::args().collect::<Vec<String>>())
+                .await
+                .map_err(|err| X8Error::Output(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let results_db = &results_db;
+
+    let progress_bars = init_progress(&config);
+
+    let scan_started = Instant::now();
+    let progress_events = if config.progress_json {
+        let progress_bars = progress_bars.clone();
+        let metrics = Arc::clone(&config.metrics);
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PROGRESS_JSON_INTERVAL);
+            loop {
+                interval.tick().await;
+                utils::write_progress_json(&progress_bars, &metrics, scan_started.elapsed(), false);
+            }
+        }))
+    } else {
+        None
+    };
+
     let runner_outputs =
-        futures::stream::iter(init_progress(&config).iter().enumerate().skip(1).map(
+        futures::stream::iter(progress_bars.iter().enumerate().skip(1).map(
             |(id, (progress_bar, url_set))| {
 
                 let shared_output_file = Arc::clone(&shared_output_file);
@@ -165,7 +319,39 @@ async fn init() -> Result<(), Box<dyn Error>> {
                     // for now url set are used only in case --one-worker-per-host option is provided
                     // otherwise it's just url sets of 1 url
                     for url in url_set {
-                        for method in &config.methods.clone() {
+                        // urls that weren't started yet are skipped once the budget is exhausted
+                        if config.budget.is_exhausted() {
+                            break;
+                        }
+
+                        // with --host-workers only a limited amount of urls of the same host are checked at once
+                        let _permit = match config.host_semaphores.get(&utils::url_host(url)) {
+                            Some(semaphore) => semaphore.acquire().await.ok(),
+                            None => None,
+                        };
+
+                        let mut methods = config.methods_for(url);
+
+                        if config.discover_methods {
+                            match discover_methods(config, url, &methods[0], progress_bar, id).await {
+                                Ok(discovered) => {
+                                    if config.scan_discovered_methods {
+                                        for method in discovered {
+                                            if !methods.contains(&method) {
+                                                methods.push(method);
+                                            }
+                                        }
+                                    }
+                                }
+                                Err(err) => fail(err, url, progress_bar, config),
+                            }
+                        }
+
+                        for method in &methods {
+                            if config.budget.is_exhausted() {
+                                break;
+                            }
+
                             // each method should have each own list of parameters (we're changing this list through the run)
                             let mut params = params.clone();
 
@@ -176,7 +362,7 @@ async fn init() -> Result<(), Box<dyn Error>> {
                             ) {
                                 Ok(val) => val,
                                 Err(err) => {
-                                    utils::error(err, Some(url), Some(progress_bar), Some(config));
+                                    fail(err, url, progress_bar, config);
                                     continue;
                                 }
                             };
@@ -185,22 +371,55 @@ async fn init() -> Result<(), Box<dyn Error>> {
                             if let Err(err) =
                                 Request::new(&request_defaults, Vec::new()).send().await
                             {
-                                utils::error(err, Some(url), Some(progress_bar), Some(config));
+                                fail(err, url, progress_bar, config);
                                 continue;
                             };
 
-                            match run(
-                                config,
-                                &mut request_defaults,
-                                &mut params,
-                                &progress_bar,
-                                id,
-                            )
-                            .await
-                            {
+                            let started = Instant::now();
+
+                            let result = if config.wordlist_batch != 0 {
+                                match WordlistBatches::new(&config.wordlist, config.wordlist_batch) {
+                                    Ok(batches) => {
+                                        let batches = batches.map(|mut batch| {
+                                            filter_params(config, &mut batch);
+
+                                            if config.mutations {
+                                                batch = mutate(batch, config.headers_discovery);
+                                            }
+
+                                            batch
+                                        });
+
+                                        scan_batches(config, &mut request_defaults, batches, &progress_bar, id).await
+                                    }
+                                    Err(err) => Err(err.into()),
+                                }
+                            } else {
+                                scan(config, &mut request_defaults, &mut params, &progress_bar, id).await
+                            };
+
+                            match result {
                                 Ok(val) => {
-                                    // if output format is not json we can print output and write to file in real time
-                                    if config.output_format != "json" {
+                                    // only the plans of the scan are printed with --dry-run
+                                    if config.dry_run {
+                                        continue;
+                                    }
+
+                                    if let Some(results_db) = results_db {
+                                        if !(config.remove_empty && val.found_params.is_empty()) {
+                                            if let Err(err) = results_db.add_target(&val, started.elapsed()).await {
+                                                fail(X8Error::Output(err.to_string()).into(), url, progress_bar, config);
+                                            }
+                                        }
+                                    }
+
+                                    // with jsonl output every found parameter was already written within the runner
+                                    // if output format is not json/sarif we can print output and write to file in real time
+                                    // porcelain output skips targets without found parameters
+                                    if config.output_format != "jsonl"
+                                        && !config.is_output_deferred()
+                                        && (config.porcelain.is_empty() || !val.found_params.is_empty())
+                                    {
                                         let mut output_file = shared_output_file.lock();
                                         let output = val.parse(config);
 
@@ -210,7 +429,7 @@ async fn init() -> Result<(), Box<dyn Error>> {
                                                 &strip_ansi_escapes::strip(&(output.normal().clear().to_string()+"\n").as_bytes()).unwrap()
                                             ).await {
                                                 Ok(()) => output_file.as_mut().unwrap().flush().await.unwrap(),
-                                                Err(err) => utils::error(err, Some(url), Some(progress_bar), Some(config)),
+                                                Err(err) => fail(X8Error::Output(err.to_string()).into(), url, progress_bar, config),
                                             };
                                         }
 
@@ -225,14 +444,14 @@ async fn init() -> Result<(), Box<dyn Error>> {
                                         } else {
                                             progress_bar.println(msg);
                                         }
+                                    }
 
-                                    } else {
+                                    // outputs are kept for the final json/sarif output and the report
+                                    if config.is_output_deferred() || !config.report.is_empty() {
                                         runner_outputs.push(val)
                                     }
                                 },
-                                Err(err) => {
-                                    utils::error(err, Some(url), Some(progress_bar), Some(config))
-                                }
+                                Err(err) => fail(err, url, progress_bar, config),
                             }
                         }
                     }
@@ -244,110 +463,161 @@ async fn init() -> Result<(), Box<dyn Error>> {
         .collect::<Vec<Vec<RunnerOutput>>>()
         .await;
 
-    // works only in case json output is used.
-    // otherwise runner_outputs is an empty vector
-    // and all the printing work is done within the futures above
-    if !runner_outputs.is_empty() {
-        let output = runner_outputs
-            .into_iter()
-            .flatten()
-            .filter(|x| !(config.remove_empty && x.found_params.is_empty()))
-            .collect::<Vec<RunnerOutput>>()
-            .parse_output(&config);
+    if let Some(progress_events) = progress_events {
+        progress_events.abort();
+        utils::write_progress_json(&progress_bars, &config.metrics, scan_started.elapsed(), true);
+    }
+
+    let runner_outputs = runner_outputs
+        .into_iter()
+        .flatten()
+        .filter(|x| !(config.remove_empty && x.found_params.is_empty()))
+        .collect::<Vec<RunnerOutput>>();
+
+    if !config.report.is_empty() {
+        fs::write(&config.report_file, html_report(&runner_outputs, config.budget.requests()))
+            .await
+            .map_err(|err| X8Error::Output(err.to_string()))?;
+        writeln!(io::stdout(), "[#] The report is saved to {}", config.report_file).ok();
+    }
+
+    // works only in case json or sarif output is used.
+    // otherwise all the printing work is done within the futures above
+    if config.is_output_deferred() && !runner_outputs.is_empty() {
+        let output = runner_outputs.parse_output(&config);
 
         if output_file.is_some() {
-            output_file.as_mut().unwrap().write_all(output.as_bytes()).await?;
-            output_file.as_mut().unwrap().flush().await?;
+            let output_file = output_file.as_mut().unwrap();
+            output_file.write_all(output.as_bytes()).await.map_err(|err| X8Error::Output(err.to_string()))?;
+            output_file.flush().await.map_err(|err| X8Error::Output(err.to_string()))?;
         }
 
         write!(io::stdout(), "\n{}", output).ok();
     }
 
+    if let Some(cache) = &config.response_cache {
+        if config.verbose > 0 {
+            writeln!(io::stdout(), "[#] Response cache: {} requests weren't resent", cache.hits()).ok();
+        }
+    }
+
+    if config.budget.is_interrupted() {
+        writeln!(
+            io::stdout(),
+            "[#] Interrupted: {} requests, {} parameters found",
+            config.budget.requests(),
+            config.budget.findings()
+        )
+        .ok();
+
+        let hint = match &config.checkpoint {
+            Some(store) => format!("Continue the scan with --resume {}", store.filename()),
+            None => "Use --checkpoint <filename> to be able to continue interrupted scans with --resume".to_string(),
+        };
+        writeln!(io::stdout(), "[#] {}", hint).ok();
+    } else if let Some(reason) = config.budget.exhausted() {
+        writeln!(
+            io::stdout(),
+            "[#] Budget exhausted ({}): {} requests, {} parameters found",
+            reason,
+            config.budget.requests(),
+            config.budget.findings()
+        )
+        .ok();
+    } else if config.verbose > 0 {
+        let requests = match config.budget.max_requests() {
+            Some(max_requests) => format!("{} of {}", config.budget.requests(), max_requests),
+            None => config.budget.requests().to_string(),
+        };
+        // stdout may be parsed (--porcelain, -O json, ..), so the summary doesn't mix with the results
+        writeln!(io::stderr(), "[#] Requests sent: {}", requests).ok();
+    }
+
+    if let Some(burp_export) = &config.burp_export {
+        burp_export.finish().map_err(|err| X8Error::Output(err.to_string()))?;
+    }
+
+    if let Some(results_db) = results_db {
+        results_db
+            .finish(config.budget.requests(), config.budget.findings(), config.budget.exhausted())
+            .await
+            .map_err(|err| X8Error::Output(err.to_string()))?;
+    }
+
+    if let Some(webhook) = &config.webhook {
+        webhook
+            .finish(json!({
+                "urls": config.urls.len(),
+                "requests": config.budget.requests(),
+                "findings": config.budget.findings(),
+                "stopped": config.budget.exhausted(),
+            }))
+            .await;
+    }
+
     Ok(())
 }
 
-async fn run(
-    config: &Config,
-    request_defaults: &mut RequestDefaults,
-    params: &mut Vec<String>,
-    progress_bar: &ProgressBar,
-    id: usize,
-) -> Result<RunnerOutput, Box<dyn Error>> {
-    let mut runner_output = Runner::new(config, request_defaults, progress_bar, id)
-        .await?
-        .run(params)
-        .await?;
-
-    // the whole block related to the recursive searching
-    if !runner_output.found_params.is_empty() {
-        for depth in 1..config.recursion_depth + 1 {
-            // remove already found parameters from the list to prevent duplicates
-            params.retain(|x| !runner_output.found_params.contains_name(x));
-
-            // custom parameters work badly with recursion enabled
-            request_defaults.disable_custom_parameters = true;
-
-            // so we are keeping parameters that don't change pages' code
-            // or change it to 200
-            // we cant simply overwrite request_defaults.parameters because there's user-supplied parameters as well.
-            request_defaults.parameters.append(&mut Vec::from_iter(
-                runner_output
-                    .found_params
-                    .iter()
-                    .filter(|x| {
-                        !request_defaults.parameters.contains_key(&x.name)
-                            && (x.reason_kind != ReasonKind::Code || x.status == 200)
-                    })
-                    .map(|x| (x.get())),
-            ));
-
-            utils::info(
-                config,
-                id,
-                progress_bar,
-                "recursion",
-                format!(
-                    "({}) repeating with {}",
-                    depth,
-                    request_defaults
-                        .parameters
-                        .iter()
-                        .map(|x| x.0.as_str())
-                        .collect::<Vec<&str>>()
-                        .join(", ")
-                ),
-            );
-
-            let mut new_found_params = Runner::new(config, request_defaults, progress_bar, id)
-                .await?
-                .run(params)
-                .await?
-                .found_params;
-
-            // no new params where found - just quit the loop
-            if !new_found_params
-                .iter()
-                .any(|x| !runner_output.found_params.contains_name(&x.name))
-            {
-                break;
-            }
+/// prints the error of the url and remembers its category for the exit code.
+/// the first failed url decides the exit code, so the rest of the urls are still checked
+fn fail(err: Box<dyn Error>, url: &str, progress_bar: &ProgressBar, config: &Config) {
+    EXIT_CODE
+        .compare_exchange(0, exit_code(&*err), Ordering::SeqCst, Ordering::SeqCst)
+        .ok();
 
-            runner_output.found_params.append(&mut new_found_params);
-        }
+    // wrong options and results that can't be written would fail the other urls as well
+    if is_permanent(&*err) {
+        config.budget.stop("the scan failed");
+    }
+
+    utils::error(err, Some(url), Some(progress_bar), Some(config));
+}
+
+/// removes parameters that break requests
+fn filter_params(config: &Config, params: &mut Vec<String>) {
+    // such headers usually cause server to timeout
+    // especially when http/2 is used
+    // probably better to add a flag for keeping such parameters?
+    if config.headers_discovery {
+        params.retain(|x| "content-length" != x.to_lowercase() && "host" != x.to_lowercase());
+    }
+}
+
+/// pauses and resumes the scan on SIGUSR1 or `p` + Enter within the terminal
+fn listen_for_pause(gate: Arc<Gate>) {
+    #[cfg(unix)]
+    {
+        let gate = Arc::clone(&gate);
+        tokio::spawn(async move {
+            let mut signals = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1()) {
+                Ok(val) => val,
+                Err(_) => return,
+            };
+
+            while signals.recv().await.is_some() {
+                toggle_pause(&gate);
+            }
+        });
     }
 
-    // we probably changed request_defaults.parameters within the loop above
-    // so we are removing all of the added parameters in there
-    // leaving only user-supplied ones
-    // (to not cause double parameters in some output types)
-    request_defaults.parameters = request_defaults
-        .parameters
-        .iter()
-        .filter(|x| !runner_output.found_params.contains_name(&x.0))
-        .map(|x| x.to_owned())
-        .collect();
+    // piped stdin is the wordlist
+    if atty::is(Stream::Stdin) {
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if line.trim().eq_ignore_ascii_case("p") {
+                    toggle_pause(&gate);
+                }
+            }
+        });
+    }
+}
 
-    runner_output.prepare(config, request_defaults);
+fn toggle_pause(gate: &Gate) {
+    let msg = if gate.toggle() {
+        "[#] Paused. Press p and Enter or send SIGUSR1 to resume"
+    } else {
+        "[#] Resumed"
+    };
 
-    Ok(runner_output)
+    writeln!(io::stderr(), "{}", msg).ok();
 }
@@ -0,0 +1,266 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt::Write as _,
+    future::Future,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// requests per second are averaged over this window
+const RPS_WINDOW: Duration = Duration::from_secs(10);
+
+/// time that workers spent waiting for a shared lock
+#[derive(Debug, Default)]
+pub struct Contention {
+    acquisitions: AtomicUsize,
+    wait_nanos: AtomicU64,
+}
+
+impl Contention {
+    /// waits for the lock and measures how long it took
+    pub async fn acquire<T>(&self, lock: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let guard = lock.await;
+
+        self.acquisitions.fetch_add(1, Ordering::SeqCst);
+        self.wait_nanos.fetch_add(started.elapsed().as_nanos() as u64, Ordering::SeqCst);
+
+        guard
+    }
+
+    pub fn acquisitions(&self) -> usize {
+        self.acquisitions.load(Ordering::SeqCst)
+    }
+
+    pub fn wait(&self) -> Duration {
+        Duration::from_nanos(self.wait_nanos.load(Ordering::SeqCst))
+    }
+}
+
+/// counters of the scan in the Prometheus text format.
+/// shared between all the urls (and all the jobs in the daemon mode)
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: AtomicUsize,
+    errors: AtomicUsize,
+
+    /// chunks of parameters waiting to be checked
+    queued: AtomicUsize,
+
+    /// found parameters by their reason kind
+    findings: Mutex<BTreeMap<String, usize>>,
+
+    /// when the requests within the last RPS_WINDOW were sent
+    recent: Mutex<VecDeque<Instant>>,
+
+    /// when the errors within the last RPS_WINDOW happened
+    recent_errors: Mutex<VecDeque<Instant>>,
+
+    /// locks shared between the workers that check parameters
+    pub diffs_lock: Contention,
+    pub green_lines_lock: Contention,
+    pub found_params_lock: Contention,
+    pub queue_lock: Contention,
+    pub skipped_params_lock: Contention,
+}
+
+impl Metrics {
+    pub fn add_request(&self) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+
+        let now = Instant::now();
+        let mut recent = self.recent.lock();
+        recent.push_back(now);
+        Self::forget_old(&mut recent, now);
+    }
+
+    /// requests that failed because of network errors
+    pub fn add_error(&self) {
+        self.errors.fetch_add(1, Ordering::SeqCst);
+
+        let now = Instant::now();
+        let mut recent_errors = self.recent_errors.lock();
+        recent_errors.push_back(now);
+        Self::forget_old(&mut recent_errors, now);
+    }
+
+    pub fn add_finding(&self, reason_kind: &str) {
+        *self.findings.lock().entry(reason_kind.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn add_queued(&self, amount: usize) {
+        self.queued.fetch_add(amount, Ordering::SeqCst);
+    }
+
+    pub fn remove_queued(&self, amount: usize) {
+        self.queued.fetch_sub(amount, Ordering::SeqCst);
+    }
+
+    fn forget_old(recent: &mut VecDeque<Instant>, now: Instant) {
+        while recent.front().is_some_and(|x| now.duration_since(*x) > RPS_WINDOW) {
+            recent.pop_front();
+        }
+    }
+
+    /// requests per second over the last RPS_WINDOW
+    pub fn requests_per_second(&self) -> f64 {
+        let mut recent = self.recent.lock();
+        Self::forget_old(&mut recent, Instant::now());
+        recent.len() as f64 / RPS_WINDOW.as_secs_f64()
+    }
+
+    /// the share of failed requests over the last RPS_WINDOW
+    pub fn error_rate(&self) -> f64 {
+        let now = Instant::now();
+
+        let mut recent = self.recent.lock();
+        Self::forget_old(&mut recent, now);
+        let mut recent_errors = self.recent_errors.lock();
+        Self::forget_old(&mut recent_errors, now);
+
+        if recent.is_empty() {
+            0.
+        } else {
+            (recent_errors.len() as f64 / recent.len() as f64).min(1.)
+        }
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::SeqCst)
+    }
+
+    pub fn findings_count(&self) -> usize {
+        self.findings.lock().values().sum()
+    }
+
+    /// returns all the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, values: Vec<(String, String)>| {
+            writeln!(text, "# HELP {} {}", name, help).ok();
+            writeln!(text, "# TYPE {} {}", name, kind).ok();
+            for (labels, value) in values {
+                writeln!(text, "{}{} {}", name, labels, value).ok();
+            }
+        };
+
+        metric(
+            "x8_requests_total",
+            "counter",
+            "Sent requests.",
+            vec![(String::new(), self.requests.load(Ordering::SeqCst).to_string())],
+        );
+        metric(
+            "x8_requests_per_second",
+            "gauge",
+            "Requests per second over the last 10 seconds.",
+            vec![(String::new(), format!("{:.2}", self.requests_per_second()))],
+        );
+        metric(
+            "x8_errors_total",
+            "counter",
+            "Requests that failed because of network errors.",
+            vec![(String::new(), self.errors.load(Ordering::SeqCst).to_string())],
+        );
+        metric(
+            "x8_findings_total",
+            "counter",
+            "Found parameters by the reason kind.",
+            self.findings
+                .lock()
+                .iter()
+                .map(|(k, v)| (format!("{{reason_kind=\"{}\"}}", k), v.to_string()))
+                .collect(),
+        );
+        metric(
+            "x8_queue_depth",
+            "gauge",
+            "Chunks of parameters waiting to be checked.",
+            vec![(String::new(), self.queued.load(Ordering::SeqCst).to_string())],
+        );
+
+        let locks = [
+            ("diffs", &self.diffs_lock),
+            ("green_lines", &self.green_lines_lock),
+            ("found_params", &self.found_params_lock),
+            ("queue", &self.queue_lock),
+            ("skipped_params", &self.skipped_params_lock),
+        ];
+        metric(
+            "x8_lock_acquisitions_total",
+            "counter",
+            "Acquisitions of the locks shared between workers.",
+            locks
+                .iter()
+                .map(|(name, lock)| (format!("{{lock=\"{}\"}}", name), lock.acquisitions().to_string()))
+                .collect(),
+        );
+        metric(
+            "x8_lock_wait_seconds_total",
+            "counter",
+            "Time workers spent waiting for the shared locks.",
+            locks
+                .iter()
+                .map(|(name, lock)| (format!("{{lock=\"{}\"}}", name), format!("{:.6}", lock.wait().as_secs_f64())))
+                .collect(),
+        );
+
+        text
+    }
+}
+
+/// answers every request on the address with the metrics. Returns once the address is bound
+pub async fn serve_metrics(listen: &str, metrics: Arc<Metrics>) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|err| format!("Unable to listen on {}: {}", listen, err))?;
+
+    writeln!(io::stderr(), "[#] Metrics are available on http://{}/metrics", listen).ok();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(val) => val,
+                Err(err) => {
+                    log::debug!("Unable to accept the connection: {}", err);
+                    continue;
+                }
+            };
+
+            let metrics = Arc::clone(&metrics);
+            tokio::spawn(async move {
+                // the request itself doesn't matter, it's read only to not reset the connection
+                let mut buf = [0u8; 8192];
+                let _ = stream.read(&mut buf).await;
+
+                stream.write_all(http_response(&metrics.render()).as_bytes()).await.ok();
+            });
+        }
+    });
+
+    Ok(())
+}
+
+pub fn http_response(metrics: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        metrics.len(),
+        metrics
+    )
+}
@@ -0,0 +1,155 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use parking_lot::Mutex;
+
+use crate::utils::random_index;
+
+use super::{response::Response, utils::Headers};
+
+/// (provider, lowercased markers). The response is considered a block page in case it contains any of the markers
+const FINGERPRINTS: [(&str, &[&str]); 8] = [
+    ("Cloudflare", &["cf-mitigated: challenge", "attention required! | cloudflare", "<title>just a moment...</title>", "cf-error-details"]),
+    ("Akamai", &["errors.edgesuite.net", "akamai-grn"]),
+    ("PerimeterX", &["_pxcaptcha", "px-captcha", "perimeterx"]),
+    ("DataDome", &["captcha-delivery.com", "x-datadome: protected"]),
+    ("Imperva", &["incapsula incident id", "_incapsula_resource"]),
+    ("AWS WAF", &["x-amzn-waf-action", "awswaf"]),
+    ("Sucuri", &["sucuri website firewall", "x-sucuri-block"]),
+    ("a captcha", &["g-recaptcha", "h-captcha", "hcaptcha.com", "captcha"]),
+];
+
+/// generic signs of a banned client
+const BAN_MARKERS: [&str; 5] = [
+    "your ip has been blocked",
+    "your ip address has been blocked",
+    "you have been blocked",
+    "request blocked",
+    "access denied",
+];
+
+/// user agents to switch to in case of a block with --block-rotate
+const USER_AGENTS: [&str; 5] = [
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+    "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+];
+
+/// recognizes block pages of WAFs and anti-bot solutions, so requests can be paused instead of being compared
+#[derive(Debug)]
+pub struct BlockDetector {
+    /// how long to pause requests after the block
+    pub pause: Duration,
+
+    /// whether to switch the User-Agent after the block
+    pub rotate: bool,
+
+    /// the block page of the first response. Targets can return block pages regardless of the scan,
+    /// e.g. captchas on login pages, so such pages are ignored
+    baseline: Mutex<Option<Option<&'static str>>>,
+
+    /// the User-Agent to use instead of the original one
+    user_agent: Mutex<Option<&'static str>>,
+
+    /// the time of the last block. Blocks within the pause are caused by the same ban
+    last_block: Mutex<Option<Instant>>,
+}
+
+impl BlockDetector {
+    pub fn new(pause: Duration, rotate: bool) -> Self {
+        Self {
+            pause,
+            rotate,
+            baseline: Mutex::new(None),
+            user_agent: Mutex::new(None),
+            last_block: Mutex::new(None),
+        }
+    }
+
+    /// returns the name of the provider in case the response is a block page that differs from the baseline one
+    pub fn detect(&self, response: &Response) -> Option<&'static str> {
+        let found = fingerprint(response);
+        let mut baseline = self.baseline.lock();
+
+        match *baseline {
+            None => {
+                if let Some(provider) = found {
+                    writeln!(
+                        io::stderr(),
+                        "{} The initial response looks like a block page ({})",
+                        "[#]".yellow(),
+                        provider
+                    )
+                    .ok();
+                }
+                *baseline = Some(found);
+                None
+            }
+            Some(base) if base == found => None,
+            _ => found,
+        }
+    }
+
+    /// announces the block and switches the User-Agent once per pause.
+    /// returns false in case the block was already handled by another request
+    pub fn block(&self, provider: &str) -> bool {
+        let mut last_block = self.last_block.lock();
+
+        if last_block.is_some_and(|x| x.elapsed() < self.pause) {
+            return false;
+        }
+        *last_block = Some(Instant::now());
+
+        writeln!(
+            io::stderr(),
+            "{} Blocked by {}. Pausing requests for {}s",
+            "[#]".yellow(),
+            provider,
+            self.pause.as_secs()
+        )
+        .ok();
+
+        if self.rotate {
+            *self.user_agent.lock() = Some(USER_AGENTS[random_index(USER_AGENTS.len())]);
+        }
+
+        true
+    }
+
+    /// replaces the User-Agent in case it was rotated
+    pub fn apply(&self, headers: &mut Vec<(String, String)>) {
+        if let Some(user_agent) = *self.user_agent.lock() {
+            match headers.get_index_case_insensitive("user-agent") {
+                Some(index) => headers[index].1 = user_agent.to_string(),
+                None => headers.push(("User-Agent".to_string(), user_agent.to_string())),
+            }
+        }
+    }
+}
+
+/// returns the name of the provider in case the response looks like a block page.
+/// only error codes are checked because the markers (like captcha scripts) are common within regular pages
+fn fingerprint(response: &Response) -> Option<&'static str> {
+    if response.code < 400 {
+        return None;
+    }
+
+    let text = response.text.to_lowercase();
+
+    FINGERPRINTS
+        .iter()
+        .find(|(_, markers)| markers.iter().any(|x| text.contains(x)))
+        .map(|(provider, _)| *provider)
+        .or_else(|| {
+            if BAN_MARKERS.iter().any(|x| text.contains(x)) {
+                Some("a ban page")
+            } else {
+                None
+            }
+        })
+}
@@ -0,0 +1,98 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// limits the whole scan by the amount of sent requests, found parameters and time.
+/// shared between all the urls, so the scan stops gracefully once any of the limits is reached or it's interrupted
+#[derive(Debug, Default)]
+pub struct Budget {
+    max_requests: Option<usize>,
+    max_findings: Option<usize>,
+    deadline: Option<Instant>,
+
+    requests: AtomicUsize,
+    findings: AtomicUsize,
+
+    /// whether the scan was stopped with Ctrl-C
+    interrupted: AtomicBool,
+
+    /// why the budget was exhausted. Remembered once so the reason doesn't change afterward
+    reason: Mutex<Option<String>>,
+}
+
+impl Budget {
+    pub fn new(max_requests: Option<usize>, max_findings: Option<usize>, max_time: Option<Duration>) -> Self {
+        Self {
+            max_requests,
+            max_findings,
+            deadline: max_time.map(|x| Instant::now() + x),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_request(&self) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn add_finding(&self) {
+        self.findings.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// the limit of --max-requests
+    pub fn max_requests(&self) -> Option<usize> {
+        self.max_requests
+    }
+
+    pub fn findings(&self) -> usize {
+        self.findings.load(Ordering::SeqCst)
+    }
+
+    /// stops the scan the same way as the exhausted budget
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.stop("interrupted");
+    }
+
+    /// stops the scan the same way as the exhausted budget, but without the hint about --resume
+    pub fn stop(&self, reason: &str) {
+        let mut current = self.reason.lock();
+        if current.is_none() {
+            *current = Some(reason.to_string());
+        }
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// whether any of the limits is reached
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted().is_some()
+    }
+
+    /// returns the reason in case any of the limits is reached
+    pub fn exhausted(&self) -> Option<String> {
+        let mut reason = self.reason.lock();
+
+        if reason.is_none() {
+            *reason = if self.max_requests.is_some_and(|x| self.requests() >= x) {
+                Some(format!("{} requests sent", self.requests()))
+            } else if self.max_findings.is_some_and(|x| self.findings() >= x) {
+                Some(format!("{} parameters found", self.findings()))
+            } else if self.deadline.is_some_and(|x| Instant::now() >= x) {
+                Some("time is up".to_string())
+            } else {
+                None
+            };
+        }
+
+        reason.clone()
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use parking_lot::Mutex;
+
+use super::{response::Response, utils::utc_date};
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// writes requests and responses that revealed parameters as Burp Suite items (the "Save items" xml format)
+#[derive(Debug)]
+pub struct BurpExport {
+    file: Mutex<File>,
+}
+
+impl BurpExport {
+    pub fn new(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let mut file = File::create(filename)
+            .map_err(|err| format!("Unable to create the burp export file {}: {}", filename, err))?;
+
+        write!(
+            file,
+            "<?xml version=\"1.0\"?>\n<items burpVersion=\"x8 {}\" exportTime=\"{}\">\n",
+            env!("CARGO_PKG_VERSION"),
+            burp_time(SystemTime::now())
+        )?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// appends the finding's request and response. The comment describes the finding
+    pub fn add(&self, response: &Response, comment: &str) {
+        let request = match &response.request {
+            Some(val) => val,
+            None => return,
+        };
+        let defaults = request.defaults;
+
+        let raw_response = with_crlf(&response.print());
+
+        let item = format!(
+            "  <item>
+    <time>{}</time>
+    <url>{}</url>
+    <host ip=\"\">{}</host>
+    <port>{}</port>
+    <protocol>{}</protocol>
+    <method>{}</method>
+    <path>{}</path>
+    <extension>null</extension>
+    <request base64=\"true\"><![CDATA[{}]]></request>
+    <status>{}</status>
+    <responselength>{}</responselength>
+    <mimetype></mimetype>
+    <response base64=\"true\"><![CDATA[{}]]></response>
+    <comment>{}</comment>
+  </item>
+",
+            burp_time(SystemTime::now()),
+            cdata(&format!("{}://{}{}", defaults.scheme, defaults.host_header(), request.path)),
+            cdata(&defaults.host),
+            defaults.port,
+            cdata(&defaults.scheme),
+            cdata(&defaults.method),
+            cdata(&request.path),
+            STANDARD.encode(with_crlf(&request.print_sent())),
+            response.code,
+            raw_response.len(),
+            STANDARD.encode(&raw_response),
+            cdata(comment)
+        );
+
+        if let Err(err) = self.file.lock().write_all(item.as_bytes()) {
+            log::debug!("Unable to write to the burp export file: {}", err);
+        }
+    }
+
+    /// closes the items list. Should be called once after all the urls are checked
+    pub fn finish(&self) -> Result<(), Box<dyn Error>> {
+        let mut file = self.file.lock();
+        file.write_all(b"</items>\n")?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Burp expects CRLF line endings within the status line and headers
+fn with_crlf(message: &str) -> String {
+    match message.split_once("\n\n") {
+        Some((head, body)) => head.replace('\n', "\r\n") + "\r\n\r\n" + body,
+        None => message.replace('\n', "\r\n"),
+    }
+}
+
+/// the text within CDATA can't contain its end marker, so the marker is split between two sections
+fn cdata(s: &str) -> String {
+    format!("<![CDATA[{}]]>", s.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+/// the time in the format of Burp Suite (java's Date.toString) in UTC, e.g. Mon Jan 01 00:00:00 UTC 2024
+fn burp_time(time: SystemTime) -> String {
+    let (year, month, day, secs_of_day) = utc_date(time);
+    let days = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 86400;
+
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02} UTC {}",
+        WEEKDAYS[(days % 7) as usize],
+        MONTHS[(month - 1) as usize],
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        year
+    )
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use parking_lot::Mutex;
+
+use super::response::Response;
+
+/// headers that change between identical requests, like the ones of signatures
+const VOLATILE_HEADERS: &[&str] = &["authorization", "x-amz-date", "x-amz-content-sha256", "x-amz-security-token"];
+
+/// the cache drops the oldest responses once they take more memory than that
+const MAX_CACHE_SIZE: usize = 128 * 1024 * 1024;
+
+/// responses to the requests that were already sent during the run (--cache-responses).
+/// requests with random values are always unique, so mostly requests with fixed values
+/// (custom parameters, pairs, values from --value-wordlist) are answered from the cache
+#[derive(Debug)]
+pub struct ResponseCache {
+    entries: Mutex<Entries>,
+    max_size: usize,
+    hits: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    responses: HashMap<u64, Response<'static>>,
+
+    /// keys in the order of insertion, so the oldest responses are dropped first
+    order: VecDeque<u64>,
+
+    /// the approximate amount of bytes the responses take
+    size: usize,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(MAX_CACHE_SIZE)
+    }
+}
+
+/// the approximate amount of memory the response takes
+fn response_size(response: &Response) -> usize {
+    response.text.len() + response.headers.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+}
+
+impl ResponseCache {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            max_size,
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn key(method: &str, url: &str, headers: &[(String, String)], body: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        method.hash(&mut hasher);
+        url.hash(&mut hasher);
+        body.hash(&mut hasher);
+
+        for (k, v) in headers
+            .iter()
+            .filter(|(k, _)| !VOLATILE_HEADERS.iter().any(|x| k.eq_ignore_ascii_case(x)))
+        {
+            k.to_lowercase().hash(&mut hasher);
+            v.hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// the cached response without the request
+    pub fn get(&self, key: u64) -> Option<Response<'static>> {
+        let response = self.entries.lock().responses.get(&key).cloned();
+
+        if response.is_some() {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+
+        response
+    }
+
+    pub fn insert(&self, key: u64, response: &Response) {
+        let size = response_size(response);
+        if size > self.max_size {
+            return;
+        }
+
+        let response = Response {
+            time: response.time,
+            code: response.code,
+            headers: response.headers.clone(),
+            text: response.text.clone(),
+            reflected_parameters: HashMap::new(),
+            header_reflected_parameters: HashMap::new(),
+            request: None,
+            http_version: response.http_version,
+            json: response.json.clone(),
+            stability_mask: None,
+            stats: response.stats,
+            redirects: response.redirects.clone(),
+            truncated: response.truncated,
+        };
+
+        let mut entries = self.entries.lock();
+
+        if let Some(old) = entries.responses.insert(key, response) {
+            entries.size -= response_size(&old);
+        } else {
+            entries.order.push_back(key);
+        }
+        entries.size += size;
+
+        while entries.size > self.max_size {
+            let oldest = match entries.order.pop_front() {
+                Some(val) => val,
+                None => break,
+            };
+
+            if let Some(old) = entries.responses.remove(&oldest) {
+                entries.size -= response_size(&old);
+            }
+        }
+    }
+
+    /// the amount of requests that weren't sent thanks to the cache
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::Write,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::error::X8Error;
+
+use super::client::{HttpClient, HttpRequest, HttpResponse};
+
+/// a recorded request together with the response to it
+#[derive(Debug, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+
+    code: u16,
+    response_headers: Vec<(String, String)>,
+
+    /// the body in case it's valid utf-8
+    response_body: String,
+
+    /// the base64 encoded body in case it isn't valid utf-8, so binary bodies are replayed as they were received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response_body_base64: Option<String>,
+}
+
+impl Exchange {
+    /// the whole request as a single string to compare requests with
+    fn signature(&self) -> String {
+        signature(&self.method, &self.url, &self.headers, &self.body)
+    }
+}
+
+fn signature(method: &str, url: &str, headers: &[(String, String)], body: &str) -> String {
+    let headers = headers
+        .iter()
+        .map(|(k, v)| format!("{}: {}", k, v))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!("{} {}\n{}\n\n{}", method, url, headers, body)
+}
+
+lazy_static! {
+    static ref RE_TOKEN: Regex = Regex::new(r"[a-zA-Z0-9]+").unwrap();
+}
+
+/// the signature with all the alphanumeric tokens replaced, so requests with different random parameters match
+fn skeleton(signature: &str) -> String {
+    RE_TOKEN.replace_all(signature, "*").to_string()
+}
+
+fn tokens(signature: &str) -> Vec<&str> {
+    RE_TOKEN.find_iter(signature).map(|x| x.as_str()).collect()
+}
+
+/// appends every request and response to the cassette file as a json line
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    pub fn new(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let file = File::create(filename)
+            .map_err(|err| format!("Unable to create the cassette file {}: {}", filename, err))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(&self, request: &HttpRequest, response: &HttpResponse) {
+        let (response_body, response_body_base64) = match String::from_utf8(response.body.clone()) {
+            Ok(val) => (val, None),
+            Err(_) => (String::new(), Some(STANDARD.encode(&response.body))),
+        };
+
+        let exchange = Exchange {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            code: response.code,
+            response_headers: response.headers.clone(),
+            response_body,
+            response_body_base64,
+        };
+
+        let mut file = self.file.lock();
+        if let Err(err) = writeln!(file, "{}", serde_json::to_string(&exchange).unwrap()) {
+            log::debug!("Unable to write to the cassette: {}", err);
+        }
+    }
+}
+
+/// the exchange from the cassette with its signature and skeleton computed in advance
+#[derive(Debug)]
+struct Recorded {
+    exchange: Exchange,
+    signature: String,
+    skeleton: String,
+
+    /// the decoded body in case it's binary
+    binary_body: Option<Vec<u8>>,
+
+    /// whether the exchange was already replayed
+    used: bool,
+}
+
+/// answers requests with the responses from the cassette instead of sending them
+#[derive(Debug)]
+pub struct Player {
+    exchanges: Mutex<Vec<Recorded>>,
+}
+
+impl Player {
+    pub fn new(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let cassette = fs::read_to_string(filename)
+            .map_err(|err| format!("Unable to read the cassette file {}: {}", filename, err))?;
+
+        let mut exchanges = Vec::new();
+        for (i, line) in cassette.lines().enumerate().filter(|(_, x)| !x.is_empty()) {
+            let exchange: Exchange = serde_json::from_str(line)
+                .map_err(|err| format!("Unable to parse the cassette file {} (line {}): {}", filename, i + 1, err))?;
+
+            let binary_body = match &exchange.response_body_base64 {
+                Some(val) => Some(STANDARD.decode(val).map_err(|err| {
+                    format!("Unable to decode the body within the cassette file {} (line {}): {}", filename, i + 1, err)
+                })?),
+                None => None,
+            };
+
+            let signature = exchange.signature();
+            exchanges.push(Recorded {
+                skeleton: skeleton(&signature),
+                binary_body,
+                signature,
+                exchange,
+                used: false,
+            });
+        }
+
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// returns the exactly matching response or the next unused one with the same structure.
+    /// in the latter case recorded random names and values are replaced with the new ones within the response
+    fn replay(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let signature = signature(&request.method, &request.url, &request.headers, &request.body);
+        let mut exchanges = self.exchanges.lock();
+
+        let index = exchanges
+            .iter()
+            .position(|x| !x.used && x.signature == signature)
+            .or_else(|| {
+                let request_skeleton = skeleton(&signature);
+                exchanges
+                    .iter()
+                    .position(|x| !x.used && x.skeleton == request_skeleton)
+            })?;
+
+        let recorded = &mut exchanges[index];
+        recorded.used = true;
+
+        let exchange = &recorded.exchange;
+        let replacements: HashMap<&str, &str> = tokens(&recorded.signature)
+            .into_iter()
+            .zip(tokens(&signature))
+            .filter(|(recorded, new)| recorded != new)
+            .collect();
+
+        let replace = |s: &str| {
+            RE_TOKEN
+                .replace_all(s, |cap: &Captures| {
+                    replacements.get(&cap[0]).copied().unwrap_or(&cap[0]).to_string()
+                })
+                .to_string()
+        };
+
+        // random names and values can't be found within binary bodies
+        let mut body = match &recorded.binary_body {
+            Some(val) => val.clone(),
+            None => replace(&exchange.response_body).into_bytes(),
+        };
+
+        // recorded bodies are cut in the same way as the live ones
+        let truncated = match request.max_body_size {
+            Some(max_body_size) if body.len() >= max_body_size => {
+                body.truncate(max_body_size);
+                true
+            }
+            _ => false,
+        };
+
+        Some(HttpResponse {
+            code: exchange.code,
+            headers: exchange
+                .response_headers
+                .iter()
+                .map(|(k, v)| (k.to_owned(), replace(v)))
+                .collect(),
+            body,
+            http_version: Some(http::Version::HTTP_11),
+            truncated,
+        })
+    }
+}
+
+impl HttpClient for Player {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Box<dyn Error + Send + Sync>>> {
+        let response: Result<HttpResponse, Box<dyn Error + Send + Sync>> = self
+            .replay(&request)
+            .ok_or_else(|| X8Error::Network(format!("{} {} isn't within the cassette", request.method, request.url)).into());
+
+        Box::pin(async move { response })
+    }
+}
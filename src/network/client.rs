@@ -0,0 +1,141 @@
+use std::{convert::TryFrom, error::Error, fmt};
+
+use futures::future::BoxFuture;
+use reqwest::Client;
+
+/// the request as it's sent to the target
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+
+    /// the body of the response is read only up to this amount of bytes
+    pub max_body_size: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub http_version: Option<http::Version>,
+
+    /// the body was cut at max_body_size of the request
+    pub truncated: bool,
+}
+
+impl HttpResponse {
+    pub fn new(code: u16, body: &str) -> Self {
+        Self {
+            code,
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            body: body.as_bytes().to_vec(),
+            http_version: Some(http::Version::HTTP_11),
+            truncated: false,
+        }
+    }
+}
+
+/// sends requests to the target. Implemented by the reqwest client and by the mock for tests
+pub trait HttpClient: Send + Sync + fmt::Debug {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Box<dyn Error + Send + Sync>>>;
+}
+
+impl HttpClient for Client {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Box<dyn Error + Send + Sync>>> {
+        Box::pin(execute(self, request, None))
+    }
+}
+
+/// sends the request via the reqwest client. The version is needed only for HTTP/3, the others are negotiated
+pub(super) async fn execute(
+    client: &Client,
+    request: HttpRequest,
+    version: Option<http::Version>,
+) -> Result<HttpResponse, Box<dyn Error + Send + Sync>> {
+    let mut builder = http::Request::builder()
+        .method(request.method.as_str())
+        .uri(request.url);
+
+    if let Some(version) = version {
+        builder = builder.version(version);
+    }
+
+    for (k, v) in &request.headers {
+        builder = builder.header(k, v)
+    }
+
+    let reqwest_req = reqwest::Request::try_from(builder.body(request.body)?)?;
+
+    let mut res = client.execute(reqwest_req).await?;
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    for (k, v) in res.headers() {
+        let k = k.to_string();
+
+        // sometimes conversion may fail
+        let v = match v.to_str() {
+            Ok(val) => val,
+            Err(_) => {
+                log::debug!("Unable to parse {} header. The value is {:?}", k, v);
+                ""
+            }
+        }.to_string();
+
+        headers.push((k, v));
+    }
+
+    let code = res.status().as_u16();
+    let http_version = Some(res.version());
+
+    // the body is streamed, so huge responses don't have to be read entirely
+    let (mut body, mut truncated) = (Vec::new(), false);
+    while let Some(chunk) = res.chunk().await? {
+        body.extend_from_slice(&chunk);
+
+        if let Some(max_body_size) = request.max_body_size {
+            if body.len() >= max_body_size {
+                truncated = true;
+                body.truncate(max_body_size);
+                break;
+            }
+        }
+    }
+
+    Ok(HttpResponse {
+        code,
+        headers,
+        body,
+        http_version,
+        truncated,
+    })
+}
+
+/// in-memory server answering every request with the handler, so the runner can be checked without a live target
+pub struct MockClient {
+    handler: Box<dyn Fn(&HttpRequest) -> HttpResponse + Send + Sync>,
+}
+
+impl MockClient {
+    pub fn new<F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static>(handler: F) -> Self {
+        Self {
+            handler: Box::new(handler),
+        }
+    }
+}
+
+impl fmt::Debug for MockClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockClient").finish()
+    }
+}
+
+impl HttpClient for MockClient {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Box<dyn Error + Send + Sync>>> {
+        let response = (self.handler)(&request);
+        Box::pin(async move { Ok(response) })
+    }
+}
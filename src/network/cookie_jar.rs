@@ -0,0 +1,70 @@
+use parking_lot::Mutex;
+
+/// cookies set by the target via Set-Cookie headers.
+/// unlike the client's cookie store, they're merged into the user supplied Cookie header as well
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    /// (name, value) pairs in the order they were set
+    cookies: Mutex<Vec<(String, String)>>,
+}
+
+impl CookieJar {
+    /// remembers cookies from Set-Cookie headers. Expired cookies are removed
+    pub fn update(&self, headers: &[(String, String)]) {
+        let mut cookies = self.cookies.lock();
+
+        for (_, v) in headers.iter().filter(|(k, _)| k.eq_ignore_ascii_case("set-cookie")) {
+            let mut attributes = v.split(';');
+
+            let (name, value) = match attributes.next().and_then(|x| x.split_once('=')) {
+                Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+                _ => continue,
+            };
+
+            let expired = attributes.any(|x| {
+                let x = x.trim().to_lowercase();
+                x == "max-age=0" || x.starts_with("max-age=-")
+            });
+
+            let position = cookies.iter().position(|(k, _)| k == name);
+
+            match (position, expired) {
+                (Some(position), true) => {
+                    cookies.remove(position);
+                }
+                (Some(position), false) => cookies[position].1 = value.to_string(),
+                (None, false) => cookies.push((name.to_string(), value.to_string())),
+                (None, true) => (),
+            }
+        }
+    }
+
+    /// replaces values of the cookies within the Cookie header value with the stored ones
+    /// and appends the missing ones. Cookies with names from skip are kept as they are
+    pub fn apply(&self, cookie_header: &str, skip: &[&str]) -> String {
+        let cookies = self.cookies.lock();
+
+        let mut parts: Vec<String> = Vec::new();
+
+        for part in cookie_header.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let name = part.split('=').next().unwrap();
+
+            match cookies.iter().find(|(k, _)| k == name) {
+                Some((k, v)) if !skip.contains(&name) => parts.push(format!("{}={}", k, v)),
+                _ => parts.push(part.to_string()),
+            }
+        }
+
+        for (k, v) in cookies.iter() {
+            if !skip.contains(&k.as_str()) && !parts.iter().any(|x| x.split('=').next() == Some(k.as_str())) {
+                parts.push(format!("{}={}", k, v));
+            }
+        }
+
+        parts.join("; ")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.lock().is_empty()
+    }
+}
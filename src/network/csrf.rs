@@ -0,0 +1,316 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+
+use super::{request::RequestDefaults, response::Response, utils::Headers};
+
+/// the token isn't fetched more often than that
+const MIN_FETCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// codes that usually mean the token was rejected, unless the page returns them regardless of the token
+const REJECTED_CODES: [u16; 2] = [403, 419];
+
+/// characters that are left as is within urlencoded tokens
+const URLENCODED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// where to take the token from
+#[derive(Debug, Clone)]
+pub enum CsrfSource {
+    /// the first capture group (or the whole match) within the body or headers
+    Regex(Regex),
+
+    /// the json pointer within the json body, like /data/csrf
+    JsonPointer(String),
+}
+
+/// where to insert the token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsrfPlace {
+    Header(String),
+    Query(String),
+
+    /// urlencoded or json body
+    Body(String),
+    Cookie(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct CsrfRule {
+    pub source: CsrfSource,
+
+    pub place: CsrfPlace,
+
+    /// the url to fetch the token from. The target url is used by default
+    pub url: Option<String>,
+
+    /// responses that match the regex mean the token was rejected (--csrf-marker)
+    pub marker: Option<Regex>,
+}
+
+impl CsrfRule {
+    /// parses --csrf-from and --csrf-to values.
+    /// values of --csrf-from that start with / are json pointers, others are regexes
+    pub fn new(from: &str, to: &str, url: Option<String>, marker: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let source = if from.starts_with('/') {
+            CsrfSource::JsonPointer(from.to_string())
+        } else {
+            CsrfSource::Regex(
+                Regex::new(from).map_err(|err| format!("Unable to parse --csrf-from: {}", err))?,
+            )
+        };
+
+        let (place, name) = to
+            .split_once(':')
+            .ok_or("--csrf-to should look like header:X-CSRF-Token, query:csrf, body:csrf or cookie:csrf")?;
+
+        let name = name.trim().to_string();
+
+        if name.is_empty() {
+            Err("The --csrf-to name is empty")?;
+        }
+
+        let place = match place.trim().to_lowercase().as_str() {
+            "header" => CsrfPlace::Header(name),
+            "query" => CsrfPlace::Query(name),
+            "body" => CsrfPlace::Body(name),
+            "cookie" => CsrfPlace::Cookie(name),
+            _ => Err(format!("Unknown --csrf-to place: {}. Use header, query, body or cookie", place))?,
+        };
+
+        let marker = marker
+            .map(Regex::new)
+            .transpose()
+            .map_err(|err| format!("Unable to parse --csrf-marker: {}", err))?;
+
+        Ok(Self { source, place, url, marker })
+    }
+
+    /// extracts the token from the response text or headers
+    fn extract(&self, text: &str, headers: &[(String, String)]) -> Option<String> {
+        match &self.source {
+            CsrfSource::Regex(re) => {
+                let headers = headers
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                [text, headers.as_str()].iter().find_map(|x| {
+                    let caps = re.captures(x)?;
+                    Some(caps.get(1).or_else(|| caps.get(0))?.as_str().to_string())
+                })
+            }
+            CsrfSource::JsonPointer(pointer) => {
+                let json: serde_json::Value = serde_json::from_str(text).ok()?;
+
+                match json.pointer(pointer)? {
+                    serde_json::Value::String(val) => Some(val.to_owned()),
+                    val => Some(val.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// the current csrf token. Shared between clones of the defaults
+#[derive(Debug)]
+pub struct Csrf {
+    rule: CsrfRule,
+
+    token: Mutex<Option<String>>,
+
+    /// the code of the first response. Codes the page returns regardless of the token don't mean the token was rejected
+    baseline: Mutex<Option<u16>>,
+
+    /// locked during the fetch, so concurrent requests don't fetch the token multiple times
+    last_fetch: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl Csrf {
+    pub fn new(rule: CsrfRule) -> Self {
+        Self {
+            rule,
+            token: Mutex::new(None),
+            baseline: Mutex::new(None),
+            last_fetch: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.lock().clone()
+    }
+
+    /// fetches the token in case it wasn't fetched yet
+    pub async fn ensure_token(&self, defaults: &RequestDefaults) -> Result<(), Box<dyn Error>> {
+        let mut last_fetch = self.last_fetch.lock().await;
+
+        if last_fetch.is_none() {
+            let result = self.fetch(defaults).await;
+            *last_fetch = Some(Instant::now());
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// fetches the new token in case the used one is still the current one.
+    /// returns whether the current token differs from the used one, so the request should be resent
+    pub async fn refresh(&self, defaults: &RequestDefaults, used: Option<String>) -> Result<bool, Box<dyn Error>> {
+        let mut last_fetch = self.last_fetch.lock().await;
+
+        // another request has already fetched the new token
+        if self.token() != used {
+            return Ok(true);
+        }
+
+        if last_fetch.is_some_and(|x| x.elapsed() < MIN_FETCH_INTERVAL) {
+            return Ok(false);
+        }
+
+        let result = self.fetch(defaults).await;
+        *last_fetch = Some(Instant::now());
+        result?;
+
+        Ok(self.token() != used)
+    }
+
+    /// whether the server rejected the token
+    pub fn is_rejected(&self, response: &Response) -> bool {
+        if let Some(marker) = &self.rule.marker {
+            return marker.is_match(&response.text);
+        }
+
+        let baseline = *self.baseline.lock().get_or_insert(response.code);
+
+        if REJECTED_CODES.contains(&response.code) && response.code != baseline {
+            return true;
+        }
+
+        (400..500).contains(&response.code) && {
+            let text = response.text.to_lowercase();
+            text.contains("csrf") || text.contains("xsrf")
+        }
+    }
+
+    async fn fetch(&self, defaults: &RequestDefaults) -> Result<(), Box<dyn Error>> {
+        let url = match &self.rule.url {
+            Some(url) => url.to_owned(),
+            None => format!(
+                "{}://{}:{}{}",
+                defaults.scheme,
+                defaults.host,
+                defaults.port,
+                defaults.path.split('?').next().unwrap()
+            ),
+        };
+
+        let mut request = http::Request::builder().method("GET").uri(url.as_str());
+
+        // the injection point can be within headers
+        for (k, v) in defaults.custom_headers.iter().filter(|(k, v)| {
+            !k.eq_ignore_ascii_case("content-type") && !k.contains("%s") && !v.contains("%s")
+        }) {
+            request = request.header(k, v);
+        }
+
+        defaults.count_request();
+
+        let res = defaults
+            .client
+            .execute(reqwest::Request::try_from(request.body(String::new())?)?)
+            .await
+            .map_err(|err| format!("Unable to fetch the csrf token from {}: {}", url, err))?;
+
+        let headers: Vec<(String, String)> = res
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect();
+
+        let text = res.text().await?;
+
+        let token = self
+            .rule
+            .extract(&text, &headers)
+            .ok_or_else(|| format!("Unable to find the csrf token within the response from {}", url))?;
+
+        log::debug!("Fetched the csrf token {}", token);
+
+        *self.token.lock() = Some(token);
+
+        Ok(())
+    }
+
+    /// inserts the current token into the request's path, headers or body.
+    /// parameters with the same name as the token's one are replaced
+    pub fn apply(&self, path: &mut String, headers: &mut Vec<(String, String)>, body: &mut String, is_json: bool) {
+        let token = match self.token() {
+            Some(val) => val,
+            None => return,
+        };
+
+        match &self.rule.place {
+            CsrfPlace::Header(name) => match headers.get_index_case_insensitive(name) {
+                Some(index) => headers[index].1 = token,
+                None => headers.push((name.to_owned(), token)),
+            },
+            CsrfPlace::Query(name) => {
+                let token = utf8_percent_encode(&token, URLENCODED).to_string();
+
+                *path = match path.split_once('?') {
+                    Some((path, query)) => format!("{}?{}", path, set_urlencoded_value(query, name, &token)),
+                    None => format!("{}?{}={}", path, name, token),
+                }
+            }
+            CsrfPlace::Body(name) if is_json => {
+                if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(body) {
+                    if let Some(object) = json.as_object_mut() {
+                        object.insert(name.to_owned(), serde_json::Value::String(token));
+                        *body = json.to_string();
+                    }
+                }
+            }
+            CsrfPlace::Body(name) => {
+                *body = set_urlencoded_value(body, name, &utf8_percent_encode(&token, URLENCODED).to_string())
+            }
+            CsrfPlace::Cookie(name) => {
+                let cookie = format!("{}={}", name, token);
+
+                match headers.get_index_case_insensitive("cookie") {
+                    Some(index) => {
+                        let mut parts: Vec<String> = headers[index]
+                            .1
+                            .split(';')
+                            .map(|x| x.trim())
+                            .filter(|x| !x.is_empty() && x.split('=').next() != Some(name.as_str()))
+                            .map(|x| x.to_string())
+                            .collect();
+
+                        parts.push(cookie);
+                        headers[index].1 = parts.join("; ");
+                    }
+                    None => headers.push(("Cookie".to_string(), cookie)),
+                }
+            }
+        }
+    }
+}
+
+/// replaces the value of the name within name=value&.. or appends the pair
+fn set_urlencoded_value(query: &str, name: &str, value: &str) -> String {
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|x| !x.is_empty() && x.split('=').next() != Some(name))
+        .map(|x| x.to_string())
+        .collect();
+
+    pairs.push(format!("{}={}", name, value));
+
+    pairs.join("&")
+}
@@ -0,0 +1,41 @@
+use std::io::{self, Read};
+
+/// decoded bodies are cut at this size unless --max-response-size is set, so a small compressed body can't take all the memory
+const MAX_DECODED_SIZE: usize = 64 * 1024 * 1024;
+
+/// decodes brotli and zstd bodies, since the http client decodes only gzip ones.
+/// bodies that can't be decoded are returned as is.
+/// returns whether the decoded body was cut at max_size
+pub fn decompress(headers: &[(String, String)], body: Vec<u8>, max_size: Option<usize>) -> (Vec<u8>, bool) {
+    let encoding = match headers.iter().find(|(k, _)| k.eq_ignore_ascii_case("content-encoding")) {
+        Some((_, v)) => v.trim().to_lowercase(),
+        None => return (body, false),
+    };
+
+    let max_size = max_size.unwrap_or(MAX_DECODED_SIZE);
+
+    let decoded = match encoding.as_str() {
+        "br" => read_up_to(brotli_decompressor::Decompressor::new(body.as_slice(), 4096), max_size),
+        "zstd" => zstd::stream::read::Decoder::new(body.as_slice()).and_then(|x| read_up_to(x, max_size)),
+        _ => return (body, false),
+    };
+
+    match decoded {
+        Ok(val) => val,
+        Err(err) => {
+            log::debug!("Unable to decode the {} body: {}", encoding, err);
+            (body, false)
+        }
+    }
+}
+
+/// reads one byte more than max_size to find out whether the body was cut
+fn read_up_to(decoder: impl Read, max_size: usize) -> io::Result<(Vec<u8>, bool)> {
+    let mut decoded = Vec::new();
+    decoder.take(max_size as u64 + 1).read_to_end(&mut decoded)?;
+
+    let truncated = decoded.len() > max_size;
+    decoded.truncate(max_size);
+
+    Ok((decoded, truncated))
+}
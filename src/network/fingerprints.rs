@@ -0,0 +1,115 @@
+use std::{error::Error, fs};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::response::Response;
+
+/// error messages of common languages, databases and frameworks
+const SIGNATURES: &[(&str, &str)] = &[
+    ("PHP", r"(?:Fatal error|Parse error|Warning|Notice)(?:</b>)?:.{0,300} on line (?:<b>)?\d+"),
+    ("MySQL", r"You have an error in your SQL syntax|mysql_fetch_\w+\(|MySqlException|valid MySQL result"),
+    ("PostgreSQL", r"PostgreSQL.{0,40}ERROR|pg_query\(\)|PSQLException|ERROR:\s+syntax error at or near"),
+    ("MSSQL", r"Unclosed quotation mark after the character string|OLE DB Provider for SQL Server|\[SQL Server\]"),
+    ("Oracle", r"\bORA-\d{5}\b"),
+    ("SQLite", r"SQLITE_ERROR|sqlite3\.OperationalError|SQLiteException"),
+    ("Java", r"\bat [\w$.]+\([\w$]+\.java:\d+\)|java\.lang\.\w+(?:Exception|Error)\b"),
+    ("Spring", r"Whitelabel Error Page"),
+    ("Tomcat", r"Apache Tomcat/\d|org\.apache\.catalina\."),
+    ("Python", r#"Traceback \(most recent call last\)|File (?:"|&quot;)[^"&]+(?:"|&quot;), line \d+, in "#),
+    ("Django", r"You're seeing this error because you have <code>DEBUG = True</code>"),
+    ("Werkzeug", r"Werkzeug Debugger|werkzeug\.exceptions\."),
+    ("Ruby on Rails", r"ActionController::\w+|ActiveRecord::\w+|ActionView::Template::Error"),
+    ("ASP.NET", r"Server Error in '[^']*' Application|System\.\w+(?:\.\w+)*Exception\b"),
+    ("Node.js", r"\bat .+ \((?:/|[A-Z]:\\).+\.js:\d+:\d+\)|TypeError: Cannot read propert"),
+    ("Laravel", r"Illuminate\\\w+|Whoops, looks like something went wrong"),
+    ("Go", r"goroutine \d+ \[running\]|panic: runtime error"),
+];
+
+lazy_static! {
+    static ref BUILTIN: Vec<Signature> = SIGNATURES
+        .iter()
+        .map(|(technology, regex)| Signature {
+            technology: technology.to_string(),
+            regex: Regex::new(regex).unwrap(),
+        })
+        .collect();
+}
+
+#[derive(Debug, Clone)]
+struct Signature {
+    technology: String,
+    regex: Regex,
+}
+
+/// looks for stack traces and error messages within responses of found parameters
+#[derive(Debug, Clone)]
+pub struct Fingerprints {
+    signatures: Vec<Signature>,
+}
+
+impl Default for Fingerprints {
+    fn default() -> Self {
+        Self {
+            signatures: BUILTIN.clone(),
+        }
+    }
+}
+
+impl Fingerprints {
+    /// adds signatures from the file with technology:regex lines to the builtin ones
+    pub fn from_file(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let mut fingerprints = Self::default();
+
+        let text = fs::read_to_string(filename)
+            .map_err(|err| format!("Unable to read the signatures file {}: {}", filename, err))?;
+
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (technology, regex) = match line.split_once(':') {
+                Some((technology, regex)) if !technology.trim().is_empty() && !regex.is_empty() => {
+                    (technology.trim(), regex)
+                }
+                _ => Err(format!(
+                    "Unable to parse the signature at line {}: {}. Use the technology:regex format",
+                    i + 1,
+                    line
+                ))?,
+            };
+
+            fingerprints.signatures.push(Signature {
+                technology: technology.to_string(),
+                regex: Regex::new(regex)
+                    .map_err(|err| format!("Unable to parse the signature at line {}: {}", i + 1, err))?,
+            });
+        }
+
+        Ok(fingerprints)
+    }
+
+    /// whether every signature matches the response. Computed once for the initial response
+    pub fn matches(&self, response: &Response) -> Vec<bool> {
+        self.signatures.iter().map(|x| x.regex.is_match(&response.text)).collect()
+    }
+
+    /// technologies with error messages that appear within the response but not within the initial one.
+    /// initial_matches are the matches of the initial response
+    pub fn detect(&self, response: &Response, initial_matches: &[bool]) -> Vec<String> {
+        let mut technologies: Vec<String> = Vec::new();
+
+        for (signature, initial_match) in self.signatures.iter().zip(initial_matches) {
+            if *initial_match || technologies.contains(&signature.technology) {
+                continue;
+            }
+
+            if signature.regex.is_match(&response.text) {
+                technologies.push(signature.technology.clone());
+            }
+        }
+
+        technologies
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// where to insert names within the graphql request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphQLMode {
+    /// query arguments or fields of inline input objects: user(%s) or user(filter: {%s})
+    Arguments,
+
+    /// selected fields: user { %s }
+    Fields,
+
+    /// fields of the input object within variables (/variables by default)
+    Variables,
+}
+
+/// the information extracted from graphql errors
+#[derive(Debug, Default)]
+pub struct GraphQLErrors {
+    /// names the server reported as unknown
+    pub unknown: HashSet<String>,
+
+    /// names from "Did you mean .." hints
+    pub suggestions: Vec<String>,
+
+    pub syntax_error: bool,
+}
+
+impl GraphQLErrors {
+    /// parses graphql errors from the response's text.
+    /// returns None in case the response doesn't look like a graphql response.
+    /// response's text is beautified, so \" within messages are already replaced with '
+    pub fn parse(text: &str) -> Option<Self> {
+        lazy_static! {
+            static ref RE_GRAPHQL_RESPONSE: Regex = Regex::new(r#""(errors|data)"\s*:"#).unwrap();
+            static ref RE_UNKNOWN: Regex = Regex::new(
+                r#"(?i)(?:unknown argument|cannot query field|unknown field|field)\s*['"`]([_A-Za-z][_0-9A-Za-z]*)['"`]\s*(?:on|is not defined|$|\.|,)"#
+            ).unwrap();
+            static ref RE_SUGGESTIONS: Regex = Regex::new(r#"(?i)did you mean ([^?]+)\?"#).unwrap();
+            static ref RE_QUOTED_NAME: Regex = Regex::new(r#"['"`]([_A-Za-z][_0-9A-Za-z]*)['"`]"#).unwrap();
+        }
+
+        if !RE_GRAPHQL_RESPONSE.is_match(text) {
+            return None;
+        }
+
+        let mut errors = GraphQLErrors {
+            syntax_error: text.contains("Syntax Error"),
+            ..Default::default()
+        };
+
+        for cap in RE_UNKNOWN.captures_iter(text) {
+            errors.unknown.insert(cap[1].to_string());
+        }
+
+        for cap in RE_SUGGESTIONS.captures_iter(text) {
+            for name in RE_QUOTED_NAME.captures_iter(&cap[1]) {
+                if !errors.suggestions.contains(&name[1].to_string()) {
+                    errors.suggestions.push(name[1].to_string());
+                }
+            }
+        }
+
+        Some(errors)
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    error::Error,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use futures::future::BoxFuture;
+use reqwest::Client;
+
+use crate::config::structs::Config;
+
+use super::{
+    client::{execute, HttpClient, HttpRequest, HttpResponse},
+    utils::client_builder,
+};
+
+/// sends requests over QUIC and falls back to the regular client (h2 or h1) in case the target doesn't answer over it
+#[derive(Debug)]
+pub struct Http3Client {
+    quic: Client,
+    fallback: Client,
+
+    /// the target answered over QUIC, so later errors are regular network errors
+    supported: AtomicBool,
+
+    /// the target didn't answer over QUIC, so the rest of the requests are sent via the fallback client right away
+    unsupported: AtomicBool,
+}
+
+impl Http3Client {
+    pub fn new(config: &Config, fallback: Client) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            quic: client_builder(config, "")?.http3_prior_knowledge().build()?,
+            fallback,
+            supported: AtomicBool::new(false),
+            unsupported: AtomicBool::new(false),
+        })
+    }
+}
+
+impl HttpClient for Http3Client {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move {
+            if self.unsupported.load(Ordering::SeqCst) {
+                return execute(&self.fallback, request, None).await;
+            }
+
+            match execute(&self.quic, request.clone(), Some(http::Version::HTTP_3)).await {
+                Ok(response) => {
+                    self.supported.store(true, Ordering::SeqCst);
+                    Ok(response)
+                }
+                Err(err) if self.supported.load(Ordering::SeqCst) => Err(err),
+                Err(err) => {
+                    log::debug!("Unable to send the request over HTTP/3, falling back to HTTP/2 and HTTP/1.1: {}", err);
+                    self.unsupported.store(true, Ordering::SeqCst);
+                    execute(&self.fallback, request, None).await
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use std::{error::Error, ops::RangeInclusive};
+
+use regex::Regex;
+
+use super::response::Response;
+
+/// numbers and ranges like 100,200-300
+#[derive(Debug, Clone, Default)]
+pub struct NumberSet(Vec<RangeInclusive<usize>>);
+
+impl NumberSet {
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        let mut ranges = Vec::new();
+
+        for part in s.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let range = match part.split_once('-') {
+                Some((start, end)) => start.trim().parse()?..=end.trim().parse()?,
+                None => {
+                    let number = part.parse()?;
+                    number..=number
+                }
+            };
+
+            if range.is_empty() {
+                Err(format!("Incorrect range: {}", part))?;
+            }
+
+            ranges.push(range);
+        }
+
+        Ok(Self(ranges))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, number: usize) -> bool {
+        self.0.iter().any(|x| x.contains(&number))
+    }
+}
+
+/// ffuf-like conditions that decide whether the response can be a sign of parameters at all.
+/// the response should match any of the matchers (in case there are any) and none of the filters
+#[derive(Debug, Clone, Default)]
+pub struct Matchers {
+    pub match_codes: NumberSet,
+    pub match_sizes: NumberSet,
+    pub match_words: NumberSet,
+    pub match_lines: NumberSet,
+    pub match_regex: Option<Regex>,
+
+    pub filter_codes: NumberSet,
+    pub filter_sizes: NumberSet,
+    pub filter_words: NumberSet,
+    pub filter_lines: NumberSet,
+
+    /// server errors caused by parameters are always interesting, even with --reflected-only
+    pub server_errors: bool,
+}
+
+impl Matchers {
+    fn has_matchers(&self) -> bool {
+        !self.match_codes.is_empty()
+            || !self.match_sizes.is_empty()
+            || !self.match_words.is_empty()
+            || !self.match_lines.is_empty()
+            || self.match_regex.is_some()
+    }
+
+    pub fn is_interesting(&self, response: &Response, initial_response: &Response) -> bool {
+        if self.server_errors && response.is_server_error(initial_response) {
+            return true;
+        }
+
+        let stats = &response.stats;
+        let (code, size, words, lines) = (response.code as usize, stats.size, stats.words, stats.lines);
+
+        if self.filter_codes.contains(code)
+            || self.filter_sizes.contains(size)
+            || self.filter_words.contains(words)
+            || self.filter_lines.contains(lines)
+        {
+            return false;
+        }
+
+        !self.has_matchers()
+            || self.match_codes.contains(code)
+            || self.match_sizes.contains(size)
+            || self.match_words.contains(words)
+            || self.match_lines.contains(lines)
+            || self.match_regex.as_ref().is_some_and(|x| is_match(x, response))
+    }
+}
+
+/// matches the regex against every header (as `Name: value`) and the body
+fn is_match(regex: &Regex, response: &Response) -> bool {
+    response.headers.iter().any(|(k, v)| regex.is_match(&format!("{}: {}", k, v))) || regex.is_match(response.body())
+}
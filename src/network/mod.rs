@@ -1,5 +1,34 @@
+pub mod block;
+pub mod budget;
+pub mod burp;
+pub mod cache;
+pub mod cassette;
+pub mod client;
+pub mod cookie_jar;
+pub mod csrf;
+pub mod decompress;
+pub mod fingerprints;
+pub mod graphql;
+#[cfg(feature = "http3")]
+pub mod http3;
+pub mod matchers;
+pub mod mutator;
+pub mod normalize;
+pub mod oob;
+pub mod proxy_pool;
+pub mod raw;
+pub mod rate_limiter;
+pub mod redirect;
 pub mod request;
 pub mod response;
+pub mod retry;
+pub mod scrape;
+pub mod script;
+pub mod session;
+pub mod sigv4;
 pub mod utils;
+pub mod values;
+pub mod webhook;
 
+#[cfg(test)]
 mod tests;
@@ -0,0 +1,85 @@
+use std::{error::Error, process::Stdio};
+
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// the external program that receives every prepared request as json on stdin
+/// and prints the request to send instead on stdout:
+/// {"method": "GET", "url": "https://example.com/?a=b", "path": "/?a=b", "headers": [["name", "value"], ..], "body": ""}.
+/// the path, headers and body can be changed, missing fields stay the same
+#[derive(Debug)]
+pub struct Mutator {
+    command: String,
+}
+
+impl Mutator {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+
+    pub async fn mutate(
+        &self,
+        method: &str,
+        url: &str,
+        path: &mut String,
+        headers: &mut Vec<(String, String)>,
+        body: &mut String,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = json!({
+            "method": method,
+            "url": url,
+            "path": path,
+            "headers": headers,
+            "body": body,
+        });
+
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c");
+            command
+        };
+
+        let mut child = command
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| format!("Unable to run the mutator: {}", err))?;
+
+        // the program may exit without reading the whole request, so the write error is ignored
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all((request.to_string() + "\n").as_bytes()).await.ok();
+        }
+
+        let output = child.wait_with_output().await?;
+
+        if !output.status.success() {
+            Err(format!("The mutator exited with {}", output.status))?;
+        }
+
+        let mutated: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|err| format!("Unable to parse the request from the mutator: {}", err))?;
+
+        if let Some(val) = mutated.get("path").and_then(|x| x.as_str()) {
+            *path = val.to_string();
+        }
+
+        if let Some(val) = mutated.get("headers") {
+            *headers = serde_json::from_value(val.clone())
+                .map_err(|err| format!("Unable to parse the headers from the mutator: {}", err))?;
+        }
+
+        if let Some(val) = mutated.get("body").and_then(|x| x.as_str()) {
+            *body = val.to_string();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::error::Error;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+/// the steps applied to every response (including the initial one) before reflections are counted and responses are compared.
+/// steps are always applied in the same order: comments, entities, regexes, whitespace, lowercase
+#[derive(Debug, Clone, Default)]
+pub struct Normalization {
+    /// remove html comments
+    pub comments: bool,
+
+    /// decode html entities
+    pub entities: bool,
+
+    /// remove matched substrings from the body and header values
+    pub regexes: Vec<Regex>,
+
+    /// collapse whitespaces within lines and remove empty lines
+    pub whitespace: bool,
+
+    pub lowercase: bool,
+}
+
+impl Normalization {
+    /// takes the names of the steps from --normalize together with --filter-regex regexes
+    pub fn new(steps: &[&str], regexes: Vec<Regex>) -> Result<Self, Box<dyn Error>> {
+        let mut normalization = Self {
+            regexes,
+            ..Default::default()
+        };
+
+        for step in steps {
+            match *step {
+                "comments" => normalization.comments = true,
+                "entities" => normalization.entities = true,
+                "whitespace" => normalization.whitespace = true,
+                "lowercase" => normalization.lowercase = true,
+                _ => Err(format!(
+                    "Unknown normalization step: {}. Use comments, entities, whitespace or lowercase",
+                    step
+                ))?,
+            }
+        }
+
+        Ok(normalization)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.comments && !self.entities && self.regexes.is_empty() && !self.whitespace && !self.lowercase
+    }
+
+    pub fn body(&self, text: String) -> String {
+        lazy_static! {
+            static ref RE_HTML_COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+        }
+
+        if self.is_empty() {
+            return text;
+        }
+
+        let mut text = if self.comments {
+            RE_HTML_COMMENT.replace_all(&text, "").to_string()
+        } else {
+            text
+        };
+
+        if self.entities {
+            text = decode_entities(&text);
+        }
+
+        text = self.remove_matches(text);
+
+        if self.whitespace {
+            text = collapse_whitespace(&text);
+        }
+
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+
+        text
+    }
+
+    /// only regexes are applied to header values
+    pub fn header(&self, value: String) -> String {
+        self.remove_matches(value)
+    }
+
+    fn remove_matches(&self, mut text: String) -> String {
+        for regex in self.regexes.iter() {
+            text = regex.replace_all(&text, "").to_string();
+        }
+
+        text
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    lazy_static! {
+        static ref RE_WHITESPACES: Regex = Regex::new(r"\s+").unwrap();
+    }
+
+    text.lines()
+        .map(|x| RE_WHITESPACES.replace_all(x.trim(), " "))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// decodes named entities that are common within pages together with numeric ones
+fn decode_entities(text: &str) -> String {
+    lazy_static! {
+        static ref RE_ENTITY: Regex = Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]{2,6});").unwrap();
+    }
+
+    RE_ENTITY
+        .replace_all(text, |caps: &Captures| {
+            let entity = &caps[1];
+
+            let decoded = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse().ok().and_then(char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    _ => None,
+                }
+            };
+
+            match decoded {
+                Some(val) => val.to_string(),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
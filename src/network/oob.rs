@@ -0,0 +1,52 @@
+use std::{error::Error, time::Duration};
+
+use crate::utils::random_line;
+
+/// the length of unique ids within callback hosts
+const ID_LENGTH: usize = 12;
+
+/// embeds unique callback urls into parameter values and checks whether they were called.
+/// interactions are collected by an external provider (interactsh-client, collaborator, own dns server, ..)
+/// and read from its log that is either a local file or an http url
+#[derive(Debug, Clone)]
+pub struct Oob {
+    /// the domain that receives the interactions, e.g. xxxx.oast.fun
+    pub domain: String,
+
+    /// the file or the url with the log of received interactions
+    pub poll: String,
+
+    /// how long to wait for the interactions after the requests are sent
+    pub wait: Duration,
+}
+
+impl Oob {
+    pub fn new(domain: &str, poll: &str, wait: Duration) -> Self {
+        Self {
+            domain: domain.trim_matches('.').to_lowercase(),
+            poll: poll.to_string(),
+            wait,
+        }
+    }
+
+    /// returns a unique id and the callback url with it
+    pub fn payload(&self) -> (String, String) {
+        let id = random_line(ID_LENGTH);
+        let url = format!("http://{}.{}/", id, self.domain);
+
+        (id, url)
+    }
+
+    /// reads the interactions log and returns the ids that were called
+    pub async fn poll<'a>(&self, ids: impl Iterator<Item = &'a String>) -> Result<Vec<String>, Box<dyn Error>> {
+        let log = if self.poll.starts_with("http://") || self.poll.starts_with("https://") {
+            reqwest::get(&self.poll).await?.text().await?
+        } else {
+            tokio::fs::read_to_string(&self.poll).await?
+        }
+        // dns is case insensitive, so some resolvers randomize the case of the queried names
+        .to_lowercase();
+
+        Ok(ids.filter(|x| log.contains(x.as_str())).cloned().collect())
+    }
+}
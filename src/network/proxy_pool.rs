@@ -0,0 +1,112 @@
+use std::{
+    error::Error,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use reqwest::Client;
+
+use crate::{config::structs::Config, utils::random_index};
+
+use super::utils::create_client_with_proxy;
+
+/// the proxy is evicted from the pool after this amount of failed requests in a row
+const MAX_FAILURES: usize = 3;
+
+/// how to choose the next proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProxyRotation {
+    #[default]
+    RoundRobin,
+    Random,
+}
+
+#[derive(Debug)]
+struct PoolProxy {
+    proxy: String,
+
+    client: Client,
+
+    /// failed requests in a row
+    failures: AtomicUsize,
+}
+
+impl PoolProxy {
+    fn is_alive(&self) -> bool {
+        self.failures.load(Ordering::Relaxed) < MAX_FAILURES
+    }
+}
+
+/// rotates requests between multiple proxies and evicts the dead ones
+#[derive(Debug)]
+pub struct ProxyPool {
+    proxies: Vec<PoolProxy>,
+
+    rotation: ProxyRotation,
+
+    /// the next proxy index for round-robin rotation
+    next: AtomicUsize,
+}
+
+impl ProxyPool {
+    /// creates a client for every proxy from config.proxies
+    pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let mut proxies = Vec::with_capacity(config.proxies.len());
+
+        for proxy in config.proxies.iter() {
+            proxies.push(PoolProxy {
+                proxy: proxy.to_owned(),
+                client: create_client_with_proxy(config, proxy)
+                    .map_err(|err| format!("Unable to use proxy {}: {}", proxy, err))?,
+                failures: AtomicUsize::new(0),
+            });
+        }
+
+        Ok(Self {
+            proxies,
+            rotation: config.proxy_rotation,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// returns the index and the client of the next alive proxy
+    pub fn next(&self) -> Option<(usize, &Client)> {
+        let index = match self.rotation {
+            ProxyRotation::RoundRobin => (0..self.proxies.len())
+                .map(|_| self.next.fetch_add(1, Ordering::Relaxed) % self.proxies.len())
+                .find(|&i| self.proxies[i].is_alive())?,
+            ProxyRotation::Random => {
+                let alive: Vec<usize> = (0..self.proxies.len())
+                    .filter(|&i| self.proxies[i].is_alive())
+                    .collect();
+
+                if alive.is_empty() {
+                    return None;
+                }
+
+                alive[random_index(alive.len())]
+            }
+        };
+
+        Some((index, &self.proxies[index].client))
+    }
+
+    /// remembers the result of the request sent via the proxy.
+    /// returns true in case the proxy was evicted because of this failure
+    pub fn report(&self, index: usize, success: bool) -> bool {
+        let proxy = &self.proxies[index];
+
+        if success {
+            proxy.failures.store(0, Ordering::Relaxed);
+            false
+        } else {
+            let failures = proxy.failures.fetch_add(1, Ordering::Relaxed) + 1;
+
+            if failures == MAX_FAILURES {
+                log::debug!("The proxy {} is evicted from the pool", proxy.proxy);
+                true
+            } else {
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// token bucket limiting the amount of requests per second.
+/// one limiter is shared between all the requests regardless of the concurrency
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// allowed requests per second
+    rps: f64,
+
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rps: f64) -> Self {
+        Self {
+            rps,
+            bucket: Mutex::new(Bucket {
+                tokens: 1.,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// allowed requests per second
+    pub fn rps(&self) -> f64 {
+        self.rps
+    }
+
+    /// waits until the request can be sent
+    pub async fn wait(&self) {
+        loop {
+            let wait_for = {
+                let mut bucket = self.bucket.lock();
+
+                let now = Instant::now();
+                let refilled = now.duration_since(bucket.last_refill).as_secs_f64() * self.rps;
+
+                // the capacity of the bucket is 1 in order to avoid bursts
+                bucket.tokens = (bucket.tokens + refilled).min(1.);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1. {
+                    bucket.tokens -= 1.;
+                    return;
+                }
+
+                Duration::from_secs_f64((1. - bucket.tokens) / self.rps)
+            };
+
+            tokio::time::sleep(wait_for).await;
+        }
+    }
+}
+
+/// pauses all the requests to the target after it responded with 429 (or 503 with Retry-After)
+#[derive(Debug, Default)]
+pub struct Pause {
+    until: Mutex<Option<Instant>>,
+}
+
+impl Pause {
+    /// pauses requests for the duration (unless they're already paused for longer)
+    pub fn pause_for(&self, duration: Duration) {
+        let mut until = self.until.lock();
+        let new_until = Instant::now() + duration;
+
+        if until.is_none_or(|x| x < new_until) {
+            *until = Some(new_until);
+        }
+    }
+
+    /// waits until the pause ends
+    pub async fn wait(&self) {
+        let wait_for = match *self.until.lock() {
+            Some(until) => until.saturating_duration_since(Instant::now()),
+            None => return,
+        };
+
+        if !wait_for.is_zero() {
+            tokio::time::sleep(wait_for).await;
+        }
+    }
+}
+
+/// holds all the requests until the scan is resumed. Toggled by the user with SIGUSR1 or the `p` key
+#[derive(Debug, Default)]
+pub struct Gate {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl Gate {
+    /// pauses or resumes requests and returns whether they are paused now
+    pub fn toggle(&self) -> bool {
+        let paused = !self.paused.fetch_xor(true, Ordering::SeqCst);
+
+        if !paused {
+            self.resumed.notify_waiters();
+        }
+
+        paused
+    }
+
+    /// resumes requests if they are paused. Used once the scan is interrupted, so paused requests can finish
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::SeqCst) {
+            self.resumed.notify_waiters();
+        }
+    }
+
+    /// waits until the scan is resumed
+    pub async fn wait(&self) {
+        loop {
+            // the future is created before the check, so the resume between them isn't missed
+            let resumed = self.resumed.notified();
+
+            if !self.paused.load(Ordering::SeqCst) {
+                return;
+            }
+
+            resumed.await;
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{rustls, TlsConnector};
+
+use crate::config::structs::Config;
+
+use super::utils::Headers;
+
+/// the response as it was read from the socket
+#[derive(Debug)]
+pub struct RawResponse {
+    pub code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub http_version: http::Version,
+}
+
+/// writes requests directly to the socket, so headers are sent exactly as they are
+/// (unusual whitespaces, duplicated Host headers, obs-fold, ..) instead of being normalized by the http client
+#[derive(Debug)]
+pub struct RawClient {
+    tls_config: Arc<rustls::ClientConfig>,
+
+    timeout: Duration,
+
+    /// (host, address) pairs that override dns resolution
+    resolve: Vec<(String, SocketAddr)>,
+
+    /// in case it's set, requests are sent via the unix socket instead of tcp
+    unix_socket: Option<String>,
+}
+
+/// accepts any certificate like the default client does
+struct NoCertificateVerification;
+
+impl rustls::client::ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+impl RawClient {
+    pub fn new(config: &Config) -> Self {
+        let mut tls_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+            .with_no_client_auth();
+
+        // raw requests are always HTTP/1.1
+        tls_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        Self {
+            tls_config: Arc::new(tls_config),
+            timeout: Duration::from_secs(config.timeout as u64),
+            resolve: config.resolve.clone(),
+            unix_socket: config.unix_socket.clone(),
+        }
+    }
+
+    /// sends the request bytes to the host and reads the response till the connection is closed
+    pub async fn send(
+        &self,
+        scheme: &str,
+        host: &str,
+        port: u16,
+        request: &[u8],
+    ) -> Result<RawResponse, Box<dyn Error>> {
+        let raw_response = tokio::time::timeout(self.timeout, self.exchange(scheme, host, port, request))
+            .await
+            .map_err(|_| "The raw request timed out")??;
+
+        parse_response(&raw_response)
+    }
+
+    async fn exchange(
+        &self,
+        scheme: &str,
+        host: &str,
+        port: u16,
+        request: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Some(unix_socket) = &self.unix_socket {
+            return exchange_via_unix_socket(unix_socket, request).await;
+        }
+
+        let stream = match self.resolve.iter().find(|(x, _)| x.eq_ignore_ascii_case(host)) {
+            Some((_, addr)) => TcpStream::connect(SocketAddr::new(addr.ip(), port)).await?,
+            None => TcpStream::connect((host, port)).await?,
+        };
+
+        if scheme == "https" {
+            let server_name = rustls::ServerName::try_from(host)
+                .map_err(|_| format!("Unable to use {} as the tls server name", host))?;
+            let mut stream = TlsConnector::from(Arc::clone(&self.tls_config))
+                .connect(server_name, stream)
+                .await?;
+
+            Ok(write_and_read(&mut stream, request).await?)
+        } else {
+            let mut stream = stream;
+            Ok(write_and_read(&mut stream, request).await?)
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn exchange_via_unix_socket(path: &str, request: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut stream = tokio::net::UnixStream::connect(path)
+        .await
+        .map_err(|err| format!("Unable to connect to {}: {}", path, err))?;
+
+    Ok(write_and_read(&mut stream, request).await?)
+}
+
+#[cfg(not(unix))]
+async fn exchange_via_unix_socket(_path: &str, _request: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("Unix sockets are not supported on this platform")?
+}
+
+/// writes the request and reads the whole response
+pub(super) async fn write_and_read<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &[u8],
+) -> io::Result<Vec<u8>> {
+    stream.write_all(request).await?;
+    stream.flush().await?;
+
+    let mut response = Vec::new();
+
+    match stream.read_to_end(&mut response).await {
+        Ok(_) => Ok(response),
+        // a lot of servers close tls connections without close_notify
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && !response.is_empty() => Ok(response),
+        Err(err) => Err(err),
+    }
+}
+
+/// creates the request bytes. Headers are written as they are.
+/// Host, Content-Length and Connection headers are added only in case they're missing
+pub fn build_request(
+    method: &str,
+    path: &str,
+    host: &str,
+    headers: &Vec<(String, String)>,
+    body: &str,
+) -> Vec<u8> {
+    let mut request = format!("{} {} HTTP/1.1\r\n", method, path);
+
+    if !headers.iter().any(|(k, _)| k.trim().eq_ignore_ascii_case("host")) {
+        request += &format!("Host: {}\r\n", host);
+    }
+
+    for (k, v) in headers.iter() {
+        request += &format!("{}: {}\r\n", k, v);
+    }
+
+    if !body.is_empty()
+        && headers.get_value_case_insensitive("content-length").is_none()
+        && headers.get_value_case_insensitive("transfer-encoding").is_none()
+    {
+        request += &format!("Content-Length: {}\r\n", body.len());
+    }
+
+    // the response is read till the connection is closed
+    if headers.get_value_case_insensitive("connection").is_none() {
+        request += "Connection: close\r\n";
+    }
+
+    request += "\r\n";
+    request += body;
+
+    request.into_bytes()
+}
+
+/// parses the status line, headers and body. Chunked bodies are decoded
+pub(super) fn parse_response(response: &[u8]) -> Result<RawResponse, Box<dyn Error>> {
+    let headers_end = response
+        .windows(4)
+        .position(|x| x == b"\r\n\r\n")
+        .ok_or("Unable to find the end of the response headers")?;
+
+    let head = String::from_utf8_lossy(&response[..headers_end]);
+    let mut lines = head.split("\r\n");
+
+    let mut status_line = lines.next().unwrap_or_default().splitn(3, ' ');
+
+    let http_version = match status_line.next() {
+        Some("HTTP/1.0") => http::Version::HTTP_10,
+        Some("HTTP/1.1") => http::Version::HTTP_11,
+        Some(version) => Err(format!("Unsupported response version {}", version))?,
+        None => Err("Unable to parse the status line")?,
+    };
+
+    let code: u16 = status_line
+        .next()
+        .ok_or("Unable to parse the response code")?
+        .parse()?;
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    for line in lines {
+        // obs-fold: the line continues the value of the previous header
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, v)) = headers.last_mut() {
+                if !v.is_empty() {
+                    v.push(' ');
+                }
+                v.push_str(line.trim());
+            }
+            continue;
+        }
+
+        let mut k_v = line.splitn(2, ':');
+        let k = k_v.next().unwrap().trim().to_lowercase();
+        let v = k_v.next().unwrap_or_default().trim().to_string();
+
+        headers.push((k, v));
+    }
+
+    let mut body = response[headers_end + 4..].to_vec();
+
+    if headers
+        .get_value_case_insensitive("transfer-encoding")
+        .is_some_and(|x| x.to_lowercase().contains("chunked"))
+    {
+        body = decode_chunked(&body)?;
+    } else if let Some(content_length) = headers.get_value_case_insensitive("content-length") {
+        if let Ok(content_length) = content_length.parse::<usize>() {
+            body.truncate(content_length);
+        }
+    }
+
+    Ok(RawResponse {
+        code,
+        headers,
+        body,
+        http_version,
+    })
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut decoded = Vec::with_capacity(body.len());
+
+    // stops in case the connection was closed in the middle of the body
+    while let Some(line_end) = body.windows(2).position(|x| x == b"\r\n") {
+        // chunk extensions are ignored
+        let size = String::from_utf8_lossy(&body[..line_end]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap().trim(), 16)
+            .map_err(|_| "Unable to parse the chunk size")?;
+
+        body = &body[line_end + 2..];
+
+        if size == 0 {
+            break;
+        }
+
+        let size = size.min(body.len());
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[size..];
+
+        if body.starts_with(b"\r\n") {
+            body = &body[2..];
+        }
+    }
+
+    Ok(decoded)
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+
+use url::Url;
+
+use super::{
+    client::{HttpRequest, HttpResponse},
+    utils::Headers,
+};
+
+/// which redirects are followed (--follow-redirects)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    None,
+    SameHost,
+    All,
+}
+
+impl RedirectPolicy {
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match s {
+            "none" => Self::None,
+            "same-host" => Self::SameHost,
+            "all" => Self::All,
+            _ => Err(format!("Unknown redirect policy: {}. Use none, same-host or all", s))?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Redirects {
+    pub policy: RedirectPolicy,
+
+    /// the maximum amount of followed redirects per request
+    pub max_hops: usize,
+}
+
+impl Default for Redirects {
+    fn default() -> Self {
+        Self {
+            policy: RedirectPolicy::None,
+            max_hops: 10,
+        }
+    }
+}
+
+impl Redirects {
+    /// the request to the location of the redirect in case the policy allows following it
+    pub fn next(&self, request: &HttpRequest, response: &HttpResponse) -> Option<HttpRequest> {
+        if self.policy == RedirectPolicy::None || !(300..400).contains(&response.code) || response.code == 304 {
+            return None;
+        }
+
+        let current = Url::parse(&request.url).ok()?;
+        let location = current.join(&response.headers.get_value_case_insensitive("location")?).ok()?;
+
+        // the scheme and the port matter too, so credentials don't leak via plain http or another service
+        let same_origin = current.origin() == location.origin();
+        if self.policy == RedirectPolicy::SameHost && !same_origin {
+            return None;
+        }
+
+        let mut next = HttpRequest {
+            method: request.method.clone(),
+            url: location.to_string(),
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+            max_body_size: request.max_body_size,
+        };
+
+        // like browsers, only 307 and 308 keep the method and the body
+        if response.code != 307 && response.code != 308 && request.method != "HEAD" {
+            next.method = "GET".to_string();
+            next.body = String::new();
+            next.headers.retain(|(k, _)| {
+                !k.eq_ignore_ascii_case("content-type") && !k.eq_ignore_ascii_case("content-length")
+            });
+        }
+
+        // credentials aren't sent to other origins
+        if !same_origin {
+            next.headers.retain(|(k, _)| {
+                !k.eq_ignore_ascii_case("authorization")
+                    && !k.eq_ignore_ascii_case("proxy-authorization")
+                    && !k.eq_ignore_ascii_case("cookie")
+            });
+        }
+
+        if let Some(host) = location.host_str() {
+            let host = match location.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            };
+
+            for (k, v) in next.headers.iter_mut() {
+                if k.eq_ignore_ascii_case("host") {
+                    *v = host.clone();
+                }
+            }
+        }
+
+        Some(next)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{config::structs::Config, utils::random_line, VALUE_LENGTH, RANDOM_LENGTH};
+use crate::{config::structs::Config, diff::html_lines, error::X8Error, metrics::Metrics, utils::random_line, VALUE_LENGTH, RANDOM_LENGTH};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use percent_encoding::utf8_percent_encode;
@@ -6,10 +6,11 @@ use regex::Regex;
 use reqwest::Client;
 use std::{
     collections::HashMap,
-    convert::TryFrom,
     error::Error,
     iter::FromIterator,
-    time::{Duration, Instant},
+    ops::{Deref, Range},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 use url::Url;
 
@@ -18,11 +19,47 @@ const HEADERS_TEMPLATE: &str = "%k\x00@%=%@\x00%v";
 const HEADERS_MIDDLE: &str = "\x00@%=%@\x00";
 const HEADERS_JOINER: &str = "\x01@%&%@\x01";
 
+/// %b is replaced with the multipart boundary
+const MULTIPART_TEMPLATE: &str = "--%b\r\nContent-Disposition: form-data; name=\"%k\"\r\n\r\n%v\r\n";
+
+/// how many times the rate limited request is resent before returning the rate limited response
+const MAX_RATE_LIMIT_RETRIES: usize = 5;
+
+/// how many times the blocked request is resent before giving up
+const MAX_BLOCK_RETRIES: usize = 3;
+
 use super::{
-    response::Response,
-    utils::{DataType, Headers, InjectionPlace, FRAGMENT, create_client, is_binary_content},
+    block::BlockDetector,
+    budget::Budget,
+    cache::ResponseCache,
+    cassette::Recorder,
+    client::{HttpClient, HttpRequest, HttpResponse},
+    graphql::GraphQLMode,
+    cookie_jar::CookieJar,
+    csrf::Csrf,
+    decompress::decompress,
+    proxy_pool::ProxyPool,
+    raw::{build_request, RawClient},
+    rate_limiter::{Gate, Pause, RateLimiter},
+    redirect::Redirects,
+    response::{BodyStats, Response},
+    retry::RetryPolicy,
+    matchers::Matchers,
+    mutator::Mutator,
+    normalize::Normalization,
+    script::Script,
+    session::Session,
+    sigv4::SigV4,
+    values::ValueStrategies,
+    utils::{
+        DataType, Headers, InjectionPlace, FRAGMENT, body_summary, create_client, insert_json_injection_point, is_binary_body, is_binary_content, is_html,
+        insert_xml_injection_point, multipart_boundary, xml_escape,
+    },
 };
 
+#[cfg(feature = "http3")]
+use super::http3::Http3Client;
+
 #[derive(Debug, Clone, Default)]
 pub struct RequestDefaults {
     /// default request data
@@ -53,6 +90,9 @@ pub struct RequestDefaults {
     /// to replace {"key": "false"} with {"key": false}
     pub is_json: bool,
 
+    /// to escape special characters within xml parameters
+    pub is_xml: bool,
+
     /// default body
     pub body: String,
 
@@ -69,16 +109,161 @@ pub struct RequestDefaults {
     /// the default amount of reflection per non existing parameter
     pub amount_of_reflections: usize,
 
+    /// lowercased names of headers that reflect non existing parameters
+    pub header_reflections: Vec<String>,
+
     /// check body of responses with binary content type
     pub check_binary: bool,
+
+    /// limits the amount of requests per second. Shared between all the requests
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// limits the amount of requests per second to the host. Shared between all the urls of the host
+    pub host_rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// pauses requests in case the target rate limits them. Shared between clones of the defaults
+    pub pause: Arc<Pause>,
+
+    /// in case it's set, requests are sent via the rotated proxies instead of the default client
+    pub proxy_pool: Option<Arc<ProxyPool>>,
+
+    /// applied to responses before comparing: removes comments and matched substrings, decodes entities, etc
+    pub normalization: Normalization,
+
+    /// responses that don't fit --match-* and --filter-* options aren't considered as changed
+    pub matchers: Matchers,
+
+    /// compare html bodies by their elements and attributes instead of their formatting
+    pub html_diff: bool,
+
+    /// compare json bodies structurally instead of line by line
+    pub json_diff: bool,
+
+    /// redirects are followed manually, so the chain can be recorded
+    pub redirects: Redirects,
+
+    /// bodies are read only up to this amount of bytes
+    pub max_body_size: Option<usize>,
+
+    /// responses to the already sent requests with the same parameters
+    pub response_cache: Option<Arc<ResponseCache>>,
+
+    /// json pointers to ignore while comparing json bodies
+    pub json_diff_ignore: Vec<String>,
+
+    /// compare json arrays regardless of the order of their elements
+    pub json_diff_unordered: bool,
+
+    /// bodies with the similarity ratio above this threshold are considered the same
+    pub similarity: Option<f64>,
+
+    /// how to resend failed requests
+    pub retry_policy: RetryPolicy,
+
+    /// in case it's set, requests are written directly to the socket instead of being sent via the default client
+    pub raw_client: Option<Arc<RawClient>>,
+
+    /// in case it's set, cookies from Set-Cookie headers are merged into the Cookie header of every request
+    pub cookie_jar: Option<Arc<CookieJar>>,
+
+    /// in case it's set, the session is refreshed when it expires and the refreshed headers override the original ones
+    pub session: Option<Arc<Session>>,
+
+    /// in case it's set, the csrf token is fetched, inserted into every request and refreshed when it's rejected
+    pub csrf: Option<Arc<Csrf>>,
+
+    /// in case it's set, every request is signed with AWS Signature Version 4
+    pub sigv4: Option<SigV4>,
+
+    /// user hooks that can change requests and responses
+    pub script: Option<Arc<Script>>,
+
+    /// in case it's set, every prepared request is replaced with the output of the external program
+    pub mutator: Option<Arc<Mutator>>,
+
+    /// in case it's set, requests are paused after WAF or anti-bot block pages
+    pub block: Option<Arc<BlockDetector>>,
+
+    /// how to generate values of parameters without user supplied values
+    pub values: ValueStrategies,
+
+    /// counts sent requests for --max-requests. Shared between all the urls
+    pub budget: Arc<Budget>,
+
+    /// holds requests while the scan is paused by the user. Shared between all the urls
+    pub gate: Arc<Gate>,
+
+    /// counts sent and failed requests for the metrics listener
+    pub metrics: Arc<Metrics>,
+
+    /// in case it's set, requests are sent via the client instead of the default one.
+    /// used to replay cassettes and to mock the target in tests
+    pub http_client: Option<Arc<dyn HttpClient>>,
+
+    /// in case it's set, every request and response is written to the cassette
+    pub recorder: Option<Arc<Recorder>>,
+}
+
+/// a part of the list of parameters. The list is shared between all its parts,
+/// so splitting chunks and creating requests doesn't copy the parameters
+#[derive(Debug, Clone)]
+pub struct Parameters {
+    all: Arc<[String]>,
+    range: Range<usize>,
+}
+
+impl Parameters {
+    /// splits the parameters into [..mid] and [mid..]
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        let mid = self.range.start + mid;
+
+        (
+            Self { all: Arc::clone(&self.all), range: self.range.start..mid },
+            Self { all: Arc::clone(&self.all), range: mid..self.range.end },
+        )
+    }
+
+    /// the part of the parameters at the given range
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        Self {
+            all: Arc::clone(&self.all),
+            range: self.range.start + range.start..self.range.start + range.end,
+        }
+    }
+}
+
+impl Deref for Parameters {
+    type Target = [String];
+
+    fn deref(&self) -> &[String] {
+        &self.all[self.range.clone()]
+    }
+}
+
+impl From<Arc<[String]>> for Parameters {
+    fn from(all: Arc<[String]>) -> Self {
+        Self { range: 0..all.len(), all }
+    }
+}
+
+impl From<Vec<String>> for Parameters {
+    fn from(all: Vec<String>) -> Self {
+        Arc::<[String]>::from(all).into()
+    }
+}
+
+impl FromIterator<String> for Parameters {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        iter.into_iter().collect::<Arc<[String]>>().into()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Request<'a> {
     pub defaults: &'a RequestDefaults,
 
-    /// vector of supplied parameters
-    pub parameters: Vec<String>,
+    /// supplied parameters. Shared with the chunk they come from, so creating requests doesn't copy them
+    pub parameters: Parameters,
 
     /// parsed parameters (key, value)
     pub prepared_parameters: Vec<(String, String)>,
@@ -98,24 +283,35 @@ pub struct Request<'a> {
     /// whether the request was prepared
     /// {{random}} things replaced, prepared_parameters filled
     pub prepared: bool,
+
+    /// whether the response can be taken from --cache-responses
+    pub cacheable: bool,
 }
 
 impl<'a> Request<'a> {
-    pub fn new(l: &'a RequestDefaults, parameters: Vec<String>) -> Self {
+    pub fn new(l: &'a RequestDefaults, parameters: impl Into<Parameters>) -> Self {
         Self {
             path: l.path.to_owned(),
             defaults: l,
             headers: Vec::new(),
             body: l.body.clone(),
-            parameters,
+            parameters: parameters.into(),
             prepared_parameters: Vec::new(), //l.parameters.clone(),
             non_random_parameters: Vec::new(),
             prepared: false,
+            cacheable: true,
         }
     }
 
+    /// the request is always sent, even when the same one is within --cache-responses.
+    /// used where the response is requested again on purpose
+    pub fn uncached(mut self) -> Self {
+        self.cacheable = false;
+        self
+    }
+
     pub fn new_random(l: &'a RequestDefaults, max: usize) -> Self {
-        let parameters = Vec::from_iter((0..max).map(|_| random_line(VALUE_LENGTH)));
+        let parameters: Parameters = (0..max).map(|_| random_line(VALUE_LENGTH)).collect();
         Request::new(l, parameters)
     }
 
@@ -160,6 +356,18 @@ impl<'a> Request<'a> {
                 })
                 .collect::<Vec<String>>()
                 .join(&self.defaults.joiner)
+        } else if self.defaults.is_xml {
+            self.prepared_parameters
+                .iter()
+                .chain(self.defaults.parameters.iter())
+                .map(|(k, v)| {
+                    self.defaults
+                        .template
+                        .replace("%k", &xml_escape(k))
+                        .replace("%v", &xml_escape(v))
+                })
+                .collect::<Vec<String>>()
+                .join(&self.defaults.joiner)
         } else {
             self.prepared_parameters
                 .iter()
@@ -192,16 +400,17 @@ impl<'a> Request<'a> {
         self.non_random_parameters = Vec::from_iter(
             self.parameters
                 .iter()
-                .filter(|x| x.contains('='))
-                .map(|x| x.split('='))
-                .map(|mut x| {
-                    (
-                        x.next().unwrap().to_owned(),
-                        x.next().unwrap_or("").to_owned(),
-                    )
-                }),
+                .filter_map(|x| x.split_once('='))
+                .map(|(k, v)| (k.to_owned(), v.to_owned())),
         );
 
+        let generated_parameters: Vec<(String, String)> = self
+            .parameters
+            .iter()
+            .filter(|x| !x.is_empty() && !x.contains('='))
+            .map(|x| (x.to_owned(), self.defaults.values.generate(x)))
+            .collect();
+
         self.prepared_parameters = Vec::from_iter(
             // append self.prepared_parameters (can be set from RequestDefaults using recursive search)
             self.prepared_parameters
@@ -214,15 +423,20 @@ impl<'a> Request<'a> {
                         .map(|(k, v)| (k.to_owned(), v.to_owned())),
                 )
                 // append random parameters
-                .chain(
-                    self.parameters
-                        .iter()
-                        .filter(|x| !x.is_empty() && !x.contains("="))
-                        .map(|x| (x.to_owned(), random_line(VALUE_LENGTH))),
-                ),
+                .chain(generated_parameters.iter().cloned()),
+        );
+
+        // values like true or 1 can be reflected an unpredictable amount of times as well
+        let defaults = self.defaults;
+        self.non_random_parameters.extend(
+            generated_parameters
+                .into_iter()
+                .filter(|(k, _)| !defaults.values.get(k).is_unique()),
         );
 
-        if self.defaults.injection_place != InjectionPlace::HeaderValue {
+        if self.defaults.injection_place != InjectionPlace::HeaderValue
+            && self.defaults.injection_place != InjectionPlace::Cookies
+        {
             for (k, v) in self.defaults.custom_headers.iter() {
                 self.set_header(k, &v.replace("{{random}}", &random_line(RANDOM_LENGTH)));
             }
@@ -231,7 +445,9 @@ impl<'a> Request<'a> {
         self.body = self.body.replace("{{random}}", &random_line(RANDOM_LENGTH));
 
         match self.defaults.injection_place {
-            InjectionPlace::Path => self.path = self.path.replace("%s", &self.make_query()),
+            InjectionPlace::Path | InjectionPlace::PathSegment => {
+                self.path = self.path.replace("%s", &self.make_query())
+            }
             InjectionPlace::Body => {
                 self.body = self.body.replace("%s", &self.make_query());
 
@@ -243,7 +459,7 @@ impl<'a> Request<'a> {
                     }
                 }
             }
-            InjectionPlace::HeaderValue => {
+            InjectionPlace::HeaderValue | InjectionPlace::Cookies => {
                 // in case someone searches headers while sending a valid body - it's usually important to set Content-Type header as well.
                 if !self.defaults.custom_headers.contains_key("Content-Type") && self.defaults.method != "GET" && self.defaults.method != "HEAD" && !self.body.is_empty() {
                     if self.body.starts_with('{') {
@@ -282,15 +498,169 @@ impl<'a> Request<'a> {
                 self.set_headers(headers);
             }
         }
+
+        if let Some(cookie_jar) = self.defaults.cookie_jar.as_ref().filter(|x| !x.is_empty()) {
+            // values of the checked parameters shouldn't be replaced
+            let skip: Vec<&str> = self.prepared_parameters.iter().map(|(k, _)| k.as_str()).collect();
+
+            match self.headers.get_index_case_insensitive("cookie") {
+                Some(index) => self.headers[index].1 = cookie_jar.apply(&self.headers[index].1, &skip),
+                None => {
+                    let cookie = cookie_jar.apply("", &skip);
+                    self.set_header("Cookie".to_string(), cookie);
+                }
+            }
+        }
+
+        if let Some(block) = &self.defaults.block {
+            block.apply(&mut self.headers);
+        }
+
+        if let Some(session) = &self.defaults.session {
+            let skip: Vec<&str> = self.prepared_parameters.iter().map(|(k, _)| k.as_str()).collect();
+            session.apply(&mut self.headers, &skip);
+        }
+
+        if let Some(csrf) = &self.defaults.csrf {
+            csrf.apply(&mut self.path, &mut self.headers, &mut self.body, self.defaults.is_json);
+        }
+
+        if let Some(script) = &self.defaults.script {
+            script.on_request(&self.defaults.method, &mut self.path, &mut self.headers, &mut self.body);
+        }
+
+        // the signature covers the final query and body, so it's computed last
+        if let Some(sigv4) = &self.defaults.sigv4 {
+            sigv4.sign(
+                &self.defaults.method,
+                &self.defaults.host_header(),
+                &self.path,
+                &mut self.headers,
+                &self.body,
+                SystemTime::now(),
+            );
+        }
     }
 
-    pub async fn send_by(self, clients: &Client) -> Result<Response<'a>, Box<dyn Error>> {
-        match self.clone().request(clients).await {
-            Ok(val) => Ok(val),
-            Err(_) => {
-                tokio::time::sleep(Duration::from_secs(10)).await;
-                Ok(self.clone().request(clients).await?)
+    /// sends the request and resends it according to the retry policy
+    /// in case of network errors or gateway error codes.
+    /// without the client, the next alive proxy from --proxy-file or the default client is used for every attempt
+    pub async fn send_by(self, client: Option<&Client>) -> Result<Response<'a>, Box<dyn Error>> {
+        let policy = &self.defaults.retry_policy;
+        let mut attempt = 0;
+        let mut rate_limit_attempt = 0;
+        let mut block_attempt = 0;
+        let mut session_refreshed = false;
+        let mut csrf_refreshed = false;
+
+        if let Some(csrf) = &self.defaults.csrf {
+            csrf.ensure_token(self.defaults).await?;
+        }
+
+        loop {
+            let csrf_token = self.defaults.csrf.as_ref().and_then(|x| x.token());
+
+            let proxy = match (&self.defaults.proxy_pool, client) {
+                (Some(proxy_pool), None) => {
+                    let (index, client) = proxy_pool
+                        .next()
+                        .ok_or_else(|| X8Error::Network("All the proxies from the pool are dead".to_string()))?;
+                    Some((proxy_pool, index, client))
+                }
+                _ => None,
+            };
+            let clients = proxy.map_or(client.unwrap_or(&self.defaults.client), |(_, _, client)| client);
+
+            let result = match &self.defaults.raw_client {
+                Some(raw_client) => self.clone().raw_request(raw_client).await,
+                None => self.clone().request(clients).await,
+            };
+
+            if result.is_err() {
+                self.defaults.metrics.add_error();
+            }
+
+            // in case of an error the request is resent via another proxy until there are no alive proxies left
+            if let Some((proxy_pool, index, _)) = proxy {
+                if let Err(err) = &result {
+                    log::debug!("Request via the proxy pool failed: {}", err);
+                    proxy_pool.report(index, false);
+                    continue;
+                }
+            }
+
+            // rate limited responses are resent after the advertised interval and aren't counted as retries
+            if let Ok(response) = &result {
+                if let Some(retry_after) = response.rate_limit_delay() {
+                    if rate_limit_attempt < MAX_RATE_LIMIT_RETRIES {
+                        log::debug!("Rate limited. Pausing requests for {}s", retry_after.as_secs());
+                        self.defaults.pause.pause_for(retry_after);
+                        rate_limit_attempt += 1;
+                        continue;
+                    }
+                }
+            }
+
+            // block pages are resent after the pause, so they aren't compared with the regular pages.
+            // parameters can trigger waf rules on their own, so only blocks of requests without them are considered bans
+            if let (Some(block), Ok(response)) = (&self.defaults.block, &result) {
+                if let Some(provider) = block.detect(response) {
+                    if self.is_banned(clients, block).await {
+                        if block_attempt >= MAX_BLOCK_RETRIES {
+                            Err(X8Error::Network(format!("The target keeps responding with a block page ({})", provider)))?;
+                        }
+                        block_attempt += 1;
+
+                        // banned proxies are rotated instead of pausing the requests
+                        if let Some((proxy_pool, index, _)) = proxy {
+                            log::debug!("The proxy from the pool is banned");
+                            proxy_pool.report(index, false);
+                            continue;
+                        }
+
+                        if block.block(provider) {
+                            self.defaults.pause.pause_for(block.pause);
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            // the request is resent once after the session refresh
+            if let (Some(session), Ok(response)) = (&self.defaults.session, &result) {
+                if !session_refreshed && session.is_expired(response) {
+                    log::debug!("The session expired. Refreshing it");
+                    session.refresh(self.defaults).await?;
+                    session_refreshed = true;
+                    continue;
+                }
+            }
+
+            // the request is resent once with the new token
+            if let (Some(csrf), Ok(response)) = (&self.defaults.csrf, &result) {
+                if !csrf_refreshed && csrf.is_rejected(response) && csrf.refresh(self.defaults, csrf_token).await? {
+                    log::debug!("The csrf token was rejected. Resending the request with the new one");
+                    csrf_refreshed = true;
+                    continue;
+                }
+            }
+
+            if let Some((proxy_pool, index, _)) = proxy {
+                proxy_pool.report(index, true);
+            }
+
+            if attempt >= policy.retries {
+                return result;
+            }
+
+            match result {
+                Ok(response) if !policy.should_retry_code(response.code) => return Ok(response),
+                Ok(response) => log::debug!("Resending the request because of {} code", response.code),
+                Err(err) => log::debug!("Resending the request because of an error: {}", err),
             }
+
+            tokio::time::sleep(policy.delay(attempt)).await;
+            attempt += 1;
         }
     }
 
@@ -304,62 +674,227 @@ impl<'a> Request<'a> {
         }
     }
 
-    pub async fn send(self) -> Result<Response<'a>, Box<dyn Error>> {
-        let dc = &self.defaults.client;
-        self.send_by(dc).await
+    pub async fn send(mut self) -> Result<Response<'a>, Box<dyn Error>> {
+        let defaults = self.defaults;
+
+        // requests without parameters check the state of the page, so they are always sent
+        let cache = match &defaults.response_cache {
+            Some(cache) if self.cacheable && !self.parameters.is_empty() => {
+                self.prepare();
+                let key = ResponseCache::key(&defaults.method, &self.url(), &self.headers, &self.body);
+
+                if let Some(response) = cache.get(key) {
+                    return Ok(Response {
+                        request: Some(self),
+                        ..response
+                    });
+                }
+
+                Some((cache, key))
+            }
+            _ => None,
+        };
+
+        let response = self.send_by(None).await?;
+
+        // rate limited responses are rechecked later, so they shouldn't be answered from the cache
+        if let Some((cache, key)) = cache {
+            if response.rate_limit_delay().is_none() {
+                cache.insert(key, &response);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// checks whether the request without parameters is blocked as well
+    async fn is_banned(&self, client: &Client, block: &BlockDetector) -> bool {
+        let request = Request::new(self.defaults, Vec::new());
+
+        let result = match &self.defaults.raw_client {
+            Some(raw_client) => request.raw_request(raw_client).await,
+            None => request.request(client).await,
+        };
+
+        result.is_ok_and(|x| block.detect(&x).is_some())
     }
 
-    async fn request(mut self, client: &Client) -> Result<Response<'a>, reqwest::Error> {
+    /// writes the request directly to the socket
+    async fn raw_request(mut self, raw_client: &RawClient) -> Result<Response<'a>, Box<dyn Error>> {
         self.prepare();
+        self.mutate().await?;
+
+        let request = build_request(
+            &self.defaults.method,
+            &self.path,
+            &self.defaults.host_header(),
+            &self.headers,
+            &self.body,
+        );
+
+        self.defaults.wait().await;
 
-        let mut request = http::Request::builder()
-            .method(self.defaults.method.as_str())
-            .uri(self.url());
+        let start = Instant::now();
 
-        for (k, v) in &self.headers {
-            request = request.header(k, v)
+        let res = raw_client
+            .send(&self.defaults.scheme, &self.defaults.host, self.defaults.port, &request)
+            .await?;
+
+        let duration = start.elapsed();
+
+        let res = HttpResponse {
+            code: res.code,
+            headers: res.headers,
+            body: res.body,
+            http_version: Some(res.http_version),
+            truncated: false,
+        };
+
+        if let Some(recorder) = &self.defaults.recorder {
+            recorder.record(&self.http_request(), &res);
         }
 
-        let request = request.body(self.body.to_owned()).unwrap();
+        Ok(self.into_response(res, duration, Vec::new()))
+    }
+
+    /// sends the request via the client, or via the client from the defaults in case it's set
+    async fn request(mut self, client: &dyn HttpClient) -> Result<Response<'a>, Box<dyn Error>> {
+        self.prepare();
+        self.mutate().await?;
 
-        tokio::time::sleep(self.defaults.delay).await;
+        let request = self.http_request();
 
-        let reqwest_req = reqwest::Request::try_from(request).unwrap();
+        self.defaults.wait().await;
+
+        let defaults = self.defaults;
+        let client = match &defaults.http_client {
+            Some(http_client) => http_client.as_ref(),
+            None => client,
+        };
 
         let start = Instant::now();
 
-        let res = client.execute(reqwest_req).await?;
+        let mut res = client.send(request.clone()).await.map_err(|err| err as Box<dyn Error>)?;
+
+        if let Some(recorder) = &self.defaults.recorder {
+            recorder.record(&request, &res);
+        }
+
+        // the final response is compared, while the chain is kept for the findings
+        let (mut current, mut redirects) = (request, Vec::new());
+        while redirects.len() < defaults.redirects.max_hops {
+            let next = match defaults.redirects.next(&current, &res) {
+                Some(val) => val,
+                None => break,
+            };
+
+            redirects.push(format!("{} {}", res.code, next.url));
+
+            defaults.count_request();
+
+            res = client.send(next.clone()).await.map_err(|err| err as Box<dyn Error>)?;
+
+            if let Some(recorder) = &self.defaults.recorder {
+                recorder.record(&next, &res);
+            }
+
+            current = next;
+        }
 
         let duration = start.elapsed();
 
-        let mut headers: Vec<(String, String)> = Vec::new();
+        Ok(self.into_response(res, duration, redirects))
+    }
 
-        for (k, v) in res.headers() {
-            let k = k.to_string();
+    /// passes the prepared request through --mutator-cmd. Done after signing, so the program gets the final request
+    async fn mutate(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(mutator) = &self.defaults.mutator {
+            let url = self.url();
+            mutator
+                .mutate(&self.defaults.method, &url, &mut self.path, &mut self.headers, &mut self.body)
+                .await?;
+        }
 
-            // sometimes conversion may fail
-            let v = match v.to_str() {
-                Ok(val) => val,
-                Err(_) => {
-                    log::debug!("Unable to parse {} header. The value is {:?}", k, v);
-                    ""
-                }
-            }.to_string();
+        Ok(())
+    }
 
-            headers.push((k, v));
+    /// the prepared request as it's sent to the target
+    fn http_request(&self) -> HttpRequest {
+        HttpRequest {
+            method: self.defaults.method.clone(),
+            url: self.url(),
+            headers: self.headers.clone(),
+            body: self.body.to_owned(),
+            max_body_size: self.defaults.max_body_size,
         }
+    }
 
-        let code = res.status().as_u16();
-        let http_version = Some(res.version());
+    /// creates the response and prepares its text for comparing
+    fn into_response(self, res: HttpResponse, duration: Duration, redirects: Vec<String>) -> Response<'a> {
+        let HttpResponse {
+            mut code,
+            mut headers,
+            body: body_bytes,
+            http_version,
+            truncated,
+        } = res;
 
-        let body_bytes = res.bytes().await?.to_vec();
+        if let Some(cookie_jar) = &self.defaults.cookie_jar {
+            cookie_jar.update(&headers);
+        }
 
-        let text = if is_binary_content(headers.get_value_case_insensitive("content-type")) && !self.defaults.check_binary {
-            String::new()
+        // the prefix of a truncated body can't be decoded
+        let (body_bytes, truncated) = if truncated {
+            (body_bytes, truncated)
+        } else {
+            decompress(&headers, body_bytes, self.defaults.max_body_size)
+        };
+
+        // binary and truncated bodies don't go through the text pipeline, so their parameters are reflected only within headers
+        let is_binary = truncated
+            || !self.defaults.check_binary
+                && (is_binary_content(headers.get_value_case_insensitive("content-type")) || is_binary_body(&body_bytes));
+        let mut text = if truncated {
+            body_summary("truncated", &body_bytes)
+        } else if is_binary {
+            body_summary("binary", &body_bytes)
         } else {
             String::from_utf8_lossy(&body_bytes).to_string()
         };
 
+        let stats = BodyStats {
+            size: body_bytes.len(),
+            ..if is_binary { BodyStats::default() } else { BodyStats::new(&text) }
+        };
+
+        // text nodes with dynamic content are skipped entirely, so the html is rewritten before the regexes remove them
+        if self.defaults.html_diff && is_html(headers.get_value_case_insensitive("content-type"), &text) {
+            text = html_lines(&text, &self.defaults.normalization.regexes);
+        }
+
+        // remove dynamic content like csrf tokens or timestamps
+        let normalization = &self.defaults.normalization;
+        if !normalization.is_empty() {
+            if !is_binary {
+                text = normalization.body(text);
+            }
+
+            for (_, v) in headers.iter_mut() {
+                *v = normalization.header(std::mem::take(v));
+            }
+        }
+
+        if let Some(script) = &self.defaults.script {
+            script.on_response(&mut code, &mut headers, &mut text);
+        }
+
+        // parsed from the processed text, so --normalize and the script apply to json responses as well
+        let json = if self.defaults.json_diff && !is_binary {
+            serde_json::from_str(&text).ok()
+        } else {
+            None
+        };
+
         let mut response = Response {
             code,
             headers,
@@ -367,13 +902,19 @@ impl<'a> Request<'a> {
             text,
             request: Some(self),
             reflected_parameters: HashMap::new(),
+            header_reflected_parameters: HashMap::new(),
             http_version,
+            json,
+            stability_mask: None,
+            stats,
+            redirects,
+            truncated,
         };
 
         response.beautify_body();
         response.add_headers();
 
-        Ok(response)
+        response
     }
 
     /// the function is used when there was a error during the request
@@ -385,8 +926,14 @@ impl<'a> Request<'a> {
             headers: Vec::new(),
             text: String::new(),
             reflected_parameters: HashMap::new(),
+            header_reflected_parameters: HashMap::new(),
             request: Some(self),
             http_version: None,
+            json: None,
+            stability_mask: None,
+            stats: BodyStats::default(),
+            redirects: Vec::new(),
+            truncated: false,
         }
     }
 
@@ -417,6 +964,39 @@ impl<'a> Request<'a> {
 
         str_req
     }
+
+    /// the sent request as a curl command that can be pasted into the shell
+    pub fn curl(&self) -> String {
+        let host = self.defaults.host_header();
+
+        let mut command = "curl -g -i".to_string();
+
+        if self.defaults.method != "GET" || !self.body.is_empty() {
+            command += &format!(" -X {}", shell_quote(&self.defaults.method));
+        }
+
+        command += &format!(" {}", shell_quote(&format!("{}://{}{}", self.defaults.scheme, host, self.path)));
+
+        for (k, v) in self.headers.iter().sorted() {
+            // curl sets them on its own
+            if (k.eq_ignore_ascii_case("host") && v == &host) || k.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+
+            command += &format!(" -H {}", shell_quote(&format!("{}: {}", k, v)));
+        }
+
+        if !self.body.is_empty() {
+            command += &format!(" --data-binary {}", shell_quote(&self.body));
+        }
+
+        command
+    }
+}
+
+/// wraps the string within single quotes for posix shells
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
 }
 
 impl<'a> RequestDefaults {
@@ -425,28 +1005,156 @@ impl<'a> RequestDefaults {
         method: S,
         url: S,
     ) -> Result<Self, Box<dyn Error>> {
-        Self::new(
-            method.into().as_str(), //method needs to be set explicitly via .set_method()
-            url.into().as_str(),    //as well as url
-            config.custom_headers.clone(),
+        let (method, url) = (method.into(), url.into());
+
+        // requests imported from a HAR file have their own headers and body
+        let (custom_headers, body, data_type) = match config.har_entry(&method, &url) {
+            Some(entry) => (
+                entry.headers.clone(),
+                entry.body.clone(),
+                config.data_type.clone().or_else(|| entry.data_type.clone()),
+            ),
+            None => (
+                config.custom_headers.clone(),
+                config.body.clone(),
+                config.data_type.clone(),
+            ),
+        };
+
+        // graphql variables are inserted into the /variables object by default
+        let json_pointer = match (&config.json_pointer, config.graphql) {
+            (Some(pointer), _) => Some(pointer.as_str()),
+            (None, Some(GraphQLMode::Variables)) => Some("/variables"),
+            (None, _) => None,
+        };
+
+        // move the injection point inside the nested json object
+        let (body, data_type) = match json_pointer {
+            Some(pointer) => (insert_json_injection_point(&body, pointer)?, Some(DataType::Json)),
+            None => (body, data_type),
+        };
+
+        // graphql arguments and fields are inserted into the query template as they are
+        let data_type = match config.graphql {
+            Some(GraphQLMode::Arguments) | Some(GraphQLMode::Fields) => {
+                if !body.contains("%s") {
+                    Err("--graphql arguments and fields modes require the %s injection point within the query")?
+                }
+                Some(DataType::Urlencoded)
+            }
+            _ => data_type,
+        };
+
+        // move the injection point inside the xml node or its attributes
+        let (body, data_type) = if config.xml_node.is_some() || config.xml_attributes {
+            (
+                insert_xml_injection_point(&body, config.xml_node.as_deref(), config.xml_attributes)?,
+                Some(DataType::Xml),
+            )
+        } else {
+            (body, data_type)
+        };
+
+        // xml attributes and graphql need different templates
+        let (default_template, default_joiner) = match config.graphql {
+            // the query is usually sent as a json string so the quotes need to be escaped
+            Some(GraphQLMode::Arguments) if body.starts_with('{') => (Some("%k: \\\"%v\\\""), Some(", ")),
+            Some(GraphQLMode::Arguments) => (Some("%k: \"%v\""), Some(", ")),
+            Some(GraphQLMode::Fields) => (Some("%k"), Some(" ")),
+            _ if config.xml_attributes => (Some("%k=\"%v\""), Some(" ")),
+            _ => (None, None),
+        };
+
+        let (template, joiner) = (
+            config.template.clone().or_else(|| default_template.map(|x| x.to_string())),
+            config.joiner.clone().or_else(|| default_joiner.map(|x| x.to_string())),
+        );
+
+        let mut request_defaults = Self::new(
+            method.as_str(), //method needs to be set explicitly via .set_method()
+            url.as_str(),    //as well as url
+            custom_headers,
             config.delay,
             create_client(config, false)?,
-            config.template.clone(),
-            config.joiner.clone(),
+            template,
+            joiner,
             config.encode,
-            config.data_type.clone(),
+            data_type,
             config.invert,
             config.headers_discovery,
-            &config.body,
+            &body,
             config.disable_custom_parameters,
             config.check_binary
-        )
+        )?;
+
+        request_defaults.rate_limiter = config.rate_limiter.clone();
+        request_defaults.host_rate_limiter = config.host_rate_limiters.get(&request_defaults.host).cloned();
+
+        request_defaults.normalization = config.normalization.clone();
+
+        request_defaults.matchers = config.matchers.clone();
+        request_defaults.html_diff = config.html_diff;
+        request_defaults.json_diff = config.json_diff;
+        request_defaults.redirects = config.redirects.clone();
+        request_defaults.max_body_size = config.max_response_size;
+        request_defaults.response_cache = config.response_cache.clone();
+        request_defaults.json_diff_ignore = config.json_diff_ignore.clone();
+        request_defaults.json_diff_unordered = config.json_diff_unordered;
+        request_defaults.similarity = config.similarity;
+        request_defaults.retry_policy = config.retry_policy.clone();
+
+        if config.cookie_jar {
+            request_defaults.cookie_jar = Some(Arc::new(CookieJar::default()));
+        }
+
+        if let Some(session_refresh) = &config.session_refresh {
+            request_defaults.session = Some(Arc::new(Session::new(
+                session_refresh.clone(),
+                request_defaults.client.clone(),
+            )));
+        }
+
+        request_defaults.sigv4 = config.sigv4.clone();
+        request_defaults.values = config.values.clone();
+        request_defaults.budget = Arc::clone(&config.budget);
+        request_defaults.gate = Arc::clone(&config.gate);
+        request_defaults.metrics = Arc::clone(&config.metrics);
+        request_defaults.recorder = config.record.clone();
+
+        #[cfg(feature = "http3")]
+        if config.http3 {
+            request_defaults.http_client = Some(Arc::new(Http3Client::new(config, request_defaults.client.clone())?));
+        }
+
+        if let Some(player) = &config.replay {
+            request_defaults.http_client = Some(Arc::clone(player) as Arc<dyn HttpClient>);
+        }
+        request_defaults.script = config.script.clone();
+        request_defaults.mutator = config.mutator.clone();
+
+        if let Some(pause) = config.block_pause {
+            request_defaults.block = Some(Arc::new(BlockDetector::new(pause, config.block_rotate)));
+        }
+
+        if let Some(csrf) = &config.csrf {
+            request_defaults.csrf = Some(Arc::new(Csrf::new(csrf.clone())));
+        }
+
+        if config.raw || config.unix_socket.is_some() {
+            request_defaults.raw_client = Some(Arc::new(RawClient::new(config)));
+        }
+
+        if !config.proxies.is_empty() {
+            request_defaults.proxy_pool = Some(Arc::new(ProxyPool::new(config)?));
+        }
+
+        Ok(request_defaults)
     }
 
     pub fn new<S: Into<String> + From<String> + std::fmt::Debug>(
         method: &str,
         url: &str,
-        custom_headers: Vec<(String, String)>,
+        mut custom_headers: Vec<(String, String)>,
         delay: Duration,
         client: Client,
         template: Option<S>,
@@ -460,8 +1168,19 @@ impl<'a> RequestDefaults {
         check_binary: bool,
     ) -> Result<Self, Box<dyn Error>> {
 
+        // %s as a whole path segment: https://example.com/api/%s/status
+        let is_path_segment = url
+            .split(&['?', '#'][..])
+            .next()
+            .unwrap_or_default()
+            .split('/')
+            .skip(3)
+            .any(|x| x == "%s");
+
         let mut injection_place = if headers_discovery {
             InjectionPlace::Headers
+        } else if is_path_segment {
+            InjectionPlace::PathSegment
         } else if (method == "POST" || method == "PUT" || method == "PATCH" || method == "DELETE") && !invert
         || (method != "POST" && method != "PUT" && method != "PATCH" && method != "DELETE" && invert) {
             InjectionPlace::Body
@@ -472,12 +1191,21 @@ impl<'a> RequestDefaults {
         if headers_discovery {
             data_type = Some(DataType::Headers);
             
-            if custom_headers.iter().any(|x| x.1.contains("%s")) {
+            if custom_headers
+                .iter()
+                .any(|x| x.0.eq_ignore_ascii_case("cookie") && x.1.contains("%s"))
+            {
+                injection_place = InjectionPlace::Cookies;
+            } else if custom_headers.iter().any(|x| x.1.contains("%s")) {
                 injection_place = InjectionPlace::HeaderValue;
             }
         }
 
-        let data_type = if data_type != Some(DataType::ProbablyJson) {
+        let data_type = if (data_type == Some(DataType::Multipart) || data_type == Some(DataType::Xml))
+            && injection_place != InjectionPlace::Body
+        {
+            Some(DataType::Urlencoded)
+        } else if data_type != Some(DataType::ProbablyJson) {
             data_type
 
         // explained in DataType enum comments
@@ -485,7 +1213,7 @@ impl<'a> RequestDefaults {
         // we don't need probablyurlencoded because urlencoded is fine for get requests
         } else if injection_place == InjectionPlace::Body && data_type == Some(DataType::ProbablyJson) {
             Some(DataType::Json)
-        } else if injection_place == InjectionPlace::Path {
+        } else if injection_place == InjectionPlace::Path || injection_place == InjectionPlace::PathSegment {
             Some(DataType::Urlencoded)
         } else {
             unreachable!()
@@ -493,6 +1221,19 @@ impl<'a> RequestDefaults {
 
         let (guessed_template, guessed_joiner, is_json, data_type) =
             RequestDefaults::guess_data_format(body, &injection_place, data_type);
+        let is_xml = data_type == Some(DataType::Xml);
+
+        // every parameter is a separate form part delimited by the boundary
+        let (guessed_template, body) = if data_type == Some(DataType::Multipart) {
+            let boundary = RequestDefaults::multipart_boundary_from_headers(&mut custom_headers);
+            (
+                guessed_template.replace("%b", &boundary),
+                RequestDefaults::fix_multipart_body(body, &boundary),
+            )
+        } else {
+            (guessed_template.to_string(), body.to_string())
+        };
+        let body = body.as_str();
 
         let (template, joiner) = (
             template
@@ -531,17 +1272,54 @@ impl<'a> RequestDefaults {
             client,
             template,
             joiner,
-            encode,
+            // special characters within names would break the path
+            encode: encode || injection_place == InjectionPlace::PathSegment,
             is_json,
+            is_xml,
             body,
-            disable_custom_parameters,
+            // values can't be sent within path segments
+            disable_custom_parameters: disable_custom_parameters || injection_place == InjectionPlace::PathSegment,
             injection_place,
 
             amount_of_reflections: 0,
+            header_reflections: Vec::new(),
 
             parameters: Vec::new(),
 
-            check_binary
+            check_binary,
+
+            rate_limiter: None,
+            host_rate_limiter: None,
+            pause: Arc::new(Pause::default()),
+
+            proxy_pool: None,
+
+            normalization: Normalization::default(),
+
+            matchers: Matchers::default(),
+            html_diff: false,
+            json_diff: false,
+            redirects: Redirects::default(),
+            max_body_size: None,
+            response_cache: None,
+            json_diff_ignore: Vec::new(),
+            json_diff_unordered: false,
+            similarity: None,
+            retry_policy: RetryPolicy::default(),
+            raw_client: None,
+            cookie_jar: None,
+            session: None,
+            csrf: None,
+            sigv4: None,
+            script: None,
+            mutator: None,
+            block: None,
+            values: ValueStrategies::default(),
+            budget: Arc::new(Budget::default()),
+            gate: Arc::new(Gate::default()),
+            metrics: Arc::new(Metrics::default()),
+            http_client: None,
+            recorder: None,
         })
     }
 
@@ -551,11 +1329,18 @@ impl<'a> RequestDefaults {
         injection_place: &InjectionPlace,
         data_type: Option<DataType>,
     ) -> (&'a str, &'a str, bool, Option<DataType>) {
+        // path segments can't contain values, so only names are sent
+        if *injection_place == InjectionPlace::PathSegment {
+            return ("%k", "/", false, Some(DataType::Urlencoded));
+        }
+
         if data_type.is_some() && data_type != Some(DataType::Headers) {
             match data_type {
                 // %v isn't within quotes because not every json value needs to be in quotes
                 Some(DataType::Json) => ("\"%k\":%v", ",", true, Some(DataType::Json)),
                 Some(DataType::Urlencoded) => ("%k=%v", "&", false, Some(DataType::Urlencoded)),
+                Some(DataType::Multipart) => (MULTIPART_TEMPLATE, "", false, Some(DataType::Multipart)),
+                Some(DataType::Xml) => ("<%k>%v</%k>", "", false, Some(DataType::Xml)),
                 _ => unreachable!(),
             }
         } else {
@@ -563,17 +1348,54 @@ impl<'a> RequestDefaults {
                 InjectionPlace::Body => {
                     if body.starts_with('{') {
                         ("\"%k\":%v", ",", true, Some(DataType::Json))
+                    } else if body.starts_with('<') {
+                        ("<%k>%v</%k>", "", false, Some(DataType::Xml))
                     } else {
                         ("%k=%v", "&", false, Some(DataType::Urlencoded))
                     }
                 }
                 InjectionPlace::HeaderValue => ("%k=%v", ";", false, None),
+                InjectionPlace::Cookies => ("%k=%v", "; ", false, None),
                 InjectionPlace::Path => ("%k=%v", "&", false, Some(DataType::Urlencoded)),
                 InjectionPlace::Headers => (HEADERS_TEMPLATE, HEADERS_JOINER, false, None),
+                InjectionPlace::PathSegment => unreachable!(),
             }
         }
     }
 
+    /// returns the boundary from the content-type header.
+    /// sets the content-type header with a random boundary in case there's no multipart content-type
+    fn multipart_boundary_from_headers(custom_headers: &mut Vec<(String, String)>) -> String {
+        let content_type = custom_headers.get_value_case_insensitive("content-type");
+
+        if let Some(boundary) = content_type.as_deref().and_then(multipart_boundary) {
+            return boundary;
+        }
+
+        let boundary = format!("----x8FormBoundary{}", random_line(16));
+        let value = format!("multipart/form-data; boundary={}", boundary);
+
+        match custom_headers.get_index_case_insensitive("content-type") {
+            Some(index) => custom_headers[index].1 = value,
+            None => custom_headers.push(("Content-Type".to_string(), value)),
+        }
+
+        boundary
+    }
+
+    /// adds the injection point before the closing delimiter so the original parts (including files) are preserved
+    fn fix_multipart_body(body: &str, boundary: &str) -> String {
+        let closing_delimiter = format!("--{}--", boundary);
+
+        if body.contains("%s") {
+            body.to_string()
+        } else if let Some(index) = body.rfind(&closing_delimiter) {
+            format!("{}%s{}", &body[..index], &body[index..])
+        } else {
+            format!("{}%s{}\r\n", body, closing_delimiter)
+        }
+    }
+
     /// adds injection points where necessary
     fn fix_path_and_body(
         path: &str,
@@ -590,11 +1412,16 @@ impl<'a> RequestDefaults {
                     match data_type {
                         DataType::Urlencoded => (path.to_string(), "%s".to_string()),
                         DataType::Json => (path.to_string(), "{%s}".to_string()),
+                        DataType::Xml => (path.to_string(), "<root>%s</root>".to_string()),
                         _ => unreachable!(),
                     }
                 } else {
                     match data_type {
                         DataType::Urlencoded => (path.to_string(), format!("{}{}%s", body, joiner)),
+                        DataType::Xml => (
+                            path.to_string(),
+                            insert_xml_injection_point(body, None, false).unwrap_or_else(|_| format!("{}%s", body)),
+                        ),
                         DataType::Json => {
                             let mut body = body.to_owned();
                             body.pop(); // remove the last '}'
@@ -625,6 +1452,33 @@ impl<'a> RequestDefaults {
         }
     }
 
+    /// waits for the user's pause, the delay, the pause and the rate limiter before sending a request
+    /// and counts the request
+    pub async fn wait(&self) {
+        self.gate.wait().await;
+
+        tokio::time::sleep(self.delay).await;
+
+        self.count_request();
+
+        self.pause.wait().await;
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.wait().await;
+        }
+
+        if let Some(host_rate_limiter) = &self.host_rate_limiter {
+            host_rate_limiter.wait().await;
+        }
+    }
+
+    /// counts the request within --max-requests and the metrics.
+    /// besides the requests with parameters, used for redirects, csrf tokens and session refreshes
+    pub fn count_request(&self) {
+        self.budget.add_request();
+        self.metrics.add_request();
+    }
+
     /// recreates url
     pub fn url(&self) -> String {
         format!("{}://{}:{}{}", self.scheme, self.host, self.port, self.path)
@@ -640,4 +1494,13 @@ impl<'a> RequestDefaults {
 
         format!("{}://{}{}{}", self.scheme, self.host, port, self.path)
     }
+
+    /// host with the port in case it's not the default one for the scheme
+    pub fn host_header(&self) -> String {
+        if (self.scheme == "https" && self.port == 443) || (self.scheme == "http" && self.port == 80) {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
 }
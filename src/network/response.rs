@@ -1,4 +1,4 @@
-use std::{collections::HashMap, error::Error, iter::FromIterator, io::{self, Write}};
+use std::{collections::HashMap, error::Error, iter::FromIterator, io::{self, Write}, time::Duration};
 
 use colored::Colorize;
 use indicatif::ProgressBar;
@@ -6,11 +6,11 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::{config::structs::Config, diff::diff, runner::utils::ReasonKind, utils::{color_id, is_id_important}};
+use crate::{config::structs::Config, diff::{cookies_diff, diff, headers_diff, json_diff, similarity, StabilityMask}, error::{categorize, X8Error}, runner::utils::ReasonKind, utils::{color_id, is_id_important}};
 
 use super::{
     request::Request,
-    utils::{save_request, Headers},
+    utils::{is_html, save_request, Headers},
 };
 
 #[derive(Debug, Clone, Default)]
@@ -30,12 +30,48 @@ pub struct Response<'a> {
     /// hashmap<parameter, amount of reflections> that fills later with possible reflected parameters
     pub reflected_parameters: HashMap<String, usize>,
 
+    /// hashmap<parameter, lowercased header names> of parameters reflected within the response headers
+    pub header_reflected_parameters: HashMap<String, Vec<String>>,
+
     /// the sent request struct itself
     /// None only in initial_request due to lifetime issues
     pub request: Option<Request<'a>>,
 
     /// None only when the request failed
     pub http_version: Option<http::Version>,
+
+    /// parsed body in case --json-diff is used and the body is a valid json
+    pub json: Option<serde_json::Value>,
+
+    /// parts of the page that vary naturally. Set only for the initial response
+    pub stability_mask: Option<StabilityMask>,
+
+    /// the size, words and lines of the body as it was received
+    pub stats: BodyStats,
+
+    /// the code and the location of every followed redirect
+    pub redirects: Vec<String>,
+
+    /// the body was cut at --max-response-size
+    pub truncated: bool,
+}
+
+/// ffuf-like statistics of the body before any normalization
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BodyStats {
+    pub size: usize,
+    pub words: usize,
+    pub lines: usize,
+}
+
+impl BodyStats {
+    pub fn new(body: &str) -> Self {
+        Self {
+            size: body.len(),
+            words: body.split_whitespace().count(),
+            lines: body.lines().count(),
+        }
+    }
 }
 
 //Owo
@@ -51,13 +87,49 @@ pub enum Status {
     Other,
 }
 
+/// the delay in seconds in case the rate limited response doesn't contain a valid Retry-After header
+const DEFAULT_RETRY_AFTER: u64 = 10;
+
+/// the upper bound for the delay from Retry-After in seconds
+const MAX_RETRY_AFTER: u64 = 300;
+
 impl<'a> Response<'a> {
+    /// returns how long to wait in case the response tells that requests are rate limited
+    /// (429, or 503 with the Retry-After header)
+    pub fn rate_limit_delay(&self) -> Option<Duration> {
+        let retry_after = self.headers.get_value_case_insensitive("retry-after");
+
+        if self.code != 429 && !(self.code == 503 && retry_after.is_some()) {
+            return None;
+        }
+
+        let seconds = retry_after
+            .and_then(|x| x.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_RETRY_AFTER);
+
+        Some(Duration::from_secs(seconds.min(MAX_RETRY_AFTER)))
+    }
+
     /// count how many times we can see the string in the response
     pub fn count(&self, string: &str) -> usize {
         let re = Regex::new(&format!("(?i){}", string)).unwrap();
         re.find_iter(&self.text).count()
     }
 
+    /// returns lowercased names of headers that contain the string either in their names or values
+    pub fn reflected_headers(&self, string: &str) -> Vec<String> {
+        let string = string.to_lowercase();
+
+        self.headers
+            .iter()
+            .filter(|(k, v)| {
+                k.to_lowercase().contains(&string) || v.to_lowercase().contains(&string)
+            })
+            .map(|(k, _)| k.to_lowercase())
+            .unique()
+            .collect()
+    }
+
     /// calls check_diffs & returns code and found diffs
     pub fn compare(
         &self,
@@ -71,8 +143,38 @@ impl<'a> Response<'a> {
             is_code_diff = true
         }
 
+        // headers are compared by their names, so parameters that change only headers can be distinguished
+        let mut found_diffs = headers_diff(&initial_response.headers, &self.headers);
+
+        // cookies are compared separately because their values are usually random,
+        // so the whole Set-Cookie header is often different every time
+        found_diffs.append(&mut cookies_diff(&initial_response.headers, &self.headers));
+
+        // bodies that are similar enough are considered the same
+        let is_similar = match self.request.as_ref().and_then(|x| x.defaults.similarity) {
+            Some(threshold) => similarity(self.body(), initial_response.body()) >= threshold,
+            None => false,
+        };
+
+        // json bodies are compared structurally
+        match (&self.json, &initial_response.json, &self.request) {
+            _ if is_similar => (),
+            (Some(json), Some(initial_json), Some(request)) => found_diffs.append(&mut json_diff(
+                initial_json,
+                json,
+                &request.defaults.json_diff_ignore,
+                request.defaults.json_diff_unordered,
+            )),
+            _ => match &initial_response.stability_mask {
+                Some(mask) if !mask.is_empty() => {
+                    found_diffs.append(&mut mask.diff(initial_response.body(), self.body())?)
+                }
+                _ => found_diffs.append(&mut diff(self.body(), initial_response.body())?),
+            },
+        };
+
         // just push every found diff to the vector of diffs
-        for diff in diff(&self.print(), &initial_response.print())? {
+        for diff in found_diffs {
             if !diffs.contains(&diff) && !old_diffs.contains(&diff) {
                 diffs.push(diff);
             // sometimes returns a few same diffs. They should be considered as well
@@ -103,6 +205,13 @@ impl<'a> Response<'a> {
                 Regex::new(r#"(?P<first>"[\w\.-]*"):(?P<second>(false|null|true)),"#).unwrap();
         }
 
+        // with --html-diff html bodies are already split into nodes
+        if self.request.as_ref().is_some_and(|x| x.defaults.html_diff)
+            && is_html(self.headers.get_value_case_insensitive("content-type"), &self.text)
+        {
+            return;
+        }
+
         self.text = if (self.headers.contains_key("content-type")
             && self
                 .headers
@@ -166,6 +275,37 @@ impl<'a> Response<'a> {
             {
                 self.reflected_parameters.insert(k.to_string(), new_count);
             }
+
+            // headers where either the value or the name of the parameter appears
+            // (excluding the ones where random parameters are reflected as well)
+            let initial_headers = initial_response.reflected_headers(k);
+            let reflected_headers: Vec<String> = self
+                .reflected_headers(v)
+                .into_iter()
+                .chain(
+                    self.reflected_headers(k)
+                        .into_iter()
+                        .filter(|x| !initial_headers.contains(x)),
+                )
+                .filter(|x| {
+                    !self
+                        .request
+                        .as_ref()
+                        .unwrap()
+                        .defaults
+                        .header_reflections
+                        .contains(x)
+                })
+                .unique()
+                .collect();
+
+            if !reflected_headers.is_empty() {
+                if !self.reflected_parameters.contains_key(k) {
+                    self.reflected_parameters.insert(k.to_string(), new_count);
+                }
+                self.header_reflected_parameters
+                    .insert(k.to_string(), reflected_headers);
+            }
         }
     }
 
@@ -228,7 +368,7 @@ impl<'a> Response<'a> {
             text += &format!("{}: {}\n", k, v);
         }
 
-        self.text = text + "\n" + &self.text;
+        self.text = text + "\n" + self.text.as_str();
     }
 
     /// write about found parameter to stdout and save when needed
@@ -257,6 +397,14 @@ impl<'a> Response<'a> {
                 initial_response.code(),
                 self.code(),
             ),
+            ReasonKind::ServerError => format!(
+                "{}{}: {} {} -> {}",
+                &id_if_important,
+                &parameter,
+                "server error".bright_red(),
+                initial_response.code(),
+                self.code(),
+            ),
             ReasonKind::Text => format!(
                 "{}{}: page {} -> {} ({})",
                 &id_if_important,
@@ -265,23 +413,75 @@ impl<'a> Response<'a> {
                 self.text.len().to_string().bright_yellow(),
                 diff.unwrap()
             ),
-            ReasonKind::Reflected => format!(
-                "{}{}: {}",
+            ReasonKind::Header => format!(
+                "{}{}: headers ({})",
                 &id_if_important,
-                "reflects".bright_blue(),
-                parameter
+                &parameter,
+                diff.unwrap().bright_green()
             ),
+            ReasonKind::Cookie => format!(
+                "{}{}: cookies ({})",
+                &id_if_important,
+                &parameter,
+                diff.unwrap().magenta()
+            ),
+            ReasonKind::Time => format!(
+                "{}{}: time {}ms -> {}ms",
+                &id_if_important,
+                &parameter,
+                initial_response.time,
+                self.time.to_string().bright_red()
+            ),
+            ReasonKind::Reflected => match diff {
+                Some(headers) => format!(
+                    "{}{}: {} ({})",
+                    &id_if_important,
+                    "reflects".bright_blue(),
+                    parameter,
+                    headers.bright_blue()
+                ),
+                None => format!(
+                    "{}{}: {}",
+                    &id_if_important,
+                    "reflects".bright_blue(),
+                    parameter
+                ),
+            },
             ReasonKind::NotReflected => format!(
                 "{}{}: {}",
                 &id_if_important,
                 "changes reflections".bright_cyan(),
                 parameter
             ),
+            ReasonKind::GraphQL => format!(
+                "{}{}: {}",
+                &id_if_important,
+                "graphql accepts".bright_magenta(),
+                parameter
+            ),
+            ReasonKind::Script => format!(
+                "{}{}: {}",
+                &id_if_important,
+                "script".bright_white(),
+                parameter
+            ),
+            ReasonKind::OutOfBand => format!(
+                "{}{}: {} ({})",
+                &id_if_important,
+                "out-of-band".green(),
+                parameter,
+                diff.unwrap_or_default()
+            ),
         };
 
-        if config.verbose > 0 {
+        // with jsonl output the finding is already printed as a json line
+        if config.verbose > 0 && config.output_format != "jsonl" {
             if !config.save_responses.is_empty() {
-                message += &format!(" [saved to {}]", save_request(config, self, parameter)?);
+                message += &format!(
+                    " [saved to {}]",
+                    save_request(config, self, initial_response, &reason_kind, parameter, diff)
+                        .map_err(|err| categorize(err, X8Error::Output))?
+                );
             }
 
             if config.disable_progress_bar {
@@ -290,9 +490,17 @@ impl<'a> Response<'a> {
                 progress_bar.println(message);
             }
         } else if !config.save_responses.is_empty() {
-            save_request(config, self, parameter)?;
+            save_request(config, self, initial_response, &reason_kind, parameter, diff)
+                .map_err(|err| categorize(err, X8Error::Output))?;
         }
 
+        if let Some(burp_export) = &config.burp_export {
+            burp_export.add(self, &format!("x8: {} ({:?})", parameter, reason_kind));
+        }
+
+        config.budget.add_finding();
+        config.metrics.add_finding(&format!("{:?}", reason_kind));
+
         Ok(())
     }
 
@@ -312,6 +520,11 @@ impl<'a> Response<'a> {
         }
     }
 
+    /// the page returns 5xx only with the parameters
+    pub fn is_server_error(&self, initial_response: &Response) -> bool {
+        self.code >= 500 && initial_response.code < 500
+    }
+
     /// returns self.code but with colors
     pub fn code(&self) -> String {
         match self.kind() {
@@ -325,33 +538,7 @@ impl<'a> Response<'a> {
 
     /// get possible parameters from the page itself
     pub fn get_possible_parameters(&self) -> Vec<String> {
-        let mut found: Vec<String> = Vec::new();
-        let body = &self.text;
-
-        let re_special_chars = Regex::new(r#"[\W]"#).unwrap();
-
-        let re_name = Regex::new(r#"(?i)name=("|')?"#).unwrap();
-        let re_inputs = Regex::new(r#"(?i)name=("|')?[\w-]+"#).unwrap();
-        for cap in re_inputs.captures_iter(body) {
-            found.push(re_name.replace_all(&cap[0], "").to_string());
-        }
-
-        let re_var = Regex::new(r#"(?i)(var|let|const)\s+?"#).unwrap();
-        let re_full_vars = Regex::new(r#"(?i)(var|let|const)\s+?[\w-]+"#).unwrap();
-        for cap in re_full_vars.captures_iter(body) {
-            found.push(re_var.replace_all(&cap[0], "").to_string());
-        }
-
-        let re_words_in_quotes = Regex::new(r#"("|')[a-zA-Z0-9]{3,20}('|")"#).unwrap();
-        for cap in re_words_in_quotes.captures_iter(body) {
-            found.push(re_special_chars.replace_all(&cap[0], "").to_string());
-        }
-
-        let re_words_within_objects = Regex::new(r#"[\{,]\s*[[:alpha:]]\w{2,25}:"#).unwrap();
-        for cap in re_words_within_objects.captures_iter(body) {
-            found.push(re_special_chars.replace_all(&cap[0], "").to_string());
-        }
-
+        let mut found = scrape_words(&self.text);
         found.sort();
         found.dedup();
         found
@@ -374,8 +561,48 @@ impl<'a> Response<'a> {
         format!("{} {} \n{}", http_version, self.code, self.text)
     }
 
+    /// returns the body without headers added by add_headers()
+    pub fn body(&self) -> &str {
+        let headers_len: usize = self.headers.iter().map(|(k, v)| k.len() + v.len() + 3).sum();
+        self.text.get(headers_len + 1..).unwrap_or("")
+    }
+
     /// print the request and response
     pub fn print_all(&self) -> String {
-        self.request.as_ref().unwrap().print_sent() + "\n\n" + &self.print()
+        self.request.as_ref().unwrap().print_sent() + "\n\n" + self.print().as_str()
+    }
+}
+
+/// returns words from the body that look like parameter names: input names, variables, words in quotes and object keys.
+/// the same word is returned as many times as it occurs
+pub fn scrape_words(body: &str) -> Vec<String> {
+    lazy_static! {
+        static ref RE_SPECIAL_CHARS: Regex = Regex::new(r#"[\W]"#).unwrap();
+        static ref RE_NAME: Regex = Regex::new(r#"(?i)name=("|')?"#).unwrap();
+        static ref RE_INPUTS: Regex = Regex::new(r#"(?i)name=("|')?[\w-]+"#).unwrap();
+        static ref RE_VAR: Regex = Regex::new(r#"(?i)(var|let|const)\s+?"#).unwrap();
+        static ref RE_FULL_VARS: Regex = Regex::new(r#"(?i)(var|let|const)\s+?[\w-]+"#).unwrap();
+        static ref RE_WORDS_IN_QUOTES: Regex = Regex::new(r#"("|')[a-zA-Z0-9]{3,20}('|")"#).unwrap();
+        static ref RE_WORDS_WITHIN_OBJECTS: Regex = Regex::new(r#"[\{,]\s*[[:alpha:]]\w{2,25}:"#).unwrap();
+    }
+
+    let mut found: Vec<String> = Vec::new();
+
+    for cap in RE_INPUTS.captures_iter(body) {
+        found.push(RE_NAME.replace_all(&cap[0], "").to_string());
+    }
+
+    for cap in RE_FULL_VARS.captures_iter(body) {
+        found.push(RE_VAR.replace_all(&cap[0], "").to_string());
     }
+
+    for cap in RE_WORDS_IN_QUOTES.captures_iter(body) {
+        found.push(RE_SPECIAL_CHARS.replace_all(&cap[0], "").to_string());
+    }
+
+    for cap in RE_WORDS_WITHIN_OBJECTS.captures_iter(body) {
+        found.push(RE_SPECIAL_CHARS.replace_all(&cap[0], "").to_string());
+    }
+
+    found
 }
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use crate::utils::random_range;
+
+/// how to resend requests that failed because of transient network errors or gateway errors
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// the amount of additional attempts
+    pub retries: usize,
+
+    /// the delay before the first retry. It's doubled with every next attempt
+    pub backoff: Duration,
+
+    /// the upper bound for the random delay added to every backoff
+    pub jitter: Duration,
+
+    /// response codes that cause the request to be resent
+    pub codes: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_secs(1),
+            jitter: Duration::from_millis(0),
+            codes: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// returns how long to wait before the attempt (starting with 0)
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let backoff = self.backoff * 2u32.saturating_pow(attempt.min(16) as u32);
+
+        let jitter = if self.jitter.as_millis() > 0 {
+            Duration::from_millis(random_range(0, self.jitter.as_millis() as u64 + 1))
+        } else {
+            Duration::from_millis(0)
+        };
+
+        backoff + jitter
+    }
+
+    pub fn should_retry_code(&self, code: u16) -> bool {
+        self.codes.contains(&code)
+    }
+}
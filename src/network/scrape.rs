@@ -0,0 +1,91 @@
+use std::{collections::HashMap, error::Error};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
+
+use super::{request::RequestDefaults, response::scrape_words};
+
+/// the max amount of scripts to fetch per page
+const MAX_SCRIPTS: usize = 20;
+
+/// scripts larger than that are usually bundled libraries
+const MAX_SCRIPT_SIZE: usize = 5 * 1024 * 1024;
+
+/// returns identifiers from the page and the scripts of the same host linked from it, the most frequent first.
+/// besides the page's words, query parameters within urls and names passed to URLSearchParams-like getters are collected
+pub async fn target_words(request_defaults: &RequestDefaults, page: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    count_words(page, &mut counts);
+
+    for url in script_urls(&request_defaults.url(), page).into_iter().take(MAX_SCRIPTS) {
+        if request_defaults.budget.is_exhausted() {
+            break;
+        }
+
+        // scripts that can't be fetched are just skipped
+        if let Ok(text) = fetch(request_defaults, &url).await {
+            count_words(&text, &mut counts);
+        }
+    }
+
+    let mut words: Vec<(String, usize)> = counts.into_iter().collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    words.into_iter().map(|(word, _)| word).collect()
+}
+
+/// returns absolute urls of the scripts of the same host
+fn script_urls(page_url: &str, page: &str) -> Vec<String> {
+    lazy_static! {
+        static ref RE_SCRIPT_SRC: Regex = Regex::new(r#"(?i)<script[^>]+src\s*=\s*["']?([^"'\s>]+)"#).unwrap();
+    }
+
+    let page_url = match Url::parse(page_url) {
+        Ok(val) => val,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut urls = Vec::new();
+
+    for cap in RE_SCRIPT_SRC.captures_iter(page) {
+        if let Ok(url) = page_url.join(&cap[1]) {
+            if url.host_str() == page_url.host_str() && !urls.contains(&url.to_string()) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+
+    urls
+}
+
+/// fetches the script with the same delay, rate limits and --max-requests as the requests with parameters
+async fn fetch(request_defaults: &RequestDefaults, url: &str) -> Result<String, Box<dyn Error>> {
+    request_defaults.wait().await;
+
+    let text = request_defaults.client.get(url).send().await?.text().await?;
+
+    if text.len() > MAX_SCRIPT_SIZE {
+        Err("The script is too large")?
+    }
+
+    Ok(text)
+}
+
+fn count_words(text: &str, counts: &mut HashMap<String, usize>) {
+    lazy_static! {
+        static ref RE_QUERY_PARAMS: Regex = Regex::new(r#"[?&]([a-zA-Z_][\w\-\[\]]{0,30})="#).unwrap();
+        static ref RE_GETTERS: Regex =
+            Regex::new(r#"\.(?:get|getAll|has|set|append)\(\s*["']([\w\-]{1,30})["']"#).unwrap();
+    }
+
+    let words = scrape_words(text).into_iter().chain(
+        RE_QUERY_PARAMS
+            .captures_iter(text)
+            .chain(RE_GETTERS.captures_iter(text))
+            .map(|cap| cap[1].to_string()),
+    );
+
+    for word in words.filter(|x| !x.is_empty()) {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+}
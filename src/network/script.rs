@@ -0,0 +1,157 @@
+use std::{error::Error, fmt};
+
+use rhai::{Array, Dynamic, Engine, FuncArgs, Map, Scope, AST};
+
+use super::response::Response;
+
+/// the user supplied rhai script with optional hooks:
+/// on_request(request) -> request, on_response(response) -> response, is_interesting(response, initial_response) -> bool.
+/// requests and responses are passed as maps with method, path, headers ([[name, value], ..]) and body
+/// or code, headers, body and time fields
+pub struct Script {
+    engine: Engine,
+
+    ast: AST,
+
+    on_request: bool,
+    on_response: bool,
+    is_interesting: bool,
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Script")
+            .field("on_request", &self.on_request)
+            .field("on_response", &self.on_response)
+            .field("is_interesting", &self.is_interesting)
+            .finish()
+    }
+}
+
+impl Script {
+    pub fn new(source: &str) -> Result<Self, Box<dyn Error>> {
+        let engine = Engine::new();
+
+        let ast = engine
+            .compile(source)
+            .map_err(|err| format!("Unable to compile the script: {}", err))?;
+
+        let has_fn = |name: &str| ast.iter_functions().any(|x| x.name == name);
+
+        let (on_request, on_response, is_interesting) =
+            (has_fn("on_request"), has_fn("on_response"), has_fn("is_interesting"));
+
+        if !on_request && !on_response && !is_interesting {
+            Err("The script doesn't define any of on_request, on_response or is_interesting functions")?;
+        }
+
+        Ok(Self {
+            engine,
+            ast,
+            on_request,
+            on_response,
+            is_interesting,
+        })
+    }
+
+    /// calls the hook. Errors are logged because requests can't be stopped in the middle of preparation
+    fn call(&self, name: &str, args: impl FuncArgs) -> Option<Dynamic> {
+        match self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, args) {
+            Ok(val) => Some(val),
+            Err(err) => {
+                log::error!("The script's {} failed: {}", name, err);
+                None
+            }
+        }
+    }
+
+    /// lets the script change the prepared request before it's sent
+    pub fn on_request(&self, method: &str, path: &mut String, headers: &mut Vec<(String, String)>, body: &mut String) {
+        if !self.on_request {
+            return;
+        }
+
+        let mut request = Map::new();
+        request.insert("method".into(), method.to_string().into());
+        request.insert("path".into(), path.clone().into());
+        request.insert("headers".into(), headers_to_dynamic(headers));
+        request.insert("body".into(), body.clone().into());
+
+        if let Some(request) = self.call("on_request", (request,)).and_then(|x| x.try_cast::<Map>()) {
+            update_string(&request, "path", path);
+            update_string(&request, "body", body);
+            update_headers(&request, headers);
+        }
+    }
+
+    /// lets the script normalize the response before it's compared
+    pub fn on_response(&self, code: &mut u16, headers: &mut Vec<(String, String)>, text: &mut String) {
+        if !self.on_response {
+            return;
+        }
+
+        let mut response = Map::new();
+        response.insert("code".into(), (*code as i64).into());
+        response.insert("headers".into(), headers_to_dynamic(headers));
+        response.insert("body".into(), text.clone().into());
+
+        if let Some(response) = self.call("on_response", (response,)).and_then(|x| x.try_cast::<Map>()) {
+            if let Some(val) = response.get("code").and_then(|x| x.as_int().ok()) {
+                *code = val as u16;
+            }
+            update_string(&response, "body", text);
+            update_headers(&response, headers);
+        }
+    }
+
+    /// whether the script considers the response as a sign of the parameter
+    pub fn is_interesting(&self, response: &Response, initial_response: &Response) -> bool {
+        if !self.is_interesting {
+            return false;
+        }
+
+        self.call("is_interesting", (response_to_map(response), response_to_map(initial_response)))
+            .and_then(|x| x.as_bool().ok())
+            .unwrap_or(false)
+    }
+}
+
+fn response_to_map(response: &Response) -> Map {
+    let mut map = Map::new();
+    map.insert("code".into(), (response.code as i64).into());
+    map.insert("headers".into(), headers_to_dynamic(&response.headers));
+    map.insert("body".into(), response.text.clone().into());
+    map.insert("time".into(), (response.time as i64).into());
+    map
+}
+
+fn headers_to_dynamic(headers: &[(String, String)]) -> Dynamic {
+    headers
+        .iter()
+        .map(|(k, v)| Dynamic::from(vec![Dynamic::from(k.to_owned()), Dynamic::from(v.to_owned())]))
+        .collect::<Array>()
+        .into()
+}
+
+fn update_string(map: &Map, key: &str, value: &mut String) {
+    if let Some(val) = map.get(key).and_then(|x| x.clone().into_string().ok()) {
+        *value = val;
+    }
+}
+
+/// replaces headers with the [[name, value], ..] array from the map
+fn update_headers(map: &Map, headers: &mut Vec<(String, String)>) {
+    let array = match map.get("headers").and_then(|x| x.clone().try_cast::<Array>()) {
+        Some(val) => val,
+        None => return,
+    };
+
+    *headers = array
+        .into_iter()
+        .filter_map(|x| x.try_cast::<Array>())
+        .filter_map(|x| match x.as_slice() {
+            [k, v] => Some((k.clone().into_string().ok()?, v.clone().into_string().ok()?)),
+            _ => None,
+        })
+        .collect();
+}
@@ -0,0 +1,210 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use regex::Regex;
+use reqwest::Client;
+
+use super::{cookie_jar::CookieJar, request::RequestDefaults, response::Response, utils::Headers};
+
+/// the session isn't refreshed more often than that
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// codes that mean the session expired unless the target returned them from the start
+const EXPIRED_CODES: [u16; 2] = [401, 403];
+
+/// how to obtain the new session
+#[derive(Debug, Clone)]
+pub enum SessionRefresh {
+    /// the shell command that outputs headers (Name: value) to use from now on
+    Command(String),
+
+    /// the request from the request file. Cookies from its response are used from now on
+    Request {
+        method: String,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    },
+}
+
+/// headers and cookies obtained via the refresh step.
+/// they override the original ones within every request
+#[derive(Debug)]
+pub struct Session {
+    refresh: SessionRefresh,
+
+    client: Client,
+
+    headers: Mutex<Vec<(String, String)>>,
+
+    cookies: CookieJar,
+
+    /// locked during the refresh, so concurrent requests don't refresh the session multiple times
+    last_refresh: tokio::sync::Mutex<Option<Instant>>,
+
+    /// the code of the first response. Expired codes are ignored when they match it
+    baseline: Mutex<Option<u16>>,
+}
+
+impl Session {
+    pub fn new(refresh: SessionRefresh, client: Client) -> Self {
+        Self {
+            refresh,
+            client,
+            headers: Mutex::new(Vec::new()),
+            cookies: CookieJar::default(),
+            last_refresh: tokio::sync::Mutex::new(None),
+            baseline: Mutex::new(None),
+        }
+    }
+
+    /// whether the response is 401/403 (while the first one wasn't) or a redirect to the login page
+    pub fn is_expired(&self, response: &Response) -> bool {
+        lazy_static! {
+            static ref RE_LOGIN: Regex =
+                Regex::new(r"(?i)(^|/)(log_?in|sign_?in|auth|sso)([/?#.]|$)").unwrap();
+        }
+
+        let baseline = *self.baseline.lock().get_or_insert(response.code);
+
+        if EXPIRED_CODES.contains(&response.code) && response.code != baseline {
+            return true;
+        }
+
+        (300..400).contains(&response.code)
+            && response
+                .headers
+                .get_value_case_insensitive("location")
+                .is_some_and(|x| RE_LOGIN.is_match(&x))
+    }
+
+    /// runs the refresh step unless the session was refreshed recently
+    pub async fn refresh(&self, defaults: &RequestDefaults) -> Result<(), Box<dyn Error>> {
+        let mut last_refresh = self.last_refresh.lock().await;
+
+        // another request has already refreshed the session
+        if last_refresh.is_some_and(|x| x.elapsed() < MIN_REFRESH_INTERVAL) {
+            return Ok(());
+        }
+
+        let result = match &self.refresh {
+            SessionRefresh::Command(command) => self.refresh_via_command(command).await,
+            SessionRefresh::Request { method, url, headers, body } => {
+                defaults.count_request();
+                self.refresh_via_request(method, url, headers, body).await
+            }
+        };
+
+        // failed refreshes aren't repeated immediately either
+        *last_refresh = Some(Instant::now());
+
+        result.map_err(|err| format!("Unable to refresh the session: {}", err).into())
+    }
+
+    async fn refresh_via_command(&self, command: &str) -> Result<(), Box<dyn Error>> {
+        let output = if cfg!(windows) {
+            tokio::process::Command::new("cmd").arg("/C").arg(command).output().await?
+        } else {
+            tokio::process::Command::new("sh").arg("-c").arg(command).output().await?
+        };
+
+        if !output.status.success() {
+            Err(format!("The refresh command exited with {}", output.status))?;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        let mut new_headers: Vec<(String, String)> = Vec::new();
+
+        for line in stdout.lines().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let (k, v) = match line.split_once(':') {
+                Some((k, v)) => (k.trim().to_string(), v.trim().to_string()),
+                None => Err(format!("Unable to parse the header from the refresh command: {}", line))?,
+            };
+
+            if k.eq_ignore_ascii_case("set-cookie") {
+                self.cookies.update(&[(k, v)]);
+            } else if k.eq_ignore_ascii_case("cookie") {
+                let cookies: Vec<(String, String)> = v
+                    .split(';')
+                    .map(|x| ("set-cookie".to_string(), x.trim().to_string()))
+                    .collect();
+
+                self.cookies.update(&cookies);
+            } else {
+                new_headers.push((k, v));
+            }
+        }
+
+        if new_headers.is_empty() && self.cookies.is_empty() {
+            Err("The refresh command didn't output any headers")?;
+        }
+
+        if !new_headers.is_empty() {
+            *self.headers.lock() = new_headers;
+        }
+
+        Ok(())
+    }
+
+    async fn refresh_via_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        body: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut request = http::Request::builder().method(method).uri(url);
+
+        for (k, v) in headers.iter() {
+            request = request.header(k, v);
+        }
+
+        let request = reqwest::Request::try_from(request.body(body.to_owned())?)?;
+
+        let res = self.client.execute(request).await?;
+
+        let set_cookies: Vec<(String, String)> = res
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .filter_map(|x| x.to_str().ok())
+            .map(|x| ("set-cookie".to_string(), x.to_string()))
+            .collect();
+
+        if set_cookies.is_empty() {
+            Err(format!("The refresh request returned {} without Set-Cookie headers", res.status().as_u16()))?;
+        }
+
+        self.cookies.update(&set_cookies);
+
+        Ok(())
+    }
+
+    /// replaces the request's headers with the refreshed ones and merges the refreshed cookies.
+    /// headers and cookies with names from skip are kept as they are
+    pub fn apply(&self, headers: &mut Vec<(String, String)>, skip: &[&str]) {
+        for (k, v) in self.headers.lock().iter() {
+            if skip.iter().any(|x| x.eq_ignore_ascii_case(k)) {
+                continue;
+            }
+
+            match headers.get_index_case_insensitive(k) {
+                Some(index) => headers[index].1 = v.to_owned(),
+                None => headers.push((k.to_owned(), v.to_owned())),
+            }
+        }
+
+        if !self.cookies.is_empty() {
+            match headers.get_index_case_insensitive("cookie") {
+                Some(index) => headers[index].1 = self.cookies.apply(&headers[index].1, skip),
+                None => headers.push(("Cookie".to_string(), self.cookies.apply("", skip))),
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    error::Error,
+    time::SystemTime,
+};
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use ring::{digest, hmac};
+
+use super::utils::utc_date;
+
+/// characters that aren't encoded within canonical requests
+const AWS_UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// headers that are replaced during signing
+const SIGNING_HEADERS: [&str; 4] = ["authorization", "x-amz-date", "x-amz-content-sha256", "x-amz-security-token"];
+
+/// signs every request with AWS Signature Version 4
+#[derive(Debug, Clone)]
+pub struct SigV4 {
+    pub access_key: String,
+    pub secret_key: String,
+    pub session_token: Option<String>,
+    pub region: String,
+    pub service: String,
+}
+
+impl SigV4 {
+    /// parses region:service and access_key:secret_key[:session_token].
+    /// credentials are taken from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN in case they aren't provided
+    pub fn new(scope: &str, credentials: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let (region, service) = scope
+            .split_once(':')
+            .ok_or("--aws-sigv4 should look like <region>:<service>, e.g. us-east-1:execute-api")?;
+
+        let (access_key, secret_key, session_token) = match credentials {
+            Some(credentials) => {
+                let mut parts = credentials.splitn(3, ':');
+                (
+                    parts.next().unwrap().to_string(),
+                    parts
+                        .next()
+                        .ok_or("--aws-credentials should look like <access_key>:<secret_key>[:<session_token>]")?
+                        .to_string(),
+                    parts.next().map(|x| x.to_string()),
+                )
+            }
+            None => (
+                std::env::var("AWS_ACCESS_KEY_ID").map_err(|_| "AWS_ACCESS_KEY_ID isn't set")?,
+                std::env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| "AWS_SECRET_ACCESS_KEY isn't set")?,
+                std::env::var("AWS_SESSION_TOKEN").ok(),
+            ),
+        };
+
+        Ok(Self {
+            access_key,
+            secret_key,
+            session_token,
+            region: region.to_string(),
+            service: service.to_string(),
+        })
+    }
+
+    /// replaces the signing headers of the request with the new ones.
+    /// host, content-type and x-amz-* headers are signed
+    pub fn sign(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        headers: &mut Vec<(String, String)>,
+        body: &str,
+        now: SystemTime,
+    ) {
+        let (amz_date, date) = format_amz_date(now);
+        let payload_hash = hex(digest::digest(&digest::SHA256, body.as_bytes()).as_ref());
+
+        headers.retain(|(k, _)| !SIGNING_HEADERS.contains(&k.to_lowercase().as_str()));
+        headers.push(("X-Amz-Date".to_string(), amz_date.clone()));
+
+        // only s3 requires the payload hash header, other services just sign the hash
+        if self.service == "s3" {
+            headers.push(("X-Amz-Content-Sha256".to_string(), payload_hash.clone()));
+        }
+
+        if let Some(session_token) = &self.session_token {
+            headers.push(("X-Amz-Security-Token".to_string(), session_token.to_owned()));
+        }
+
+        let mut signed: Vec<(String, String)> = headers
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), v.split_whitespace().collect::<Vec<&str>>().join(" ")))
+            .filter(|(k, _)| k == "content-type" || k.starts_with("x-amz-"))
+            .chain(std::iter::once(("host".to_string(), host.to_string())))
+            .collect();
+        signed.sort();
+
+        let signed_headers = signed.iter().map(|(k, _)| k.as_str()).collect::<Vec<&str>>().join(";");
+        let canonical_headers: String = signed.iter().map(|(k, v)| format!("{}:{}\n", k, v)).collect();
+
+        let (uri, query) = path.split_once('?').unwrap_or((path, ""));
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            self.canonical_uri(uri),
+            canonical_query(query),
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+        );
+
+        let signing_key = [date.as_str(), self.region.as_str(), self.service.as_str(), "aws4_request"]
+            .iter()
+            .fold(format!("AWS4{}", self.secret_key).into_bytes(), |key, x| {
+                hmac_sha256(&key, x.as_bytes())
+            });
+
+        headers.push((
+            "Authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key,
+                scope,
+                signed_headers,
+                hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+            ),
+        ));
+    }
+
+    /// every path segment is encoded twice for all the services except s3
+    fn canonical_uri(&self, uri: &str) -> String {
+        if uri.is_empty() {
+            return "/".to_string();
+        }
+
+        uri.split('/')
+            .map(|x| {
+                let encoded = aws_encode(x);
+
+                if self.service == "s3" {
+                    encoded
+                } else {
+                    utf8_percent_encode(&encoded, AWS_UNRESERVED).to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+}
+
+/// decodes the value and encodes it the way aws does
+fn aws_encode(s: &str) -> String {
+    utf8_percent_encode(&percent_decode_str(s).decode_utf8_lossy(), AWS_UNRESERVED).to_string()
+}
+
+/// query pairs sorted by their encoded names and values
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let (k, v) = x.split_once('=').unwrap_or((x, ""));
+            (aws_encode(k), aws_encode(v))
+        })
+        .collect();
+    pairs.sort();
+
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// returns (YYYYMMDD'T'HHMMSS'Z', YYYYMMDD) in UTC
+fn format_amz_date(time: SystemTime) -> (String, String) {
+    let (year, month, day, secs_of_day) = utc_date(time);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+
+    (
+        format!(
+            "{}T{:02}{:02}{:02}Z",
+            date,
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60
+        ),
+        date,
+    )
+}
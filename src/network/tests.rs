@@ -1,90 +1,534 @@
-#[cfg(test)]
-mod tests {
-    use tokio::time::Duration;
+use tokio::time::Duration;
 
-    use crate::network::{
-        request::{Request, RequestDefaults},
-        utils::{Headers, InjectionPlace, is_binary_content},
+use crate::network::{
+    raw::{build_request, parse_response},
+    request::{Parameters, Request, RequestDefaults},
+    cache::ResponseCache,
+    cassette::{Player, Recorder},
+    client::{HttpClient, HttpRequest, HttpResponse},
+    decompress::decompress,
+    matchers::{Matchers, NumberSet},
+    rate_limiter::Gate,
+    redirect::{RedirectPolicy, Redirects},
+    response::Response,
+    session::{Session, SessionRefresh},
+    sigv4::SigV4,
+    utils::{Headers, InjectionPlace, is_binary_content},
+};
+
+#[test]
+fn check_is_binary_content(){
+    assert!(is_binary_content(Some("application/pdf".to_string())));
+    assert!(is_binary_content(Some("something/zip".to_string())));
+    assert!(is_binary_content(Some("image/png".to_string())));
+    assert!(is_binary_content(Some("image/something".to_string())));
+
+    assert!(!is_binary_content(Some("application/json".to_string())));
+    assert!(!is_binary_content(Some("application/html".to_string())));
+}
+
+#[test]
+fn query_creation() {
+    let mut l = RequestDefaults::default();
+    l.template = "%k=payload".to_string();
+    l.joiner = "&".to_string();
+    let parameters = vec!["test1".to_string()];
+    let mut request = Request::new(&l, parameters);
+    request.prepare();
+
+    assert_eq!(request.make_query(), "test1=payload");
+}
+
+#[test]
+fn request_defaults_generation() {
+    let defaults = RequestDefaults::new::<String>(
+        "GET",
+        "https://example.com:8443/path",
+        Vec::from([("X-Header".to_string(), "Value".to_string())]),
+        Duration::from_millis(0),
+        Default::default(),
+        None,
+        None,
+        false,
+        None,
+        false,
+        false,
+        "",
+        false,
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(defaults.scheme, "https");
+    assert_eq!(defaults.host, "example.com");
+    assert_eq!(defaults.port, 8443);
+    assert_eq!(defaults.path, "/path?%s");
+    assert_eq!(
+        defaults.custom_headers.get_value("X-Header").unwrap(),
+        "Value"
+    );
+    assert_eq!(defaults.template, "%k=%v");
+    assert_eq!(defaults.joiner, "&");
+    assert_eq!(defaults.injection_place, InjectionPlace::Path);
+}
+
+#[test]
+fn json_request_body_generation() {
+    let defaults = RequestDefaults::new::<String>(
+        "POST",
+        "https://example.com:8443/path",
+        Vec::from([("X-Header".to_string(), "Value".to_string())]),
+        Duration::from_millis(0),
+        Default::default(),
+        None,
+        None,
+        false,
+        None,
+        false,
+        false,
+        "{\"something\":1}",
+        false,
+        false,
+    )
+    .unwrap();
+
+    assert!(defaults.is_json);
+    assert_eq!(defaults.body, "{\"something\":1, %s}");
+    assert_eq!(defaults.template, "\"%k\": %v");
+}
+
+#[test]
+fn raw_response_parsing() {
+    let response = parse_response(b"HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nContent-Length: 4\r\n\r\nbody and the rest").unwrap();
+
+    assert_eq!(response.code, 404);
+    assert_eq!(response.http_version, http::Version::HTTP_11);
+    assert_eq!(response.headers.get_value_case_insensitive("content-type"), Some("text/html".to_string()));
+    assert_eq!(response.body, b"body");
+
+    assert!(parse_response(b"HTTP/2 200 OK\r\n\r\n").is_err());
+    assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+}
+
+#[test]
+fn raw_response_obs_fold() {
+    let response = parse_response(b"HTTP/1.0 200 OK\r\nX-Folded: one\r\n two\r\n\tthree\r\nX-Next: 1\r\n\r\n").unwrap();
+
+    assert_eq!(response.http_version, http::Version::HTTP_10);
+    assert_eq!(
+        response.headers,
+        [
+            ("x-folded".to_string(), "one two three".to_string()),
+            ("x-next".to_string(), "1".to_string())
+        ]
+    );
+}
+
+#[test]
+fn raw_response_chunked() {
+    let response = parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;ext=1\r\nbody\r\n6\r\n chunk\r\n0\r\n\r\n").unwrap();
+    assert_eq!(response.body, b"body chunk");
+
+    // the connection was closed in the middle of the body
+    let response = parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\npartial").unwrap();
+    assert_eq!(response.body, b"partial");
+
+    assert!(parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").is_err());
+}
+
+#[test]
+fn raw_request_building() {
+    let headers = vec![("X-Dup".to_string(), "1".to_string()), ("X-Dup ".to_string(), " 2".to_string())];
+
+    assert_eq!(
+        String::from_utf8(build_request("POST", "/path?a=b", "example.com", &headers, "a=b")).unwrap(),
+        "POST /path?a=b HTTP/1.1\r\nHost: example.com\r\nX-Dup: 1\r\nX-Dup :  2\r\nContent-Length: 3\r\nConnection: close\r\n\r\na=b"
+    );
+
+    let headers = vec![("host".to_string(), "other.com".to_string()), ("Connection".to_string(), "keep-alive".to_string())];
+
+    assert_eq!(
+        String::from_utf8(build_request("GET", "/", "example.com", &headers, "")).unwrap(),
+        "GET / HTTP/1.1\r\nhost: other.com\r\nConnection: keep-alive\r\n\r\n"
+    );
+}
+
+#[test]
+fn session_expiration() {
+    let response = |code: u16, location: &str| Response {
+        code,
+        headers: vec![("Location".to_string(), location.to_string())],
+        ..Default::default()
     };
 
-    #[test]
-    fn check_is_binary_content(){
-        assert!(is_binary_content(Some("application/pdf".to_string())));
-        assert!(is_binary_content(Some("something/zip".to_string())));
-        assert!(is_binary_content(Some("image/png".to_string())));
-        assert!(is_binary_content(Some("image/something".to_string())));
+    // the target returns 403 from the start, so 403 doesn't mean the session expired
+    let session = Session::new(SessionRefresh::Command(String::new()), reqwest::Client::new());
+    assert!(!session.is_expired(&response(403, "")));
+    assert!(!session.is_expired(&response(403, "")));
+    assert!(session.is_expired(&response(401, "")));
 
-        assert!(!is_binary_content(Some("application/json".to_string())));
-        assert!(!is_binary_content(Some("application/html".to_string())));
+    let session = Session::new(SessionRefresh::Command(String::new()), reqwest::Client::new());
+    assert!(!session.is_expired(&response(200, "")));
+    assert!(session.is_expired(&response(403, "")));
+    assert!(session.is_expired(&response(302, "https://example.com/login?next=/")));
+    assert!(session.is_expired(&response(302, "/auth/sso")));
+    assert!(!session.is_expired(&response(302, "/oauth/callback")));
+    assert!(!session.is_expired(&response(302, "/author/1")));
+}
+
+#[tokio::test]
+async fn gate_resume() {
+    let gate = std::sync::Arc::new(Gate::default());
+    assert!(gate.toggle());
+
+    let waiting = tokio::spawn({
+        let gate = std::sync::Arc::clone(&gate);
+        async move { gate.wait().await }
+    });
+
+    tokio::task::yield_now().await;
+    gate.resume();
+
+    tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+
+    // resuming the running scan doesn't pause it
+    gate.resume();
+    assert!(gate.toggle());
+}
+
+fn redirect(code: u16, location: &str) -> HttpResponse {
+    let mut response = HttpResponse::new(code, "");
+    response.headers.push(("Location".to_string(), location.to_string()));
+    response
+}
+
+#[test]
+fn redirect_methods() {
+    let redirects = Redirects { policy: RedirectPolicy::All, max_hops: 10 };
+    let request = HttpRequest {
+        method: "POST".to_string(),
+        url: "https://example.com/a".to_string(),
+        headers: vec![
+            ("Host".to_string(), "example.com".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ],
+        body: "{}".to_string(),
+        max_body_size: None,
+    };
+
+    for code in [301, 302, 303] {
+        let next = redirects.next(&request, &redirect(code, "/b")).unwrap();
+        assert_eq!(next.method, "GET");
+        assert_eq!(next.url, "https://example.com/b");
+        assert!(next.body.is_empty());
+        assert_eq!(next.headers, vec![("Host".to_string(), "example.com".to_string())]);
     }
 
-    #[test]
-    fn query_creation() {
-        let mut l = RequestDefaults::default();
-        l.template = "%k=payload".to_string();
-        l.joiner = "&".to_string();
-        let parameters = vec!["test1".to_string()];
-        let mut request = Request::new(&l, parameters);
-        request.prepare();
+    for code in [307, 308] {
+        let next = redirects.next(&request, &redirect(code, "/b")).unwrap();
+        assert_eq!(next.method, "POST");
+        assert_eq!(next.body, "{}");
+        assert_eq!(next.headers, request.headers);
+    }
+
+    assert!(redirects.next(&request, &redirect(304, "/b")).is_none());
+    assert!(redirects.next(&request, &HttpResponse::new(302, "")).is_none());
+}
 
-        assert_eq!(request.make_query(), "test1=payload");
+#[test]
+fn redirect_credentials() {
+    let request = HttpRequest {
+        method: "GET".to_string(),
+        url: "https://example.com/a".to_string(),
+        headers: vec![
+            ("Host".to_string(), "example.com".to_string()),
+            ("Authorization".to_string(), "Bearer x".to_string()),
+            ("Proxy-Authorization".to_string(), "Basic x".to_string()),
+            ("Cookie".to_string(), "a=b".to_string()),
+        ],
+        body: String::new(),
+        max_body_size: None,
+    };
+
+    let redirects = Redirects { policy: RedirectPolicy::All, max_hops: 10 };
+
+    let next = redirects.next(&request, &redirect(302, "/b")).unwrap();
+    assert_eq!(next.headers, request.headers);
+
+    for location in ["https://other.com/b", "http://example.com/b", "https://example.com:8443/b"] {
+        let next = redirects.next(&request, &redirect(302, location)).unwrap();
+        assert_eq!(next.headers.len(), 1);
+        assert_eq!(next.headers[0].0, "Host");
     }
 
-    #[test]
-    fn request_defaults_generation() {
-        let defaults = RequestDefaults::new::<String>(
-            "GET",
-            "https://example.com:8443/path",
-            Vec::from([("X-Header".to_string(), "Value".to_string())]),
-            Duration::from_millis(0),
-            Default::default(),
-            None,
-            None,
-            false,
-            None,
-            false,
-            false,
-            "",
-            false,
-            false,
-        )
-        .unwrap();
-
-        assert_eq!(defaults.scheme, "https");
-        assert_eq!(defaults.host, "example.com");
-        assert_eq!(defaults.port, 8443);
-        assert_eq!(defaults.path, "/path?%s");
+    let next = redirects.next(&request, &redirect(302, "https://example.com:8443/b")).unwrap();
+    assert_eq!(next.headers[0].1, "example.com:8443");
+
+    let redirects = Redirects { policy: RedirectPolicy::SameHost, max_hops: 10 };
+    assert!(redirects.next(&request, &redirect(302, "/b")).is_some());
+    assert!(redirects.next(&request, &redirect(302, "https://other.com/b")).is_none());
+    assert!(redirects.next(&request, &redirect(302, "http://example.com/b")).is_none());
+}
+
+#[test]
+fn decompression() {
+    let body = "<html>page</html>".repeat(100);
+
+    let mut br = Vec::new();
+    brotli::BrotliCompress(&mut body.as_bytes(), &mut br, &Default::default()).unwrap();
+    let zstd = zstd::stream::encode_all(body.as_bytes(), 0).unwrap();
+
+    for (encoding, encoded) in [("br", br), ("zstd", zstd)] {
+        let headers = vec![("Content-Encoding".to_string(), encoding.to_string())];
+
+        assert_eq!(decompress(&headers, encoded.clone(), None), (body.as_bytes().to_vec(), false));
         assert_eq!(
-            defaults.custom_headers.get_value("X-Header").unwrap(),
-            "Value"
+            decompress(&headers, encoded.clone(), Some(body.len())),
+            (body.as_bytes().to_vec(), false)
         );
-        assert_eq!(defaults.template, "%k=%v");
-        assert_eq!(defaults.joiner, "&");
-        assert_eq!(defaults.injection_place, InjectionPlace::Path);
+
+        // the decoded body is cut like the raw one
+        assert_eq!(decompress(&headers, encoded, Some(10)), (body.as_bytes()[..10].to_vec(), true));
+    }
+
+    // unknown encodings and broken bodies are returned as is
+    let headers = vec![("Content-Encoding".to_string(), "zstd".to_string())];
+    assert_eq!(decompress(&headers, b"plain".to_vec(), None), (b"plain".to_vec(), false));
+    let headers = vec![("Content-Encoding".to_string(), "identity".to_string())];
+    assert_eq!(decompress(&headers, b"plain".to_vec(), None), (b"plain".to_vec(), false));
+}
+
+#[test]
+fn response_cache_size() {
+    let cache = ResponseCache::new(25);
+    let response = |text: &str| Response {
+        text: text.to_string(),
+        ..Default::default()
+    };
+
+    cache.insert(1, &response("0123456789"));
+    cache.insert(2, &response("0123456789"));
+    assert!(cache.get(1).is_some());
+
+    // the oldest response is dropped to fit the new one
+    cache.insert(3, &response("0123456789"));
+    assert!(cache.get(1).is_none());
+    assert!(cache.get(2).is_some());
+    assert!(cache.get(3).is_some());
+
+    // responses larger than the whole cache aren't stored
+    cache.insert(4, &response(&"0".repeat(30)));
+    assert!(cache.get(4).is_none());
+    assert!(cache.get(3).is_some());
+    assert_eq!(cache.hits(), 4);
+}
+
+#[test]
+fn parameters_splitting() {
+    let params: Parameters = (0..10).map(|x| x.to_string()).collect();
+
+    let (first, second) = params.split_at(4);
+    assert_eq!(&*first, &params[..4]);
+    assert_eq!(&*second, &params[4..]);
+
+    let (first, second) = second.split_at(3);
+    assert_eq!(first.to_vec(), vec!["4", "5", "6"]);
+    assert_eq!(second.to_vec(), vec!["7", "8", "9"]);
+
+    assert_eq!(params.slice(8..10).to_vec(), vec!["8", "9"]);
+    assert_eq!(second.slice(1..2).to_vec(), vec!["8"]);
+}
+
+#[test]
+fn matchers() {
+    let mut response = Response {
+        code: 200,
+        headers: vec![("X-Debug".to_string(), "enabled".to_string())],
+        text: "<p>debug page</p>".to_string(),
+        ..Default::default()
+    };
+    response.add_headers();
+    let initial_response = Response::default();
+
+    let matchers = Matchers {
+        match_regex: Some(regex::Regex::new("^X-Debug: enabled$").unwrap()),
+        ..Default::default()
+    };
+    assert!(matchers.is_interesting(&response, &initial_response));
+
+    let matchers = Matchers {
+        match_regex: Some(regex::Regex::new("^<p>debug").unwrap()),
+        ..Default::default()
+    };
+    assert!(matchers.is_interesting(&response, &initial_response));
+
+    let matchers = Matchers {
+        match_regex: Some(regex::Regex::new("admin").unwrap()),
+        ..Default::default()
+    };
+    assert!(!matchers.is_interesting(&response, &initial_response));
+
+    let matchers = Matchers {
+        match_codes: NumberSet::parse("300-399").unwrap(),
+        filter_codes: NumberSet::parse("200").unwrap(),
+        ..Default::default()
+    };
+    assert!(!matchers.is_interesting(&response, &initial_response));
+}
+
+#[tokio::test]
+async fn cassette_replay() {
+    let filename = std::env::temp_dir().join(format!("x8-cassette-{}.jsonl", std::process::id()));
+    let filename = filename.to_str().unwrap();
+
+    let request = |url: &str| HttpRequest {
+        method: "GET".to_string(),
+        url: url.to_string(),
+        headers: vec![("Host".to_string(), "example.com".to_string())],
+        body: String::new(),
+        max_body_size: None,
+    };
+
+    let recorder = Recorder::new(filename).unwrap();
+    recorder.record(&request("https://example.com/?abcd=efgh"), &HttpResponse::new(200, "<p>efgh</p>"));
+
+    let mut binary = HttpResponse::new(200, "");
+    binary.body = vec![0xff, 0x00, 0xfe, 0x80];
+    recorder.record(&request("https://example.com/file"), &binary);
+    drop(recorder);
+
+    let player = Player::new(filename).unwrap();
+    std::fs::remove_file(filename).ok();
+
+    // the exact request isn't within the cassette, so the one with the same structure is replayed
+    // with the recorded random values replaced
+    let response = player.send(request("https://example.com/?wxyz=ijkl")).await.unwrap();
+    assert_eq!(response.body, b"<p>ijkl</p>");
+
+    let response = player.send(request("https://example.com/file")).await.unwrap();
+    assert_eq!(response.body, vec![0xff, 0x00, 0xfe, 0x80]);
+
+    // every exchange is replayed once
+    assert!(player.send(request("https://example.com/file")).await.is_err());
+}
+
+/// get-vanilla and post-x-www-form-urlencoded from the AWS Signature Version 4 test suite
+#[test]
+fn sigv4_test_suite() {
+    let sigv4 = SigV4::new("us-east-1:service", Some("AKIDEXAMPLE:wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")).unwrap();
+    let now = std::time::UNIX_EPOCH + Duration::from_secs(1440938160);
+
+    let authorization = |headers: &[(String, String)]| {
+        headers.iter().find(|(k, _)| k == "Authorization").unwrap().1.clone()
+    };
+
+    let mut headers = Vec::new();
+    sigv4.sign("GET", "example.amazonaws.com", "/", &mut headers, "", now);
+    assert!(headers.contains(&("X-Amz-Date".to_string(), "20150830T123600Z".to_string())));
+    assert_eq!(
+        authorization(&headers),
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+        SignedHeaders=host;x-amz-date, \
+        Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    );
+
+    let mut headers = vec![("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string())];
+    sigv4.sign("POST", "example.amazonaws.com", "/", &mut headers, "Param1=value1", now);
+    assert_eq!(
+        authorization(&headers),
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+        SignedHeaders=content-type;host;x-amz-date, \
+        Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+    );
+}
+
+/// serves every request with the csrf token and remembers the request lines and the csrf headers
+async fn csrf_server(listener: tokio::net::TcpListener, requests: std::sync::Arc<parking_lot::Mutex<Vec<String>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    while let Ok((mut stream, _)) = listener.accept().await {
+        let mut head = Vec::new();
+        let mut buf = [0u8; 1024];
+
+        while !head.windows(4).any(|x| x == b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => head.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        let head = String::from_utf8_lossy(&head).to_lowercase();
+        let token = head.lines().find(|x| x.starts_with("x-csrf-token:")).unwrap_or("");
+        requests.lock().push(format!("{} {}", head.lines().next().unwrap_or(""), token));
+
+        let body = r#"<input name="csrf" value="token1">"#;
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+        stream.write_all(response.as_bytes()).await.ok();
     }
+}
 
-    #[test]
-    fn json_request_body_generation() {
-        let defaults = RequestDefaults::new::<String>(
-            "POST",
-            "https://example.com:8443/path",
-            Vec::from([("X-Header".to_string(), "Value".to_string())]),
-            Duration::from_millis(0),
-            Default::default(),
-            None,
-            None,
-            false,
-            None,
-            false,
-            false,
-            "{\"something\":1}",
-            false,
-            false,
-        )
-        .unwrap();
-
-        assert!(defaults.is_json);
-        assert_eq!(defaults.body, "{\"something\":1, %s}");
-        assert_eq!(defaults.template, "\"%k\": %v");
+#[tokio::test]
+async fn proxy_pool_csrf() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let requests = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+    tokio::spawn(csrf_server(listener, requests.clone()));
+
+    let proxy_file = std::env::temp_dir().join(format!("x8-proxies-{}.txt", std::process::id()));
+    std::fs::write(&proxy_file, format!("http://{}\n", addr)).unwrap();
+
+    // the server is the proxy and the target at the same time
+    let url = format!("http://{}/", addr);
+    let args: Vec<String> = [
+        "-u", &url, "--proxy-file", proxy_file.to_str().unwrap(),
+        "--csrf-from", r#"value="(\w+)""#, "--csrf-to", "header:X-CSRF-Token",
+    ]
+    .iter()
+    .map(|x| x.to_string())
+    .collect();
+
+    let config = crate::config::args::get_config_from(&args).unwrap();
+    std::fs::remove_file(&proxy_file).ok();
+
+    let defaults = RequestDefaults::from_config(&config, "GET", &url).unwrap();
+    let response = Request::new(&defaults, vec!["abcd=efgh".to_string()]).send().await.unwrap();
+    assert_eq!(response.code, 200);
+
+    // the token is fetched first, and the request is sent via the proxy with the token
+    let requests = requests.lock();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].starts_with(&format!("get http://{}/?abcd=efgh", addr)));
+    assert!(requests[1].ends_with("x-csrf-token: token1"));
+}
+
+#[cfg(feature = "http3")]
+#[tokio::test]
+async fn http3_fallback() {
+    use crate::{config::structs::Config, network::{http3::Http3Client, utils::create_client}};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let requests = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+    tokio::spawn(csrf_server(listener, requests.clone()));
+
+    let config = Config { timeout: 2, ..Default::default() };
+    let client = Http3Client::new(&config, create_client(&config, false).unwrap()).unwrap();
+
+    // the server doesn't listen for QUIC, so both requests are sent over HTTP/1.1
+    for _ in 0..2 {
+        let request = HttpRequest {
+            method: "GET".to_string(),
+            url: format!("http://{}/", addr),
+            headers: Vec::new(),
+            body: String::new(),
+            max_body_size: None,
+        };
+
+        let response = client.send(request).await.unwrap();
+        assert_eq!(response.http_version, Some(http::Version::HTTP_11));
     }
+
+    assert_eq!(requests.lock().len(), 2);
 }
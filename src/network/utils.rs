@@ -1,12 +1,18 @@
-use std::{time::Duration, error::Error};
+use std::{
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    hash::{Hash, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use lazy_static::lazy_static;
 use percent_encoding::{AsciiSet, CONTROLS};
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{Client, ClientBuilder};
 use serde::Serialize;
+use serde_json::json;
 
-use crate::{config::structs::Config, utils::random_line};
+use crate::{config::structs::Config, runner::utils::ReasonKind, utils::random_line};
 
 use super::response::Response;
 
@@ -26,6 +32,25 @@ lazy_static! {
         .add(b'%');
 }
 
+/// returns (year, month, day, seconds since the start of the day) in UTC
+pub fn utc_date(time: SystemTime) -> (i64, i64, i64, i64) {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // converts days since the epoch to the civil date
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, secs_of_day)
+}
+
 /// enum mainly created for the correct json parsing
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DataType {
@@ -40,16 +65,28 @@ pub enum DataType {
     ProbablyJson,
 
     Urlencoded,
-    Headers
+    Headers,
+
+    /// multipart/form-data. Parameters are sent as additional form parts
+    Multipart,
+
+    /// parameters are sent as child elements (or attributes) of a xml node
+    Xml,
 }
 
 /// where to insert parameters
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Copy)]
 pub enum InjectionPlace {
     Path,
+
+    /// a whole path segment: /api/%s/status. Only one candidate per request
+    PathSegment,
     Body,
     Headers,
     HeaderValue,
+
+    /// name=value pairs within the Cookie header
+    Cookies,
 }
 
 impl Default for InjectionPlace {
@@ -97,41 +134,114 @@ impl Headers for Vec<(String, String)> {
     }
 }
 
-/// writes request and response to a file
-/// return file location
+/// writes the exact request, the full response and the finding's details to
+/// <save-responses>/<host>/<method>/<parameter>-<nonce>/{request.txt, response.txt, finding.json}
+/// returns the directory location
 pub(super) fn save_request(
     config: &Config,
     response: &Response,
+    initial_response: &Response,
+    reason_kind: &ReasonKind,
     param_key: &str,
+    diff: Option<&str>,
 ) -> Result<String, Box<dyn Error>> {
-    let output = response.print_all();
+    let request = response.request.as_ref().unwrap();
 
-    let filename = format!(
-        "{}/{}-{}-{}-{}",
+    let dirname = format!(
+        "{}/{}/{}/{}-{}",
         &config.save_responses,
-        &response.request.as_ref().unwrap().defaults.host,
-        response
-            .request
-            .as_ref()
-            .unwrap()
-            .defaults
-            .method
-            .to_lowercase(),
-        param_key,
+        sanitize_filename(&request.defaults.host),
+        request.defaults.method.to_lowercase(),
+        sanitize_filename(param_key),
         random_line(3) //nonce to prevent overwrites
     );
 
-    std::fs::write(&filename, output)?;
+    std::fs::create_dir_all(&dirname)?;
 
-    Ok(filename)
+    std::fs::write(format!("{}/request.txt", dirname), request.print_sent())?;
+    std::fs::write(format!("{}/response.txt", dirname), response.print())?;
+    std::fs::write(
+        format!("{}/finding.json", dirname),
+        serde_json::to_string_pretty(&json!({
+            "method": request.defaults.method,
+            "url": request.defaults.url_without_default_port(),
+            "parameter": param_key,
+            "reason_kind": reason_kind,
+            "diff": diff,
+            "initial_status": initial_response.code,
+            "status": response.code,
+            "initial_size": initial_response.text.len(),
+            "size": response.text.len(),
+            "initial_time": initial_response.time,
+            "time": response.time,
+        }))?,
+    )?;
+
+    Ok(dirname)
+}
+
+/// replaces characters that can't be or shouldn't be within filenames
+fn sanitize_filename(s: &str) -> String {
+    let s: String = s
+        .chars()
+        .map(|x| if x.is_ascii_alphanumeric() || "-_.=".contains(x) { x } else { '_' })
+        .take(64)
+        .collect();
+
+    // prevents . and .. from pointing to other directories
+    if s.chars().all(|x| x == '.') {
+        s.replace('.', "_")
+    } else {
+        s
+    }
+}
+
+/// parses proxy url. http:// is used in case the scheme is not specified.
+/// socks5h:// resolves hostnames on the proxy side
+pub fn parse_proxy(proxy: &str) -> Result<reqwest::Proxy, Box<dyn Error>> {
+    let proxy = if proxy.contains("://") {
+        proxy.to_string()
+    } else {
+        format!("http://{}", proxy)
+    };
+
+    let scheme = proxy.split("://").next().unwrap().to_lowercase();
+
+    if !["http", "https", "socks5", "socks5h"].contains(&scheme.as_str()) {
+        Err(format!(
+            "Unsupported proxy scheme {}://. Supported schemes: http, https, socks5, socks5h",
+            scheme
+        ))?;
+    }
+
+    Ok(reqwest::Proxy::all(&proxy)?)
 }
 
 pub fn create_client(config: &Config, replay: bool) -> Result<Client, Box<dyn Error>> {
+    if replay {
+        match create_client_with_proxy(config, &config.replay_proxy) {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                Err(format!("Unable to parse replay_proxy: {}", err))?
+            }
+        }
+    } else {
+        create_client_with_proxy(config, &config.proxy)
+    }
+}
+
+/// creates a client that sends requests via the proxy. Empty proxy means no proxy
+pub fn create_client_with_proxy(config: &Config, proxy: &str) -> Result<Client, Box<dyn Error>> {
+    Ok(client_builder(config, proxy)?.build()?)
+}
+
+/// the builder with all the client options from the config
+pub fn client_builder(config: &Config, proxy: &str) -> Result<ClientBuilder, Box<dyn Error>> {
     let mut client = Client::builder()
         .danger_accept_invalid_certs(true)
         .timeout(Duration::from_secs(config.timeout as u64))
         .http1_title_case_headers()
-        .cookie_store(true)
+        .cookie_store(!config.pin_cookies && !config.cookie_jar)
         .http09_responses()
         .use_rustls_tls();
 
@@ -139,32 +249,162 @@ pub fn create_client(config: &Config, replay: bool) -> Result<Client, Box<dyn Er
         client = client.no_trust_dns();
     }
 
-    if replay {
-        client = client.proxy(match reqwest::Proxy::all(&config.replay_proxy) {
-            Ok(val) => val,
-            Err(err) => {
-                Err(format!("Unable to parse replay_proxy: {}", err))?
-            }
-        });
-    } else {
-        if !config.proxy.is_empty() {
-            client = client.proxy(reqwest::Proxy::all(&config.proxy)?);
-        }
+    // idle connections aren't kept at all, so every request opens a new one
+    if !config.keepalive {
+        client = client.pool_max_idle_per_host(0);
+    } else if let Some(pool_max_idle) = config.pool_max_idle {
+        client = client.pool_max_idle_per_host(pool_max_idle);
+    }
+
+    if let Some(pool_idle_timeout) = config.pool_idle_timeout {
+        client = client.pool_idle_timeout(pool_idle_timeout);
+    }
+
+    if let Some(tcp_keepalive) = config.tcp_keepalive {
+        client = client.tcp_keepalive(tcp_keepalive);
+    }
+
+    if !proxy.is_empty() {
+        client = client.proxy(parse_proxy(proxy)?);
     }
 
-    if !config.follow_redirects {
-        client = client.redirect(reqwest::redirect::Policy::none());
+    // reqwest ignores the port of overridden addresses, so the port from the url is used
+    for (host, addr) in config.resolve.iter() {
+        client = client.resolve(host, *addr);
     }
 
+    if let Some(identity) = &config.client_identity {
+        client = client.identity(
+            reqwest::Identity::from_pem(identity)
+                .map_err(|err| format!("Unable to use the client certificate: {}", err))?,
+        );
+    }
+
+    // redirects are followed by requests themselves according to --follow-redirects
+    client = client.redirect(reqwest::redirect::Policy::none());
+
     if config.http_version.is_some() {
         match config.http_version {
             Some(http::Version::HTTP_11) => client = client.http1_only(),
-            Some(http::Version::HTTP_2) => client = client.http2_prior_knowledge(),
+            Some(http::Version::HTTP_2) if config.http2_prior_knowledge => client = client.http2_prior_knowledge(),
+            // h2 is negotiated via ALPN
+            Some(http::Version::HTTP_2) => (),
             _ => unreachable!()
         }
     }
 
-    Ok(client.build()?)
+    Ok(client)
+}
+
+/// inserts the injection point into the nested json object selected via the json pointer (like /data/filters)
+/// so the parameters become keys of that object instead of the top level ones.
+/// the keys of the body are reordered alphabetically during the process
+pub fn insert_json_injection_point(body: &str, pointer: &str) -> Result<String, Box<dyn Error>> {
+    const MARKER: &str = "x8_json_injection_point";
+
+    let body = if body.is_empty() { "{}" } else { body };
+
+    let mut json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|err| format!("Unable to parse the body as json for --json-pointer: {}", err))?;
+
+    let object = json
+        .pointer_mut(pointer)
+        .ok_or_else(|| format!("Unable to find {} within the body", pointer))?
+        .as_object_mut()
+        .ok_or_else(|| format!("{} doesn't point to a json object", pointer))?;
+
+    object.insert(MARKER.to_string(), serde_json::Value::Null);
+
+    Ok(serde_json::to_string(&json)?.replace(&format!("\"{}\":null", MARKER), "%s"))
+}
+
+/// returns the boundary from the multipart/form-data content type
+pub fn multipart_boundary(content_type: &str) -> Option<String> {
+    if !content_type.to_lowercase().contains("multipart/form-data") {
+        return None;
+    }
+
+    content_type
+        .split(';')
+        .map(|x| x.trim())
+        .find(|x| x.to_lowercase().starts_with("boundary="))
+        .map(|x| x["boundary=".len()..].trim_matches('"').to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// escapes special xml characters
+pub fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// returns the positions of '<' and '>' of the first opening tag with the given name
+/// (or of the root element in case the name isn't provided) and the tag's name.
+/// namespace prefixes can be omitted: Body matches soap:Body
+fn find_xml_opening_tag(body: &str, node: Option<&str>) -> Option<(usize, usize, String)> {
+    for (start, _) in body.match_indices('<') {
+        let tag = &body[start + 1..];
+
+        // skip the prolog, comments, doctypes and closing tags
+        if tag.starts_with('?') || tag.starts_with('!') || tag.starts_with('/') {
+            continue;
+        }
+
+        let name: String = tag
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+            .collect();
+
+        let matches = match node {
+            Some(node) => name == node || name.rsplit(':').next() == Some(node),
+            None => true,
+        };
+
+        if matches {
+            let end = start + tag.find('>')? + 1;
+            return Some((start, end, name));
+        }
+    }
+
+    None
+}
+
+/// inserts the injection point into the xml node (the root element by default).
+/// parameters become either child elements or attributes of the node
+pub fn insert_xml_injection_point(
+    body: &str,
+    node: Option<&str>,
+    attributes: bool,
+) -> Result<String, Box<dyn Error>> {
+    if body.is_empty() {
+        return match node {
+            Some(node) if attributes => Ok(format!("<{} %s/>", node)),
+            Some(node) => Ok(format!("<{}>%s</{}>", node, node)),
+            None if attributes => Ok("<root %s/>".to_string()),
+            None => Ok("<root>%s</root>".to_string()),
+        };
+    }
+
+    let (start, end, name) = find_xml_opening_tag(body, node).ok_or_else(|| match node {
+        Some(node) => format!("Unable to find <{}> within the body", node),
+        None => "Unable to find the root element within the body".to_string(),
+    })?;
+
+    let self_closing = body[start..end].ends_with('/');
+
+    if attributes {
+        // <node a="b"> -> <node a="b" %s>
+        let insert_at = if self_closing { end - 1 } else { end };
+        Ok(format!("{} %s{}", body[..insert_at].trim_end(), &body[insert_at..]))
+    } else if self_closing {
+        // <node/> -> <node>%s</node>
+        Ok(format!("{}>%s</{}>{}", body[..end - 1].trim_end(), name, &body[end + 1..]))
+    } else {
+        Ok(format!("{}%s{}", &body[..=end], &body[end + 1..]))
+    }
 }
 
 /// check whether the content is binary
@@ -178,9 +418,42 @@ pub fn is_binary_content(content_type: Option<String>) -> bool {
         static ref RE_BINARY_MIME: Regex = Regex::new(
             "((video|audio|font|image)/\
     |\
-    /(zip|octet-stream|x-tar|vnd\\.rar|pdf|gzip|epub-zip|x-bzip|x-bzip2|x-freearc|x-7z-compressed))"
+    /(zip|octet-stream|x-tar|vnd\\.rar|pdf|gzip|epub-zip|x-bzip|x-bzip2|x-freearc|x-7z-compressed|(x-)?protobuf|grpc|msgpack|cbor))"
         ).unwrap();
     }
 
     content_type.is_some() && RE_BINARY_MIME.is_match(&content_type.unwrap())
+}
+
+/// bodies with null bytes at the beginning are binary regardless of their content type
+pub fn is_binary_body(body: &[u8]) -> bool {
+    body.iter().take(1024).any(|x| *x == 0)
+}
+
+/// binary and truncated bodies are compared by their hash and their size rounded to 2 significant digits,
+/// so the lines of the summary change separately in case the content changes slightly
+pub fn body_summary(kind: &str, body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+
+    let mut magnitude = 1;
+    while body.len() / magnitude >= 100 {
+        magnitude *= 10;
+    }
+
+    format!(
+        "{} size ~{}\n{} hash {:016x}",
+        kind,
+        body.len() / magnitude * magnitude,
+        kind,
+        hasher.finish()
+    )
+}
+
+/// whether the body should be compared as html with --html-diff
+pub fn is_html(content_type: Option<String>, body: &str) -> bool {
+    match content_type {
+        Some(val) => val.to_lowercase().contains("html"),
+        None => body.trim_start().starts_with('<'),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{
+    utils::{random_index, random_line, random_range},
+    VALUE_LENGTH,
+};
+
+/// how to generate values of parameters
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ValueStrategy {
+    /// random alphanumeric string
+    #[default]
+    Random,
+
+    /// the same string for every parameter
+    Fixed(String),
+
+    /// random number without leading zeros
+    Numeric,
+
+    /// true or false
+    Boolean,
+
+    /// random uuid v4
+    Uuid,
+
+    /// random line from the file
+    File(Vec<String>),
+}
+
+impl ValueStrategy {
+    /// accepts random, numeric, boolean, uuid, fixed:<value> and file:<path>
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match value {
+            "random" => ValueStrategy::Random,
+            "numeric" => ValueStrategy::Numeric,
+            "boolean" => ValueStrategy::Boolean,
+            "uuid" => ValueStrategy::Uuid,
+            _ if value.starts_with("fixed:") => ValueStrategy::Fixed(value["fixed:".len()..].to_string()),
+            _ if value.starts_with("file:") => {
+                let lines: Vec<String> = std::fs::read_to_string(&value["file:".len()..])?
+                    .lines()
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+                    .collect();
+
+                if lines.is_empty() {
+                    Err(format!("The values file {} is empty", &value["file:".len()..]))?
+                }
+
+                ValueStrategy::File(lines)
+            }
+            _ => Err(format!(
+                "Unknown value strategy: {}. Use random, numeric, boolean, uuid, fixed:<value> or file:<path>",
+                value
+            ))?,
+        })
+    }
+
+    pub fn generate(&self) -> String {
+        match self {
+            ValueStrategy::Random => random_line(VALUE_LENGTH),
+            ValueStrategy::Fixed(value) => value.to_owned(),
+            ValueStrategy::Numeric => random_range(10u64.pow(VALUE_LENGTH as u32 - 1), 10u64.pow(VALUE_LENGTH as u32)).to_string(),
+            ValueStrategy::Boolean => if random_range(0, 2) == 1 { "true" } else { "false" }.to_string(),
+            ValueStrategy::Uuid => {
+                let hex: String = (0..32).map(|_| format!("{:x}", random_range(0, 16))).collect();
+                format!(
+                    "{}-{}-4{}-{}{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[13..16],
+                    ["8", "9", "a", "b"][random_index(4)],
+                    &hex[17..20],
+                    &hex[20..]
+                )
+            }
+            ValueStrategy::File(lines) => lines[random_index(lines.len())].to_owned(),
+        }
+    }
+
+    /// whether the values are unique enough to count their reflections
+    pub fn is_unique(&self) -> bool {
+        matches!(self, ValueStrategy::Random | ValueStrategy::Numeric | ValueStrategy::Uuid)
+    }
+}
+
+/// the default value strategy and per parameter overrides
+#[derive(Debug, Clone, Default)]
+pub struct ValueStrategies {
+    pub default: ValueStrategy,
+    pub overrides: HashMap<String, ValueStrategy>,
+}
+
+impl ValueStrategies {
+    /// parses values like `numeric debug=boolean token=fixed:abc`.
+    /// name=strategy pairs override the strategy for specific parameters
+    pub fn parse<'a>(values: impl Iterator<Item = &'a str>) -> Result<Self, Box<dyn Error>> {
+        let mut strategies = ValueStrategies::default();
+
+        for value in values {
+            let is_override = !value.starts_with("fixed:") && !value.starts_with("file:") && value.contains('=');
+
+            if is_override {
+                let (name, strategy) = value.split_once('=').unwrap();
+                strategies.overrides.insert(name.to_string(), ValueStrategy::parse(strategy)?);
+            } else {
+                strategies.default = ValueStrategy::parse(value)?;
+            }
+        }
+
+        Ok(strategies)
+    }
+
+    pub fn get(&self, name: &str) -> &ValueStrategy {
+        self.overrides.get(name).unwrap_or(&self.default)
+    }
+
+    pub fn generate(&self, name: &str) -> String {
+        self.get(name).generate()
+    }
+}
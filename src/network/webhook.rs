@@ -0,0 +1,174 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use colored::Colorize;
+use parking_lot::Mutex;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+/// how long findings are collected before they're sent together
+const BATCH_WINDOW: Duration = Duration::from_secs(10);
+
+/// how many times a failed notification is resent
+const RETRIES: u32 = 3;
+
+/// discord rejects messages longer than 2000 chars
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flavor {
+    Slack,
+    Discord,
+    Generic,
+}
+
+impl Flavor {
+    fn from_url(url: &str) -> Self {
+        if url.contains("hooks.slack.com/") {
+            Flavor::Slack
+        } else if url.contains("discord.com/api/webhooks/") || url.contains("discordapp.com/api/webhooks/") {
+            Flavor::Discord
+        } else {
+            Flavor::Generic
+        }
+    }
+}
+
+/// posts found parameters and the final summary to the webhook.
+/// findings are batched, so a burst of them results in a single message
+#[derive(Debug)]
+pub struct Webhook {
+    url: String,
+    flavor: Flavor,
+    client: Client,
+
+    /// findings waiting for the current batch window to end
+    pending: Mutex<Vec<Value>>,
+
+    /// makes batches and the summary arrive in order
+    sending: tokio::sync::Mutex<()>,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            Err(format!("The webhook url should start with http:// or https://: {}", url))?;
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            flavor: Flavor::from_url(url),
+            client: Client::builder().timeout(Duration::from_secs(15)).build()?,
+            pending: Mutex::new(Vec::new()),
+            sending: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// queues the finding. The first finding of a batch starts the window, after which the whole batch is sent
+    pub fn add_finding(self: &Arc<Self>, finding: Value) {
+        let mut pending = self.pending.lock();
+        pending.push(finding);
+
+        if pending.len() == 1 {
+            let webhook = Arc::clone(self);
+            tokio::spawn(async move {
+                tokio::time::sleep(BATCH_WINDOW).await;
+                webhook.flush().await;
+            });
+        }
+    }
+
+    /// sends the findings that are still pending and the summary of the scan
+    pub async fn finish(&self, summary: Value) {
+        self.flush().await;
+
+        let _sending = self.sending.lock().await;
+        self.post(&self.payload("summary", summary_text(&summary), json!({ "summary": summary }))).await;
+    }
+
+    async fn flush(&self) {
+        let _sending = self.sending.lock().await;
+
+        let findings = std::mem::take(&mut *self.pending.lock());
+        if findings.is_empty() {
+            return;
+        }
+
+        let text = findings.iter().map(finding_text).collect::<Vec<String>>().join("\n");
+        self.post(&self.payload("findings", text, json!({ "findings": findings }))).await;
+    }
+
+    /// slack and discord expect messages, other webhooks receive the raw data
+    fn payload(&self, event: &str, text: String, data: Value) -> Value {
+        match self.flavor {
+            Flavor::Slack => json!({ "text": text }),
+            Flavor::Discord => json!({ "content": truncate(&text, DISCORD_MAX_LENGTH) }),
+            Flavor::Generic => {
+                let mut payload = json!({ "event": event });
+                payload.as_object_mut().unwrap().extend(data.as_object().unwrap().clone());
+                payload
+            }
+        }
+    }
+
+    /// resends the payload with an exponential backoff. Failed notifications never stop the scan
+    async fn post(&self, payload: &Value) {
+        let mut last_error = String::new();
+
+        for attempt in 0..=RETRIES {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt - 1))).await;
+            }
+
+            match self.client.post(&self.url).json(payload).send().await {
+                Ok(res) if res.status().is_success() => return,
+                Ok(res) => last_error = format!("the webhook returned {}", res.status()),
+                Err(err) => last_error = err.to_string(),
+            }
+        }
+
+        writeln!(
+            io::stderr(),
+            "{} Unable to notify the webhook: {}",
+            "[#]".yellow(),
+            last_error
+        )
+        .ok();
+    }
+}
+
+fn finding_text(finding: &Value) -> String {
+    let field = |name: &str| finding[name].as_str().unwrap_or_default().to_string();
+
+    format!(
+        "x8: found `{}` ({}) on {} {}",
+        field("name"),
+        field("reason_kind"),
+        field("method"),
+        field("url")
+    )
+}
+
+fn summary_text(summary: &Value) -> String {
+    let mut text = format!(
+        "x8: scan finished. {} parameter(s) found with {} request(s)",
+        summary["findings"], summary["requests"]
+    );
+
+    if let Some(reason) = summary["stopped"].as_str() {
+        text += &format!(" (stopped: {})", reason);
+    }
+
+    text
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        text.chars().take(max - 3).collect::<String>() + "..."
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{self, Write},
+};
+
+use colored::Colorize;
+use indicatif::ProgressBar;
+use serde::Deserialize;
+
+use crate::{
+    config::args::get_config_from,
+    network::{
+        request::{Request, RequestDefaults},
+        utils::create_client,
+    },
+    runner::{runner::Runner, utils::FoundParameter},
+    utils,
+};
+
+/// a url:method pair from --output-format json or json-by-target
+#[derive(Debug, Deserialize)]
+pub(crate) struct SavedOutput {
+    pub method: String,
+    pub url: String,
+
+    #[serde(default)]
+    pub found_params: Vec<FoundParameter>,
+}
+
+/// a single finding from --output-format jsonl
+#[derive(Debug, Deserialize)]
+struct SavedParameter {
+    method: String,
+    url: String,
+
+    #[serde(flatten)]
+    found_param: FoundParameter,
+}
+
+/// resends every found parameter from the results file and prints which of them still reproduce.
+/// args are the options of the original scan, -u and -X are taken from the results
+pub async fn replay(
+    results: &str,
+    replay_proxy: Option<&str>,
+    args: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let outputs = parse_results(&std::fs::read_to_string(results)?)?;
+
+    let (mut total, mut reproduced_total) = (0, 0);
+
+    for output in outputs.iter().filter(|x| !x.found_params.is_empty()) {
+        total += output.found_params.len();
+
+        let reproduced = match reproduce(output, replay_proxy, args).await {
+            Ok(val) => val,
+            Err(err) => {
+                utils::error(err, Some(&output.url), None, None);
+                continue;
+            }
+        };
+
+        reproduced_total += reproduced.len();
+
+        let not_reproduced = output
+            .found_params
+            .iter()
+            .filter(|x| !reproduced.iter().any(|y| y.name == x.name))
+            .map(|x| x.name.red().to_string())
+            .collect::<Vec<String>>();
+
+        let mut line = format!(
+            "{} {} % {}",
+            output.method.blue(),
+            output.url,
+            reproduced
+                .iter()
+                .map(|x| x.get_colored())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        if !not_reproduced.is_empty() {
+            line += &format!(" (not reproduced: {})", not_reproduced.join(", "));
+        }
+
+        writeln!(io::stdout(), "{}", line).ok();
+    }
+
+    writeln!(
+        io::stdout(),
+        "\n[#] {} of {} found parameters reproduced",
+        reproduced_total,
+        total
+    )
+    .ok();
+
+    Ok(())
+}
+
+async fn reproduce(
+    output: &SavedOutput,
+    replay_proxy: Option<&str>,
+    args: &[String],
+) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+    let mut scan_args = args.to_vec();
+    scan_args.extend(
+        ["-u", output.url.as_str(), "-X", output.method.as_str()]
+            .iter()
+            .map(|x| x.to_string()),
+    );
+
+    if let Some(proxy) = replay_proxy {
+        scan_args.extend(["--replay-proxy", proxy].iter().map(|x| x.to_string()));
+    }
+
+    let mut config = get_config_from(&scan_args)?;
+    config.disable_progress_bar = true;
+
+    let mut request_defaults = RequestDefaults::from_config(&config, output.method.as_str(), output.url.as_str())?;
+
+    if !config.replay_proxy.is_empty() {
+        request_defaults.client = create_client(&config, true)?;
+    }
+
+    // get cookies
+    Request::new(&request_defaults, Vec::new()).send().await?;
+
+    let progress_bar = ProgressBar::hidden();
+
+    Runner::new(&config, &mut request_defaults, &progress_bar, 0)
+        .await?
+        .reproduce(&output.found_params)
+        .await
+}
+
+/// accepts json, json-by-target and jsonl outputs
+pub(crate) fn parse_results(text: &str) -> Result<Vec<SavedOutput>, Box<dyn Error>> {
+    if let Ok(outputs) = serde_json::from_str::<Vec<SavedOutput>>(text) {
+        return Ok(outputs);
+    }
+
+    if let Ok(outputs) = serde_json::from_str::<HashMap<String, Vec<SavedOutput>>>(text) {
+        return Ok(outputs.into_values().flatten().collect());
+    }
+
+    let mut outputs: Vec<SavedOutput> = Vec::new();
+
+    for line in text.lines().filter(|x| !x.trim().is_empty()) {
+        let saved: SavedParameter = serde_json::from_str(line)
+            .map_err(|_| "Unable to parse the results. Use the output of --output-format json, json-by-target or jsonl")?;
+
+        match outputs
+            .iter_mut()
+            .find(|x| x.method == saved.method && x.url == saved.url)
+        {
+            Some(output) => output.found_params.push(saved.found_param),
+            None => outputs.push(SavedOutput {
+                method: saved.method,
+                url: saved.url,
+                found_params: vec![saved.found_param],
+            }),
+        }
+    }
+
+    Ok(outputs)
+}
@@ -0,0 +1,123 @@
+use std::{collections::HashSet, error::Error};
+
+use crate::{
+    network::{request::Request, response::Response},
+    utils::{self, random_line},
+};
+
+use super::runner::Runner;
+
+/// shapes of parameter names (and path segments) that can't exist but look like the ones from wordlists.
+/// {} is replaced with a random string
+const CALIBRATION_PROBES: &[&str] = &["admin{}", "{}_id", "is_{}", "{}[]", "{}.php", "_{}"];
+
+/// the change that nonexistent parameters cause as well
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wildcard {
+    pub code: u16,
+
+    /// diffs with the names of the parameters removed
+    pub diffs: Vec<String>,
+}
+
+impl<'a> Runner<'a> {
+    /// sends nonexistent parameters of different shapes and remembers the changes they cause.
+    /// catch-all pages (soft 404s, pages for any name with a specific prefix, ..) are ignored afterward
+    pub(super) async fn calibrate(&mut self) -> Result<(), Box<dyn Error>> {
+        for probe in CALIBRATION_PROBES {
+            let name = probe.replace("{}", &random_line(8));
+
+            let response = Request::new(&self.request_defaults, vec![name.clone()])
+                .send()
+                .await?;
+
+            let (is_code_diff, diffs) = response.compare(&self.initial_response, &self.diffs)?;
+
+            if !is_code_diff && diffs.is_empty() {
+                continue;
+            }
+
+            let wildcard = Wildcard {
+                code: response.code,
+                diffs: strip_names(diffs, &[name]),
+            };
+
+            if !self.wildcards.contains(&wildcard) {
+                self.wildcards.push(wildcard);
+            }
+        }
+
+        if !self.wildcards.is_empty() {
+            utils::info(
+                self.config,
+                self.id,
+                self.progress_bar,
+                "~",
+                format!("Nonexistent parameters change the page in {} way(s). Such changes are ignored", self.wildcards.len()),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// whether the response changed in the same way as with nonexistent parameters
+    pub(super) fn is_wildcard(
+        &self,
+        response: &Response,
+        params: &[String],
+        diffs: &[String],
+    ) -> Result<bool, Box<dyn Error>> {
+        if self.wildcards.is_empty() || !self.wildcards.iter().any(|x| x.code == response.code) {
+            return Ok(false);
+        }
+
+        let (_, new_diffs) = response.compare(&self.initial_response, diffs)?;
+
+        let wildcard = Wildcard {
+            code: response.code,
+            diffs: strip_names(new_diffs, params),
+        };
+
+        Ok(self.wildcards.contains(&wildcard))
+    }
+}
+
+/// removes the names of the parameters from the diffs, because catch-all pages often reflect them.
+/// only whole tokens are removed, so a name doesn't cut parts out of longer words
+pub(super) fn strip_names(diffs: Vec<String>, params: &[String]) -> Vec<String> {
+    let names: HashSet<&str> = params
+        .iter()
+        .filter_map(|x| x.split('=').next())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    let mut diffs: Vec<String> = diffs.iter().map(|diff| strip_tokens(diff, &names)).collect();
+
+    diffs.sort();
+    diffs
+}
+
+/// characters that can't be a part of the reflected name
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || "\"'`<>&=?/\\;:,(){}#".contains(c)
+}
+
+fn strip_tokens(diff: &str, names: &HashSet<&str>) -> String {
+    let mut stripped = String::with_capacity(diff.len());
+    let mut rest = diff;
+
+    while !rest.is_empty() {
+        let (token, tail) = rest.split_at(rest.find(is_separator).unwrap_or(rest.len()));
+
+        if !names.contains(token) {
+            stripped.push_str(token);
+        }
+
+        // the separator itself is kept
+        let separator_len = tail.chars().next().map_or(0, char::len_utf8);
+        stripped.push_str(&tail[..separator_len]);
+        rest = &tail[separator_len..];
+    }
+
+    stripped
+}
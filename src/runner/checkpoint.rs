@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::utils::FoundParameter;
+
+/// how often the checkpoint is written to the disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// state of interrupted scans
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// "METHOD URL" -> state
+    pub targets: HashMap<String, TargetState>,
+}
+
+/// state of the wordlist checking for a single url:method pair
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TargetState {
+    /// parameters that were already checked
+    pub checked_params: Vec<String>,
+
+    /// collected page's diffs
+    pub diffs: Vec<String>,
+
+    /// unexpected response codes counters
+    pub green_lines: HashMap<String, usize>,
+
+    pub found_params: Vec<FoundParameter>,
+}
+
+impl Checkpoint {
+    pub fn load(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let checkpoint = fs::read_to_string(filename)
+            .map_err(|err| format!("Unable to read the checkpoint file {}: {}", filename, err))?;
+
+        Ok(serde_json::from_str(&checkpoint)?)
+    }
+}
+
+/// shares the checkpoint between runners and periodically writes it to the file
+#[derive(Debug)]
+pub struct CheckpointStore {
+    filename: String,
+
+    checkpoint: Mutex<Checkpoint>,
+
+    last_save: Mutex<Instant>,
+}
+
+impl CheckpointStore {
+    pub fn new<S: Into<String>>(filename: S, checkpoint: Checkpoint) -> Self {
+        Self {
+            filename: filename.into(),
+            checkpoint: Mutex::new(checkpoint),
+            last_save: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// returns the saved state of the target
+    pub fn get(&self, key: &str) -> Option<TargetState> {
+        self.checkpoint.lock().targets.get(key).cloned()
+    }
+
+    /// changes the state of the target (creates an empty one if needed)
+    pub fn update<F: FnOnce(&mut TargetState)>(&self, key: &str, f: F) {
+        let mut checkpoint = self.checkpoint.lock();
+        f(checkpoint.targets.entry(key.to_string()).or_default());
+    }
+
+    /// writes the checkpoint to the file.
+    /// the temporary file is used in order not to corrupt the checkpoint in case of a crash during writing
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let serialized = serde_json::to_string(&*self.checkpoint.lock())?;
+
+        let tmp_filename = format!("{}.tmp", self.filename);
+        fs::write(&tmp_filename, serialized)?;
+        fs::rename(&tmp_filename, &self.filename)?;
+
+        *self.last_save.lock() = Instant::now();
+
+        Ok(())
+    }
+
+    /// writes the checkpoint to the file in case it wasn't written for a while
+    pub fn save_if_due(&self) -> Result<(), Box<dyn Error>> {
+        if self.last_save.lock().elapsed() >= SAVE_INTERVAL {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+}
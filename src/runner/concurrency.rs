@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+/// how many 5xx responses in a row are considered as a burst
+const SERVER_ERRORS_BURST: usize = 3;
+
+/// controls the amount of concurrent requests per url.
+/// raises the limit while the target responds fast and without errors
+/// and backs off on timeouts, 429 and bursts of 5xx responses
+#[derive(Debug)]
+pub struct ConcurrencyController {
+    state: Mutex<State>,
+
+    /// the upper bound of the limit
+    max: usize,
+}
+
+#[derive(Debug)]
+struct State {
+    /// the current amount of allowed concurrent requests
+    limit: usize,
+
+    /// the amount of currently running requests
+    in_flight: usize,
+
+    /// successful responses since the last change of the limit
+    successes: usize,
+
+    /// 5xx responses in a row
+    server_errors: usize,
+
+    /// exponential moving average of response times
+    latency: f64,
+
+    /// the lowest observed average response time
+    baseline_latency: f64,
+}
+
+impl ConcurrencyController {
+    pub fn new(initial: usize, max: usize) -> Self {
+        let max = max.max(1);
+
+        Self {
+            state: Mutex::new(State {
+                limit: initial.max(1).min(max),
+                in_flight: 0,
+                successes: 0,
+                server_errors: 0,
+                latency: 0.,
+                baseline_latency: 0.,
+            }),
+            max,
+        }
+    }
+
+    /// the current amount of allowed concurrent requests
+    pub fn limit(&self) -> usize {
+        self.state.lock().limit
+    }
+
+    /// the upper bound of concurrent requests
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// waits until there's a free slot for a request
+    pub async fn acquire(&self) {
+        loop {
+            {
+                let mut state = self.state.lock();
+                if state.in_flight < state.limit {
+                    state.in_flight += 1;
+                    return;
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+    }
+
+    /// frees the slot taken by acquire()
+    pub fn release(&self) {
+        let mut state = self.state.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    /// adjusts the limit based on the response.
+    /// code 0 means that the request failed (usually timeout or connection reset)
+    pub fn record(&self, code: u16, time: u128) {
+        let mut state = self.state.lock();
+
+        if code >= 500 {
+            state.server_errors += 1;
+        } else {
+            state.server_errors = 0;
+        }
+
+        if code == 0 || code == 429 || state.server_errors >= SERVER_ERRORS_BURST {
+            let by = state.limit / 2;
+            state.decrease(by);
+            return;
+        }
+
+        let time = time as f64;
+        if state.latency == 0. {
+            state.latency = time;
+            state.baseline_latency = time;
+        } else {
+            state.latency = state.latency * 0.8 + time * 0.2;
+            state.baseline_latency = state.baseline_latency.min(state.latency);
+        }
+
+        // the target became noticeably slower
+        if state.latency > state.baseline_latency * 3. + 100. {
+            state.decrease(1);
+            return;
+        }
+
+        state.successes += 1;
+
+        // increase the limit only after the whole current window was successful
+        if state.successes >= state.limit && state.limit < self.max {
+            state.limit += 1;
+            state.successes = 0;
+            log::debug!("concurrency limit increased to {}", state.limit);
+        }
+    }
+}
+
+impl State {
+    fn decrease(&mut self, by: usize) {
+        let limit = self.limit.saturating_sub(by.max(1)).max(1);
+
+        if limit != self.limit {
+            log::debug!("concurrency limit decreased to {}", limit);
+        }
+
+        self.limit = limit;
+        self.successes = 0;
+        self.server_errors = 0;
+    }
+}
@@ -0,0 +1,173 @@
+use std::error::Error;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    network::{
+        request::{Request, RequestDefaults},
+        utils::{Headers, InjectionPlace},
+    },
+    utils::{self, progress_style_check_requests, random_line},
+    VALUE_LENGTH,
+};
+
+use super::{
+    pollution::{join_pairs, strip_injection_point},
+    runner::Runner,
+    utils::FoundParameter,
+};
+
+/// ways to send parameters besides the original one
+const ENCODINGS: [&str; 4] = ["query", "urlencoded", "json", "multipart"];
+
+const MULTIPART_BOUNDARY: &str = "----x8FormBoundary7MA4YWxkTrZu0gW";
+
+impl<'a> Runner<'a> {
+    /// resends every found parameter with the other encodings and remembers the ones the parser accepts.
+    /// every parameter is compared with a random parameter sent with the same encoding
+    pub(super) async fn check_content_types(&self, found_params: &mut [FoundParameter]) -> Result<(), Box<dyn Error>> {
+        let current = self.current_encoding();
+        let encodings: Vec<&str> = ENCODINGS.iter().copied().filter(|x| Some(*x) != current).collect();
+
+        self.prepare_progress_bar(
+            "content-types",
+            progress_style_check_requests(self.config),
+            encodings.len() * (found_params.len() + 2),
+        );
+
+        for encoding in encodings {
+            // learn how the page behaves with the encoding
+            let control_params = vec![(random_line(VALUE_LENGTH), random_line(VALUE_LENGTH))];
+            let control_defaults = self.encoded_defaults(encoding, &control_params);
+            let control = Request::new(&control_defaults, Vec::new()).send().await?;
+            self.progress_bar.inc(1);
+
+            let check_params = vec![(random_line(VALUE_LENGTH), random_line(VALUE_LENGTH))];
+            let check_defaults = self.encoded_defaults(encoding, &check_params);
+            let (_, diffs) = Request::new(&check_defaults, Vec::new())
+                .send()
+                .await?
+                .compare(&control, &[])?;
+            self.progress_bar.inc(1);
+
+            let random_values: Vec<&String> = control_params
+                .iter()
+                .chain(check_params.iter())
+                .flat_map(|(k, v)| vec![k, v])
+                .collect();
+
+            for found_param in found_params.iter_mut() {
+                self.progress_bar.inc(1);
+
+                let (name, value) = found_param.get();
+                let request_defaults = self.encoded_defaults(encoding, &[(name.clone(), value.clone())]);
+                let response = Request::new(&request_defaults, Vec::new()).send().await?;
+
+                let (is_code_diff, new_diffs) = response.compare(&control, &diffs)?;
+
+                // reflections of the parameters aren't considered
+                let new_diffs: Vec<String> = new_diffs
+                    .into_iter()
+                    .filter(|x| !x.contains(&value) && !random_values.iter().any(|y| x.contains(y.as_str())))
+                    .collect();
+
+                if !is_code_diff && new_diffs.is_empty() {
+                    continue;
+                }
+
+                utils::info(
+                    self.config,
+                    self.id,
+                    self.progress_bar,
+                    "encoding",
+                    format!("{}: accepted as {}", name, encoding),
+                );
+
+                found_param.encodings.push(encoding.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the encoding of the original request. None in case the body is xml
+    fn current_encoding(&self) -> Option<&'static str> {
+        if self.request_defaults.injection_place == InjectionPlace::Path {
+            return Some("query");
+        }
+
+        let content_type = self
+            .request_defaults
+            .custom_headers
+            .get_value_case_insensitive("content-type")
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if self.request_defaults.is_json {
+            Some("json")
+        } else if self.request_defaults.is_xml {
+            None
+        } else if content_type.contains("multipart/form-data") {
+            Some("multipart")
+        } else {
+            Some("urlencoded")
+        }
+    }
+
+    /// returns request defaults that send only the given parameters with the encoding.
+    /// the original query is kept, while the original body is replaced
+    fn encoded_defaults(&self, encoding: &str, params: &[(String, String)]) -> RequestDefaults {
+        let mut request_defaults = self.request_defaults.clone();
+        let path = strip_injection_point(&request_defaults.path);
+
+        request_defaults
+            .custom_headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case("content-type"));
+
+        let (path, body, content_type) = match encoding {
+            "query" => {
+                let joiner = if path.contains('?') { '&' } else { '?' };
+                (format!("{}{}{}", path, joiner, join_pairs(params)), String::new(), None)
+            }
+            "urlencoded" => (
+                path,
+                join_pairs(params),
+                Some("application/x-www-form-urlencoded".to_string()),
+            ),
+            "json" => {
+                let object: Map<String, Value> = params
+                    .iter()
+                    .map(|(k, v)| (k.to_owned(), Value::String(v.to_owned())))
+                    .collect();
+
+                (path, Value::Object(object).to_string(), Some("application/json".to_string()))
+            }
+            _ => {
+                // the same boundary for all the requests, so it doesn't cause diffs in case it's reflected
+                let boundary = MULTIPART_BOUNDARY;
+                let mut body = String::new();
+
+                for (k, v) in params {
+                    body += &format!(
+                        "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                        boundary, k, v
+                    );
+                }
+                body += &format!("--{}--\r\n", boundary);
+
+                (path, body, Some(format!("multipart/form-data; boundary={}", boundary)))
+            }
+        };
+
+        request_defaults.path = path;
+        request_defaults.body = body;
+
+        if let Some(content_type) = content_type {
+            request_defaults
+                .custom_headers
+                .push(("Content-Type".to_string(), content_type));
+        }
+
+        request_defaults
+    }
+}
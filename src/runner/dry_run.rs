@@ -0,0 +1,120 @@
+use std::io::{self, Write};
+
+use colored::Colorize;
+
+use crate::network::utils::InjectionPlace;
+
+use super::runner::Runner;
+
+impl<'a> Runner<'a> {
+    /// prints what the scan of the url would look like: the amount of requests, how long it would take,
+    /// where the parameters are injected and whether the page is stable enough.
+    /// called instead of checking parameters with --dry-run
+    pub(super) fn print_plan(&self, params: &[String]) {
+        let max = self.max.max(1);
+        let chunks = params.len().div_ceil(max);
+
+        let mut msg = format!(
+            "  parameters: {} ({} per request)\n  requests: ~{} (+ verifying of found parameters)\n  duration: ~{}\n  injection: {:?} {}\n  stability: code {}, body {}, reflections {}",
+            params.len(),
+            self.max,
+            chunks,
+            format_duration(chunks as f64 / self.requests_per_second()),
+            self.request_defaults.injection_place,
+            self.injection_points().join(", "),
+            "stable".green(),
+            verdict(self.stable.body),
+            verdict(self.stable.reflections),
+        );
+
+        if self.latency.mean > 0. {
+            msg += &format!(
+                "\n  latency: {:.0}ms (±{:.0}ms)",
+                self.latency.mean, self.latency.stddev
+            );
+        }
+
+        if !self.diffs.is_empty() {
+            msg += &format!("\n  persistent diffs: {}", self.diffs.len());
+        }
+
+        if !self.wildcards.is_empty() {
+            msg += &format!("\n  wildcard responses: {}", self.wildcards.len());
+        }
+
+        if self.config.disable_progress_bar {
+            writeln!(io::stdout(), "{}", msg).ok();
+        } else {
+            self.progress_bar.println(msg);
+        }
+    }
+
+    /// the expected speed of the scan limited by the rate limits, the delay and the response time
+    fn requests_per_second(&self) -> f64 {
+        let defaults = &self.request_defaults;
+
+        let time_per_request = self.latency.mean / 1000. + defaults.delay.as_secs_f64();
+        let mut rps = if time_per_request > 0. {
+            self.concurrency as f64 / time_per_request
+        } else {
+            f64::INFINITY
+        };
+
+        for limiter in defaults.rate_limiter.iter().chain(defaults.host_rate_limiter.iter()) {
+            rps = rps.min(limiter.rps());
+        }
+
+        rps
+    }
+
+    /// the parts of the request that contain the injection point
+    fn injection_points(&self) -> Vec<String> {
+        let defaults = &self.request_defaults;
+
+        let mut points = Vec::new();
+
+        if defaults.path.contains("%s") {
+            points.push(format!("path {}", defaults.path));
+        }
+
+        if defaults.body.contains("%s") {
+            points.push(format!("body {}", defaults.body));
+        }
+
+        for (key, value) in defaults.custom_headers.iter() {
+            if key.contains("%s") || value.contains("%s") {
+                points.push(format!("header {}: {}", key, value));
+            }
+        }
+
+        if points.is_empty() && defaults.injection_place == InjectionPlace::Headers {
+            points.push("new headers".to_string());
+        }
+
+        points
+    }
+}
+
+fn verdict(stable: bool) -> colored::ColoredString {
+    if stable {
+        "stable".green()
+    } else {
+        "unstable".red()
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    if !secs.is_finite() {
+        return "0s".to_string();
+    }
+
+    let secs = secs.ceil() as u64;
+
+    if secs >= 3600 {
+        format!("{}h{}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
@@ -1,84 +1,98 @@
-use std::{cmp, collections::HashMap, error::Error, sync::Arc};
+use std::{
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
-use async_recursion::async_recursion;
-use futures::stream::StreamExt;
 use parking_lot::Mutex;
 
 use crate::{
-    network::request::Request,
-    runner::utils::{FoundParameter, ReasonKind}, utils::progress_style_check_requests,
+    error::{is_permanent, X8Error},
+    network::{graphql::GraphQLErrors, request::{Parameters, Request}},
+    runner::utils::{FoundParameter, ReasonKind, UnstableCodeAction}, utils::{self, progress_style_check_requests},
 };
 
-use super::runner::Runner;
+use super::{
+    runner::Runner,
+    shared::{Chunk, SharedState},
+};
 
-/// impl logic for checking parameters
-impl<'a> Runner<'a> {
-    /// just splits params into two parts and runs check_parameters_recursion for every part
-    async fn repeat(
-        &self,
-        shared_diffs: Arc<Mutex<&'a mut Vec<String>>>,
-        shared_green_lines: Arc<Mutex<&'a mut HashMap<String, usize>>>,
-        shared_found_params: Arc<Mutex<&'a mut Vec<FoundParameter>>>,
-        mut params: Vec<String>,
-        recursion_depth: usize,
-    ) -> Result<(), Box<dyn Error>> {
-        // Prevent stack overflow - limit recursion depth
-        if recursion_depth > 50 {
-            return Ok(());
-        }
-        
-        // Base case: if we have 1 or fewer parameters, no need to split
-        if params.len() <= 1 {
-            return self.check_parameters_recursion(
-                shared_diffs,
-                shared_green_lines,
-                shared_found_params,
-                params,
-                recursion_depth + 1,
-            ).await;
+/// how many times the chunk can be rechecked without being split (or resent due to network errors)
+const MAX_CHUNK_RETRIES: usize = 3;
+
+/// the chunk is put back to the queue after this delay in case the target is unreachable
+const UNREACHABLE_RECHECK_DELAY: Duration = Duration::from_secs(5);
+
+/// the chunk can't be checked right now, so it's put back to the queue after the delay.
+/// rechecks because of rate limits and pauses aren't counted as retries
+#[derive(Debug)]
+struct Recheck {
+    delay: Duration,
+    is_retry: bool,
+}
+
+impl fmt::Display for Recheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The chunk is rechecked in {}s", self.delay.as_secs())
+    }
+}
+
+impl Error for Recheck {}
+
+/// splits params into two parts that are checked separately
+fn split(params: Parameters) -> Vec<Parameters> {
+    if params.len() <= 1 {
+        return vec![params];
+    }
+
+    let (first_params_part, second_params_part) = params.split_at(params.len() / 2);
+    vec![first_params_part, second_params_part]
+}
+
+/// groups skipped parameters into ranges of the wordlist: [a, b, c, x] -> [a..c, x]
+fn skipped_ranges(skipped_params: &[String], params: &[String]) -> Vec<String> {
+    let skipped_params: HashSet<&String> = skipped_params.iter().collect();
+    let mut ranges: Vec<(&String, &String)> = Vec::new();
+    let mut prev_skipped = false;
+
+    for param in params {
+        let is_skipped = skipped_params.contains(param);
+
+        if is_skipped {
+            match ranges.last_mut() {
+                Some(range) if prev_skipped => range.1 = param,
+                _ => ranges.push((param, param)),
+            }
         }
-        
-        let second_params_part = params.split_off(params.len() / 2);
-
-        self.check_parameters_recursion(
-            Arc::clone(&shared_diffs),
-            Arc::clone(&shared_green_lines),
-            Arc::clone(&shared_found_params),
-            params,
-            recursion_depth + 1,
-        )
-        .await?;
-        self.check_parameters_recursion(
-            shared_diffs,
-            shared_green_lines,
-            shared_found_params,
-            second_params_part,
-            recursion_depth + 1,
-        )
-        .await
+
+        prev_skipped = is_skipped;
     }
 
-    #[async_recursion(?Send)]
-    async fn check_parameters_recursion(
+    ranges
+        .into_iter()
+        .map(|(first, last)| if first == last { first.to_owned() } else { format!("{}..{}", first, last) })
+        .collect()
+}
+
+/// impl logic for checking parameters
+impl<'a> Runner<'a> {
+    /// checks the chunk of parameters and returns the chunks that need to be checked next
+    async fn check_chunk(
         &self,
-        shared_diffs: Arc<Mutex<&'a mut Vec<String>>>,
-        shared_green_lines: Arc<Mutex<&'a mut HashMap<String, usize>>>,
-        shared_found_params: Arc<Mutex<&'a mut Vec<FoundParameter>>>,
-        mut params: Vec<String>,
-        recursion_depth: usize,
-    ) -> Result<(), Box<dyn Error>> {
-        // Base case: if no parameters left, nothing to check
+        shared: &SharedState<'_>,
+        mut params: Parameters,
+    ) -> Result<Vec<Parameters>, Box<dyn Error>> {
         if params.is_empty() {
-            return Ok(());
-        }
-        
-        // Prevent stack overflow - limit recursion depth  
-        if recursion_depth > 50 {
-            return Ok(());
+            return Ok(Vec::new());
         }
-        
-        let request = Request::new(&self.request_defaults, params.clone());
-        let mut response = match request.clone().wrapped_send().await {
+
+        let mut response = match Request::new(&self.request_defaults, params.clone())
+            .wrapped_send()
+            .await
+        {
             Ok(val) => val,
             Err(_) => match Request::new_random(&self.request_defaults, params.len())
                 .send()
@@ -86,20 +100,82 @@ impl<'a> Runner<'a> {
             {
                 //we don't return the actual response because it was a random request without original parameters
                 //instead we return an empty response from the original request
-                Ok(_) => request.empty_response(),
-                //looks like either server or network is down
-                Err(err) => Err(format!("Unable to reach server ({})", err))?,
+                Ok(_) => Request::new(&self.request_defaults, params.clone()).empty_response(),
+                //looks like either server or network is down. The chunk is rechecked later
+                Err(err) => {
+                    log::debug!("Unable to reach server ({})", err);
+                    Err(Recheck { delay: UNREACHABLE_RECHECK_DELAY, is_retry: true })?
+                }
             },
         };
 
+        if let Some(controller) = &self.concurrency_controller {
+            controller.record(response.code, response.time);
+        }
+
+        // the target still rate limits requests, so the chunk is rechecked later
+        // instead of treating the code as the page's change
+        if let Some(delay) = response.rate_limit_delay() {
+            if response.code != self.initial_response.code {
+                Err(Recheck { delay, is_retry: false })?;
+            }
+        }
+
+        // responses that don't fit --match-* and --filter-* can't reveal parameters
+        if !self.request_defaults.matchers.is_interesting(&response, &self.initial_response) {
+            return Ok(Vec::new());
+        }
+
+        // nonexistent parameters change the page in the same way.
+        // the change may still hide an existing parameter within the chunk, so only single parameters are dropped
+        if self.is_wildcard(&response, &params, &shared.diffs().await)? {
+            if params.len() > 1 {
+                return Ok(split(params));
+            }
+            return Ok(Vec::new());
+        }
+
+        // graphql servers explicitly report unknown names, so there's no need to compare pages
+        if self.config.graphql.is_some() {
+            if let Some(errors) = GraphQLErrors::parse(&response.text) {
+                if errors.syntax_error {
+                    Err(X8Error::Config(format!(
+                        "{} GraphQL syntax error. Check the query template",
+                        self.request_defaults.url()
+                    )))?;
+                }
+
+                // nothing was reported as unknown, but the query may fail for another reason
+                // (like a missing required argument), so the names are checked in smaller groups
+                if errors.unknown.is_empty() && params.len() > 1 {
+                    return Ok(split(params));
+                }
+
+                let accepted = params
+                    .iter()
+                    .filter(|x| !errors.unknown.contains(x.split('=').next().unwrap()))
+                    .chain(errors.suggestions.iter());
+
+                let mut found_params = shared.found_params_mut().await;
+                for name in accepted {
+                    if found_params.iter().any(|x| x.name == *name) {
+                        continue;
+                    }
+
+                    self.add_found_param(&mut found_params, &response, name, &[], None, ReasonKind::GraphQL)?;
+                }
+
+                return Ok(Vec::new());
+            }
+        }
+
         if self.stable.reflections {
             response.fill_reflected_parameters(&self.initial_response);
 
             let (reflected_parameter, repeat) = response.proceed_reflected_parameters();
 
             if let Some(reflected_parameter) = reflected_parameter {
-
-                let mut found_params = shared_found_params.lock();
+                let mut found_params = shared.found_params_mut().await;
                 if !found_params.iter().any(|x| x.name == reflected_parameter) {
                     let mut kind = ReasonKind::Reflected;
                     // explained in response.proceed_reflected_parameters() method
@@ -108,22 +184,34 @@ impl<'a> Runner<'a> {
                         kind = ReasonKind::NotReflected;
                     }
 
-                    found_params.push(FoundParameter::new(
+                    // reflections within headers are worth checking for header injections and open redirects
+                    let reflected_headers = response
+                        .header_reflected_parameters
+                        .get(reflected_parameter)
+                        .map(|x| x.join(", "));
+
+                    let found_param = self.found_param(
                         reflected_parameter,
-                        &vec![],
-                        response.code,
-                        response.text.len(),
+                        &reflected_headers
+                            .iter()
+                            .map(|x| format!("headers: {}", x))
+                            .collect::<Vec<String>>(),
+                        &response,
                         kind.clone(),
-                    ));
+                    );
+                    self.stream_found_parameter(&found_param)?;
+                    found_params.push(found_param);
                     drop(found_params);
 
                     // remove found parameter from the list
-                    params.remove(
-                        params
+                    let mut remaining_params = params.to_vec();
+                    remaining_params.remove(
+                        remaining_params
                             .iter()
                             .position(|x| *x == reflected_parameter)
                             .unwrap(),
                     );
+                    params = remaining_params.into();
 
                     response.write_and_save(
                         self.id,
@@ -131,26 +219,21 @@ impl<'a> Runner<'a> {
                         &self.initial_response,
                         kind,
                         reflected_parameter,
-                        None,
+                        reflected_headers.as_deref(),
                         self.progress_bar,
                     )?;
                 }
             }
 
             if repeat {
-                return self
-                    .repeat(
-                        shared_diffs,
-                        shared_green_lines,
-                        shared_found_params,
-                        params.clone(),
-                        recursion_depth + 1,
-                    )
-                    .await;
+                return Ok(split(params));
             }
 
-            if self.config.reflected_only {
-                return Ok(());
+            // --isolate-server-errors still looks for the parameter that breaks the page
+            if self.config.reflected_only
+                && !(self.request_defaults.matchers.server_errors && response.is_server_error(&self.initial_response))
+            {
+                return Ok(Vec::new());
             }
         }
 
@@ -159,12 +242,12 @@ impl<'a> Runner<'a> {
             // helps to notice whether the page's completely changed
             // like, for example, when the IP got banned by the server
             {
-                let mut green_lines = shared_green_lines.lock();
+                let mut green_lines = shared.green_lines().await;
                 match green_lines.get(&response.code.to_string()) {
                     Some(val) => {
                         let n_val = *val;
                         green_lines.insert(response.code.to_string(), n_val + 1);
-                        if n_val > 50 {
+                        if n_val > self.config.unstable_code_thresholds.get(response.code) {
                             drop(green_lines);
 
                             let check_response =
@@ -173,14 +256,37 @@ impl<'a> Runner<'a> {
                                     .await
                                     .unwrap_or_default();
 
+                            shared.green_lines().await.insert(response.code.to_string(), 0);
+
                             if check_response.code != self.initial_response.code {
-                                return Err(format!(
-                                    "{} The page became unstable (code)",
-                                    self.request_defaults.url()
-                                ))?;
-                            } else {
-                                let mut green_lines = shared_green_lines.lock();
-                                green_lines.insert(response.code.to_string(), 0);
+                                match self.config.unstable_code_action {
+                                    UnstableCodeAction::Abort => {
+                                        self.unstable.store(true, Ordering::SeqCst);
+
+                                        return Err(X8Error::Stability(format!(
+                                            "{} The page became unstable (code {})",
+                                            self.request_defaults.url(),
+                                            response.code
+                                        )))?;
+                                    }
+                                    UnstableCodeAction::Pause(duration) => {
+                                        utils::info(
+                                            self.config,
+                                            self.id,
+                                            self.progress_bar,
+                                            "~",
+                                            format!(
+                                                "The page became unstable (code {}). Pausing requests for {}s",
+                                                response.code,
+                                                duration.as_secs()
+                                            ),
+                                        );
+
+                                        self.request_defaults.pause.pause_for(duration);
+                                        Err(Recheck { delay: duration, is_retry: false })?;
+                                    }
+                                    UnstableCodeAction::Ignore => (),
+                                }
                             }
                         }
                     }
@@ -192,53 +298,32 @@ impl<'a> Runner<'a> {
 
             // there's only 1 parameter left that's changing the page's code
             if params.len() == 1 {
-                response.write_and_save(
-                    self.id,
-                    self.config,
-                    &self.initial_response,
-                    ReasonKind::Code,
+                let kind = if response.is_server_error(&self.initial_response) {
+                    ReasonKind::ServerError
+                } else {
+                    ReasonKind::Code
+                };
+
+                self.add_found_param(
+                    &mut *shared.found_params_mut().await,
+                    &response,
                     &params[0],
+                    &[format!("{} -> {}", &self.initial_response.code, response.code)],
                     None,
-                    self.progress_bar,
+                    kind,
                 )?;
-
-                let mut found_params = shared_found_params.lock();
-                found_params.push(FoundParameter::new(
-                    &params[0],
-                    &vec![format!(
-                        "{} -> {}",
-                        &self.initial_response.code, response.code
-                    )],
-                    response.code,
-                    response.text.len(),
-                    ReasonKind::Code,
-                ));
             // there's more than 1 parameter left - split the list and repeat
             } else {
-                return self
-                    .repeat(
-                        shared_diffs,
-                        shared_green_lines,
-                        shared_found_params,
-                        params.clone(),
-                        recursion_depth + 1,
-                    )
-                    .await;
+                return Ok(split(params));
             }
         } else if self.stable.body {
             // check whether the new_diff has at least 1 unique diff compared to stored diffs
-            let (_, new_diffs) = {
-                let diffs = shared_diffs.lock();
-                response.compare(&self.initial_response, &diffs)?
-            };
+            let (_, new_diffs) = response.compare(&self.initial_response, &shared.diffs().await)?;
 
             // and then make a new request to check whether it's a permament diff or not
             if !new_diffs.is_empty() {
-                if self.config.strict {
-                    let found_params = shared_found_params.lock();
-                    if found_params.iter().any(|x| x.diffs == new_diffs.join("|")) {
-                        return Ok(());
-                    }
+                if self.config.strict && shared.found_params().await.iter().any(|x| x.diffs == new_diffs.join("|")) {
+                    return Ok(Vec::new());
                 }
 
                 // just request the page with random parameters and store it's diffs
@@ -247,116 +332,382 @@ impl<'a> Runner<'a> {
                     .send()
                     .await?;
 
-                let (_, tmp_diffs) = {
-                    let diffs = shared_diffs.lock();
-                    tmp_resp.compare(&self.initial_response, &diffs)?
-                };
-
-                let mut diffs = shared_diffs.lock();
-                for diff in tmp_diffs {
-                    diffs.push(diff);
-                }
+                let (_, tmp_diffs) = tmp_resp.compare(&self.initial_response, &shared.diffs().await)?;
+                shared.add_diffs(tmp_diffs).await;
             }
 
-            let diffs = shared_diffs.lock();
+            let diffs = shared.diffs().await;
+
+            // parameters that change only headers or cookies are reported separately
+            let kind = ReasonKind::from_diffs(new_diffs.iter().filter(|x| !diffs.contains(x)));
 
             // check whether the page still(after making a random request and storing it's diffs) has an unique diffs
             for diff in new_diffs.iter() {
                 if !diffs.contains(diff) {
-                    let mut found_params = shared_found_params.lock();
+                    let mut found_params = shared.found_params_mut().await;
 
                     // there's only one parameter left that changing the page
                     if params.len() == 1 && !found_params.iter().any(|x| x.name == params[0]) {
                         // repeating --strict checks. We need to do it twice because we're usually running in parallel
                         // and some parameters may be found after the first check
                         if self.config.strict && found_params.iter().any(|x| x.diffs == new_diffs.join("|")) {
-                            return Ok(());
+                            return Ok(Vec::new());
                         }
 
-                        response.write_and_save(
-                            self.id,
-                            self.config,
-                            &self.initial_response,
-                            ReasonKind::Text,
-                            &params[0],
-                            Some(diff),
-                            self.progress_bar,
-                        )?;
-
-                        found_params.push(FoundParameter::new(
-                            &params[0],
-                            &new_diffs,
-                            response.code,
-                            response.text.len(),
-                            ReasonKind::Text,
-                        ));
-                        break;
+                        self.add_found_param(&mut found_params, &response, &params[0], &new_diffs, Some(diff), kind)?;
+                        return Ok(Vec::new());
                     // we don't know what parameter caused the difference in response yet
                     // so we are repeating
                     } else {
-                        drop(diffs);
-                        drop(found_params);
-                        return self
-                            .repeat(
-                                shared_diffs,
-                                shared_green_lines,
-                                shared_found_params,
-                                params.clone(),
-                                recursion_depth + 1,
-                            )
-                            .await;
+                        return Ok(split(params));
                     }
                 }
             }
         }
 
-        Ok(())
+        // the user script decides on its own whether the response is a sign of the parameter
+        if let Some(script) = &self.request_defaults.script {
+            if !params.is_empty() && script.is_interesting(&response, &self.initial_response) {
+                // the same amount of random parameters shouldn't be interesting as well
+                let random_response = Request::new_random(&self.request_defaults, params.len())
+                    .send()
+                    .await?;
+
+                if script.is_interesting(&random_response, &self.initial_response) {
+                    return Ok(Vec::new());
+                }
+
+                if params.len() > 1 {
+                    return Ok(split(params));
+                }
+
+                let mut found_params = shared.found_params_mut().await;
+                if !found_params.iter().any(|x| x.name == params[0]) {
+                    self.add_found_param(&mut found_params, &response, &params[0], &[], None, ReasonKind::Script)?;
+                }
+
+                return Ok(Vec::new());
+            }
+        }
+
+        // the response didn't change but took noticeably more time
+        if self.config.time_based
+            && !params.is_empty()
+            && self.latency.is_slow(response.time, self.config.time_sigma)
+        {
+            if params.len() > 1 {
+                return Ok(split(params));
+            }
+
+            if shared.found_params().await.iter().any(|x| x.name == params[0]) {
+                return Ok(Vec::new());
+            }
+
+            if self.confirm_time_shift(&params[0]).await? {
+                self.add_found_param(
+                    &mut *shared.found_params_mut().await,
+                    &response,
+                    &params[0],
+                    &[format!("{}ms -> {}ms", self.latency.mean as u128, response.time)],
+                    None,
+                    ReasonKind::Time,
+                )?;
+            }
+        }
+
+        Ok(Vec::new())
     }
 
-    /// check parameters in a loop chunk by chunk
+    /// check parameters in a loop chunk by chunk.
+    /// in case checkpoint is true and --checkpoint or --resume is used -- the progress is saved and restored
     pub async fn check_parameters(
         &self,
         params: &Vec<String>,
+        checkpoint: bool,
     ) -> Result<(Vec<String>, Vec<FoundParameter>), Box<dyn Error>> {
+        let checkpoint_store = if checkpoint {
+            self.config.checkpoint.as_ref()
+        } else {
+            None
+        };
+        let checkpoint_key = self.checkpoint_key();
+
+        let mut diffs = self.diffs.clone();
+        let mut green_lines = HashMap::new();
+        let mut found_params = Vec::new();
+
+        // restore the saved state and skip already checked parameters
+        let params: Vec<String> = match checkpoint_store.and_then(|x| x.get(&checkpoint_key)) {
+            Some(state) => {
+                for diff in state.diffs {
+                    if !diffs.contains(&diff) {
+                        diffs.push(diff);
+                    }
+                }
+                green_lines = state.green_lines;
+                found_params = state.found_params;
+
+                let checked_params: HashSet<&String> = state.checked_params.iter().collect();
+
+                let params: Vec<String> = params
+                    .iter()
+                    .filter(|x| !checked_params.contains(x))
+                    .cloned()
+                    .collect();
+
+                utils::info(
+                    self.config,
+                    self.id,
+                    self.progress_bar,
+                    "checkpoint",
+                    format!(
+                        "resuming with {} unchecked parameters and {} found parameters",
+                        params.len(),
+                        found_params.len()
+                    ),
+                );
+
+                params
+            }
+            None => params.clone(),
+        };
+
+        if params.is_empty() {
+            return Ok((diffs, found_params));
+        }
+
         let max = cmp::min(self.max, params.len());
 
         // the amount of requests needed for process all the parameters
         let all = params.len() / max;
 
         // change and reset the progress bar
-        self.prepare_progress_bar(progress_style_check_requests(self.config), all + 1);
+        self.prepare_progress_bar("parameters", progress_style_check_requests(self.config), all + 1);
+
+        // all the chunks share the same list of parameters
+        let params: Parameters = params.into();
+        let roots: Vec<Parameters> = (0..params.len())
+            .step_by(max)
+            .map(|start| params.slice(start..cmp::min(start + max, params.len())))
+            .collect();
+        self.config.metrics.add_queued(roots.len());
+
+        // chunks are split into smaller ones and added back to the queue until the changing parameters are found
+        let queue: VecDeque<Chunk> = roots
+            .iter()
+            .enumerate()
+            .map(|(root, chunk)| Chunk {
+                params: chunk.clone(),
+                root,
+                retries: 0,
+            })
+            .collect();
+
+        // wrap the variables to share them between workers
+        let shared_state = SharedState::new(&self.config.metrics, diffs, green_lines, found_params, queue);
+
+        // (the amount of unchecked chunks, whether any of the chunks failed) for every initial chunk
+        let roots_state: Mutex<Vec<(usize, bool)>> = Mutex::new(vec![(1, false); roots.len()]);
+
+        // the amount of chunks that are being checked right now
+        let in_progress = AtomicUsize::new(0);
+
+        // wrong options and results that can't be written stop the check
+        let permanent_error: Mutex<Option<Box<dyn Error>>> = Mutex::new(None);
+
+        // with --adaptive-concurrency the actual amount of concurrent requests is limited by the controller
+        let workers = match &self.concurrency_controller {
+            Some(controller) => controller.max(),
+            None => self.concurrency,
+        };
 
-        // wrap the variables to share them between futures
-        let mut diffs = self.diffs.clone();
-        let mut green_lines = HashMap::new();
-        let mut found_params = Vec::new();
+        let (roots, roots_state, in_progress, permanent_error) = (&roots, &roots_state, &in_progress, &permanent_error);
+        let shared = &shared_state;
+        let checkpoint_key = &checkpoint_key;
+
+        futures::future::join_all((0..workers).map(move |_| async move {
+            loop {
+                // the rest of the queue is left unchecked, the chunks in progress are finished
+                if self.config.budget.is_exhausted()
+                    || self.unstable.load(Ordering::SeqCst)
+                    || permanent_error.lock().is_some()
+                {
+                    break;
+                }
+
+                let chunk = {
+                    let mut queue = shared.queue().await;
+                    let chunk = queue.pop_front();
+                    if chunk.is_some() {
+                        in_progress.fetch_add(1, Ordering::SeqCst);
+                        self.config.metrics.remove_queued(1);
+                    }
+                    chunk
+                };
+
+                let Chunk { params, root, retries } = match chunk {
+                    Some(chunk) => chunk,
+                    // other workers can still add new chunks to the queue
+                    None if in_progress.load(Ordering::SeqCst) != 0 => {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                        continue;
+                    }
+                    None => break,
+                };
 
-        let shared_diffs = Arc::new(Mutex::new(&mut diffs));
-        let shared_green_lines = Arc::new(Mutex::new(&mut green_lines));
-        let shared_found_params = Arc::new(Mutex::new(&mut found_params));
+                let result = if retries > MAX_CHUNK_RETRIES {
+                    Err("Unable to check the chunk".into())
+                } else {
+                    if let Some(controller) = &self.concurrency_controller {
+                        controller.acquire().await;
+                    }
+
+                    let result = self
+                        .check_chunk(shared, params.clone())
+                        .await;
+
+                    if let Some(controller) = &self.concurrency_controller {
+                        controller.release();
+                    }
+
+                    result
+                };
+
+                // the chunk is put back to the queue once the pause or the recheck delay is over
+                let result = match result {
+                    Err(err) => match err.downcast::<Recheck>() {
+                        Ok(recheck) => {
+                            tokio::time::sleep(recheck.delay).await;
+
+                            let retries = if recheck.is_retry { retries + 1 } else { retries };
+                            Ok(vec![(params.clone(), retries)])
+                        }
+                        Err(err) => Err(err),
+                    },
+                    Ok(chunks) => Ok(chunks
+                        .into_iter()
+                        .map(|new_params| {
+                            // the chunk wasn't split, so it's a recheck
+                            let retries = if new_params.len() >= params.len() {
+                                retries + 1
+                            } else {
+                                0
+                            };
+                            (new_params, retries)
+                        })
+                        .collect()),
+                };
+
+                let (chunks, chunk_failed) = match result {
+                    Ok(chunks) => (chunks, false),
+                    Err(err) if is_permanent(err.as_ref()) => {
+                        *permanent_error.lock() = Some(err);
+                        (Vec::new(), true)
+                    }
+                    // the chunk is quarantined, so the rest of the parameters are still checked
+                    Err(err) => {
+                        utils::info(
+                            self.config,
+                            self.id,
+                            self.progress_bar,
+                            "~",
+                            format!("{}. Skipping {} parameter(s)", err, params.len()),
+                        );
+                        shared.skipped_params().await.extend(params.iter().cloned());
+                        (Vec::new(), true)
+                    }
+                };
+
+                // new chunks are counted before they're queued, so the root can't be considered checked in between
+                let (unchecked, failed) = {
+                    let mut roots_state = roots_state.lock();
+
+                    roots_state[root].0 += chunks.len();
+                    roots_state[root].1 |= chunk_failed;
+                    roots_state[root].0 -= 1;
+                    roots_state[root]
+                };
 
-        let _futures_data = futures::stream::iter(params.chunks(max).map(|chunk| {
-            let shared_diffs = Arc::clone(&shared_diffs);
-            let shared_green_lines = Arc::clone(&shared_green_lines);
-            let shared_found_params = Arc::clone(&shared_found_params);
+                if !chunks.is_empty() {
+                    let mut queue = shared.queue().await;
+                    for (new_params, retries) in chunks {
+                        self.config.metrics.add_queued(1);
+                        queue.push_front(Chunk {
+                            params: new_params,
+                            root,
+                            retries,
+                        });
+                    }
+                }
+
+                in_progress.fetch_sub(1, Ordering::SeqCst);
+
+                if unchecked != 0 {
+                    continue;
+                }
 
-            async move {
                 self.progress_bar.inc(1);
 
-                self.check_parameters_recursion(
-                    shared_diffs,
-                    shared_green_lines,
-                    shared_found_params,
-                    chunk.to_vec(),
-                    0,
-                )
-                .await
+                // remember the checked chunk together with the current state
+                if let (Some(store), false) = (checkpoint_store, failed) {
+                    let diffs = shared.diffs().await.to_vec();
+                    let green_lines = shared.green_lines().await.clone();
+                    let found_params = shared.found_params().await.to_vec();
+
+                    store.update(checkpoint_key, |state| {
+                        state.checked_params.extend_from_slice(&roots[root]);
+                        state.diffs = diffs;
+                        state.green_lines = green_lines;
+                        state.found_params = found_params;
+                    });
+
+                    if let Err(err) = store.save_if_due() {
+                        utils::info(self.config, self.id, self.progress_bar, "checkpoint", err);
+                    }
+                }
             }
         }))
-        .buffer_unordered(self.config.concurrency)
-        .collect::<Vec<Result<(), Box<dyn Error>>>>()
         .await;
 
+        // chunks left after the budget was exhausted or the page became unstable
+        let unchecked: Vec<Chunk> = shared_state.queue().await.drain(..).collect();
+        self.config.metrics.remove_queued(unchecked.len());
+
+        if let Some(err) = permanent_error.lock().take() {
+            return Err(err);
+        }
+
+        let (diffs, _, found_params, mut skipped_params) = shared_state.into_inner();
+
+        // the rest of the parameters can't be checked on the unstable page
+        if self.unstable.load(Ordering::SeqCst) {
+            for chunk in unchecked {
+                skipped_params.extend(chunk.params.iter().cloned());
+            }
+        }
+
+        if !skipped_params.is_empty() {
+            utils::info(
+                self.config,
+                self.id,
+                self.progress_bar,
+                "~",
+                format!(
+                    "{} parameter(s) were skipped due to errors: {}",
+                    skipped_params.len(),
+                    skipped_ranges(&skipped_params, &params).join(", ")
+                ),
+            );
+        }
+
+        if let Some(store) = checkpoint_store {
+            if let Err(err) = store.save() {
+                utils::info(self.config, self.id, self.progress_bar, "checkpoint", err);
+            }
+        }
+
+        self.skipped_params.lock().await.append(&mut skipped_params);
+
         Ok((diffs, found_params))
     }
 }
@@ -0,0 +1,122 @@
+use std::error::Error;
+
+use indicatif::ProgressBar;
+
+use crate::{
+    config::structs::Config,
+    network::request::{Request, RequestDefaults},
+    utils,
+};
+
+/// methods to probe the endpoint with
+const METHODS: [&str; 6] = ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+/// headers that are often used to override the method of POST requests
+const METHOD_OVERRIDE_HEADERS: [&str; 3] = [
+    "X-HTTP-Method-Override",
+    "X-HTTP-Method",
+    "X-Method-Override",
+];
+
+/// how many additional baseline requests to make in order to learn the page's dynamic parts
+const LEARN_REQUESTS: usize = 2;
+
+/// probes the url with alternative methods and method override headers
+/// and reports the ones that cause responses different from the default method.
+/// returns methods (without overrides) that caused different responses
+pub async fn discover_methods(
+    config: &Config,
+    url: &str,
+    method: &str,
+    progress_bar: &ProgressBar,
+    id: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let request_defaults = RequestDefaults::from_config(config, method, url)?;
+
+    let initial_response = Request::new(&request_defaults, Vec::new()).send().await?;
+
+    // collect diffs between the same requests so they aren't reported as differences
+    let mut diffs: Vec<String> = Vec::new();
+    for _ in 0..LEARN_REQUESTS {
+        let response = Request::new(&request_defaults, Vec::new()).send().await?;
+        let (_, new_diffs) = response.compare(&initial_response, &diffs)?;
+        diffs.extend(new_diffs);
+    }
+
+    let mut candidates: Vec<(String, Option<&str>)> = METHODS
+        .iter()
+        .filter(|x| !x.eq_ignore_ascii_case(method))
+        .map(|x| (x.to_string(), None))
+        .collect();
+
+    for header in METHOD_OVERRIDE_HEADERS.iter() {
+        for overridden in METHODS.iter().filter(|x| **x != "GET" && **x != "POST") {
+            candidates.push((overridden.to_string(), Some(*header)));
+        }
+    }
+
+    // overridden methods are compared with the plain POST request
+    let post_request_defaults = RequestDefaults::from_config(config, "POST", url)?;
+    let post_response = Request::new(&post_request_defaults, Vec::new()).send().await?;
+
+    let mut interesting_methods = Vec::new();
+
+    for (candidate, header) in candidates {
+        let mut candidate_defaults = match header {
+            Some(_) => RequestDefaults::from_config(config, "POST", url)?,
+            None => RequestDefaults::from_config(config, candidate.as_str(), url)?,
+        };
+
+        if let Some(header) = header {
+            candidate_defaults
+                .custom_headers
+                .push((header.to_string(), candidate.clone()));
+        }
+
+        let response = match Request::new(&candidate_defaults, Vec::new()).send().await {
+            Ok(val) => val,
+            Err(err) => {
+                utils::info(config, id, progress_bar, "method", format!("{}: {}", candidate, err));
+                continue;
+            }
+        };
+
+        let reference_response = if header.is_some() {
+            &post_response
+        } else {
+            &initial_response
+        };
+
+        let (is_code_diff, new_diffs) = response.compare(reference_response, &diffs)?;
+
+        if !is_code_diff && new_diffs.is_empty() {
+            continue;
+        }
+
+        let description = match header {
+            Some(header) => format!("POST + {}: {}", header, candidate),
+            None => candidate.clone(),
+        };
+
+        utils::info(
+            config,
+            id,
+            progress_bar,
+            "method",
+            format!(
+                "{}: code {} -> {}, page {} -> {}",
+                description,
+                reference_response.code(),
+                response.code(),
+                reference_response.text.len(),
+                response.text.len()
+            ),
+        );
+
+        if header.is_none() {
+            interesting_methods.push(candidate);
+        }
+    }
+
+    Ok(interesting_methods)
+}
@@ -1,4 +1,19 @@
+pub mod calibration;
+pub mod checkpoint;
+pub mod concurrency;
+pub mod content_type;
+pub mod dry_run;
 pub mod logic;
+pub mod methods;
+pub mod nuclei;
 pub mod output;
+pub mod pollution;
+pub mod report;
 pub mod runner;
+pub mod scan;
+pub mod shared;
 pub mod utils;
+pub mod warmup;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,196 @@
+use std::error::Error;
+
+use crate::{
+    diff::{changed_lines, COOKIE_DIFF_PREFIX, HEADER_DIFF_PREFIX},
+    network::{request::Request, response::Response},
+    utils::{self, random_line},
+    VALUE_LENGTH,
+};
+
+use super::{
+    runner::Runner,
+    utils::{FoundParameter, ReasonKind},
+};
+
+/// changed lines shorter than this are too common to match on
+const MIN_WORD_LENGTH: usize = 4;
+
+impl<'a> Runner<'a> {
+    /// writes a nuclei template for every found parameter to --nuclei-templates.
+    /// every parameter is sent once more to take the matchers from the actual response
+    pub(super) async fn write_nuclei_templates(&self, found_params: &[FoundParameter]) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.config.nuclei_templates)?;
+
+        for found_param in found_params {
+            let (id, template) = match self.nuclei_template(found_param).await {
+                Ok(val) => val,
+                Err(err) => {
+                    utils::info(
+                        self.config,
+                        self.id,
+                        self.progress_bar,
+                        "~",
+                        format!("Unable to make the nuclei template for {}: {}", found_param.name, err),
+                    );
+                    continue;
+                }
+            };
+
+            std::fs::write(format!("{}/{}.yaml", self.config.nuclei_templates, id), template)?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the id of the template and the template itself
+    async fn nuclei_template(&self, found_param: &FoundParameter) -> Result<(String, String), Box<dyn Error>> {
+        let defaults = &self.request_defaults;
+
+        // the value is replaced within the raw request afterward, so the request is prepared with a plain token
+        let token = random_line(VALUE_LENGTH);
+        let value = match (&found_param.reason_kind, &found_param.value) {
+            (ReasonKind::OutOfBand, _) => "{{interactsh-url}}".to_string(),
+            (_, Some(value)) => value.to_owned(),
+            (_, None) => token.clone(),
+        };
+
+        let mut request = Request::new(defaults, vec![format!("{}={}", found_param.name, token)]);
+        let raw = request
+            .print()
+            .replacen(&format!("Host: {}\n", defaults.host), "Host: {{Hostname}}\n", 1)
+            .replace(&token, &value);
+
+        let matchers = if found_param.reason_kind == ReasonKind::OutOfBand {
+            vec![
+                "      - type: word\n        part: interactsh_protocol\n        words:\n          - \"dns\"\n          - \"http\"\n        condition: or".to_string(),
+            ]
+        } else {
+            let response = Request::new(defaults, vec![format!("{}={}", found_param.name, value)])
+                .send()
+                .await?;
+            self.nuclei_matchers(found_param, &value, &response)
+        };
+
+        let path = defaults.path.split('?').next().unwrap_or_default();
+        let id = nuclei_id(&format!("x8-{}-{}-{}-{}", defaults.host, defaults.method, path, found_param.name));
+
+        let template = format!(
+            "id: {}
+
+info:
+  name: {}
+  author: x8
+  severity: info
+  description: {}
+  reference:
+    - {}
+  tags: x8,hidden-parameter
+
+http:
+  - raw:
+      - |
+{}
+    matchers-condition: and
+    matchers:
+{}
+",
+            id,
+            yaml_string(&format!("Hidden parameter {} ({} {})", found_param.name, defaults.method, path)),
+            yaml_string(found_param.reason_kind.description()),
+            yaml_string(&defaults.url_without_default_port()),
+            raw.lines().map(|x| format!("        {}", x)).collect::<Vec<String>>().join("\n"),
+            matchers.join("\n")
+        );
+
+        Ok((id, template))
+    }
+
+    /// the response code together with the change that revealed the parameter
+    fn nuclei_matchers(&self, found_param: &FoundParameter, value: &str, response: &Response) -> Vec<String> {
+        let mut matchers = vec![format!("      - type: status\n        status:\n          - {}", response.code)];
+
+        match found_param.reason_kind {
+            ReasonKind::Reflected => matchers.push(word_matcher("body", &[value.to_string()])),
+            ReasonKind::Text => {
+                let added = changed_lines(&self.initial_response.text, &response.text)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|x| x.starts_with('+'))
+                    .map(|x| x[1..].trim().to_string())
+                    .find(|x| x.len() >= MIN_WORD_LENGTH && !x.contains(value));
+
+                if let Some(line) = added {
+                    matchers.push(word_matcher("response", &[line]));
+                }
+            }
+            ReasonKind::Header => {
+                let headers: Vec<String> = found_param
+                    .diffs
+                    .split('|')
+                    .filter_map(|x| x.strip_prefix(HEADER_DIFF_PREFIX))
+                    .filter(|x| !x.starts_with('-'))
+                    .map(|x| &x[1..])
+                    .filter_map(|name| {
+                        response
+                            .headers
+                            .iter()
+                            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                            .map(|(k, v)| format!("{}: {}", k, v))
+                    })
+                    .collect();
+
+                if !headers.is_empty() {
+                    matchers.push(word_matcher("header", &headers) + "\n        case-insensitive: true");
+                }
+            }
+            ReasonKind::Cookie => {
+                let cookies: Vec<String> = found_param
+                    .diffs
+                    .split('|')
+                    .filter_map(|x| x.strip_prefix(COOKIE_DIFF_PREFIX))
+                    .filter(|x| !x.starts_with('-'))
+                    .map(|x| format!("{}=", &x[1..]))
+                    .collect();
+
+                if !cookies.is_empty() {
+                    matchers.push(word_matcher("header", &cookies));
+                }
+            }
+            ReasonKind::Time => matchers.push(format!(
+                "      - type: dsl\n        dsl:\n          - \"duration>={:.1}\"",
+                response.time as f64 / 1000. * 0.8
+            )),
+            _ => (),
+        }
+
+        matchers
+    }
+}
+
+fn word_matcher(part: &str, words: &[String]) -> String {
+    format!(
+        "      - type: word\n        part: {}\n        words:\n{}",
+        part,
+        words
+            .iter()
+            .map(|x| format!("          - {}", yaml_string(x)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    )
+}
+
+/// json strings are valid yaml strings, so special characters don't break the template
+fn yaml_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// nuclei ids may contain only lowercase letters, digits and dashes
+fn nuclei_id(s: &str) -> String {
+    let id = s
+        .to_lowercase()
+        .chars()
+        .map(|x| if x.is_ascii_alphanumeric() { x } else { '-' })
+        .collect::<String>();
+
+    id.split('-').filter(|x| !x.is_empty()).collect::<Vec<&str>>().join("-")
+}
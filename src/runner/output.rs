@@ -1,4 +1,5 @@
 use serde::Serialize;
+use serde_json::json;
 use colored::Colorize;
 
 use crate::{
@@ -6,11 +7,11 @@ use crate::{
     network::{
         request::{Request, RequestDefaults},
         response::Response,
-        utils::InjectionPlace,
+        utils::{xml_escape, InjectionPlace},
     },
 };
 
-use super::utils::FoundParameter;
+use super::utils::{FoundParameter, ReasonKind};
 
 #[derive(Debug, Serialize)]
 pub struct RunnerOutput {
@@ -30,6 +31,9 @@ pub struct RunnerOutput {
 
     pub injection_place: InjectionPlace,
 
+    /// parameters of the chunks that kept failing and were left unchecked
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_params: Vec<String>,
 
     /// prepared query with found parameters
     #[serde(skip_serializing)]
@@ -40,10 +44,46 @@ pub struct RunnerOutput {
     pub request: String,
 }
 
+/// a single found parameter together with its target
+/// used for --output-format jsonl where every finding is written as soon as it's found
+#[derive(Debug, Serialize)]
+pub struct FoundParameterLine<'a> {
+    pub method: &'a str,
+
+    pub url: String,
+
+    #[serde(flatten)]
+    pub found_param: &'a FoundParameter,
+}
+
+impl<'a> FoundParameterLine<'a> {
+    pub fn new(request_defaults: &'a RequestDefaults, found_param: &'a FoundParameter) -> Self {
+        Self {
+            method: &request_defaults.method,
+            url: url_without_injection_point(request_defaults),
+            found_param,
+        }
+    }
+
+    /// returns the finding as a standalone json line
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
 pub trait ParseOutputs {
     fn parse_output(&self, config: &Config) -> String;
 }
 
+/// removes the injection point in case it's within the url
+fn url_without_injection_point(request_defaults: &RequestDefaults) -> String {
+    if request_defaults.injection_place == InjectionPlace::Path {
+        request_defaults.url_without_default_port().replace("?%s", "").replace("&%s", "")
+    } else {
+        request_defaults.url_without_default_port()
+    }
+}
+
 impl RunnerOutput {
     pub fn new(
         request_defaults: &RequestDefaults,
@@ -53,40 +93,40 @@ impl RunnerOutput {
         Self {
             method: request_defaults.method.clone(),
             //remove injection point in case the injection point within url
-            url: if request_defaults.injection_place == InjectionPlace::Path {
-                request_defaults.url_without_default_port().replace("?%s", "").replace("&%s", "")
-            } else {
-                request_defaults.url_without_default_port()
-            },
+            url: url_without_injection_point(request_defaults),
             status: initial_response.code,
             size: initial_response.text.len(),
             found_params,
             injection_place: request_defaults.injection_place,
+            skipped_params: Vec::new(),
             query: String::new(),
             request: String::new(),
         }
     }
 
+    pub fn with_skipped_params(mut self, skipped_params: Vec<String>) -> Self {
+        self.skipped_params = skipped_params;
+        self
+    }
+
     /// fills self.request and self.query if they're needed for output
+    /// and curl commands of the found parameters for the report and --porcelain curl
     pub fn prepare(&mut self, config: &Config, request_defaults: &RequestDefaults) {
+        if config.porcelain == "curl" || !config.report.is_empty() {
+            for found_param in self.found_params.iter_mut() {
+                let mut request = Request::new(request_defaults, vec![found_param.to_param()]);
+                request.prepare();
+                found_param.curl = Some(request.curl());
+            }
+        }
+
         if config.output_format == "url" || config.output_format == "request" {
             let mut request = Request::new(
                 request_defaults,
                 self.found_params
                     .iter()
-                    .map(
-                        //in case a parameter has a non standart value (like 'true')
-                        //it should be treated differently (=true) should be added
-                        //otherwise that parameter will have random value
-                        |x| {
-                            if x.value.is_none() {
-                                x.name.to_owned()
-                            } else {
-                                format!("{}={}", x.name, x.value.as_ref().unwrap())
-                            }
-                        },
-                    )
-                    .collect(),
+                    .map(|x| x.to_param())
+                    .collect::<Vec<String>>(),
             );
 
             request.prepare();
@@ -101,6 +141,15 @@ impl RunnerOutput {
 
     /// parses the runner output struct to one specified in config format
     pub fn parse(&self, config: &Config) -> String {
+        if config.porcelain == "curl" {
+            return self
+                .found_params
+                .iter()
+                .filter_map(|x| x.curl.clone())
+                .collect::<Vec<String>>()
+                .join("\n");
+        }
+
         match config.output_format.as_str() {
             "url" => {
                 //make line an url with injection point
@@ -121,27 +170,251 @@ impl RunnerOutput {
 
             "request" => self.request.clone(),
 
+            "jsonl" => self
+                .found_params
+                .iter()
+                .map(|x| {
+                    serde_json::to_string(&FoundParameterLine {
+                        method: &self.method,
+                        url: self.url.clone(),
+                        found_param: x,
+                    })
+                    .unwrap()
+                        + "\n"
+                })
+                .collect::<String>(),
+
             _ => {
-                format!(
+                let (pairs, found_params): (Vec<&FoundParameter>, Vec<&FoundParameter>) =
+                    self.found_params.iter().partition(|x| x.pair);
+
+                let mut line = format!(
                     "{} {} % {}",
                     &self.method.blue(),
                     &self.url,
-                    self.found_params
+                    found_params
                         .iter()
                         .map(|x| x.get_colored())
                         .collect::<Vec<String>>()
                         .join(", ")
-                )
+                );
+
+                // pairs from --pairs are reported separately
+                if !pairs.is_empty() {
+                    line += &format!(
+                        " {} {}",
+                        "| pairs:".magenta(),
+                        pairs
+                            .iter()
+                            .map(|x| x.get_colored())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    );
+                }
+
+                if !self.skipped_params.is_empty() {
+                    line += &format!(" {}", format!("| skipped: {}", self.skipped_params.len()).yellow());
+                }
+
+                line
             }
         }
     }
 }
 
+/// converts outputs to SARIF v2.1.0 log. Every found parameter becomes a separate result
+fn sarif(outputs: &[RunnerOutput]) -> String {
+    let mut kinds: Vec<&ReasonKind> = Vec::new();
+    let mut results = Vec::new();
+
+    for output in outputs {
+        for found_param in output.found_params.iter() {
+            if !kinds.contains(&&found_param.reason_kind) {
+                kinds.push(&found_param.reason_kind);
+            }
+
+            results.push(json!({
+                "ruleId": format!("{:?}", found_param.reason_kind),
+                "level": "warning",
+                "rank": found_param.confidence as f64,
+                "message": {
+                    "text": format!(
+                        "{} {}: {} (code {} -> {})",
+                        output.method,
+                        found_param.name,
+                        found_param.reason_kind.description(),
+                        output.status,
+                        found_param.status
+                    )
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": output.url }
+                    }
+                }],
+                "properties": {
+                    "method": output.method,
+                    "parameter": found_param.name,
+                    "value": found_param.value,
+                    "diffs": found_param.diffs,
+                    "initial_status": output.status,
+                    "status": found_param.status,
+                    "size": found_param.size,
+                    "confidence": found_param.confidence,
+                    "unkeyed": found_param.unkeyed,
+                    "injection_place": output.injection_place,
+                }
+            }));
+        }
+    }
+
+    let rules = kinds
+        .iter()
+        .map(|x| {
+            json!({
+                "id": format!("{:?}", x),
+                "shortDescription": { "text": x.description() },
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "x8",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Sh1Yo/x8",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+    .to_string()
+}
+
+/// converts outputs to JUnit XML. Every url:method pair is a test suite and every found parameter is a failed test case.
+/// pairs without found parameters get a single passed test case, so CI systems show them as checked
+fn junit(outputs: &[RunnerOutput]) -> String {
+    let mut suites = String::new();
+    let (mut tests, mut failures, mut skipped) = (0, 0, 0);
+
+    for output in outputs {
+        let suite_name = xml_escape(&format!("{} {}", output.method, output.url));
+        let mut cases = String::new();
+
+        for found_param in output.found_params.iter() {
+            let message = format!(
+                "{} (code {} -> {})",
+                found_param.reason_kind.description(),
+                output.status,
+                found_param.status
+            );
+
+            let details = format!(
+                "method: {}
+url: {}
+parameter: {}
+value: {}
+reason: {:?}
+confidence: {}
+diffs: {}",
+                output.method,
+                output.url,
+                found_param.name,
+                found_param.value.as_deref().unwrap_or_default(),
+                found_param.reason_kind,
+                found_param.confidence,
+                found_param.diffs
+            );
+
+            cases += &format!(
+                r#"    <testcase classname="{}" name="{}">
+      <failure message="{}" type="{:?}">{}</failure>
+    </testcase>
+"#,
+                suite_name,
+                xml_escape(&found_param.name),
+                xml_escape(&message),
+                found_param.reason_kind,
+                xml_escape(&details)
+            );
+        }
+
+        if output.found_params.is_empty() {
+            cases += &format!(
+                r#"    <testcase classname="{}" name="no hidden parameters"/>
+"#,
+                suite_name
+            );
+        }
+
+        if !output.skipped_params.is_empty() {
+            cases += &format!(
+                r#"    <testcase classname="{}" name="skipped parameters">
+      <skipped message="{} parameter(s) were skipped due to errors"/>
+    </testcase>
+"#,
+                suite_name,
+                output.skipped_params.len()
+            );
+        }
+
+        let suite_tests = output.found_params.len().max(1) + (!output.skipped_params.is_empty()) as usize;
+        let suite_skipped = (!output.skipped_params.is_empty()) as usize;
+
+        suites += &format!(
+            r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0" skipped="{}">
+{}  </testsuite>
+"#,
+            suite_name,
+            suite_tests,
+            output.found_params.len(),
+            suite_skipped,
+            cases
+        );
+
+        tests += suite_tests;
+        failures += output.found_params.len();
+        skipped += suite_skipped;
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="x8" tests="{}" failures="{}" errors="0" skipped="{}">
+{}</testsuites>
+"#,
+        tests, failures, skipped, suites
+    )
+}
+
 impl ParseOutputs for Vec<RunnerOutput> {
     fn parse_output(&self, config: &Config) -> String {
         // print an array of json objects instead of just new line separeted new objects
         if config.output_format.as_str() == "json" {
             serde_json::to_string(&self).unwrap()
+        } else if config.output_format.as_str() == "json-by-target" {
+            // {"<url>": [<output per method>, ..], ..}
+            let mut report = serde_json::Map::new();
+
+            for output in self.iter() {
+                if let Some(outputs) = report
+                    .entry(output.url.clone())
+                    .or_insert_with(|| json!([]))
+                    .as_array_mut()
+                {
+                    outputs.push(serde_json::to_value(output).unwrap());
+                }
+            }
+
+            serde_json::Value::Object(report).to_string()
+        } else if config.output_format.as_str() == "sarif" {
+            sarif(self)
+        } else if config.output_format.as_str() == "junit" {
+            junit(self)
         // otherwise calls .parse on every RunnerOutput
         } else {
             self.iter()
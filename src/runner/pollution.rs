@@ -0,0 +1,176 @@
+use std::error::Error;
+
+use crate::{
+    network::request::Request,
+    utils::{progress_style_check_requests, random_line},
+    VALUE_LENGTH,
+};
+
+use super::{
+    runner::Runner,
+    utils::{FoundParameter, ReasonKind},
+};
+
+/// where the original parameter is
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Place {
+    Query,
+    Body,
+}
+
+/// (name, value) pairs of the query or the urlencoded body
+pub(super) type Pairs = Vec<(String, String)>;
+
+impl<'a> Runner<'a> {
+    /// resends parameters that are already present within the query or the urlencoded body
+    /// with a different value, duplicated before and after the original one, and moved to the other place.
+    /// reports parameters together with the strategies that change the response
+    pub(super) async fn check_pollution(&self) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+        let path = strip_injection_point(&self.request_defaults.path);
+        let (base, query) = split_query(&path);
+
+        let query = parse_pairs(query);
+
+        // only urlencoded bodies are polluted
+        let is_urlencoded = !self.request_defaults.is_json && !self.request_defaults.is_xml;
+        let body = if is_urlencoded {
+            parse_pairs(&strip_injection_point(&self.request_defaults.body))
+        } else {
+            Vec::new()
+        };
+
+        let params: Vec<(Place, usize)> = (0..query.len())
+            .map(|i| (Place::Query, i))
+            .chain((0..body.len()).map(|i| (Place::Body, i)))
+            .collect();
+
+        self.prepare_progress_bar("pollution", progress_style_check_requests(self.config), params.len() * 4);
+
+        let mut found_params = Vec::new();
+
+        for (place, index) in params {
+            let name = match place {
+                Place::Query => &query[index].0,
+                Place::Body => &body[index].0,
+            };
+
+            // parameters can't be moved to the body in case it isn't urlencoded
+            let can_move = place == Place::Body || !body.is_empty();
+
+            let mut found_param: Option<FoundParameter> = None;
+
+            for strategy in ["replaced", "duplicate-first", "duplicate-last", "other-place"] {
+                self.progress_bar.inc(1);
+
+                let value = random_line(VALUE_LENGTH);
+                let (mut query, mut body) = (query.clone(), body.clone());
+
+                {
+                    let (same, other) = match place {
+                        Place::Query => (&mut query, &mut body),
+                        Place::Body => (&mut body, &mut query),
+                    };
+
+                    match strategy {
+                        "replaced" => same[index].1 = value.clone(),
+                        "duplicate-first" => same.insert(index, (name.to_owned(), value.clone())),
+                        "duplicate-last" => same.push((name.to_owned(), value.clone())),
+                        _ if can_move => other.push((name.to_owned(), value.clone())),
+                        _ => continue,
+                    }
+                }
+
+                let mut request_defaults = self.request_defaults.clone();
+                request_defaults.path = join_query(base, &query);
+                if is_urlencoded {
+                    request_defaults.body = join_pairs(&body);
+                }
+
+                let response = Request::new(&request_defaults, Vec::new()).send().await?;
+                let (is_code_diff, diffs) = response.compare(&self.initial_response, &self.diffs)?;
+
+                // the reflected value isn't a change of the behavior
+                let diffs: Vec<String> = diffs.into_iter().filter(|x| !x.contains(&value)).collect();
+
+                if !is_code_diff && diffs.is_empty() {
+                    continue;
+                }
+
+                if let Some(found_param) = found_param.as_mut() {
+                    found_param.pollution.push(strategy.to_string());
+                    continue;
+                }
+
+                let (kind, diffs) = if is_code_diff {
+                    let kind = if response.is_server_error(&self.initial_response) {
+                        ReasonKind::ServerError
+                    } else {
+                        ReasonKind::Code
+                    };
+                    (kind, vec![format!("{} -> {}", self.initial_response.code, response.code)])
+                } else {
+                    (ReasonKind::from_diffs(&diffs), diffs)
+                };
+
+                response.write_and_save(
+                    self.id,
+                    self.config,
+                    &self.initial_response,
+                    kind.clone(),
+                    &format!("{} ({})", name, strategy),
+                    diffs.first().map(|x| x.as_str()),
+                    self.progress_bar,
+                )?;
+
+                let mut new_found_param = self.found_param(name, &diffs, &response, kind);
+                new_found_param.pollution.push(strategy.to_string());
+                found_param = Some(new_found_param);
+            }
+
+            if let Some(found_param) = found_param {
+                self.stream_found_parameter(&found_param)?;
+                found_params.push(found_param);
+            }
+        }
+
+        Ok(found_params)
+    }
+}
+
+/// removes the parameters' injection point, so only the original parameters are sent
+pub(super) fn strip_injection_point(s: &str) -> String {
+    s.replace("?%s", "").replace("&%s", "").replace("%s", "")
+}
+
+fn split_query(path: &str) -> (&str, &str) {
+    match path.split_once('?') {
+        Some((base, query)) => (base, query),
+        None => (path, ""),
+    }
+}
+
+fn parse_pairs(s: &str) -> Pairs {
+    s.split('&')
+        .filter(|x| !x.is_empty())
+        .map(|x| match x.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (x.to_string(), String::new()),
+        })
+        .collect()
+}
+
+pub(super) fn join_pairs(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn join_query(base: &str, query: &[(String, String)]) -> String {
+    if query.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, join_pairs(query))
+    }
+}
@@ -0,0 +1,137 @@
+use super::{output::RunnerOutput, utils::FoundParameter};
+
+/// diff lines above the limit are cut to keep the report readable
+const MAX_DIFF_LINES: usize = 200;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;background:#fafafa;color:#222}\
+h1{font-size:1.6em}h2{font-size:1.3em;margin-top:2em;border-bottom:1px solid #ccc}\
+section{background:#fff;border:1px solid #ddd;border-radius:4px;padding:1em;margin:1em 0}\
+table{border-collapse:collapse}td{padding:.2em 1em .2em 0;vertical-align:top}\
+pre{background:#f3f3f3;padding:.8em;overflow-x:auto;white-space:pre-wrap;word-break:break-all}\
+.kind{display:inline-block;padding:0 .5em;border-radius:3px;background:#333;color:#fff;font-size:.9em}\
+.add{background:#e6ffed;color:#22863a}.del{background:#ffeef0;color:#b31d28}.hunk{color:#6f42c1}";
+
+/// builds a standalone html report with a section for every found parameter
+pub fn html_report(outputs: &[RunnerOutput], requests: usize) -> String {
+    let found: usize = outputs.iter().map(|x| x.found_params.len()).sum();
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>x8 report</title>\n<style>{}</style>\n</head>\n<body>\n\
+<h1>x8 report</h1>\n<p>{} parameters found across {} url:method pairs, {} requests sent</p>\n",
+        STYLE,
+        found,
+        outputs.len(),
+        requests
+    );
+
+    for output in outputs {
+        html += &format!(
+            "<h2>{} {}</h2>\n<p>initial response: code {}, size {}, injection place {:?}</p>\n",
+            escape(&output.method),
+            escape(&output.url),
+            output.status,
+            output.size,
+            output.injection_place
+        );
+
+        if output.found_params.is_empty() {
+            html += "<p>No parameters found</p>\n";
+        }
+
+        for found_param in output.found_params.iter() {
+            html += &finding(output, found_param);
+        }
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+fn finding(output: &RunnerOutput, found_param: &FoundParameter) -> String {
+    let name = match &found_param.value {
+        Some(value) => format!("{}={}", found_param.name, value),
+        None => found_param.name.to_owned(),
+    };
+
+    let mut html = format!(
+        "<section>\n<h3>{} <span class=\"kind\">{:?}</span></h3>\n<table>\n\
+<tr><td>reason</td><td>{}</td></tr>\n<tr><td>confidence</td><td>{}%</td></tr>\n<tr><td>code</td><td>{} -> {}</td></tr>\n<tr><td>size</td><td>{} -> {}</td></tr>\n",
+        escape(&name),
+        found_param.reason_kind,
+        escape(found_param.reason_kind.description()),
+        found_param.confidence,
+        output.status,
+        found_param.status,
+        output.size,
+        found_param.size
+    );
+
+    if !found_param.diffs.is_empty() {
+        html += &format!("<tr><td>diffs</td><td>{}</td></tr>\n", escape(&found_param.diffs));
+    }
+
+    if !found_param.pollution.is_empty() {
+        html += &format!("<tr><td>pollution</td><td>{}</td></tr>\n", escape(&found_param.pollution.join(", ")));
+    }
+
+    if !found_param.encodings.is_empty() {
+        html += &format!("<tr><td>encodings</td><td>{}</td></tr>\n", escape(&found_param.encodings.join(", ")));
+    }
+
+    if found_param.pair {
+        html += "<tr><td>source</td><td>--pairs</td></tr>\n";
+    }
+
+    if found_param.unkeyed {
+        html += "<tr><td>cache</td><td>unkeyed, the change persists in the cache without the header</td></tr>\n";
+    }
+
+    if !found_param.values.is_empty() {
+        html += &format!("<tr><td>values</td><td>{}</td></tr>\n", escape(&found_param.values.join(", ")));
+    }
+
+    if let Some(curl) = &found_param.curl {
+        html += &format!("<tr><td>curl</td><td><code>{}</code></td></tr>\n", escape(curl));
+    }
+
+    if let Some(evidence) = &found_param.evidence {
+        html += &format!("<tr><td>time</td><td>{}ms</td></tr>\n</table>\n", evidence.time);
+        html += &format!("<h4>Request</h4>\n<pre>{}</pre>\n", escape(&evidence.request));
+
+        if !evidence.diff.is_empty() {
+            html += "<h4>Diff</h4>\n<pre>";
+
+            for line in evidence.diff.iter().take(MAX_DIFF_LINES) {
+                let class = match line.chars().next() {
+                    Some('+') => "add",
+                    Some('-') => "del",
+                    _ => "hunk",
+                };
+                html += &format!("<span class=\"{}\">{}</span>\n", class, escape(line));
+            }
+
+            if evidence.diff.len() > MAX_DIFF_LINES {
+                html += &format!("... {} more lines\n", evidence.diff.len() - MAX_DIFF_LINES);
+            }
+
+            html += "</pre>\n";
+        }
+
+        html += &format!(
+            "<details>\n<summary>Response</summary>\n<pre>{}</pre>\n</details>\n",
+            escape(&evidence.response)
+        );
+    } else {
+        html += "</table>\n";
+    }
+
+    html += "</section>\n";
+    html
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
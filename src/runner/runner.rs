@@ -1,24 +1,40 @@
-use std::{error::Error, io::{self, Write}};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs::OpenOptions,
+    io::{self, Write},
+    sync::atomic::AtomicBool,
+};
 
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
+use itertools::Itertools;
+use tokio::sync::Mutex;
 
 use crate::{
     config::structs::Config,
+    diff::{changed_lines, StabilityMask},
+    error::X8Error,
     network::{
         request::{Request, RequestDefaults},
         response::Response,
+        scrape::target_words,
         utils::{create_client, InjectionPlace},
     },
-    utils::{self, color_id, random_line, progress_style_learn_requests, is_id_important},
-    DEFAULT_PROGRESS_URL_MAX_LEN, MAX_PAGE_SIZE,
+    utils::{self, color_id, random_line, progress_style_check_requests, progress_style_learn_requests, is_id_important},
+    DEFAULT_PROGRESS_URL_MAX_LEN, MAX_PAGE_SIZE, VALUE_LENGTH,
 };
 
 use super::{
-    output::RunnerOutput,
-    utils::{fold_url, replay, verify, FoundParameter, Parameters, Stable},
+    calibration::Wildcard,
+    concurrency::ConcurrencyController,
+    output::{FoundParameterLine, RunnerOutput},
+    utils::{fold_url, replay, verify, Evidence, FoundParameter, Latency, Parameters, ReasonKind, Stable},
 };
 
+/// array and object syntaxes appended to parameter names with --array-syntax
+const ARRAY_SYNTAXES: [&str; 3] = ["[]", "[0]", "[key]"];
+
 pub struct Runner<'a> {
     /// unique id of the runner to distinguish output between different urls
     pub id: usize,
@@ -40,11 +56,32 @@ pub struct Runner<'a> {
     /// initial response to compare with
     pub initial_response: Response<'a>,
 
+    /// error signatures that already match the initial response, so they aren't reported for found parameters
+    pub initial_fingerprints: Vec<bool>,
+
     /// page's diffs for the current url|method pair
     pub diffs: Vec<String>,
 
+    /// changes caused by nonexistent parameters as well
+    pub wildcards: Vec<Wildcard>,
+
     /// progress bar object to print progress bar & found parameters
     pub progress_bar: &'a ProgressBar,
+
+    /// the amount of concurrent requests. Can be chosen by --auto-tune
+    pub concurrency: usize,
+
+    /// adjusts the amount of concurrent requests in case --adaptive-concurrency is used
+    pub concurrency_controller: Option<ConcurrencyController>,
+
+    /// response times of requests with random parameters
+    pub latency: Latency,
+
+    /// parameters of the chunks that kept failing and were left unchecked
+    pub skipped_params: Mutex<Vec<String>>,
+
+    /// the page kept returning an unexpected code, so the rest of the parameters aren't checked
+    pub unstable: AtomicBool,
 }
 
 impl<'a> Runner<'a> {
@@ -68,16 +105,57 @@ impl<'a> Runner<'a> {
 
         let initial_response = Request::new(&temp_request_defaults, vec![]).send().await?;
 
+        if config.http_version == Some(http::Version::HTTP_2)
+            && !config.http2_prior_knowledge
+            && initial_response.http_version != Some(http::Version::HTTP_2)
+        {
+            utils::info(
+                config,
+                id,
+                progress_bar,
+                "~",
+                "The server didn't negotiate HTTP/2. Use --http2-prior-knowledge to force it",
+            );
+        }
+
+        if config.http3 && initial_response.http_version != Some(http::Version::HTTP_3) {
+            utils::info(
+                config,
+                id,
+                progress_bar,
+                "~",
+                "The server didn't answer over HTTP/3, so HTTP/2 or HTTP/1.1 is used instead",
+            );
+        }
+
         // add possible parameters to the list of parameters in case the injection place is not headers
         let possible_params = if request_defaults.injection_place != InjectionPlace::Headers {
-            initial_response.get_possible_parameters()
+            if config.scrape_js {
+                target_words(request_defaults, &initial_response.text).await
+            } else {
+                initial_response.get_possible_parameters()
+            }
         } else {
             Vec::new()
         };
 
+        // the page returns these codes by default, so they aren't worth resending
+        request_defaults
+            .retry_policy
+            .codes
+            .retain(|x| *x != initial_response.code);
+
         // find how many times was the random parameter reflected
-        request_defaults.amount_of_reflections =
-            initial_response.count(&temp_request_defaults.parameters.first().unwrap().1);
+        let (random_name, random_value) = temp_request_defaults.parameters.first().unwrap();
+        request_defaults.amount_of_reflections = initial_response.count(random_value);
+
+        // find in what headers the random parameter was reflected
+        request_defaults.header_reflections = initial_response
+            .reflected_headers(random_value)
+            .into_iter()
+            .chain(initial_response.reflected_headers(random_name))
+            .unique()
+            .collect();
 
         // some "magic" to be able to return initial_response
         // otherwise throws lifetime errors
@@ -88,8 +166,14 @@ impl<'a> Runner<'a> {
             headers: initial_response.headers,
             text: initial_response.text,
             reflected_parameters: initial_response.reflected_parameters,
+            header_reflected_parameters: initial_response.header_reflected_parameters,
             request: None,
             http_version: initial_response.http_version,
+            json: initial_response.json,
+            stability_mask: None,
+            stats: initial_response.stats,
+            redirects: initial_response.redirects,
+            truncated: initial_response.truncated,
         };
 
         Ok(Runner {
@@ -98,19 +182,39 @@ impl<'a> Runner<'a> {
             possible_params,
             max: 0, //to be filled later, in stability-checker()
             stable: Default::default(),
+            initial_fingerprints: config.fingerprints.matches(&initial_response),
             initial_response,
             diffs: Vec::new(),
+            wildcards: Vec::new(),
             progress_bar,
             id,
+            concurrency: config.concurrency,
+            concurrency_controller: config
+                .adaptive_concurrency
+                .map(|max| ConcurrencyController::new(config.concurrency, max)),
+            latency: Latency::default(),
+            skipped_params: Mutex::new(Vec::new()),
+            unstable: AtomicBool::new(false),
         })
     }
 
     /// actually runs the runner
     pub async fn run(mut self, params: &mut Vec<String>) -> Result<RunnerOutput, Box<dyn Error>> {
-        self.write_banner_url();
+        self.learn().await?;
 
-        // makes a few request to check page's behavior
-        self.stability_checker().await?;
+        if !self.config.fuzz_headers.is_empty() || self.config.pollution {
+            let found_params = if self.config.pollution {
+                self.check_pollution().await?
+            } else {
+                self.fuzz_header_values().await?
+            };
+
+            return Ok(RunnerOutput::new(
+                &self.request_defaults,
+                &self.initial_response,
+                found_params,
+            ));
+        }
 
         if self.config.max.is_none() {
             utils::info(
@@ -122,17 +226,52 @@ impl<'a> Runner<'a> {
             );
         }
 
-        // add only unique possible params to the vec of all params (the tool works properly only with unique parameters)
-        // less efficient than making it within the sorted vec but I want to preserve the order
-        for param in self.possible_params.iter() {
-            if !params.contains(param) {
-                params.push(param.to_owned());
+        if self.config.scrape_js {
+            // words from the target are more likely to be parameters, so they are checked first
+            let possible_params: HashSet<&String> = self.possible_params.iter().collect();
+            params.retain(|x| !possible_params.contains(x));
+            params.splice(0..0, self.possible_params.iter().cloned());
+        } else {
+            // add only unique possible params to the vec of all params (the tool works properly only with unique parameters)
+            // less efficient than making it within the sorted vec but I want to preserve the order
+            for param in self.possible_params.iter() {
+                if !params.contains(param) {
+                    params.push(param.to_owned());
+                }
             }
         }
 
+        if self.config.dry_run {
+            self.print_plan(params);
+
+            return Ok(RunnerOutput::new(
+                &self.request_defaults,
+                &self.initial_response,
+                Vec::new(),
+            ));
+        }
+
+        Ok(self.discover(params, true).await?.1)
+    }
+
+    /// prints the banner and makes a few requests to check page's behavior.
+    /// done by run(), but distributed workers call it once and then discover() for every lease
+    pub async fn learn(&mut self) -> Result<(), Box<dyn Error>> {
+        self.write_banner_url();
+        self.stability_checker().await
+    }
+
+    /// checks the parameters and makes the additional checks of the found ones.
+    /// custom parameters and --pairs are checked only with pair_checks, since workers check them once per url:method pair.
+    /// returns the diffs collected during the check as well
+    pub async fn discover(
+        &self,
+        params: &Vec<String>,
+        pair_checks: bool,
+    ) -> Result<(Vec<String>, RunnerOutput), Box<dyn Error>> {
         // try to find existing parameters from the list
         let (diffs, mut found_params) = if !params.is_empty() {
-            self.check_parameters(params).await?
+            self.check_parameters(params, true).await?
         } else {
             utils::info(
                 self.config,
@@ -144,12 +283,40 @@ impl<'a> Runner<'a> {
             (Vec::new(), Vec::new())
         };
 
-        self.check_non_random_parameters(&mut found_params).await?;
+        // the found parameters are returned without the additional checks
+        if let Some(reason) = self.config.budget.exhausted() {
+            utils::info(self.config, self.id, self.progress_bar, "budget", format!("exhausted ({})", reason));
+
+            let mut found_params = found_params.process(self.request_defaults.injection_place);
+            for found_param in found_params.iter_mut() {
+                found_param.score(&self.stable, 0, self.config.time_confirmations);
+            }
+            found_params.retain(|x| x.confidence >= self.config.min_confidence);
+
+            return Ok((
+                diffs,
+                RunnerOutput::new(&self.request_defaults, &self.initial_response, found_params)
+                    .with_skipped_params(std::mem::take(&mut *self.skipped_params.lock().await)),
+            ));
+        }
+
+        if pair_checks {
+            self.check_non_random_parameters(&mut found_params).await?;
+
+            if !self.config.pairs.is_empty() {
+                self.check_pairs(&mut found_params).await?;
+            }
+        }
+
+        if self.config.array_syntax && self.supports_array_syntax() {
+            self.check_array_syntax(params, &mut found_params).await?;
+        }
 
         // remove duplicates
         let mut found_params = found_params.process(self.request_defaults.injection_place);
 
         // verify found parameters
+        let mut reproductions = 0;
         if self.config.verify {
             found_params = if let Ok(filtered_params) = verify(
                 &self.initial_response,
@@ -157,9 +324,11 @@ impl<'a> Runner<'a> {
                 &found_params,
                 &diffs,
                 &self.stable,
+                self.config.verify_attempts,
             )
             .await
             {
+                reproductions = self.config.verify_attempts;
                 filtered_params
             } else {
                 utils::info(
@@ -173,6 +342,41 @@ impl<'a> Runner<'a> {
             };
         }
 
+        if !self.config.value_wordlist.is_empty() && !found_params.is_empty() {
+            self.fuzz_values(&mut found_params, &diffs).await?;
+        }
+
+        if self.config.switch_content_type
+            && matches!(self.request_defaults.injection_place, InjectionPlace::Path | InjectionPlace::Body)
+            && !found_params.is_empty()
+        {
+            self.check_content_types(&mut found_params).await?;
+        }
+
+        // parameters consumed on the server side don't have to change the response
+        if self.config.oob.is_some() {
+            let names: Vec<String> = params
+                .iter()
+                .map(|x| x.split('=').next().unwrap().to_string())
+                .filter(|x| !found_params.contains_name(x))
+                .collect();
+
+            let mut oob_params = self.check_oob(&names).await?;
+            found_params.append(&mut oob_params);
+        }
+
+        if self.config.cache_poisoning
+            && self.request_defaults.injection_place == InjectionPlace::Headers
+            && !found_params.is_empty()
+        {
+            self.check_cache_poisoning(&mut found_params).await?;
+        }
+
+        for found_param in found_params.iter_mut() {
+            found_param.score(&self.stable, reproductions, self.config.time_confirmations);
+        }
+        found_params.retain(|x| x.confidence >= self.config.min_confidence);
+
         // replay request with found parameters via another proxy
         if !self.config.replay_proxy.is_empty() {
 
@@ -210,49 +414,478 @@ impl<'a> Runner<'a> {
             }
         }
 
-        Ok(RunnerOutput::new(
-            &self.request_defaults,
-            &self.initial_response,
-            found_params,
+        if !self.config.nuclei_templates.is_empty() && !found_params.is_empty() {
+            if let Err(err) = self.write_nuclei_templates(&found_params).await {
+                utils::info(
+                    self.config,
+                    self.id,
+                    self.progress_bar,
+                    "~",
+                    format!("Unable to write nuclei templates: {}", err),
+                );
+            }
+        }
+
+        Ok((
+            diffs,
+            RunnerOutput::new(&self.request_defaults, &self.initial_response, found_params)
+                .with_skipped_params(std::mem::take(&mut *self.skipped_params.lock().await)),
         ))
     }
 
+    /// sends every found parameter with values from --value-wordlist
+    /// and remembers the values that change the response compared to a random value
+    async fn fuzz_values(
+        &self,
+        found_params: &mut [FoundParameter],
+        diffs: &[String],
+    ) -> Result<(), Box<dyn Error>> {
+        self.prepare_progress_bar(
+            "values",
+            progress_style_check_requests(self.config),
+            found_params.len() * self.config.value_wordlist.len(),
+        );
+
+        for found_param in found_params.iter_mut() {
+            // parameters with non random values were already checked with specific values
+            if found_param.value.is_some() || found_param.reason_kind == ReasonKind::GraphQL {
+                continue;
+            }
+
+            let random_value = random_line(VALUE_LENGTH);
+            let random_response = self.send_with_value(&found_param.name, &random_value).await?;
+
+            // learn the parameter's dynamic parts with another random value
+            let mut param_diffs = diffs.to_vec();
+            let check_value = random_line(VALUE_LENGTH);
+            let (_, new_diffs) = self
+                .send_with_value(&found_param.name, &check_value)
+                .await?
+                .compare(&random_response, &param_diffs)?;
+            param_diffs.extend(new_diffs.into_iter().filter(|x| !x.contains(&check_value)));
+
+            for value in self.config.value_wordlist.iter() {
+                self.progress_bar.inc(1);
+
+                let response = self.send_with_value(&found_param.name, value).await?;
+                let (is_code_diff, new_diffs) = response.compare(&random_response, &param_diffs)?;
+
+                // lines with the values are usually just reflections
+                let new_diffs: Vec<String> = new_diffs
+                    .into_iter()
+                    .filter(|x| !x.contains(value.as_str()) && !x.contains(&random_value))
+                    .collect();
+
+                if !is_code_diff && new_diffs.is_empty() {
+                    continue;
+                }
+
+                utils::info(
+                    self.config,
+                    self.id,
+                    self.progress_bar,
+                    "value",
+                    format!(
+                        "{}={}: code {} -> {}, page {} -> {}",
+                        found_param.name,
+                        value,
+                        random_response.code(),
+                        response.code(),
+                        random_response.text.len(),
+                        response.text.len()
+                    ),
+                );
+
+                found_param.values.push(value.to_owned());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// sends every header from --fuzz-header with values from the value wordlist
+    /// and reports header=value pairs that change the response compared to a random value
+    async fn fuzz_header_values(&self) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+        self.prepare_progress_bar(
+            "header-values",
+            progress_style_check_requests(self.config),
+            self.config.fuzz_headers.len() * self.config.value_wordlist.len(),
+        );
+
+        let mut found_params = Vec::new();
+
+        for name in self.config.fuzz_headers.iter() {
+            let random_value = random_line(VALUE_LENGTH);
+            let random_response = self.send_with_value(name, &random_value).await?;
+
+            // learn the header's dynamic parts with another random value
+            let mut header_diffs = self.diffs.clone();
+            let check_value = random_line(VALUE_LENGTH);
+            let (_, new_diffs) = self
+                .send_with_value(name, &check_value)
+                .await?
+                .compare(&random_response, &header_diffs)?;
+            header_diffs.extend(new_diffs.into_iter().filter(|x| !x.contains(&check_value)));
+
+            for value in self.config.value_wordlist.iter() {
+                self.progress_bar.inc(1);
+
+                let response = self.send_with_value(name, value).await?;
+                let (is_code_diff, new_diffs) = response.compare(&random_response, &header_diffs)?;
+
+                // lines with the values are usually just reflections
+                let new_diffs: Vec<String> = new_diffs
+                    .into_iter()
+                    .filter(|x| !x.contains(value.as_str()) && !x.contains(&random_value))
+                    .collect();
+
+                if !is_code_diff && new_diffs.is_empty() {
+                    continue;
+                }
+
+                let (kind, diffs) = if is_code_diff {
+                    let kind = if response.is_server_error(&random_response) {
+                        ReasonKind::ServerError
+                    } else {
+                        ReasonKind::Code
+                    };
+                    (kind, vec![format!("{} -> {}", random_response.code, response.code)])
+                } else {
+                    (ReasonKind::from_diffs(&new_diffs), new_diffs)
+                };
+
+                let param = format!("{}={}", name, value);
+
+                response.write_and_save(
+                    self.id,
+                    self.config,
+                    &random_response,
+                    kind.clone(),
+                    &param,
+                    diffs.first().map(|x| x.as_str()),
+                    self.progress_bar,
+                )?;
+
+                let found_param = self.found_param(&param, &diffs, &response, kind);
+                self.stream_found_parameter(&found_param)?;
+                found_params.push(found_param);
+            }
+        }
+
+        Ok(found_params)
+    }
+
+    /// sends every found header to a unique cache buster url and then requests the url without the header.
+    /// headers whose changes persist are unkeyed inputs that can be used for cache poisoning
+    async fn check_cache_poisoning(&self, found_params: &mut [FoundParameter]) -> Result<(), Box<dyn Error>> {
+        self.prepare_progress_bar("cache-poisoning", progress_style_check_requests(self.config), found_params.len() * 3);
+
+        for found_param in found_params.iter_mut() {
+            // the diffs caused by the cache buster itself, e.g. cache misses
+            let mut control_defaults = self.request_defaults.clone();
+            let control_buster = random_line(VALUE_LENGTH);
+            control_defaults.path = with_cache_buster(&control_defaults.path, &control_buster);
+
+            let mut diffs = self.diffs.clone();
+            let (_, control_diffs) = Request::new(&control_defaults, Vec::new())
+                .send()
+                .await?
+                .compare(&self.initial_response, &diffs)?;
+            diffs.extend(control_diffs.into_iter().filter(|x| !x.contains(&control_buster)));
+            self.progress_bar.inc(1);
+
+            let mut request_defaults = self.request_defaults.clone();
+            let buster = random_line(VALUE_LENGTH);
+            request_defaults.path = with_cache_buster(&request_defaults.path, &buster);
+
+            let (name, value) = found_param.get();
+            let poisoned = Request::new(&request_defaults, vec![format!("{}={}", name, value)])
+                .send()
+                .await?;
+            let (is_code_diff, poisoned_diffs) = poisoned.compare(&self.initial_response, &diffs)?;
+            let poisoned_diffs: Vec<String> = poisoned_diffs.into_iter().filter(|x| !x.contains(&buster)).collect();
+            self.progress_bar.inc(1);
+
+            // the header doesn't influence the response anymore
+            if !is_code_diff && poisoned_diffs.is_empty() {
+                self.progress_bar.inc(1);
+                continue;
+            }
+
+            let cached = Request::new(&request_defaults, Vec::new()).send().await?;
+            let (is_cached_code_diff, cached_diffs) = cached.compare(&self.initial_response, &diffs)?;
+            self.progress_bar.inc(1);
+
+            if (is_code_diff && is_cached_code_diff && cached.code == poisoned.code)
+                || cached_diffs.iter().any(|x| poisoned_diffs.contains(x))
+            {
+                utils::info(
+                    self.config,
+                    self.id,
+                    self.progress_bar,
+                    "cache",
+                    format!("{}: the change persists in the cache without the header", name),
+                );
+
+                found_param.unkeyed = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// resends the parameter several times and checks that it increases the response time every time.
+    /// requests with random parameters are sent in between to make sure that the whole server didn't become slower
+    pub(super) async fn confirm_time_shift(&self, param: &str) -> Result<bool, Box<dyn Error>> {
+        for _ in 0..self.config.time_confirmations {
+            let response = Request::new(&self.request_defaults, vec![param.to_string()])
+                .send()
+                .await?;
+
+            if !self.latency.is_slow(response.time, self.config.time_sigma) {
+                return Ok(false);
+            }
+
+            let random_response = Request::new_random(&self.request_defaults, 1).send().await?;
+
+            if self.latency.is_slow(random_response.time, self.config.time_sigma) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// learns the page's behavior and resends previously found parameters.
+    /// returns the parameters that still change the page
+    pub async fn reproduce(mut self, found_params: &[FoundParameter]) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+        self.max = 1;
+        self.empty_reqs().await?;
+
+        let (oob_params, found_params): (Vec<FoundParameter>, Vec<FoundParameter>) = found_params
+            .iter()
+            .cloned()
+            .partition(|x| x.reason_kind == ReasonKind::OutOfBand);
+
+        let (time_params, other_params): (Vec<FoundParameter>, Vec<FoundParameter>) = found_params
+            .into_iter()
+            .partition(|x| x.reason_kind == ReasonKind::Time);
+
+        let mut reproduced = verify(
+            &self.initial_response,
+            &self.request_defaults,
+            &other_params,
+            &self.diffs,
+            &self.stable,
+            self.config.verify_attempts,
+        )
+        .await?;
+
+        // verify() trusts time-based parameters, so they are measured again
+        for param in time_params {
+            let param_str = match &param.value {
+                Some(value) => format!("{}={}", param.name, value),
+                None => param.name.clone(),
+            };
+
+            if self.confirm_time_shift(&param_str).await? {
+                reproduced.push(param);
+            }
+        }
+
+        // out-of-band parameters are checked with new callback urls. They can't be reproduced without --oob
+        if self.config.oob.is_some() && !oob_params.is_empty() {
+            let names: Vec<String> = oob_params.iter().map(|x| x.name.to_owned()).collect();
+            reproduced.append(&mut self.check_oob(&names).await?);
+        }
+
+        Ok(reproduced)
+    }
+
+    /// sends parameters with unique callback urls as values, waits for interactions
+    /// and returns the parameters whose urls were requested or resolved
+    async fn check_oob(&self, names: &[String]) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+        let oob = self.config.oob.as_ref().unwrap();
+
+        let chunks: Vec<&[String]> = names.chunks(self.max.max(1)).collect();
+        self.prepare_progress_bar("out-of-band", progress_style_check_requests(self.config), chunks.len());
+
+        // id -> (parameter, the index of the chunk's response)
+        let mut ids: HashMap<String, (String, usize)> = HashMap::new();
+        let mut responses = Vec::with_capacity(chunks.len());
+
+        for chunk in chunks {
+            let params = chunk
+                .iter()
+                .map(|name| {
+                    let (id, url) = oob.payload();
+                    ids.insert(id, (name.to_owned(), responses.len()));
+                    format!("{}={}", name, url)
+                })
+                .collect::<Vec<String>>();
+
+            responses.push(Request::new(&self.request_defaults, params).send().await?);
+            self.progress_bar.inc(1);
+        }
+
+        utils::info(
+            self.config,
+            self.id,
+            self.progress_bar,
+            "oob",
+            format!("waiting {}s for interactions", oob.wait.as_secs()),
+        );
+        tokio::time::sleep(oob.wait).await;
+
+        let mut found_params = Vec::new();
+
+        for id in oob.poll(ids.keys()).await? {
+            let (name, index) = &ids[&id];
+            let diff = format!("{}.{}", id, oob.domain);
+
+            self.add_found_param(
+                &mut found_params,
+                &responses[*index],
+                name,
+                std::slice::from_ref(&diff),
+                Some(&diff),
+                ReasonKind::OutOfBand,
+            )?;
+        }
+
+        Ok(found_params)
+    }
+
+    /// sends the request with the only parameter with the specified value
+    async fn send_with_value(&self, name: &str, value: &str) -> Result<Response<'_>, Box<dyn Error>> {
+        Request::new(&self.request_defaults, vec![format!("{}={}", name, value)])
+            .send()
+            .await
+    }
+
     /// check parameters with non random values
     async fn check_non_random_parameters(
         &self,
         found_params: &mut Vec<FoundParameter>,
     ) -> Result<(), Box<dyn Error>> {
         if !self.request_defaults.disable_custom_parameters {
-            let mut custom_parameters = self.config.custom_parameters.clone();
-            let mut params = Vec::new();
-
-            // in a loop check common parameters like debug, admin, .. with common values true, 1, false..
-            // until there's no values left
-            loop {
-                for (k, v) in custom_parameters.iter_mut() {
-                    //do not request parameters that already have been found
-                    if found_params
-                        .iter()
-                        .map(|x| x.name.split('=').next().unwrap())
-                        .any(|x| x == k)
-                    {
-                        continue;
-                    }
-
-                    if !v.is_empty() {
-                        params.push([k.as_str(), "=", v.pop().unwrap().as_str()].concat());
-                    }
-                }
+            // common parameters like debug, admin, .. with common values true, 1, false..
+            let custom_parameters = self.config.custom_parameters.clone().into_iter().collect();
+
+            let mut new_found_params = self.check_values(custom_parameters, found_params, true).await?;
+            found_params.append(&mut new_found_params);
+        }
+
+        Ok(())
+    }
+
+    /// checks name=value parameters in rounds, so different values of the same name aren't sent within one request.
+    /// values are taken from the end. Names within found_params are skipped,
+    /// as well as the ones found during the previous rounds in case skip_found is set
+    async fn check_values(
+        &self,
+        mut values: Vec<(String, Vec<String>)>,
+        found_params: &[FoundParameter],
+        skip_found: bool,
+    ) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
+        let mut new_found_params: Vec<FoundParameter> = Vec::new();
+
+        loop {
+            let params: Vec<String> = values
+                .iter_mut()
+                .filter(|(k, _)| {
+                    !(found_params.iter().any(|x| x.name == *k) || skip_found && new_found_params.contains_name(k))
+                })
+                .filter_map(|(k, v)| v.pop().map(|v| format!("{}={}", k, v)))
+                .collect();
+
+            if params.is_empty() {
+                break;
+            }
+
+            new_found_params.append(&mut self.check_parameters(&params, false).await?.1);
+        }
+
+        Ok(new_found_params)
+    }
+
+    /// only query and urlencoded bodies have the array syntax
+    fn supports_array_syntax(&self) -> bool {
+        match self.request_defaults.injection_place {
+            InjectionPlace::Path => true,
+            InjectionPlace::Body => !self.request_defaults.is_json && !self.request_defaults.is_xml,
+            _ => false,
+        }
+    }
+
+    /// checks parameters that weren't found with every array syntax: name[]=x, name[0]=x, name[key]=x.
+    /// some parameters are only parsed as arrays or objects, so they don't change the response with name=x
+    async fn check_array_syntax(
+        &self,
+        params: &[String],
+        found_params: &mut Vec<FoundParameter>,
+    ) -> Result<(), Box<dyn Error>> {
+        let names: Vec<&String> = params
+            .iter()
+            .filter(|x| !x.contains('=') && !x.contains('[') && !found_params.contains_name(x))
+            .collect();
+
+        // base names of the parameters found with one of the syntaxes aren't checked with the next ones
+        let mut found_names: HashSet<String> = HashSet::new();
+
+        for syntax in ARRAY_SYNTAXES.iter() {
+            let array_params: Vec<String> = names
+                .iter()
+                .filter(|x| !found_names.contains(x.as_str()))
+                .map(|x| format!("{}{}", x, syntax))
+                .collect();
+
+            if array_params.is_empty() {
+                break;
+            }
 
-                if params.is_empty() {
-                    break;
+            utils::info(
+                self.config,
+                self.id,
+                self.progress_bar,
+                "array",
+                format!("checking {} parameters as name{}", array_params.len(), syntax),
+            );
+
+            for found_param in self.check_parameters(&array_params, false).await?.1 {
+                if let Some(name) = found_param.name.strip_suffix(syntax) {
+                    found_names.insert(name.to_string());
                 }
+                found_params.push(found_param);
+            }
+        }
 
-                found_params.append(&mut self.check_parameters(&params).await?.1);
-                params.clear();
+        Ok(())
+    }
+
+    /// check name=value pairs from --pairs with exactly their values.
+    /// a name can have several values, so every round sends only one value per name
+    async fn check_pairs(&self, found_params: &mut Vec<FoundParameter>) -> Result<(), Box<dyn Error>> {
+        let mut pairs: Vec<(String, Vec<String>)> = Vec::new();
+
+        for (name, value) in self.config.pairs.iter() {
+            match pairs.iter_mut().find(|(k, _)| k == name) {
+                Some((_, values)) => values.push(value.to_owned()),
+                None => pairs.push((name.to_owned(), vec![value.to_owned()])),
             }
         }
 
+        // values are sent in the order of the file
+        for (_, values) in pairs.iter_mut() {
+            values.reverse();
+        }
+
+        // every value of the pair is sent, even after the name is found with one of them
+        for mut found_param in self.check_values(pairs, found_params, false).await? {
+            found_param.pair = true;
+            found_params.push(found_param);
+        }
+
         Ok(())
     }
 
@@ -261,12 +894,15 @@ impl<'a> Runner<'a> {
     async fn stability_checker(&mut self) -> Result<(), Box<dyn Error>> {
         // guess or get from the user the amount of parameters to send per request
         let default_max = match self.config.max {
+            // path segments can't be batched
+            _ if self.request_defaults.injection_place == InjectionPlace::PathSegment => 1,
             Some(var) => var as isize,
             None => match self.request_defaults.injection_place {
-                InjectionPlace::Body => -512,
-                InjectionPlace::Path => self.try_to_guess_the_right_max_for_query().await?,
-                InjectionPlace::Headers => -64,
-                InjectionPlace::HeaderValue => -64,
+                InjectionPlace::Body => self.calibrate_max(512).await?,
+                // cookies are limited by the header size in the same way as the query is limited by the url length
+                InjectionPlace::Path | InjectionPlace::Cookies => self.calibrate_max(128).await?,
+                InjectionPlace::Headers | InjectionPlace::HeaderValue => self.calibrate_max(64).await?,
+                InjectionPlace::PathSegment => unreachable!(),
             },
         };
 
@@ -275,15 +911,24 @@ impl<'a> Runner<'a> {
         // make a few requests and collect all persistent diffs, check for stability
         self.empty_reqs().await?;
 
+        // learn how the page reacts to nonexistent parameters
+        if !self.config.disable_calibration {
+            self.calibrate().await?;
+        }
+
         if self.config.reflected_only && !self.stable.reflections {
-            Err("Reflections are not stable")?;
+            Err(X8Error::Stability("Reflections are not stable".to_string()))?;
         }
 
         // check whether it is possible to use 192 or 256 params in a single request instead of 128 default
-        if default_max == -128 {
+        if default_max == -128 || (default_max < 0 && self.config.auto_tune.is_some_and(|x| x.max)) {
             self.try_to_increase_max().await?;
         }
 
+        if let Some(auto_tune) = self.config.auto_tune {
+            self.auto_tune(auto_tune).await?;
+        }
+
         Ok(())
     }
 
@@ -295,9 +940,12 @@ impl<'a> Runner<'a> {
             reflections: true,
         };
         let mut diffs: Vec<String> = Vec::new();
+        let mut times: Vec<u128> = Vec::with_capacity(self.config.learn_requests_count);
+        let mut mask = StabilityMask::default();
+        let mut responses = Vec::with_capacity(self.config.learn_requests_count);
 
         // set up progress bar
-        self.prepare_progress_bar(progress_style_learn_requests(self.config), self.config.learn_requests_count);
+        self.prepare_progress_bar("learning", progress_style_learn_requests(self.config), self.config.learn_requests_count);
 
         for _ in 0..self.config.learn_requests_count {
             // to increase stability
@@ -308,22 +956,31 @@ impl<'a> Runner<'a> {
                 .await?;
 
             self.progress_bar.inc(1);
+            times.push(response.time);
 
             // do not check pages >25MB because usually its just a binary file or sth
             if response.text.len() > MAX_PAGE_SIZE && !self.config.force {
-                Err("The page's size > 25MB. Use --force flag to disable this error")?;
+                Err(X8Error::Stability("The page's size > 25MB. Use --force flag to disable this error".to_string()))?;
             }
 
             if !response.reflected_parameters.is_empty() {
                 stable.reflections = false;
             }
 
-            let (is_code_diff, mut new_diffs) = response.compare(&self.initial_response, &diffs)?;
-
-            if is_code_diff {
-                Err("The page is not stable (code)")?
+            if response.code != self.initial_response.code {
+                Err(X8Error::Stability("The page is not stable (code)".to_string()))?
             }
 
+            // learn what parts of the page vary between the baseline responses
+            mask.learn(self.initial_response.body(), response.body())?;
+            responses.push(response);
+        }
+
+        // the diffs are collected after the whole mask is learned, so only the changes outside of it are stored
+        self.initial_response.stability_mask = Some(mask);
+
+        for response in responses.iter() {
+            let (_, mut new_diffs) = response.compare(&self.initial_response, &diffs)?;
             diffs.append(&mut new_diffs);
         }
 
@@ -349,6 +1006,7 @@ impl<'a> Runner<'a> {
         }
 
         (self.diffs, self.stable) = (diffs, stable);
+        self.latency = Latency::new(&times);
 
         Ok(())
     }
@@ -394,54 +1052,76 @@ impl<'a> Runner<'a> {
         Ok(())
     }
 
-    /// tries to detect the right amount of parameters that can be send per request in query
-    /// TODO maybe detect based on reflection as well
-    pub async fn try_to_guess_the_right_max_for_query(&mut self) -> Result<isize, Box<dyn Error>> {
-
-        let mut max = 128;
-
-        let mut response = match Request::new_random(&self.request_defaults, max)
-            .send()
-            .await {
-                Ok(val) => val,
-                // some servers may cut connection in case url is too long
-                // that's why we assume that this request returned response with status code = 0.
-                Err(_) => {
-                    Request::empty_response(Request::new_random(&self.request_defaults, 0))
-                }
+    /// finds the max amount of parameters per request the server accepts.
+    /// starts with the default amount and binary searches the limit in case the server rejects it
+    /// with a different code (414, 431, 413, 400, ..) or cuts the connection due to the url or headers size.
+    /// returns a negative number to distinguish guessed values from the user supplied ones
+    pub async fn calibrate_max(&mut self, default: usize) -> Result<isize, Box<dyn Error>> {
+        let code = match self.limit_code(default).await {
+            // the default amount is accepted
+            None => return Ok(-(default as isize)),
+            Some(code) => code,
         };
 
-        loop {
-            // the choosen max is okay
-            if self.initial_response.code == response.code {
-                break
-            }
+        if Request::new_random(&self.request_defaults, 0).send().await?.code != self.initial_response.code {
+            Err(X8Error::Stability("The page became unstable (code)".to_string()))?
+        }
 
-            if Request::new_random(&self.request_defaults, 0).send().await?.code != self.initial_response.code {
-                Err("The page became unstable (code)")?
-            };
+        // accepted <= limit < rejected
+        let (mut accepted, mut rejected) = (0, default);
 
-            max /= 2;
+        while rejected - accepted > 1 {
+            let middle = (accepted + rejected) / 2;
 
-            if max < 4 {
-                Err("Unable to guess the max amount of parameters per request. Try to use --max command line argument.")?
+            if self.limit_code(middle).await.is_none() {
+                accepted = middle;
+            } else {
+                rejected = middle;
             }
+        }
 
-            response = match Request::new_random(&self.request_defaults, max)
-                .send()
-                .await {
-                    Ok(val) => val,
-                    Err(_) => {
-                        Request::empty_response(Request::new_random(&self.request_defaults, 0))
-                    }
-            };
+        if accepted == 0 {
+            Err(X8Error::Stability(
+                "Unable to guess the max amount of parameters per request. Try to use --max command line argument.".to_string(),
+            ))?
         }
 
-        Ok(max as isize *-1)
+        // real parameter names are often longer than the random ones
+        let max = (accepted * 3 / 4).max(1);
+
+        utils::info(
+            self.config,
+            self.id,
+            self.progress_bar,
+            "info",
+            format!(
+                "the server rejects {} parameters per request (code {}), using {}",
+                rejected, code, max
+            ),
+        );
+
+        Ok(max as isize * -1)
     }
 
-    pub fn prepare_progress_bar(&self, sty: ProgressStyle, length: usize) {
+    /// returns the code of the response in case the server rejects the amount of parameters.
+    /// some servers cut the connection in case the url is too long, such responses have code 0
+    async fn limit_code(&self, amount: usize) -> Option<u16> {
+        let code = match Request::new_random(&self.request_defaults, amount).send().await {
+            Ok(response) => response.code,
+            Err(_) => 0,
+        };
+
+        if code == self.initial_response.code {
+            None
+        } else {
+            Some(code)
+        }
+    }
+
+    /// the phase is kept as the message of the progress bar for --progress json
+    pub fn prepare_progress_bar(&self, phase: &'static str, sty: ProgressStyle, length: usize) {
         self.progress_bar.reset();
+        self.progress_bar.set_message(phase);
         self.progress_bar.set_prefix(self.make_progress_prefix());
         self.progress_bar.set_style(sty);
         self.progress_bar.set_length(length as u64);
@@ -500,4 +1180,127 @@ impl<'a> Runner<'a> {
             self.progress_bar.println(msg);
         }
     }
+
+    /// identifies the url:method pair within the checkpoint file
+    pub(super) fn checkpoint_key(&self) -> String {
+        let mut key = format!(
+            "{} {}",
+            self.request_defaults.method,
+            self.request_defaults.url_without_default_port()
+        );
+
+        // during the recursive search the found parameters are added to every request
+        if !self.request_defaults.parameters.is_empty() {
+            key += &format!(
+                " +{}",
+                self.request_defaults
+                    .parameters
+                    .iter()
+                    .map(|x| x.0.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(",")
+            );
+        }
+
+        key
+    }
+
+    /// collects the request and the response of the finding in case the report is requested
+    pub fn evidence(&self, response: &Response) -> Option<Evidence> {
+        if self.config.report.is_empty() {
+            return None;
+        }
+
+        Some(Evidence {
+            request: response.request.as_ref().map(|x| x.print_sent()).unwrap_or_default(),
+            response: response.print(),
+            time: response.time,
+            diff: changed_lines(&self.initial_response.text, &response.text).unwrap_or_default(),
+        })
+    }
+
+    /// technologies whose error messages appeared because of the parameter
+    pub fn fingerprints(&self, response: &Response) -> Vec<String> {
+        self.config.fingerprints.detect(response, &self.initial_fingerprints)
+    }
+
+    /// the parameter found via the response together with the evidence, fingerprints and redirects of the response
+    pub fn found_param(&self, name: &str, diffs: &[String], response: &Response, kind: ReasonKind) -> FoundParameter {
+        FoundParameter::new(name, diffs, response.code, response.text.len(), kind)
+            .with_evidence(self.evidence(response))
+            .with_fingerprints(self.fingerprints(response))
+            .with_redirects(&response.redirects)
+    }
+
+    /// prints and saves the response of the found parameter, streams the parameter and adds it to found_params
+    pub fn add_found_param(
+        &self,
+        found_params: &mut Vec<FoundParameter>,
+        response: &Response,
+        name: &str,
+        diffs: &[String],
+        diff: Option<&str>,
+        kind: ReasonKind,
+    ) -> Result<(), Box<dyn Error>> {
+        response.write_and_save(
+            self.id,
+            self.config,
+            &self.initial_response,
+            kind.clone(),
+            name,
+            diff,
+            self.progress_bar,
+        )?;
+
+        let found_param = self.found_param(name, diffs, response, kind);
+        self.stream_found_parameter(&found_param)?;
+        found_params.push(found_param);
+
+        Ok(())
+    }
+
+    /// passes the found parameter to the webhook and
+    /// writes it as a standalone json line to stdout and the output file in case --output-format jsonl is used
+    pub fn stream_found_parameter(&self, found_param: &FoundParameter) -> Result<(), Box<dyn Error>> {
+        let line = FoundParameterLine::new(&self.request_defaults, found_param);
+
+        if let Some(webhook) = &self.config.webhook {
+            webhook.add_finding(serde_json::to_value(&line)?);
+        }
+
+        if self.config.output_format != "jsonl" {
+            return Ok(());
+        }
+
+        let line = line.to_line();
+
+        if !self.config.output_file.is_empty() {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.config.output_file)
+                .map_err(|err| X8Error::Output(err.to_string()))?;
+
+            writeln!(file, "{}", line).map_err(|err| X8Error::Output(err.to_string()))?;
+        }
+
+        if self.config.disable_progress_bar {
+            writeln!(io::stdout(), "{}", line).ok();
+        } else {
+            self.progress_bar.println(line);
+        }
+
+        Ok(())
+    }
+}
+
+/// adds a random query parameter so the request gets its own cache entry
+fn with_cache_buster(path: &str, buster: &str) -> String {
+    let (path, fragment) = match path.find('#') {
+        Some(index) => path.split_at(index),
+        None => (path, ""),
+    };
+
+    let joiner = if path.contains('?') { '&' } else { '?' };
+    format!("{}{}cb={}{}", path, joiner, buster, fragment)
 }
@@ -0,0 +1,167 @@
+use std::{error::Error, iter::FromIterator};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    config::structs::Config,
+    network::{request::RequestDefaults, utils::Headers},
+    utils,
+};
+
+use super::{
+    output::RunnerOutput,
+    runner::Runner,
+    utils::{Parameters, ReasonKind},
+};
+
+/// searches for parameters of the url:method pair from request_defaults.
+/// in case config.recursion_depth > 0 -- repeats the search with the found parameters added to every request.
+/// params are changed during the search, request_defaults keeps only the user supplied parameters afterwards
+pub async fn scan(
+    config: &Config,
+    request_defaults: &mut RequestDefaults,
+    params: &mut Vec<String>,
+    progress_bar: &ProgressBar,
+    id: usize,
+) -> Result<RunnerOutput, Box<dyn Error>> {
+    let mut runner_output = Runner::new(config, request_defaults, progress_bar, id)
+        .await?
+        .run(params)
+        .await?;
+
+    // the whole block related to the recursive searching
+    if !runner_output.found_params.is_empty() {
+        for depth in 1..config.recursion_depth + 1 {
+            if config.budget.is_exhausted() {
+                break;
+            }
+
+            // remove already found parameters from the list to prevent duplicates
+            params.retain(|x| !runner_output.found_params.contains_name(x));
+
+            // custom parameters work badly with recursion enabled
+            request_defaults.disable_custom_parameters = true;
+
+            // so we are keeping parameters that don't change pages' code
+            // or change it to 200 (server errors are never 200)
+            // we cant simply overwrite request_defaults.parameters because there's user-supplied parameters as well.
+            request_defaults.parameters.append(&mut Vec::from_iter(
+                runner_output
+                    .found_params
+                    .iter()
+                    .filter(|x| {
+                        !request_defaults.parameters.contains_key(&x.name)
+                            && (!matches!(x.reason_kind, ReasonKind::Code | ReasonKind::ServerError) || x.status == 200)
+                    })
+                    .map(|x| x.get()),
+            ));
+
+            utils::info(
+                config,
+                id,
+                progress_bar,
+                "recursion",
+                format!(
+                    "({}) repeating with {}",
+                    depth,
+                    request_defaults
+                        .parameters
+                        .iter()
+                        .map(|x| x.0.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            );
+
+            let new_output = Runner::new(config, request_defaults, progress_bar, id)
+                .await?
+                .run(params)
+                .await?;
+
+            // the same parameters are rechecked with every depth
+            for param in new_output.skipped_params {
+                if !runner_output.skipped_params.contains(&param) {
+                    runner_output.skipped_params.push(param);
+                }
+            }
+            let mut new_found_params = new_output.found_params;
+
+            // no new params where found - just quit the loop
+            if !new_found_params
+                .iter()
+                .any(|x| !runner_output.found_params.contains_name(&x.name))
+            {
+                break;
+            }
+
+            runner_output.found_params.append(&mut new_found_params);
+        }
+    }
+
+    // we probably changed request_defaults.parameters within the loop above
+    // so we are removing all of the added parameters in there
+    // leaving only user-supplied ones
+    // (to not cause double parameters in some output types)
+    request_defaults.parameters = request_defaults
+        .parameters
+        .iter()
+        .filter(|x| !runner_output.found_params.contains_name(&x.0))
+        .map(|x| x.to_owned())
+        .collect();
+
+    runner_output.prepare(config, request_defaults);
+
+    Ok(runner_output)
+}
+
+/// scans the url:method pair with every batch of parameters separately and merges the found parameters.
+/// used with --wordlist-batch to avoid loading the whole wordlist into memory
+pub async fn scan_batches(
+    config: &Config,
+    request_defaults: &mut RequestDefaults,
+    batches: impl Iterator<Item = Vec<String>>,
+    progress_bar: &ProgressBar,
+    id: usize,
+) -> Result<RunnerOutput, Box<dyn Error>> {
+    let mut runner_output: Option<RunnerOutput> = None;
+
+    for (index, mut params) in batches.enumerate() {
+        utils::info(
+            config,
+            id,
+            progress_bar,
+            "wordlist",
+            format!("batch {} with {} parameters", index + 1, params.len()),
+        );
+
+        let batch_output = scan(config, request_defaults, &mut params, progress_bar, id).await?;
+
+        match runner_output.as_mut() {
+            Some(output) => {
+                output.skipped_params.extend(batch_output.skipped_params);
+
+                for found_param in batch_output.found_params {
+                    if !output.found_params.contains_name(&found_param.name) {
+                        output.found_params.push(found_param);
+                    }
+                }
+            }
+            None => runner_output = Some(batch_output),
+        }
+
+        if config.budget.is_exhausted() {
+            break;
+        }
+    }
+
+    let mut runner_output = match runner_output {
+        Some(val) => val,
+        // the wordlist is empty, so only custom and scraped parameters are checked
+        None => scan(config, request_defaults, &mut Vec::new(), progress_bar, id).await?,
+    };
+
+    // url and request outputs should contain parameters from all the batches
+    runner_output.prepare(config, request_defaults);
+
+    Ok(runner_output)
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{metrics::Metrics, network::request::Parameters};
+
+use super::utils::FoundParameter;
+
+/// a part of the wordlist waiting in the queue
+pub struct Chunk {
+    pub params: Parameters,
+
+    /// index of the initial chunk the parameters come from
+    pub root: usize,
+
+    /// how many times the chunk was rechecked without being split
+    pub retries: usize,
+}
+
+/// the state shared between the workers that check parameters.
+/// diffs are needed for every comparison but rarely change, so workers compare against snapshots of them
+/// instead of holding a lock while comparing.
+/// the locks are async-aware, so waiting workers yield to the others instead of blocking the executor thread
+pub struct SharedState<'a> {
+    diffs: RwLock<Arc<Vec<String>>>,
+    green_lines: Mutex<HashMap<String, usize>>,
+    found_params: RwLock<Vec<FoundParameter>>,
+    queue: Mutex<VecDeque<Chunk>>,
+    skipped_params: Mutex<Vec<String>>,
+    metrics: &'a Metrics,
+}
+
+impl<'a> SharedState<'a> {
+    pub fn new(
+        metrics: &'a Metrics,
+        diffs: Vec<String>,
+        green_lines: HashMap<String, usize>,
+        found_params: Vec<FoundParameter>,
+        queue: VecDeque<Chunk>,
+    ) -> Self {
+        Self {
+            diffs: RwLock::new(Arc::new(diffs)),
+            green_lines: Mutex::new(green_lines),
+            found_params: RwLock::new(found_params),
+            queue: Mutex::new(queue),
+            skipped_params: Mutex::new(Vec::new()),
+            metrics,
+        }
+    }
+
+    /// the current diffs. Diffs added later don't appear in the snapshot
+    pub async fn diffs(&self) -> Arc<Vec<String>> {
+        Arc::clone(&*self.metrics.diffs_lock.acquire(self.diffs.read()).await)
+    }
+
+    /// adds the diffs that aren't known yet. Snapshots that are still in use are left untouched
+    pub async fn add_diffs(&self, new_diffs: Vec<String>) {
+        let known = self.diffs().await;
+        if new_diffs.iter().all(|x| known.contains(x)) {
+            return;
+        }
+        drop(known);
+
+        let mut diffs = self.metrics.diffs_lock.acquire(self.diffs.write()).await;
+        let diffs = Arc::make_mut(&mut diffs);
+        for diff in new_diffs {
+            if !diffs.contains(&diff) {
+                diffs.push(diff);
+            }
+        }
+    }
+
+    pub async fn green_lines(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        self.metrics.green_lines_lock.acquire(self.green_lines.lock()).await
+    }
+
+    pub async fn found_params(&self) -> RwLockReadGuard<'_, Vec<FoundParameter>> {
+        self.metrics.found_params_lock.acquire(self.found_params.read()).await
+    }
+
+    /// the write lock is also taken to check that the parameter wasn't found yet before adding it,
+    /// so two workers can't report the same parameter
+    pub async fn found_params_mut(&self) -> RwLockWriteGuard<'_, Vec<FoundParameter>> {
+        self.metrics.found_params_lock.acquire(self.found_params.write()).await
+    }
+
+    /// chunks are split and put back to the queue by the workers until the changing parameters are found
+    pub async fn queue(&self) -> MutexGuard<'_, VecDeque<Chunk>> {
+        self.metrics.queue_lock.acquire(self.queue.lock()).await
+    }
+
+    /// parameters of the chunks that kept failing and were left unchecked
+    pub async fn skipped_params(&self) -> MutexGuard<'_, Vec<String>> {
+        self.metrics.skipped_params_lock.acquire(self.skipped_params.lock()).await
+    }
+
+    /// returns the diffs, green lines, found and skipped parameters
+    pub fn into_inner(self) -> (Vec<String>, HashMap<String, usize>, Vec<FoundParameter>, Vec<String>) {
+        let diffs = Arc::try_unwrap(self.diffs.into_inner()).unwrap_or_else(|x| x.to_vec());
+
+        (
+            diffs,
+            self.green_lines.into_inner(),
+            self.found_params.into_inner(),
+            self.skipped_params.into_inner(),
+        )
+    }
+}
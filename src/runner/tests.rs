@@ -0,0 +1,203 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    config::structs::Config,
+    network::{
+        client::{HttpRequest, HttpResponse, MockClient},
+        request::RequestDefaults,
+        utils::InjectionPlace,
+    },
+    runner::{
+        calibration::strip_names,
+        output::{ParseOutputs, RunnerOutput},
+        runner::Runner,
+        utils::{FoundParameter, ReasonKind},
+    },
+};
+
+const PAGE: &str = "<html>\n<body>\nstatic page\n</body>\n</html>";
+
+const PARAMS: [&str; 12] = [
+    "id", "user", "page", "admin", "lang", "q", "debug", "sort", "limit", "offset", "token", "callback",
+];
+
+fn query_value(request: &HttpRequest, name: &str) -> Option<String> {
+    let (_, query) = request.url.split_once('?')?;
+
+    query.split('&').find_map(|x| match x.split_once('=') {
+        Some((k, v)) if k == name => Some(v.to_string()),
+        _ => None,
+    })
+}
+
+/// runs the whole check against the mocked target
+async fn run<F: Fn(&HttpRequest) -> HttpResponse + Send + Sync + 'static>(handler: F) -> Vec<FoundParameter> {
+    let config = Config {
+        max: Some(8),
+        disable_custom_parameters: true,
+        ..Default::default()
+    };
+
+    let mut request_defaults = RequestDefaults::from_config(&config, "GET", "http://example.com/?%s").unwrap();
+    request_defaults.http_client = Some(Arc::new(MockClient::new(handler)));
+
+    let progress_bar = ProgressBar::hidden();
+    let mut params = PARAMS.iter().map(|x| x.to_string()).collect();
+
+    Runner::new(&config, &mut request_defaults, &progress_bar, 0)
+        .await
+        .unwrap()
+        .run(&mut params)
+        .await
+        .unwrap()
+        .found_params
+}
+
+#[tokio::test]
+async fn code_change() {
+    let found_params = run(|request| match query_value(request, "admin") {
+        Some(_) => HttpResponse::new(403, PAGE),
+        None => HttpResponse::new(200, PAGE),
+    })
+    .await;
+
+    assert_eq!(found_params.len(), 1);
+    assert_eq!(found_params[0].name, "admin");
+    assert_eq!(found_params[0].reason_kind, ReasonKind::Code);
+}
+
+#[tokio::test]
+async fn body_change() {
+    let found_params = run(|request| match query_value(request, "debug") {
+        Some(_) => HttpResponse::new(200, &format!("{}\ndebug mode", PAGE)),
+        None => HttpResponse::new(200, PAGE),
+    })
+    .await;
+
+    assert_eq!(found_params.len(), 1);
+    assert_eq!(found_params[0].name, "debug");
+    assert_eq!(found_params[0].reason_kind, ReasonKind::Text);
+}
+
+#[tokio::test]
+async fn reflection() {
+    let found_params = run(|request| match query_value(request, "q") {
+        Some(value) => HttpResponse::new(200, &format!("{}\nresults for {}", PAGE, value)),
+        None => HttpResponse::new(200, PAGE),
+    })
+    .await;
+
+    assert_eq!(found_params.len(), 1);
+    assert_eq!(found_params[0].name, "q");
+    assert_eq!(found_params[0].reason_kind, ReasonKind::Reflected);
+}
+
+#[tokio::test]
+async fn rate_limited_chunks() {
+    let rate_limited = AtomicUsize::new(0);
+
+    // more rate limited responses than the chunk is allowed to be retried
+    let found_params = run(move |request| match query_value(request, "admin") {
+        Some(_) if rate_limited.fetch_add(1, Ordering::SeqCst) < 40 => {
+            let mut response = HttpResponse::new(429, PAGE);
+            response.headers.push(("retry-after".to_string(), "0".to_string()));
+            response
+        }
+        Some(_) => HttpResponse::new(403, PAGE),
+        None => HttpResponse::new(200, PAGE),
+    })
+    .await;
+
+    assert_eq!(found_params.len(), 1);
+    assert_eq!(found_params[0].name, "admin");
+}
+
+#[tokio::test]
+async fn nothing_found() {
+    let found_params = run(|_| HttpResponse::new(200, PAGE)).await;
+
+    assert!(found_params.is_empty());
+}
+
+#[test]
+fn junit_output() {
+    let config = Config {
+        output_format: "junit".to_string(),
+        ..Default::default()
+    };
+
+    let output = |url: &str, found_params| RunnerOutput {
+        method: "GET".to_string(),
+        url: url.to_string(),
+        status: 200,
+        size: PAGE.len(),
+        found_params,
+        injection_place: InjectionPlace::Path,
+        skipped_params: Vec::new(),
+        query: String::new(),
+        request: String::new(),
+    };
+
+    let outputs = vec![
+        output(
+            "http://example.com/?a=\"<&>\"",
+            vec![FoundParameter::new("admin", &["-1 <b>".to_string()], 403, 0, ReasonKind::Code)],
+        ),
+        output("http://example.com/empty", Vec::new()).with_skipped_params(vec!["id".to_string()]),
+    ];
+
+    let xml = outputs.parse_output(&config);
+    let document = roxmltree::Document::parse(&xml).unwrap();
+
+    let root = document.root_element();
+    assert_eq!(root.tag_name().name(), "testsuites");
+    assert_eq!(root.attribute("tests"), Some("3"));
+    assert_eq!(root.attribute("failures"), Some("1"));
+    assert_eq!(root.attribute("skipped"), Some("1"));
+
+    let suites: Vec<_> = root.children().filter(|x| x.has_tag_name("testsuite")).collect();
+    assert_eq!(suites.len(), 2);
+    assert_eq!(suites[0].attribute("name"), Some("GET http://example.com/?a=\"<&>\""));
+
+    let failure = suites[0].descendants().find(|x| x.has_tag_name("failure")).unwrap();
+    assert_eq!(failure.attribute("type"), Some("Code"));
+    assert!(failure.text().unwrap().contains("diffs: -1 <b>"));
+
+    let cases: Vec<_> = suites[1].children().filter_map(|x| x.attribute("name")).collect();
+    assert_eq!(cases, ["no hidden parameters", "skipped parameters"]);
+}
+
+#[test]
+fn wildcard_names_stripping() {
+    let params = vec!["id".to_string(), "admin=1".to_string()];
+    let diffs = vec![
+        "<a href=\"/?id=5\">id</a>".to_string(),
+        "<p>Unknown: admin</p>".to_string(),
+        "<p>identity administrator</p>".to_string(),
+    ];
+
+    assert_eq!(
+        strip_names(diffs, &params),
+        vec![
+            "<a href=\"/?=5\"></a>".to_string(),
+            "<p>Unknown: </p>".to_string(),
+            "<p>identity administrator</p>".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn found_parameter_values() {
+    let found_param = FoundParameter::new("redirect=/a?b=c", &[], 200, 0, ReasonKind::Code);
+    assert_eq!(found_param.name, "redirect");
+    assert_eq!(found_param.value.as_deref(), Some("/a?b=c"));
+
+    let found_param = FoundParameter::new("debug", &[], 200, 0, ReasonKind::Code);
+    assert_eq!(found_param.name, "debug");
+    assert_eq!(found_param.value, None);
+}
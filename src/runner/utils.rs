@@ -1,17 +1,21 @@
 use std::{
+    collections::HashMap,
     error::Error,
+    time::Duration,
 };
 
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::Client;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use colored::Colorize;
 
 use crate::{
     config::structs::Config,
+    diff::{COOKIE_DIFF_PREFIX, HEADER_DIFF_PREFIX},
     network::{
         request::{Request, RequestDefaults},
+        graphql::GraphQLErrors,
         response::Response,
         utils::InjectionPlace,
     },
@@ -24,15 +28,181 @@ pub struct Stable {
     pub reflections: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// the minimal shift of the response time (in ms) that can be considered as caused by a parameter
+const MIN_TIME_SHIFT: f64 = 250.;
+
+/// response time distribution collected during the learning phase
+#[derive(Debug, Default)]
+pub struct Latency {
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Latency {
+    pub fn new(times: &[u128]) -> Self {
+        if times.is_empty() {
+            return Self::default();
+        }
+
+        let mean = times.iter().sum::<u128>() as f64 / times.len() as f64;
+        let variance = times
+            .iter()
+            .map(|x| (*x as f64 - mean).powi(2))
+            .sum::<f64>()
+            / times.len() as f64;
+
+        Self {
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    /// whether the response time is beyond mean + sigma * stddev (but not less than MIN_TIME_SHIFT)
+    pub fn is_slow(&self, time: u128, sigma: f64) -> bool {
+        time as f64 > self.mean + (sigma * self.stddev).max(MIN_TIME_SHIFT)
+    }
+}
+
+/// what to do when the page keeps returning an unexpected code
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnstableCodeAction {
+    /// stop checking the url and skip the rest of the parameters
+    #[default]
+    Abort,
+
+    /// pause requests and recheck the chunk afterward
+    Pause(Duration),
+
+    /// reset the counter and keep checking
+    Ignore,
+}
+
+impl UnstableCodeAction {
+    /// accepts abort, ignore and pause[:<secs>]
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(match value.split_once(':') {
+            _ if value == "abort" => UnstableCodeAction::Abort,
+            _ if value == "ignore" => UnstableCodeAction::Ignore,
+            _ if value == "pause" => UnstableCodeAction::Pause(Duration::from_secs(60)),
+            Some(("pause", secs)) => UnstableCodeAction::Pause(Duration::from_secs(secs.parse()?)),
+            _ => Err(format!(
+                "Unknown unstable code action: {}. Use abort, ignore or pause[:<secs>]",
+                value
+            ))?,
+        })
+    }
+}
+
+/// how many times the page can return an unexpected code before it's considered unstable
+#[derive(Debug, Clone)]
+pub struct UnstableCodeThresholds {
+    pub default: usize,
+
+    /// thresholds for specific codes
+    pub codes: HashMap<u16, usize>,
+}
+
+impl Default for UnstableCodeThresholds {
+    fn default() -> Self {
+        Self {
+            default: 50,
+            codes: HashMap::new(),
+        }
+    }
+}
+
+impl UnstableCodeThresholds {
+    /// parses values like `50 429=5 403=20`
+    pub fn parse<'a>(values: impl Iterator<Item = &'a str>) -> Result<Self, Box<dyn Error>> {
+        let mut thresholds = UnstableCodeThresholds::default();
+
+        for value in values {
+            match value.split_once('=') {
+                Some((code, threshold)) => {
+                    thresholds.codes.insert(code.parse()?, threshold.parse()?);
+                }
+                None => thresholds.default = value.parse()?,
+            }
+        }
+
+        Ok(thresholds)
+    }
+
+    pub fn get(&self, code: u16) -> usize {
+        *self.codes.get(&code).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReasonKind {
     Code,
+
+    /// the parameter causes 5xx while the initial response isn't one
+    ServerError,
     Text,
+
+    /// the parameter changes only headers
+    Header,
+
+    /// the parameter makes the server issue new or different cookies
+    Cookie,
+
+    /// the parameter consistently increases the response time
+    Time,
     Reflected,
     NotReflected,
+
+    /// the graphql server didn't report the name as unknown
+    GraphQL,
+
+    /// is_interesting from the user script returned true
+    Script,
+
+    /// the parameter's callback url was requested or resolved (--oob)
+    OutOfBand,
+}
+
+impl ReasonKind {
+    /// explains what the parameter does
+    pub fn description(&self) -> &'static str {
+        match self {
+            ReasonKind::Code => "The parameter changes the page's code",
+            ReasonKind::ServerError => "The parameter causes a server error",
+            ReasonKind::Text => "The parameter changes the page's body",
+            ReasonKind::Header => "The parameter changes the page's headers",
+            ReasonKind::Cookie => "The parameter changes the cookies issued by the server",
+            ReasonKind::Time => "The parameter consistently increases the response time",
+            ReasonKind::Reflected => "The parameter reflects on the page a different amount of times",
+            ReasonKind::NotReflected => "The parameter causes other parameters to reflect a different amount of times",
+            ReasonKind::GraphQL => "The GraphQL server accepts the name",
+            ReasonKind::Script => "The user script considers the response interesting",
+            ReasonKind::OutOfBand => "The server interacts with the url from the parameter's value",
+        }
+    }
+
+    /// parameters that change only headers (and cookies among them) are reported separately from the body changes
+    pub fn from_diffs<'a, I: IntoIterator<Item = &'a String>>(diffs: I) -> Self {
+        let (mut headers, mut cookies, mut body) = (false, false, false);
+
+        for diff in diffs {
+            if diff.starts_with(COOKIE_DIFF_PREFIX) {
+                cookies = true;
+            } else if diff.starts_with(HEADER_DIFF_PREFIX) {
+                headers = true;
+            } else {
+                body = true;
+            }
+        }
+
+        match (body, cookies, headers) {
+            (false, true, _) => ReasonKind::Cookie,
+            (false, false, true) => ReasonKind::Header,
+            _ => ReasonKind::Text,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FoundParameter {
     pub name: String,
 
@@ -43,6 +213,61 @@ pub struct FoundParameter {
     pub status: u16,
     pub size: usize,
     pub reason_kind: ReasonKind,
+
+    /// values from --value-wordlist that change the response compared to random values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+
+    /// how likely the parameter is a real one, from 0 to 100
+    #[serde(default)]
+    pub confidence: u8,
+
+    /// the header changes cached responses of other requests (--cache-poisoning)
+    #[serde(default)]
+    pub unkeyed: bool,
+
+    /// ways of duplicating the parameter that change the response (--pollution)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pollution: Vec<String>,
+
+    /// other encodings the parser accepts the parameter with (--switch-content-type)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encodings: Vec<String>,
+
+    /// technologies whose error messages appeared in the response with the parameter
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fingerprints: Vec<String>,
+
+    /// the code and the location of every redirect followed with the parameter (--follow-redirects)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<String>,
+
+    /// the name=value pair comes from --pairs
+    #[serde(default)]
+    pub pair: bool,
+
+    /// the request and the response that revealed the parameter. Collected only for --report
+    #[serde(skip)]
+    pub evidence: Option<Evidence>,
+
+    /// curl command to reproduce the finding. Made only for --report and --porcelain curl
+    #[serde(skip)]
+    pub curl: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Evidence {
+    /// the sent request with all the parameters of the chunk
+    pub request: String,
+
+    /// status line, headers and body
+    pub response: String,
+
+    /// response time in ms
+    pub time: u128,
+
+    /// changed lines compared to the initial response
+    pub diff: Vec<String>,
 }
 
 impl FoundParameter {
@@ -55,14 +280,9 @@ impl FoundParameter {
     ) -> Self {
         let name = name.into();
 
-        let (name, value) = if name.contains('=') {
-            let mut name = name.split('=');
-            (
-                name.next().unwrap().to_string(),
-                Some(name.next().unwrap().to_string()),
-            )
-        } else {
-            (name, None)
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (name, None),
         };
 
         Self {
@@ -72,9 +292,79 @@ impl FoundParameter {
             status,
             size,
             reason_kind,
+            values: Vec::new(),
+            confidence: 0,
+            unkeyed: false,
+            pollution: Vec::new(),
+            encodings: Vec::new(),
+            fingerprints: Vec::new(),
+            redirects: Vec::new(),
+            pair: false,
+            evidence: None,
+            curl: None,
         }
     }
 
+    /// scores the parameter based on its reason kind, the amount of diffs,
+    /// the amount of successful reproductions and the page's stability
+    pub fn score(&mut self, stable: &Stable, reproductions: usize, time_confirmations: usize) {
+        let mut score: isize = match self.reason_kind {
+            ReasonKind::OutOfBand => 95,
+            ReasonKind::GraphQL | ReasonKind::ServerError => 80,
+            ReasonKind::Code | ReasonKind::Reflected | ReasonKind::Script => 70,
+            ReasonKind::Text | ReasonKind::Cookie => 60,
+            ReasonKind::Header | ReasonKind::Time => 50,
+            ReasonKind::NotReflected => 40,
+        };
+
+        match self.reason_kind {
+            // a few specific diffs are more convincing than the whole page changing
+            ReasonKind::Text | ReasonKind::Header | ReasonKind::Cookie => {
+                score += match self.diffs.split('|').filter(|x| !x.is_empty()).count() {
+                    0 => -20,
+                    1..=3 => 10,
+                    4..=10 => 0,
+                    _ => -15,
+                };
+
+                if !stable.body {
+                    score -= 20;
+                }
+            }
+            ReasonKind::Reflected | ReasonKind::NotReflected if !stable.reflections => score -= 20,
+            ReasonKind::Time => score += 5 * time_confirmations.min(5) as isize,
+            _ => (),
+        }
+
+        score += 10 * reproductions.min(3) as isize;
+
+        if !self.values.is_empty() {
+            score += 5;
+        }
+
+        // error messages rarely appear without a reason
+        if !self.fingerprints.is_empty() {
+            score += 15;
+        }
+
+        self.confidence = score.clamp(0, 100) as u8;
+    }
+
+    pub fn with_evidence(mut self, evidence: Option<Evidence>) -> Self {
+        self.evidence = evidence;
+        self
+    }
+
+    pub fn with_fingerprints(mut self, fingerprints: Vec<String>) -> Self {
+        self.fingerprints = fingerprints;
+        self
+    }
+
+    pub fn with_redirects(mut self, redirects: &[String]) -> Self {
+        self.redirects = redirects.to_vec();
+        self
+    }
+
     /// just returns (Key, Value) pair
     pub fn get(&self) -> (String, String) {
         (
@@ -83,19 +373,64 @@ impl FoundParameter {
         )
     }
 
+    /// the parameter as it's passed to Request::new. Parameters without specific values get random ones
+    pub fn to_param(&self) -> String {
+        match &self.value {
+            Some(value) => format!("{}={}", self.name, value),
+            None => self.name.to_owned(),
+        }
+    }
+
     /// returns colored param name and param=value in case a non random value is used
     pub fn get_colored(&self) -> String {
         let param = match self.reason_kind {
             ReasonKind::Code => self.name.yellow(),
+            ReasonKind::ServerError => self.name.red(),
             ReasonKind::Text => self.name.bright_yellow(),
+            ReasonKind::Header => self.name.bright_green(),
+            ReasonKind::Cookie => self.name.magenta(),
+            ReasonKind::Time => self.name.bright_red(),
             ReasonKind::Reflected => self.name.bright_blue(),
             ReasonKind::NotReflected => self.name.bright_cyan(),
+            ReasonKind::GraphQL => self.name.bright_magenta(),
+            ReasonKind::Script => self.name.bright_white(),
+            ReasonKind::OutOfBand => self.name.green(),
         };
 
-        if self.value.is_some() {
+        let param = if self.value.is_some() {
             format!("{}={}", param, self.value.as_ref().unwrap())
         } else {
             param.to_string()
+        };
+
+        let param = if self.values.is_empty() {
+            param
+        } else {
+            format!("{} ({})", param, self.values.join(", "))
+        };
+
+        let param = if self.pollution.is_empty() {
+            param
+        } else {
+            format!("{} [{}]", param, self.pollution.join(", "))
+        };
+
+        let param = if self.encodings.is_empty() {
+            param
+        } else {
+            format!("{} {{{}}}", param, self.encodings.join(", "))
+        };
+
+        let param = if self.fingerprints.is_empty() {
+            param
+        } else {
+            format!("{} {}", param, format!("<{}>", self.fingerprints.join(", ")).bright_red())
+        };
+
+        if self.unkeyed {
+            format!("{} {}", param, "[unkeyed]".red())
+        } else {
+            param
         }
     }
 }
@@ -196,7 +531,7 @@ pub(super) async fn replay<'a>(
 
     // get cookies
     Request::new(request_defaults, vec![])
-        .send_by(replay_client)
+        .send_by(Some(replay_client))
         .await?;
 
     if config.replay_once {
@@ -208,13 +543,13 @@ pub(super) async fn replay<'a>(
                 .map(|(x, y)| format!("{}={}", x, y))
                 .collect::<Vec<String>>(),
         )
-        .send_by(replay_client)
+        .send_by(Some(replay_client))
         .await?;
     } else {
         for param in found_params {
             let param = param.get();
             Request::new(request_defaults, vec![format!("{}={}", param.0, param.1)])
-                .send_by(replay_client)
+                .send_by(Some(replay_client))
                 .await?;
         }
     }
@@ -222,32 +557,89 @@ pub(super) async fn replay<'a>(
     Ok(())
 }
 
-/// verifies found parameters by requesting the page with found parameters yet one time
-pub(super) async fn verify<'a>(
+/// verifies found parameters by requesting the page with every found parameter separately.
+/// the parameter is kept only in case the change reproduces in every one of the attempts
+pub(crate) async fn verify<'a>(
     initial_response: &'a Response<'a>,
     request_defaults: &'a RequestDefaults,
     found_params: &Vec<FoundParameter>,
     diffs: &Vec<String>,
     stable: &Stable,
+    attempts: usize,
 ) -> Result<Vec<FoundParameter>, Box<dyn Error>> {
     let mut filtered_params = Vec::with_capacity(found_params.len());
 
     for param in found_params {
-        let param_value = param.get();
-        let mut response = Request::new(request_defaults, vec![format!("{}={}", param_value.0, param_value.1)])
-            .send()
-            .await?;
+        // time-based parameters are already confirmed with repeated measurements during the discovery
+        if param.reason_kind == ReasonKind::Time {
+            filtered_params.push(param.clone());
+            continue;
+        }
 
-        let (is_code_diff, new_diffs) = response.compare(initial_response, diffs)?;
-        let mut is_the_body_the_same = true;
+        // parameters without specific values should get a new random value every attempt
+        // otherwise their reflections won't be checked
+        let param_str = match &param.value {
+            Some(value) => format!("{}={}", param.name, value),
+            None => param.name.clone(),
+        };
 
-        if !new_diffs.is_empty() {
-            is_the_body_the_same = false;
-        }
+        let mut reproduced = true;
 
-        response.fill_reflected_parameters(initial_response);
+        for _ in 0..attempts {
+            let mut response = Request::new(request_defaults, vec![param_str.clone()])
+                .uncached()
+                .send()
+                .await?;
+
+            if !request_defaults.matchers.is_interesting(&response, initial_response) {
+                reproduced = false;
+                break;
+            }
+
+            // the name should still not be reported as unknown
+            if param.reason_kind == ReasonKind::GraphQL {
+                match GraphQLErrors::parse(&response.text) {
+                    Some(errors) if !errors.unknown.contains(&param.name) => continue,
+                    _ => {
+                        reproduced = false;
+                        break;
+                    }
+                }
+            }
+
+            // the script should still consider the response interesting
+            if param.reason_kind == ReasonKind::Script {
+                match &request_defaults.script {
+                    Some(script) if script.is_interesting(&response, initial_response) => continue,
+                    _ => {
+                        reproduced = false;
+                        break;
+                    }
+                }
+            }
+
+            let (is_code_diff, new_diffs) = response.compare(initial_response, diffs)?;
+            let mut is_the_body_the_same = true;
+
+            if !new_diffs.is_empty() {
+                is_the_body_the_same = false;
+            }
+
+            response.fill_reflected_parameters(initial_response);
+
+            // the code should change to the same one as during the discovery
+            if matches!(param.reason_kind, ReasonKind::Code | ReasonKind::ServerError) && response.code != param.status {
+                reproduced = false;
+                break;
+            }
+
+            if !is_code_diff && response.reflected_parameters.is_empty() && (is_the_body_the_same || !stable.body) {
+                reproduced = false;
+                break;
+            }
+        }
 
-        if is_code_diff || !response.reflected_parameters.is_empty() || stable.body && !is_the_body_the_same {
+        if reproduced {
             filtered_params.push(param.clone());
         }
     }
@@ -392,7 +784,7 @@ pub(super) async fn _smart_verify(
 pub(super) fn fold_url(url: &str, n: usize) -> String {
     if url.len() <= n + 2 {
         //we need to add some spaces to align the progress bars
-        url.to_string() + &" ".repeat(2 + n - url.len())
+        url.to_string() + " ".repeat(2 + n - url.len()).as_str()
     } else {
         "..".to_owned() + &url[url.len() - n..]
     }
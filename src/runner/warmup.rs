@@ -0,0 +1,93 @@
+use std::error::Error;
+
+use crate::{network::request::Request, utils};
+
+use super::runner::Runner;
+
+/// the upper bound of the concurrency chosen by --auto-tune
+const MAX_CONCURRENCY: usize = 32;
+
+/// the smallest amount of parameters per request chosen by --auto-tune
+const MIN_MAX: usize = 8;
+
+/// what --auto-tune is allowed to choose. Values set by the user are left as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoTune {
+    /// the upper bound of requests spent on measuring the target
+    pub requests: usize,
+
+    /// whether to choose the amount of parameters per request (no --max)
+    pub max: bool,
+
+    /// whether to choose the amount of concurrent requests (no -c or --adaptive-concurrency)
+    pub concurrency: bool,
+}
+
+impl<'a> Runner<'a> {
+    /// measures how the target handles bigger requests and more concurrent requests
+    /// and picks the amount of parameters per request and the concurrency.
+    /// uses the latency of the learning requests as the baseline, so it runs after them
+    pub(super) async fn auto_tune(&mut self, auto_tune: AutoTune) -> Result<(), Box<dyn Error>> {
+        // every parameter noticeably slows the page down, so smaller requests are checked faster
+        if auto_tune.max
+            && self.max > MIN_MAX
+            && self.latency.mean > self.initial_response.time as f64 * 4. + 500.
+        {
+            self.max = (self.max / 2).max(MIN_MAX);
+        }
+
+        let mut sent = 0;
+        let mut failed = 0;
+
+        if auto_tune.concurrency {
+            let mut concurrency = 1;
+            let mut level = 2;
+
+            // the concurrency is doubled until the target starts to fail, rate limit or slow down
+            while level <= MAX_CONCURRENCY && sent + level <= auto_tune.requests {
+                let responses = futures::future::join_all(
+                    (0..level).map(|_| Request::new_random(&self.request_defaults, self.max).send()),
+                )
+                .await;
+                sent += level;
+
+                let mut times = Vec::with_capacity(level);
+                for response in responses.iter() {
+                    match response {
+                        Ok(response)
+                            if response.code != 0
+                                && response.rate_limit_delay().is_none()
+                                && !response.is_server_error(&self.initial_response) =>
+                        {
+                            times.push(response.time)
+                        }
+                        _ => failed += 1,
+                    }
+                }
+
+                let mean = times.iter().sum::<u128>() as f64 / times.len().max(1) as f64;
+                if failed != 0 || mean > self.latency.mean * 2. + 100. {
+                    break;
+                }
+
+                concurrency = level;
+                level *= 2;
+            }
+
+            self.concurrency = concurrency;
+        }
+
+        utils::info(
+            self.config,
+            self.id,
+            self.progress_bar,
+            "auto-tune",
+            format!(
+                "concurrency - {}, parameters per request - {} ({} warm-up requests, {} failed, {}ms per request)",
+                self.concurrency, self.max, sent, failed, self.latency.mean as u128
+            ),
+        );
+
+        Ok(())
+    }
+}
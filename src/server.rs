@@ -0,0 +1,498 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use indicatif::ProgressBar;
+use parking_lot::Mutex;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+use crate::{
+    config::{args::get_remote_config_from, structs::Config},
+    metrics::{http_response, Metrics},
+    network::request::{Request, RequestDefaults},
+    runner::scan::scan,
+};
+
+/// requests with bigger bodies are rejected
+const MAX_REQUEST_SIZE: usize = 10 * 1024 * 1024;
+
+/// finished jobs that weren't fetched are dropped starting from the oldest ones after this amount
+const MAX_FINISHED_JOBS: usize = 1000;
+
+/// slow clients are disconnected after this timeout
+pub(crate) const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the body of POST /jobs
+#[derive(Debug, Deserialize)]
+struct JobRequest {
+    /// the same arguments as for the command line tool, e.g. ["-u", "https://example.com", "-X", "POST"]
+    args: Vec<String>,
+
+    /// parameters to check
+    params: Vec<String>,
+}
+
+/// the request to the api
+pub(crate) struct ApiRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ApiRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// returns the error response in case the request doesn't have the token or can be sent by a browser from another site.
+    /// browsers add Origin to cross-site requests and can't send Authorization or json without a CORS preflight
+    pub fn check(&self, token: &str) -> Option<(u16, serde_json::Value)> {
+        let authorization = self.header("authorization").and_then(|x| x.strip_prefix("Bearer "));
+
+        if !authorization.is_some_and(|x| is_same_token(x.trim(), token)) {
+            Some((401, json!({ "error": "The token is missing or incorrect" })))
+        } else if self.header("origin").is_some() {
+            Some((403, json!({ "error": "Cross-origin requests aren't allowed" })))
+        } else if self.method == "POST"
+            && !self
+                .header("content-type")
+                .is_some_and(|x| x.split(';').next().unwrap().trim().eq_ignore_ascii_case("application/json"))
+        {
+            Some((415, json!({ "error": "The body should be application/json" })))
+        } else {
+            None
+        }
+    }
+}
+
+/// compares tokens in constant time
+fn is_same_token(value: &str, token: &str) -> bool {
+    value.len() == token.len() && value.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// the token for the api in case it's not provided
+pub(crate) fn generate_token() -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(32).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct Job {
+    id: usize,
+
+    args: Vec<String>,
+
+    status: JobStatus,
+
+    /// url:method pairs to check and already checked ones
+    total: usize,
+    checked: usize,
+
+    /// outputs of every checked url:method pair in the same format as --output-format json
+    results: Vec<serde_json::Value>,
+
+    errors: Vec<String>,
+}
+
+impl Job {
+    fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Default)]
+struct JobList {
+    /// ids aren't reused after the jobs are dropped
+    last_id: usize,
+
+    list: Vec<Job>,
+}
+
+impl JobList {
+    /// drops the oldest finished jobs in case there are too many of them
+    fn drop_finished(&mut self) {
+        let mut excess = self.list.iter().filter(|x| x.is_finished()).count().saturating_sub(MAX_FINISHED_JOBS);
+
+        self.list.retain(|x| {
+            if excess > 0 && x.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+type Jobs = Arc<Mutex<JobList>>;
+
+/// listens for the REST API requests and runs submitted jobs on the internal queue:
+/// POST /jobs -- submit the job, GET /jobs -- list jobs without results, GET /jobs/<id> -- the job with results,
+/// GET /metrics -- metrics of all the jobs in case they're enabled.
+/// finished jobs are dropped once they're fetched via GET /jobs/<id>.
+/// every request needs the token within the Authorization header
+pub async fn serve(listen: &str, max_jobs: usize, metrics: bool, token: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|err| format!("Unable to listen on {}: {}", listen, err))?;
+
+    let token: Arc<str> = match token {
+        Some(token) => {
+            writeln!(io::stdout(), "Listening on http://{}", listen).ok();
+            token.into()
+        }
+        None => {
+            let token = generate_token();
+            writeln!(io::stdout(), "Listening on http://{} with the token {}", listen, token).ok();
+            token.into()
+        }
+    };
+
+    let jobs: Jobs = Arc::new(Mutex::new(JobList::default()));
+    let metrics = if metrics { Some(Arc::new(Metrics::default())) } else { None };
+    let (sender, receiver) = mpsc::unbounded_channel::<(usize, Config, Vec<String>)>();
+    let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+
+    // scans aren't Send, so workers run within the same task as the listener instead of being spawned
+    let workers = futures::future::join_all((0..max_jobs.max(1)).map(|_| {
+        let jobs = Arc::clone(&jobs);
+        let receiver = Arc::clone(&receiver);
+
+        async move {
+            loop {
+                let next = receiver.lock().await.recv().await;
+
+                match next {
+                    Some((id, config, params)) => run_job(&jobs, id, config, params).await,
+                    None => break,
+                }
+            }
+        }
+    }));
+
+    let accept = async {
+        loop {
+            let (mut stream, addr) = match listener.accept().await {
+                Ok(val) => val,
+                Err(err) => {
+                    log::debug!("Unable to accept the connection: {}", err);
+                    continue;
+                }
+            };
+
+            let (jobs, sender, metrics, token) = (Arc::clone(&jobs), sender.clone(), metrics.clone(), Arc::clone(&token));
+
+            // every connection is handled separately, so slow clients don't block the others
+            tokio::spawn(async move {
+                if let Err(err) = tokio::time::timeout(
+                    CLIENT_TIMEOUT,
+                    handle_connection(&mut stream, &jobs, &sender, metrics.as_ref(), &token),
+                )
+                .await
+                .unwrap_or_else(|_| Err("timed out".into()))
+                {
+                    log::debug!("Unable to handle the request from {}: {}", addr, err);
+                }
+            });
+        }
+    };
+
+    futures::join!(workers, accept);
+
+    Ok(())
+}
+
+async fn run_job(jobs: &Jobs, id: usize, config: Config, params: Vec<String>) {
+    let pairs: Vec<(String, String)> = config
+        .urls
+        .iter()
+        .flat_map(|url| {
+            config
+                .methods_for(url)
+                .into_iter()
+                .map(move |method| (url.to_owned(), method))
+        })
+        .collect();
+
+    update_job(jobs, id, |job| {
+        job.status = JobStatus::Running;
+        job.total = pairs.len();
+    });
+
+    let progress_bar = ProgressBar::hidden();
+
+    for (url, method) in pairs {
+        let mut params = params.clone();
+
+        let result = async {
+            let mut request_defaults = RequestDefaults::from_config(&config, &method, &url)?;
+
+            // get cookies
+            Request::new(&request_defaults, Vec::new()).send().await?;
+
+            let output = scan(&config, &mut request_defaults, &mut params, &progress_bar, id).await?;
+
+            Ok::<serde_json::Value, Box<dyn Error>>(serde_json::to_value(&output)?)
+        }
+        .await;
+
+        update_job(jobs, id, |job| {
+            match result {
+                Ok(val) => job.results.push(val),
+                Err(err) => job.errors.push(format!("{} {}: {}", method, url, err)),
+            }
+            job.checked += 1;
+        });
+    }
+
+    update_job(jobs, id, |job| {
+        job.status = if job.results.is_empty() && !job.errors.is_empty() {
+            JobStatus::Failed
+        } else {
+            JobStatus::Done
+        }
+    });
+
+    jobs.lock().drop_finished();
+}
+
+fn update_job<F: FnOnce(&mut Job)>(jobs: &Jobs, id: usize, f: F) {
+    if let Some(job) = jobs.lock().list.iter_mut().find(|x| x.id == id) {
+        f(job);
+    }
+}
+
+async fn handle_connection(
+    stream: &mut TcpStream,
+    jobs: &Jobs,
+    sender: &mpsc::UnboundedSender<(usize, Config, Vec<String>)>,
+    metrics: Option<&Arc<Metrics>>,
+    token: &str,
+) -> Result<(), Box<dyn Error>> {
+    let request = read_request(stream).await?;
+
+    if let Some((code, response)) = request.check(token) {
+        return write_json(stream, code, &response).await;
+    }
+
+    let (method, path) = (request.method.as_str(), request.path.trim_end_matches('/'));
+
+    if let (Some(metrics), "GET", "/metrics") = (metrics, method, path) {
+        stream.write_all(http_response(&metrics.render()).as_bytes()).await?;
+        return Ok(());
+    }
+
+    let (code, response) = match (method, path) {
+        ("POST", "/jobs") => match submit_job(jobs, sender, metrics, &request.body) {
+            Ok(id) => (201, json!({ "id": id })),
+            Err(err) => (400, json!({ "error": err.to_string() })),
+        },
+        ("GET", "/jobs") => {
+            let jobs = jobs.lock();
+            (
+                200,
+                jobs.list
+                    .iter()
+                    .map(|x| {
+                        json!({
+                            "id": x.id,
+                            "status": x.status,
+                            "total": x.total,
+                            "checked": x.checked,
+                            "found": x.results.iter().map(|x| x["found_params"].as_array().map_or(0, |x| x.len())).sum::<usize>(),
+                        })
+                    })
+                    .collect(),
+            )
+        }
+        ("GET", path) if path.starts_with("/jobs/") => {
+            let job = path["/jobs/".len()..].parse::<usize>().ok().and_then(|id| fetch_job(jobs, id));
+
+            match job {
+                Some(val) => (200, val?),
+                None => (404, json!({ "error": "The job is not found" })),
+            }
+        }
+        _ => (404, json!({ "error": "Not found" })),
+    };
+
+    write_json(stream, code, &response).await
+}
+
+/// returns the job and drops it in case it's finished, so the results of finished jobs aren't kept forever
+fn fetch_job(jobs: &Jobs, id: usize) -> Option<Result<serde_json::Value, serde_json::Error>> {
+    let mut jobs = jobs.lock();
+    let pos = jobs.list.iter().position(|x| x.id == id)?;
+
+    if jobs.list[pos].is_finished() {
+        Some(serde_json::to_value(jobs.list.remove(pos)))
+    } else {
+        Some(serde_json::to_value(&jobs.list[pos]))
+    }
+}
+
+/// writes the json response and closes the connection
+pub(crate) async fn write_json(stream: &mut TcpStream, code: u16, response: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+    let body = response.to_string();
+    let reason = match code {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        415 => "Unsupported Media Type",
+        _ => "Not Found",
+    };
+
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                code,
+                reason,
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// parses the job's arguments and puts it into the queue
+fn submit_job(
+    jobs: &Jobs,
+    sender: &mpsc::UnboundedSender<(usize, Config, Vec<String>)>,
+    metrics: Option<&Arc<Metrics>>,
+    body: &str,
+) -> Result<usize, Box<dyn Error>> {
+    let request: JobRequest = serde_json::from_str(body)?;
+
+    if request.params.is_empty() {
+        Err("The job has no parameters to check")?;
+    }
+
+    let mut config = get_remote_config_from(&request.args, &[])?;
+
+    // jobs are checked in the background, so there's nothing to print
+    config.verbose = 0;
+    config.disable_progress_bar = true;
+    config.remove_banner = true;
+
+    // all the jobs share the same counters
+    if let Some(metrics) = metrics {
+        config.metrics = Arc::clone(metrics);
+    }
+
+    let mut jobs = jobs.lock();
+    jobs.last_id += 1;
+    let id = jobs.last_id;
+
+    jobs.list.push(Job {
+        id,
+        args: request.args,
+        status: JobStatus::Queued,
+        total: 0,
+        checked: 0,
+        results: Vec::new(),
+        errors: Vec::new(),
+    });
+
+    sender.send((id, config, request.params)).map_err(|_| "The queue is closed")?;
+
+    Ok(id)
+}
+
+/// reads the request line, the headers and the body
+pub(crate) async fn read_request(stream: &mut TcpStream) -> Result<ApiRequest, Box<dyn Error>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 8192];
+
+    let headers_end = loop {
+        if let Some(pos) = request.windows(4).position(|x| x == b"\r\n\r\n") {
+            break pos;
+        }
+
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            Err("The connection was closed before the end of headers")?;
+        }
+        request.extend_from_slice(&buf[..n]);
+
+        if request.len() > MAX_REQUEST_SIZE {
+            Err("The request is too big")?;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&request[..headers_end]).to_string();
+    let mut lines = head.split("\r\n");
+
+    let mut first_line = lines.next().unwrap_or_default().split(' ');
+    let method = first_line.next().unwrap_or_default().to_string();
+    let path = first_line.next().ok_or("Unable to parse the path")?.to_string();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|x| x.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .map(|(_, v)| v.parse())
+        .transpose()?
+        .unwrap_or(0);
+
+    if content_length > MAX_REQUEST_SIZE {
+        Err("The request is too big")?;
+    }
+
+    let mut body = request[headers_end + 4..].to_vec();
+
+    while body.len() < content_length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            Err(format!(
+                "The connection was closed after {} of {} bytes of the body",
+                body.len(),
+                content_length
+            ))?;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+
+    body.truncate(content_length);
+
+    Ok(ApiRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+
+use super::{fetch_job, read_request, Job, JobList, JobStatus, Jobs, MAX_FINISHED_JOBS};
+
+fn job(id: usize, status: JobStatus) -> Job {
+    Job {
+        id,
+        args: Vec::new(),
+        status,
+        total: 1,
+        checked: 0,
+        results: Vec::new(),
+        errors: Vec::new(),
+    }
+}
+
+/// sends the raw request and closes the connection
+async fn read_raw(raw: &'static str) -> Result<super::ApiRequest, String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(raw.as_bytes()).await.unwrap();
+        stream.shutdown().await.unwrap();
+    });
+
+    let (mut stream, _) = listener.accept().await.unwrap();
+    read_request(&mut stream).await.map_err(|err| err.to_string())
+}
+
+#[tokio::test]
+async fn complete_body() {
+    let request = read_raw("POST /jobs HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello").await.unwrap();
+
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/jobs");
+    assert_eq!(request.body, "hello");
+}
+
+#[tokio::test]
+async fn truncated_body() {
+    let err = read_raw("POST /jobs HTTP/1.1\r\nContent-Length: 50\r\n\r\n{\"args\": []").await.err().unwrap();
+
+    assert!(err.contains("11 of 50 bytes"), "{}", err);
+}
+
+#[test]
+fn finished_jobs_dropped_after_fetch() {
+    let jobs: Jobs = Arc::new(Mutex::new(JobList {
+        last_id: 2,
+        list: vec![job(1, JobStatus::Running), job(2, JobStatus::Done)],
+    }));
+
+    // running jobs stay until they're finished
+    assert!(fetch_job(&jobs, 1).unwrap().is_ok());
+    assert!(fetch_job(&jobs, 1).is_some());
+
+    assert_eq!(fetch_job(&jobs, 2).unwrap().unwrap()["status"], "done");
+    assert!(fetch_job(&jobs, 2).is_none());
+}
+
+#[test]
+fn finished_jobs_limit() {
+    let mut jobs = JobList::default();
+
+    jobs.list.push(job(0, JobStatus::Queued));
+    for id in 1..=MAX_FINISHED_JOBS + 2 {
+        jobs.list.push(job(id, JobStatus::Failed));
+    }
+
+    jobs.drop_finished();
+
+    assert_eq!(jobs.list.len(), MAX_FINISHED_JOBS + 1);
+    assert_eq!(jobs.list.iter().map(|x| x.id).take(3).collect::<Vec<_>>(), [0, 3, 4]);
+}
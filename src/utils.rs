@@ -1,16 +1,25 @@
 use std::{
+    fmt,
     fs::File,
     io::{self, BufRead, Write},
     path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use colored::*;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle, ProgressDrawTarget};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use lazy_static::lazy_static;
 use linked_hash_map::LinkedHashMap;
-use rand::Rng;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use serde_json::json;
 use url::Url;
 
-use crate::{config::structs::Config, RANDOM_CHARSET};
+use crate::{config::structs::Config, metrics::Metrics, RANDOM_CHARSET};
 
 pub fn progress_style_learn_requests(config: &Config) -> ProgressStyle {
     if config.disable_colors {
@@ -24,16 +33,44 @@ pub fn progress_style_learn_requests(config: &Config) -> ProgressStyle {
     }
 }
 
+/// besides the checked chunks shows the speed, the share of failed requests, the sent requests, the remaining time and the amount of found parameters.
+/// the speed, errors and findings are taken from the metrics, so they're shared between all the urls
 pub fn progress_style_check_requests(config: &Config) -> ProgressStyle {
-    if config.disable_colors {
-        ProgressStyle::with_template(&format!("{{prefix}} {{bar:{}}} {{pos:>7}}/{{len:7}}", config.progress_bar_len))
-            .unwrap()
-            .progress_chars("##-")
+    let bar = if config.disable_colors {
+        format!("{{bar:{}}}", config.progress_bar_len)
     } else {
-        ProgressStyle::with_template(&format!("{{prefix}} {{bar:{}.cyan/blue}} {{pos:>7}}/{{len:7}}", config.progress_bar_len))
-            .unwrap()
-            .progress_chars("##-")
-    }
+        format!("{{bar:{}.cyan/blue}}", config.progress_bar_len)
+    };
+
+    let (rps, errors, found) = (
+        Arc::clone(&config.metrics),
+        Arc::clone(&config.metrics),
+        Arc::clone(&config.metrics),
+    );
+    let budget = Arc::clone(&config.budget);
+
+    ProgressStyle::with_template(&format!(
+        "{{prefix}} {} {{pos:>7}}/{{len:7}} {{rps}} req/s, {{errors}} errors, {{requests}} requests, eta {{eta}}, found {{found}}",
+        bar
+    ))
+    .unwrap()
+    .with_key("rps", move |_: &ProgressState, w: &mut dyn fmt::Write| {
+        write!(w, "{:.1}", rps.requests_per_second()).ok();
+    })
+    .with_key("errors", move |_: &ProgressState, w: &mut dyn fmt::Write| {
+        write!(w, "{:.0}%", errors.error_rate() * 100.).ok();
+    })
+    .with_key("found", move |_: &ProgressState, w: &mut dyn fmt::Write| {
+        write!(w, "{}", found.findings_count()).ok();
+    })
+    // with --max-requests the consumed part of the budget is shown
+    .with_key("requests", move |_: &ProgressState, w: &mut dyn fmt::Write| {
+        match budget.max_requests() {
+            Some(max_requests) => write!(w, "{}/{}", budget.requests(), max_requests).ok(),
+            None => write!(w, "{}", budget.requests()).ok(),
+        };
+    })
+    .progress_chars("##-")
 }
 
 
@@ -123,6 +160,52 @@ pub fn init_progress(config: &Config) -> Vec<(ProgressBar, Vec<String>)> {
     urls_to_progress
 }
 
+/// writes the state of the scan as a json line to stderr. Used instead of progress bars with --progress json.
+/// the phase of every url set is taken from the message of its progress bar
+pub fn write_progress_json(
+    progress_bars: &[(ProgressBar, Vec<String>)],
+    metrics: &Metrics,
+    elapsed: Duration,
+    finished: bool,
+) {
+    let (mut done, mut total) = (0, 0);
+
+    // the first progress bar is the empty line
+    let targets: Vec<serde_json::Value> = progress_bars
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, (progress_bar, _))| !progress_bar.message().is_empty())
+        .map(|(id, (progress_bar, url_set))| {
+            let (position, length) = (progress_bar.position(), progress_bar.length().unwrap_or_default());
+            done += position;
+            total += length;
+
+            json!({
+                "id": id,
+                "urls": url_set,
+                "phase": progress_bar.message(),
+                "done": position,
+                "total": length,
+            })
+        })
+        .collect();
+
+    let event = json!({
+        "event": if finished { "finished" } else { "progress" },
+        "elapsed": elapsed.as_secs_f64(),
+        "requests": metrics.requests(),
+        "errors": metrics.errors(),
+        "rps": metrics.requests_per_second(),
+        "findings": metrics.findings_count(),
+        "done": done,
+        "total": total,
+        "targets": targets,
+    });
+
+    writeln!(io::stderr(), "{}", event).ok();
+}
+
 /// read wordlist with parameters
 pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where
@@ -138,14 +221,54 @@ pub fn read_stdin_lines() -> Vec<String> {
     stdin.lock().lines().filter_map(|x| x.ok()).collect()
 }
 
+lazy_static! {
+    /// set with --seed. All the random names and values are taken from it so the scan can be repeated
+    static ref SEEDED_RNG: Mutex<Option<StdRng>> = Mutex::new(None);
+}
+
+/// whether SEEDED_RNG is set, so unseeded scans don't lock the mutex for every random value
+static SEEDED: AtomicBool = AtomicBool::new(false);
+
+/// makes all the following random values reproducible
+pub fn set_seed(seed: u64) {
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (seed >> (8 * (i % 8))) as u8;
+    }
+
+    *SEEDED_RNG.lock() = Some(StdRng::from_seed(bytes));
+    SEEDED.store(true, Ordering::SeqCst);
+}
+
+/// calls f with the seeded rng in case --seed is used and with the thread one otherwise
+fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    if SEEDED.load(Ordering::SeqCst) {
+        if let Some(rng) = SEEDED_RNG.lock().as_mut() {
+            return f(rng);
+        }
+    }
+
+    f(&mut rand::thread_rng())
+}
+
+/// random number within [low, high). Taken from the seeded rng in case --seed is used
+pub fn random_range(low: u64, high: u64) -> u64 {
+    with_rng(|rng| rng.gen_range(low, high))
+}
+
+/// random index of a slice with the given length
+pub fn random_index(len: usize) -> usize {
+    random_range(0, len as u64) as usize
+}
+
 /// generate random word of RANDOM_CHARSET chars
 pub fn random_line(size: usize) -> String {
-    (0..size)
-        .map(|_| {
-            let idx = rand::thread_rng().gen_range(0, RANDOM_CHARSET.len());
-            RANDOM_CHARSET[idx] as char
-        })
-        .collect()
+    // the whole line is generated under one lock of the seeded rng
+    with_rng(|rng| {
+        (0..size)
+            .map(|_| RANDOM_CHARSET[rng.gen_range(0, RANDOM_CHARSET.len())] as char)
+            .collect()
+    })
 }
 
 /// returns colored id when > 1 url is being tested in the same time
@@ -192,6 +315,25 @@ pub fn order_urls(urls: &[String]) -> Vec<Vec<String>> {
     ordered_urls
 }
 
+/// orders urls so the urls of the same host are as far from each other as possible:
+/// a1, a2, b1 -> a1, b1, a2
+pub fn interleave_hosts(urls: &[String]) -> Vec<String> {
+    let groups = order_urls(urls);
+    let max_len = groups.iter().map(|x| x.len()).max().unwrap_or(0);
+
+    (0..max_len)
+        .flat_map(|i| groups.iter().filter_map(move |x| x.get(i).cloned()))
+        .collect()
+}
+
+/// returns the host of the url or an empty string in case the url can't be parsed
+pub fn url_host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|x| x.host_str().map(|x| x.to_string()))
+        .unwrap_or_default()
+}
+
 /// returns true if more than 1 url is being checked a time
 pub fn is_id_important(config: &Config) -> bool {
     !(
@@ -0,0 +1,212 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, Lines},
+    path::Path,
+};
+
+/// bits per expected item. Together with HASHES gives ~1% of false positives
+const BITS_PER_ITEM: usize = 10;
+const HASHES: u64 = 7;
+
+/// the expected amount of items in case the size of the wordlist is unknown
+const MIN_EXPECTED_ITEMS: usize = 1 << 20;
+
+/// prefixes and suffixes added to parameter names with --mutations
+const PREFIXES: [&str; 3] = ["debug_", "is_", "enable_"];
+const SUFFIXES: [&str; 3] = ["_id", "_debug", "_enabled"];
+
+/// remembers seen items within a fixed amount of memory.
+/// false positives are possible, so a small part of unique items can be considered duplicates
+#[derive(Debug)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    pub fn new(expected_items: usize) -> Self {
+        let bits = expected_items.max(MIN_EXPECTED_ITEMS) * BITS_PER_ITEM;
+
+        Self {
+            bits: vec![0; bits / 64 + 1],
+        }
+    }
+
+    /// adds the item and returns true in case it wasn't seen before
+    pub fn insert(&mut self, item: &str) -> bool {
+        let (first, second) = (hash(item, 0), hash(item, 1));
+        let size = self.bits.len() as u64 * 64;
+        let mut is_new = false;
+
+        // double hashing to get HASHES positions from 2 hashes
+        for i in 0..HASHES {
+            let bit = first.wrapping_add(i.wrapping_mul(second)) % size;
+            let (index, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+
+            if self.bits[index] & mask == 0 {
+                self.bits[index] |= mask;
+                is_new = true;
+            }
+        }
+
+        is_new
+    }
+}
+
+fn hash(item: &str, seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// reads the wordlist lazily and returns it in batches of unique parameters,
+/// so huge wordlists don't have to fit into memory
+pub struct WordlistBatches {
+    lines: Lines<BufReader<File>>,
+    seen: BloomFilter,
+    batch_size: usize,
+}
+
+impl WordlistBatches {
+    pub fn new<P: AsRef<Path>>(filename: P, batch_size: usize) -> io::Result<Self> {
+        let file = File::open(filename)?;
+
+        // an average parameter takes ~8 bytes with the newline
+        let expected_items = file.metadata()?.len() as usize / 8;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            seen: BloomFilter::new(expected_items),
+            batch_size: batch_size.max(1),
+        })
+    }
+}
+
+impl Iterator for WordlistBatches {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        let mut batch = Vec::with_capacity(self.batch_size);
+
+        for line in self.lines.by_ref().map_while(Result::ok) {
+            if !line.is_empty() && self.seen.insert(&line) {
+                batch.push(line);
+
+                if batch.len() == self.batch_size {
+                    break;
+                }
+            }
+        }
+
+        if batch.is_empty() {
+            None
+        } else {
+            Some(batch)
+        }
+    }
+}
+
+/// adds case and affix variants of every parameter after the original ones, skipping already present names.
+/// user_id -> userId, user-id, debug_user_id, user_id_enabled, ..
+/// for headers only the X- prefix and the case variants are added: forwarded_host -> X-Forwarded-Host, Forwarded-Host.
+/// header names are case insensitive, so they are compared in lowercase
+pub fn mutate(params: Vec<String>, headers: bool) -> Vec<String> {
+    let key = |x: &str| if headers { x.to_lowercase() } else { x.to_string() };
+
+    let mut seen: HashSet<String> = params.iter().map(|x| key(x)).collect();
+    let mut variants = Vec::new();
+
+    for param in params.iter() {
+        let parts = split_words(param);
+
+        if parts.is_empty() {
+            continue;
+        }
+
+        let mut candidates = if headers {
+            let kebab = parts
+                .iter()
+                .map(|x| capitalize(x))
+                .collect::<Vec<String>>()
+                .join("-");
+
+            vec![format!("X-{}", kebab), kebab]
+        } else {
+            let snake = parts.join("_");
+
+            let mut candidates = vec![
+                camel_case(&parts),
+                snake.clone(),
+                parts.join("-"),
+            ];
+
+            candidates.extend(PREFIXES.iter().map(|x| format!("{}{}", x, snake)));
+            candidates.extend(SUFFIXES.iter().map(|x| format!("{}{}", snake, x)));
+            candidates
+        };
+
+        // the X- prefix shouldn't be doubled
+        if headers && parts[0] == "x" {
+            candidates.remove(0);
+        }
+
+        for candidate in candidates {
+            if seen.insert(key(&candidate)) {
+                variants.push(candidate);
+            }
+        }
+    }
+
+    let mut params = params;
+    params.append(&mut variants);
+    params
+}
+
+/// splits the name into lowercased words by separators and camelCase boundaries
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lowercase = false;
+
+    for c in name.chars() {
+        if c == '_' || c == '-' || c == '.' || c == ' ' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lowercase = false;
+            continue;
+        }
+
+        if c.is_uppercase() && prev_lowercase && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        prev_lowercase = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn camel_case(parts: &[String]) -> String {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, x)| if i == 0 { x.to_owned() } else { capitalize(x) })
+        .collect()
+}